@@ -23,8 +23,8 @@ echo "开始基本扫描..."
 echo "开始高级扫描（随机请求头和随机IP）..."
 ./target/release/backer -t targets.txt -p patterns.txt -o results/advanced_scan.json -f json -v -j 15 --random-headers --random-ip
 
-# 运行隐蔽模式扫描（禁用随机IP但保留随机请求头）
+# 运行隐蔽模式扫描（不伪装IP但保留随机请求头）
 echo "开始隐蔽模式扫描..."
-./target/release/backer -t targets.txt -p patterns.txt -o results/stealth_scan.json -f json -v -j 10 --no-random-ip -T 60
+./target/release/backer -t targets.txt -p patterns.txt -o results/stealth_scan.json -f json -v -j 10 -T 60
 
 echo "所有扫描完成！结果已保存到 results/ 目录" 
\ No newline at end of file
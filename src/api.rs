@@ -0,0 +1,105 @@
+use serde_json::Value;
+
+/// API定义文件开头的下载大小(字节)，足以解析常见的集合和规范文件头部
+pub const API_PREVIEW_SIZE: usize = 256 * 1024;
+
+/// 判断URL是否指向API定义或集合文件
+pub fn is_api_leak_url(url: &str) -> bool {
+    let url = url.to_lowercase();
+    let name = url.rsplit('/').next().unwrap_or(&url);
+    let name = name.trim_end_matches(".bak").trim_end_matches(".old");
+    name.starts_with(".graphqlconfig")
+        || name.starts_with(".graphqlrc")
+        || name.ends_with(".graphql")
+        || name.ends_with("postman_collection.json")
+        || name.ends_with("postman_environment.json")
+        || name == "insomnia.json"
+        || ["swagger", "openapi", "api-docs"].iter().any(|prefix| {
+            name.strip_prefix(prefix).is_some_and(|rest| matches!(rest, ".json" | ".yaml" | ".yml"))
+        })
+}
+
+/// 识别API定义文件内容，返回单行描述，无法识别时返回None
+///
+/// 支持 OpenAPI/Swagger (JSON或YAML)、Postman集合和环境、Insomnia导出、
+/// GraphQL配置和SDL。只解析已下载的开头部分，截断的JSON按关键字识别
+pub fn describe_api_leak(content: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(content);
+    let trimmed = text.trim_start();
+
+    if let Ok(json) = serde_json::from_str::<Value>(trimmed) {
+        if let Some(description) = describe_json(&json) {
+            return Some(description);
+        }
+    }
+
+    // YAML或截断的JSON，按顶层关键字识别
+    for line in trimmed.lines().take(50) {
+        let line = line.trim().trim_start_matches('{').trim();
+        for (key, kind) in [("openapi", "OpenAPI"), ("swagger", "Swagger")] {
+            if let Some(rest) = line.strip_prefix(key).or_else(|| line.strip_prefix(&format!("\"{}\"", key))) {
+                if let Some(version) = rest.trim_start().strip_prefix(':') {
+                    let version = version.trim().trim_matches(|c| c == '"' || c == '\'' || c == ',');
+                    return Some(format!("{} {}", kind, version));
+                }
+            }
+        }
+    }
+    if trimmed.contains("schema.getpostman.com") {
+        return Some("Postman集合".to_string());
+    }
+    if trimmed.contains("\"_type\"") && trimmed.contains("insomnia") {
+        return Some("Insomnia导出".to_string());
+    }
+    if trimmed.lines().any(|line| {
+        let line = line.trim_start();
+        line.starts_with("type Query") || line.starts_with("schema {") || line.starts_with("type Mutation")
+    }) {
+        return Some("GraphQL Schema".to_string());
+    }
+    if trimmed.lines().take(20).any(|line| line.trim_start().starts_with("schema:") || line.trim_start().starts_with("schemaPath:")) {
+        return Some("GraphQL配置".to_string());
+    }
+    None
+}
+
+/// 识别JSON格式的API定义
+fn describe_json(json: &Value) -> Option<String> {
+    let title = json.pointer("/info/title").and_then(Value::as_str);
+    let with_title = |kind: String| match title {
+        Some(title) => format!("{}: {}", kind, title),
+        None => kind,
+    };
+
+    if let Some(version) = json.get("openapi").and_then(Value::as_str) {
+        return Some(with_title(format!("OpenAPI {} ({} 个路径)", version, count(json.get("paths")))));
+    }
+    if let Some(version) = json.get("swagger").and_then(Value::as_str) {
+        return Some(with_title(format!("Swagger {} ({} 个路径)", version, count(json.get("paths")))));
+    }
+    if json.pointer("/info/_postman_id").is_some()
+        || json.pointer("/info/schema").and_then(Value::as_str).is_some_and(|s| s.contains("getpostman.com")) {
+        let name = json.pointer("/info/name").and_then(Value::as_str).unwrap_or("未命名");
+        return Some(format!("Postman集合: {} ({} 个条目)", name, count(json.get("item"))));
+    }
+    if json.get("_postman_variable_scope").is_some() {
+        let name = json.get("name").and_then(Value::as_str).unwrap_or("未命名");
+        return Some(format!("Postman环境: {} ({} 个变量)", name, count(json.get("values"))));
+    }
+    if json.get("_type").and_then(Value::as_str) == Some("export") {
+        return Some(format!("Insomnia导出 ({} 个资源)", count(json.get("resources"))));
+    }
+    if json.get("schema").is_some() || json.get("schemaPath").is_some() || json.get("projects").is_some() {
+        return Some("GraphQL配置".to_string());
+    }
+    None
+}
+
+/// 数组或对象的元素数
+fn count(value: Option<&Value>) -> usize {
+    match value {
+        Some(Value::Array(items)) => items.len(),
+        Some(Value::Object(map)) => map.len(),
+        _ => 0,
+    }
+}
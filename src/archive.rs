@@ -0,0 +1,173 @@
+use flate2::read::GzDecoder;
+use std::io::{Cursor, Read};
+
+/// 默认参与内存解压扫描的最大压缩包大小(字节)
+pub const DEFAULT_ARCHIVE_SCAN_MAX_SIZE: u64 = 5 * 1024 * 1024;
+
+/// 默认解压后读取的最大字节数，防止解压炸弹耗尽内存
+pub const DEFAULT_MAX_DECOMPRESSED_SIZE: u64 = 64 * 1024 * 1024;
+
+/// 默认的最大解压比，正常的备份很少超过 20:1，deflate的理论上限约为 1032:1
+pub const DEFAULT_MAX_COMPRESSION_RATIO: u64 = 200;
+
+/// 解压后至少达到该大小才检查解压比，小文件的解压比没有意义
+const RATIO_CHECK_MIN_SIZE: u64 = 1024 * 1024;
+
+/// 解压时每次读取的大小
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+/// 内存解压的限制
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecompressionLimits {
+    /// 解压后读取的最大字节数，超过时只列出已解压部分中的文件
+    pub max_size: u64,
+    /// 最大解压比(解压后大小 / 压缩大小)，超过时视为解压炸弹
+    pub max_ratio: u64,
+}
+
+impl Default for DecompressionLimits {
+    fn default() -> Self {
+        Self { max_size: DEFAULT_MAX_DECOMPRESSED_SIZE, max_ratio: DEFAULT_MAX_COMPRESSION_RATIO }
+    }
+}
+
+impl DecompressionLimits {
+    /// 解压比超过上限时返回说明
+    fn check_ratio(&self, decompressed: u64, compressed: u64) -> Option<String> {
+        if decompressed < RATIO_CHECK_MIN_SIZE || decompressed <= compressed.max(1).saturating_mul(self.max_ratio) {
+            return None;
+        }
+        Some(format!(
+            "压缩数据 {} 字节，解压后 {} 字节，解压比超过上限 {}:1",
+            compressed, decompressed, self.max_ratio
+        ))
+    }
+}
+
+/// 压缩包的解析结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Listing {
+    /// 压缩包中的文件名
+    Entries(Vec<String>),
+    /// 疑似解压炸弹，附带原因
+    Bomb(String),
+}
+
+/// 压缩包内的高价值文件特征
+const HIGH_VALUE_INDICATORS: &[&str] = &[
+    "wp-config.php",
+    "configuration.php",
+    "config.php",
+    "settings.py",
+    "database.yml",
+    "web.config",
+    "appsettings.json",
+    ".env",
+    ".htpasswd",
+    "id_rsa",
+    "id_dsa",
+    "id_ecdsa",
+    "id_ed25519",
+    ".pem",
+    ".key",
+    ".sql",
+    ".sqlite",
+    ".mdb",
+    ".kdbx",
+    "shadow",
+    "credentials",
+    ".git/config",
+];
+
+/// 列出内存中压缩包的文件名，支持zip、gzip和tar(含tar.gz)，无法识别时返回None
+///
+/// zip按中央目录声明的解压大小、gzip按实际解压的大小检查解压比，超过上限时不再解压
+pub fn list_entries(content: &[u8], limits: DecompressionLimits) -> Option<Listing> {
+    if content.starts_with(b"PK\x03\x04") {
+        return list_zip_entries(content, limits);
+    }
+
+    if content.starts_with(&[0x1f, 0x8b]) {
+        let decompressed = match gunzip(content, limits)? {
+            Ok(decompressed) => decompressed,
+            Err(reason) => return Some(Listing::Bomb(reason)),
+        };
+
+        // tar.gz：解压后继续列出tar中的文件
+        if is_tar(&decompressed) {
+            return list_tar_entries(&decompressed).map(Listing::Entries);
+        }
+        let inner_name = GzDecoder::new(content).header().and_then(|h| h.filename())
+            .map(|name| String::from_utf8_lossy(name).to_string());
+        return Some(Listing::Entries(inner_name.into_iter().collect()));
+    }
+
+    if is_tar(content) {
+        return list_tar_entries(content).map(Listing::Entries);
+    }
+
+    None
+}
+
+/// 分块解压gzip，每块之后按已读取的压缩数据检查解压比；超过大小上限时截断，
+/// 解压比超限时返回原因，数据损坏时返回None
+fn gunzip(content: &[u8], limits: DecompressionLimits) -> Option<std::result::Result<Vec<u8>, String>> {
+    let mut input = Cursor::new(content);
+    let mut decoder = GzDecoder::new(&mut input);
+    let mut decompressed = Vec::new();
+    let mut buffer = vec![0u8; READ_CHUNK_SIZE];
+    while (decompressed.len() as u64) < limits.max_size {
+        let read = decoder.read(&mut buffer).ok()?;
+        if read == 0 {
+            break;
+        }
+        decompressed.extend_from_slice(&buffer[..read]);
+        let consumed = decoder.get_ref().position();
+        if let Some(reason) = limits.check_ratio(decompressed.len() as u64, consumed) {
+            return Some(Err(reason));
+        }
+    }
+    decompressed.truncate(limits.max_size as usize);
+    Some(Ok(decompressed))
+}
+
+/// 在文件名中查找高价值特征，返回命中的文件名
+pub fn find_indicators(entries: &[String]) -> Vec<String> {
+    entries.iter()
+        .filter(|entry| {
+            let lower = entry.to_lowercase();
+            HIGH_VALUE_INDICATORS.iter().any(|indicator| lower.ends_with(indicator))
+        })
+        .cloned()
+        .collect()
+}
+
+/// 列出zip中的文件名，中央目录声明的解压总大小超过解压比上限时视为解压炸弹
+fn list_zip_entries(content: &[u8], limits: DecompressionLimits) -> Option<Listing> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(content)).ok()?;
+    let mut declared = 0u64;
+    for index in 0..archive.len() {
+        if let Ok(entry) = archive.by_index_raw(index) {
+            declared = declared.saturating_add(entry.size());
+        }
+    }
+    if let Some(reason) = limits.check_ratio(declared, content.len() as u64) {
+        return Some(Listing::Bomb(reason));
+    }
+    Some(Listing::Entries(archive.file_names().map(String::from).collect()))
+}
+
+/// 列出tar中的文件名
+fn list_tar_entries(content: &[u8]) -> Option<Vec<String>> {
+    let mut archive = tar::Archive::new(content);
+    let entries = archive.entries().ok()?;
+    Some(entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.path().ok().map(|p| p.to_string_lossy().to_string()))
+        .collect())
+}
+
+/// 判断内容是否为tar格式(ustar魔数位于偏移257)
+fn is_tar(content: &[u8]) -> bool {
+    content.len() > 262 && &content[257..262] == b"ustar"
+}
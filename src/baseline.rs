@@ -0,0 +1,43 @@
+use crate::report::load_results;
+use crate::{BackerError, Result, ScanResult};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// 基线：之前的扫描结果中已接受风险的发现
+///
+/// 与基线中URL相同的发现视为已知，不输出、不发布、不通知；两边都记录了内容哈希(-v 时计算)时
+/// 还要求哈希相同，文件内容变化后重新作为新发现报告
+#[derive(Debug, Default)]
+pub struct Baseline {
+    /// URL -> 内容哈希
+    entries: HashMap<String, Option<String>>,
+}
+
+impl Baseline {
+    /// 从JSON或NDJSON结果文件加载基线
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let results = load_results(path)
+            .map_err(|e| BackerError::Config(format!("无法加载基线 {}: {}", path.display(), e)))?;
+        let entries = results.into_iter().map(|result| (result.url, result.content_hash)).collect();
+        Ok(Self { entries })
+    }
+
+    /// 发现是否已在基线中
+    pub fn is_known(&self, result: &ScanResult) -> bool {
+        match self.entries.get(&result.url) {
+            Some(Some(hash)) => result.content_hash.as_ref().is_none_or(|current| current == hash),
+            Some(None) => true,
+            None => false,
+        }
+    }
+
+    /// 基线中的发现数
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
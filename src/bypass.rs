@@ -0,0 +1,53 @@
+use url::Url;
+
+/// 403绕过技巧生成的请求变体
+#[derive(Debug, Clone)]
+pub struct BypassVariant {
+    /// 技巧名称
+    pub technique: &'static str,
+    /// 实际请求的URL
+    pub url: String,
+    /// 需要附加的请求头
+    pub header: Option<(&'static str, String)>,
+}
+
+/// 为返回403的URL生成有限的绕过变体
+///
+/// 注意 `%2e/` 形式的点路径会被URL解析规范化掉，因此只使用解析后仍然保留的技巧
+pub fn bypass_variants(url: &str) -> Vec<BypassVariant> {
+    let parsed = match Url::parse(url) {
+        Ok(parsed) => parsed,
+        Err(_) => return Vec::new(),
+    };
+
+    let origin = parsed.origin().ascii_serialization();
+    let path = parsed.path().to_string();
+    let mut variants = Vec::new();
+
+    let mut push = |technique, url: String, header| {
+        variants.push(BypassVariant { technique, url, header });
+    };
+
+    // 路径后缀技巧，部分服务器(如IIS)会忽略末尾的点和空格
+    push("trailing-%2e", format!("{}{}%2e", origin, path), None);
+    push("trailing-%20", format!("{}{}%20", origin, path), None);
+    push("trailing-semicolon", format!("{}{};", origin, path), None);
+    push("empty-query", format!("{}{}?", origin, path), None);
+
+    // 双斜杠
+    push("double-slash", format!("{}/{}", origin, path), None);
+
+    // 大小写变换，仅对最后一段生效
+    if let Some((dir, file)) = path.rsplit_once('/') {
+        let upper = file.to_uppercase();
+        if upper != file {
+            push("uppercase", format!("{}{}/{}", origin, dir, upper), None);
+        }
+    }
+
+    // 通过重写请求头访问，请求根路径
+    push("x-original-url", format!("{}/", origin), Some(("x-original-url", path.clone())));
+    push("x-rewrite-url", format!("{}/", origin), Some(("x-rewrite-url", path)));
+
+    variants
+}
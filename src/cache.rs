@@ -0,0 +1,115 @@
+use crate::http::UrlCheck;
+use dashmap::DashMap;
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// 缓存条目的有效期
+pub const RESPONSE_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// 最多缓存的响应内容数，达到后不再缓存新的内容
+const MAX_CACHED_BODIES: usize = 4096;
+
+/// 最多缓存的探测结果数
+const MAX_CACHED_CHECKS: usize = 100_000;
+
+/// 缓存的GET响应开头内容
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+    /// 内容短于请求的长度，即已读完整个响应
+    complete: bool,
+    stored: Instant,
+}
+
+/// 一次扫描内的响应缓存，按URL复用之前的响应，避免预览、SQL摘要、API摘要、质询页检查等
+/// 不同阶段对同一URL重复请求，重复的目标也复用探测结果
+///
+/// 条目在有效期后失效；复查等需要重新请求的场合不经过缓存
+#[derive(Debug)]
+pub struct ResponseCache {
+    ttl: Duration,
+    bodies: DashMap<String, CachedResponse>,
+    checks: DashMap<(String, bool), (UrlCheck, Instant)>,
+    hits: AtomicUsize,
+}
+
+impl ResponseCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            bodies: DashMap::new(),
+            checks: DashMap::new(),
+            hits: AtomicUsize::new(0),
+        }
+    }
+
+    /// 有效期内、至少包含开头 `size` 字节(或已是完整内容)的响应，内容截取到 `size`
+    pub fn get(&self, url: &str, size: usize) -> Option<CachedResponse> {
+        let entry = self.bodies.get(url)?;
+        if entry.stored.elapsed() > self.ttl {
+            drop(entry);
+            self.bodies.remove(url);
+            return None;
+        }
+        if entry.body.len() < size && !entry.complete {
+            return None;
+        }
+        let mut response = entry.clone();
+        response.body.truncate(size);
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(response)
+    }
+
+    /// 记录请求开头 `size` 字节得到的响应，已有更长的内容时保留原条目
+    pub fn insert(&self, url: &str, size: usize, status: StatusCode, headers: &HeaderMap, body: &[u8]) {
+        if self.bodies.len() >= MAX_CACHED_BODIES && !self.bodies.contains_key(url) {
+            return;
+        }
+        let complete = body.len() < size;
+        let response = CachedResponse { status, headers: headers.clone(), body: body.to_vec(), complete, stored: Instant::now() };
+        self.bodies.entry(url.to_string())
+            .and_modify(|cached| {
+                if cached.stored.elapsed() > self.ttl || response.complete || response.body.len() > cached.body.len() {
+                    *cached = response.clone();
+                }
+            })
+            .or_insert(response);
+    }
+
+    /// 有效期内同一URL、同一验证模式的探测结果
+    pub fn check(&self, url: &str, verify_content: bool) -> Option<UrlCheck> {
+        let key = (url.to_string(), verify_content);
+        let entry = self.checks.get(&key)?;
+        if entry.1.elapsed() > self.ttl {
+            drop(entry);
+            self.checks.remove(&key);
+            return None;
+        }
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(entry.0.clone())
+    }
+
+    /// 记录探测结果，要求稍后重试的结果不缓存
+    pub fn insert_check(&self, url: &str, verify_content: bool, check: &UrlCheck) {
+        if check.retry_after.is_some() || self.checks.len() >= MAX_CACHED_CHECKS {
+            return;
+        }
+        self.checks.insert((url.to_string(), verify_content), (check.clone(), Instant::now()));
+    }
+
+    /// 清空缓存和命中计数，每次扫描开始时调用，监控模式的各轮扫描不复用上一轮的响应
+    pub fn clear(&self) {
+        self.bodies.clear();
+        self.checks.clear();
+        self.hits.store(0, Ordering::Relaxed);
+    }
+
+    /// 命中缓存、省去的请求数
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,136 @@
+use compact_str::CompactString;
+use reqwest::Method;
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
+use url::Url;
+
+/// 生成候选路径的规则，作为候选的 `pattern_id`
+pub mod rule {
+    /// 根目录下的完整路径
+    pub const FULL_PATH: u16 = 0;
+    /// 根目录下的前缀与后缀组合
+    pub const PREFIX: u16 = 1;
+    /// 根目录下的域名与后缀组合
+    pub const DOMAIN: u16 = 2;
+    /// 根目录下的域名变体与后缀组合
+    pub const DOMAIN_VARIANT: u16 = 3;
+    /// 备份目录下的域名与后缀组合
+    pub const DIR_DOMAIN: u16 = 4;
+    /// 备份目录下的通用备份名
+    pub const DIR_COMMON_NAME: u16 = 5;
+    /// 备份目录下的前缀与后缀组合
+    pub const DIR_PREFIX: u16 = 6;
+    /// 备份目录下的完整路径
+    pub const DIR_FULL_PATH: u16 = 7;
+    /// 备份目录下的域名变体与后缀组合
+    pub const DIR_DOMAIN_VARIANT: u16 = 8;
+    /// 简单回退方案生成的路径
+    pub const SIMPLE: u16 = 9;
+    /// 识别出的技术栈的专属路径
+    pub const TECHNOLOGY: u16 = 10;
+    /// 额外启用的模式分类的路径
+    pub const CATEGORY: u16 = 11;
+    /// 模式行首指定了请求方法的路径
+    pub const METHOD_PROBE: u16 = 12;
+    /// 用户脚本生成的路径
+    pub const SCRIPT: u16 = 13;
+    /// 前提命中后追加的条件模式路径
+    pub const CONDITIONAL: u16 = 14;
+    /// 元数据文件列出的文件名的备份路径
+    pub const LISTING: u16 = 15;
+}
+
+/// 路径中需要百分号编码的ASCII字符，`/` 作为分隔符保留
+const PATH_RESERVED: &[u8] = b" \"#<>?`{}|\\^";
+
+/// 对路径中的非ASCII字符、空白和保留字符进行百分号编码，已有的 `%XX` 编码保持不变
+///
+/// 爬取的文件名、目录列表和自定义模式中可能包含中文、空格或 `#`，直接拼接会被截断为片段或查询
+pub fn encode_path(path: &str) -> Cow<'_, str> {
+    let bytes = path.as_bytes();
+    let is_escape = |i: usize| i + 2 < bytes.len() && bytes[i + 1].is_ascii_hexdigit() && bytes[i + 2].is_ascii_hexdigit();
+    let needs_encoding = |i: usize| {
+        let byte = bytes[i];
+        !byte.is_ascii_graphic() || PATH_RESERVED.contains(&byte) || (byte == b'%' && !is_escape(i))
+    };
+    if !(0..bytes.len()).any(needs_encoding) {
+        return Cow::Borrowed(path);
+    }
+    let mut encoded = String::with_capacity(bytes.len() * 3);
+    for (i, byte) in bytes.iter().enumerate() {
+        if needs_encoding(i) {
+            encoded.push_str(&format!("%{:02X}", byte));
+        } else {
+            encoded.push(*byte as char);
+        }
+    }
+    Cow::Owned(encoded)
+}
+
+/// 候选的基础URL：协议、主机和非默认端口，如 `https://example.com:8443`
+pub fn base_url(url: &Url) -> Arc<str> {
+    let host = url.host_str().unwrap_or("");
+    match url.port() {
+        Some(port) => Arc::from(format!("{}://{}:{}", url.scheme(), host, port)),
+        None => Arc::from(format!("{}://{}", url.scheme(), host)),
+    }
+}
+
+/// 待检测的备份文件候选
+///
+/// 同一目标的所有候选共享基础URL，路径较短时内联存储，
+/// 只在发送请求时才拼接成完整URL，减少大范围扫描的内存占用
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Candidate {
+    /// 基础URL，如 `https://example.com`
+    pub base: Arc<str>,
+    /// 相对路径，不含前导斜杠，如 `backup/www.zip`
+    pub path: CompactString,
+    /// 生成该候选的规则，见 [`rule`]
+    pub pattern_id: u16,
+    /// 模式指定的请求方法，未指定时按默认的HEAD优先流程探测
+    pub method: Option<Method>,
+}
+
+impl Candidate {
+    /// 创建候选
+    pub fn new(base: &Arc<str>, path: impl Into<CompactString>, pattern_id: u16) -> Self {
+        Self {
+            base: base.clone(),
+            path: path.into(),
+            pattern_id,
+            method: None,
+        }
+    }
+
+    /// 指定探测该候选使用的请求方法
+    pub fn with_method(mut self, method: Method) -> Self {
+        self.method = Some(method);
+        self
+    }
+
+    /// 拼接完整URL，路径经过百分号编码，见 [`encode_path`]
+    pub fn url(&self) -> String {
+        let path = encode_path(&self.path);
+        let mut url = String::with_capacity(self.base.len() + 1 + path.len());
+        url.push_str(&self.base);
+        url.push('/');
+        url.push_str(&path);
+        url
+    }
+
+    /// 路径最后一段(未编码)，与 [`crate::stats::pattern_key`] 对完整URL的结果一致
+    pub fn file_name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+}
+
+impl fmt::Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.method {
+            Some(method) => write!(f, "{} {}/{}", method, self.base, self.path),
+            None => write!(f, "{}/{}", self.base, self.path),
+        }
+    }
+}
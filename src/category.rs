@@ -0,0 +1,197 @@
+use crate::api::is_api_leak_url;
+use crate::candidate::Candidate;
+use crate::patterndb::{merge_paths, PatternDb};
+use crate::patterns::CandidateIter;
+use crate::{BackerError, Result};
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// 模式分类
+///
+/// `archive` 即默认的备份文件生成规则，始终启用；其余分类默认不启用，通过 `--category` 开启
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    /// 网站和数据库备份
+    Archive,
+    /// 敏感配置文件
+    Config,
+    /// 版本控制元数据
+    Vcs,
+    /// 接口文档备份、GraphQL配置、导出的API集合
+    Api,
+}
+
+impl Category {
+    /// 分类名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Category::Archive => "archive",
+            Category::Config => "config",
+            Category::Vcs => "vcs",
+            Category::Api => "api",
+        }
+    }
+
+    /// 按名称解析
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "archive" => Some(Category::Archive),
+            "config" => Some(Category::Config),
+            "vcs" => Some(Category::Vcs),
+            "api" => Some(Category::Api),
+            _ => None,
+        }
+    }
+
+    /// 按发现的URL推断所属分类：版本控制目录(含 `.DS_Store`)、API定义和敏感配置文件，其余归为 `archive`
+    pub fn of_url(url: &str) -> Self {
+        let path = url.split(['?', '#']).next().unwrap_or(url).to_lowercase();
+        let path = path.split_once("://").map_or(path.as_str(), |(_, rest)| rest);
+        if path.split('/').skip(1).any(|segment| VCS_DIRS.contains(&segment)) || path.ends_with("/.ds_store") {
+            return Category::Vcs;
+        }
+        if is_api_leak_url(url) {
+            return Category::Api;
+        }
+        let name = path.rsplit('/').next().unwrap_or("");
+        let stem = BACKUP_SUFFIXES.iter().fold(name, |name, suffix| name.strip_suffix(suffix).unwrap_or(name));
+        if stem.starts_with(".env") || CONFIG_FILES.contains(&stem) {
+            return Category::Config;
+        }
+        Category::Archive
+    }
+}
+
+/// 版本控制元数据目录
+const VCS_DIRS: &[&str] = &[".git", ".svn", ".hg", ".bzr", "cvs"];
+
+/// 敏感配置文件名
+const CONFIG_FILES: &[&str] = &[
+    ".htpasswd", ".htaccess", "config.php", "wp-config.php", "configuration.php", "web.config",
+    "settings.py", "config.yml", "config.yaml", "config.json", "database.yml", "application.properties",
+];
+
+/// 推断配置文件时去掉的备份后缀
+const BACKUP_SUFFIXES: &[&str] = &[".bak", ".old", ".orig", ".save", ".swp", "~"];
+
+/// 汇总已启用分类在模式库中的路径和导入路径，去除重复
+///
+/// `archive` 始终启用，因此导入的备份类字典总会参与扫描；
+/// 模式库中的路径相对站点根目录原样请求，`archive` 由默认生成规则覆盖
+pub fn category_patterns(categories: &[Category], db: &PatternDb, imported: &HashMap<Category, Vec<String>>) -> Vec<String> {
+    let mut enabled = vec![Category::Archive];
+    enabled.extend(categories.iter().filter(|c| **c != Category::Archive));
+
+    merge_paths(enabled.into_iter().map(|category| (db.category(category.name()), imported.get(&category))))
+}
+
+/// 合计预算下轮流调度各分类候选的顺序，数量少、价值高的分类在前
+const SCHEDULE_ORDER: [Category; 4] = [Category::Vcs, Category::Config, Category::Api, Category::Archive];
+
+/// 单个目标按模式分类的请求预算，如 `archive=500,vcs=50,config=100,total=600`
+///
+/// 各分类超出预算的候选不再请求；指定 `total` 合计上限时各分类的候选轮流请求，
+/// 候选很多的分类不会占满合计上限而挤掉其他分类
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CategoryBudget {
+    /// 各分类的候选数上限
+    pub limits: Vec<(Category, usize)>,
+    /// 所有分类合计的候选数上限
+    pub total: Option<usize>,
+}
+
+impl CategoryBudget {
+    /// 解析 `分类=数量` 列表，逗号分隔，分类名也可以用复数形式(如 `archives`)，`total` 为合计上限
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut budget = Self::default();
+        for entry in spec.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let invalid = || BackerError::Config(format!("无效的预算: {} (格式为 分类=数量，如 archive=500,vcs=50)", entry));
+            let (name, count) = entry.split_once('=').ok_or_else(invalid)?;
+            let count: usize = count.trim().parse().map_err(|_| invalid())?;
+            let name = name.trim().to_lowercase();
+            if name == "total" {
+                budget.total = Some(count);
+                continue;
+            }
+            let category = Category::parse(&name)
+                .or_else(|| name.strip_suffix('s').and_then(Category::parse))
+                .ok_or_else(|| BackerError::Config(format!("未知的模式分类: {} (可选 archive、config、vcs、api、total)", name)))?;
+            budget.limits.retain(|(existing, _)| *existing != category);
+            budget.limits.push((category, count));
+        }
+        if budget.limits.is_empty() && budget.total.is_none() {
+            return Err(BackerError::Config("预算为空".to_string()));
+        }
+        Ok(budget)
+    }
+
+    /// 分类的候选数上限
+    pub fn limit(&self, category: Category) -> Option<usize> {
+        self.limits.iter().find(|(existing, _)| *existing == category).map(|(_, count)| *count)
+    }
+
+    /// 说明，如 "archive 500，vcs 50，合计 600"
+    pub fn describe(&self) -> String {
+        let mut parts: Vec<String> = self.limits.iter()
+            .map(|(category, count)| format!("{} {}", category.name(), count))
+            .collect();
+        if let Some(total) = self.total {
+            parts.push(format!("合计 {}", total));
+        }
+        parts.join("，")
+    }
+
+    /// 按预算筛选和调度候选，超出预算跳过的候选数累加到 `skipped`
+    ///
+    /// 只有分类上限时逐个过滤，仍然惰性生成；有合计上限时先按分类分组(每组最多缓存到上限)，
+    /// 再按 [`SCHEDULE_ORDER`] 轮流取出
+    pub fn apply(&self, candidates: CandidateIter, skipped: Arc<AtomicUsize>) -> CandidateIter {
+        let Some(total) = self.total else {
+            let budget = self.clone();
+            let mut used: HashMap<Category, usize> = HashMap::new();
+            return Box::new(candidates.filter(move |candidate| {
+                let category = candidate_category(candidate);
+                let used = used.entry(category).or_default();
+                if budget.limit(category).is_some_and(|limit| *used >= limit) {
+                    skipped.fetch_add(1, Ordering::Relaxed);
+                    return false;
+                }
+                *used += 1;
+                true
+            }));
+        };
+
+        let mut queues: Vec<VecDeque<Candidate>> = vec![VecDeque::new(); SCHEDULE_ORDER.len()];
+        let mut dropped = 0;
+        for candidate in candidates {
+            let category = candidate_category(&candidate);
+            let index = SCHEDULE_ORDER.iter().position(|c| *c == category).unwrap_or(SCHEDULE_ORDER.len() - 1);
+            let cap = self.limit(category).map_or(total, |limit| limit.min(total));
+            if queues[index].len() < cap {
+                queues[index].push_back(candidate);
+            } else {
+                dropped += 1;
+            }
+        }
+
+        let mut scheduled = Vec::new();
+        while scheduled.len() < total && queues.iter().any(|queue| !queue.is_empty()) {
+            for queue in &mut queues {
+                if scheduled.len() < total {
+                    scheduled.extend(queue.pop_front());
+                }
+            }
+        }
+        dropped += queues.iter().map(VecDeque::len).sum::<usize>();
+        skipped.fetch_add(dropped, Ordering::Relaxed);
+        Box::new(scheduled.into_iter())
+    }
+}
+
+/// 候选所属的模式分类
+fn candidate_category(candidate: &Candidate) -> Category {
+    Category::of_url(&format!("/{}", candidate.path))
+}
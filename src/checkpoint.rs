@@ -0,0 +1,232 @@
+use crate::{BackerError, Result, ScanResult, TargetReport};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 记录已检查URL后的最短保存间隔，发现和目标完成时立即保存
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 断点续扫的状态文件内容
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CheckpointState {
+    /// 已完成目标的报告
+    #[serde(default)]
+    pub completed: Vec<TargetReport>,
+    /// 未完成目标中已检查过的URL
+    #[serde(default)]
+    pub checked_urls: HashSet<String>,
+    /// 已保存的发现
+    #[serde(default)]
+    pub results: Vec<ScanResult>,
+    /// 调度进度，跨重启累计
+    #[serde(default)]
+    pub progress: ScanProgress,
+}
+
+/// 扫描调度进度，写入状态文件，重启后继续累计而不是从零开始
+///
+/// 已完成目标的已检查URL会从状态文件中移除，这里的计数不受影响
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScanProgress {
+    /// 已检查的URL总数
+    #[serde(default)]
+    pub checked: usize,
+    /// 发现总数
+    #[serde(default)]
+    pub findings: usize,
+    /// 按主机(含端口)统计的队列进度
+    #[serde(default)]
+    pub hosts: BTreeMap<String, HostProgress>,
+}
+
+impl ScanProgress {
+    /// 已完成的目标数
+    pub fn completed(&self) -> usize {
+        self.hosts.values().map(|host| host.completed).sum()
+    }
+
+    /// 队列中的目标总数
+    pub fn targets(&self) -> usize {
+        self.hosts.values().map(|host| host.targets).sum()
+    }
+}
+
+/// 单个主机队列的进度
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HostProgress {
+    /// 队列中的目标数
+    pub targets: usize,
+    /// 已完成的目标数
+    pub completed: usize,
+    /// 已检查的URL数
+    pub checked: usize,
+    /// 发现数
+    pub findings: usize,
+}
+
+/// 进度按主机(含端口)归类，与扫描时按主机分组的方式一致
+pub fn host_key(url: &str) -> String {
+    url.split("://").nth(1)
+        .and_then(|rest| rest.split('/').next())
+        .unwrap_or(url)
+        .to_string()
+}
+
+/// 扫描检查点，扫描过程中定期写入状态文件，重新启动时跳过已完成的工作
+///
+/// 目标在存活检测、扫描和覆盖统计都结束后才记为完成；中断时正在扫描的目标
+/// 重新扫描，但跳过其中已检查过的URL
+#[derive(Debug)]
+pub struct Checkpoint {
+    path: PathBuf,
+    /// 启动时已检查过的URL，扫描中只读
+    resumed_urls: HashSet<String>,
+    state: Mutex<CheckpointState>,
+    last_saved: Mutex<Instant>,
+}
+
+impl Checkpoint {
+    /// 打开状态文件，文件不存在时从头开始
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let state: CheckpointState = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).map_err(|e| BackerError::Config(
+                format!("无法解析状态文件 {}: {}", path.display(), e)
+            ))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => CheckpointState::default(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path,
+            resumed_urls: state.checked_urls.clone(),
+            state: Mutex::new(state),
+            last_saved: Mutex::new(Instant::now()),
+        })
+    }
+
+    /// 状态文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 是否从已有的状态继续
+    pub fn is_resumed(&self) -> bool {
+        let state = self.lock();
+        !state.completed.is_empty() || !state.checked_urls.is_empty() || !state.results.is_empty()
+    }
+
+    /// 已完成目标的报告，未完成时为None
+    pub fn completed_report(&self, target: &str) -> Option<TargetReport> {
+        self.lock().completed.iter().find(|report| report.target == target).cloned()
+    }
+
+    /// URL是否在中断前已检查过
+    pub fn was_checked(&self, url: &str) -> bool {
+        self.resumed_urls.contains(url)
+    }
+
+    /// 已完成的目标数、已检查的URL数和已保存的发现数
+    ///
+    /// 已检查的URL数包括已完成目标的URL；旧版本的状态文件没有调度进度，只能统计未完成目标的URL
+    pub fn progress(&self) -> (usize, usize, usize) {
+        let state = self.lock();
+        (state.completed.len(), state.progress.checked.max(state.checked_urls.len()), state.results.len())
+    }
+
+    /// 调度进度
+    pub fn scan_progress(&self) -> ScanProgress {
+        self.lock().progress.clone()
+    }
+
+    /// 记录主机队列中的目标数，重启后队列可能变化(如目标文件更新)，以最新的为准
+    pub fn plan_host(&self, host: &str, targets: usize) {
+        self.lock().progress.hosts.entry(host.to_string()).or_default().targets = targets;
+    }
+
+    /// 已保存的发现
+    pub fn results(&self) -> Vec<ScanResult> {
+        self.lock().results.clone()
+    }
+
+    /// 记录已检查的URL，距上次保存超过间隔时写入文件
+    pub fn record_url(&self, url: String) {
+        {
+            let mut state = self.lock();
+            let host = host_key(&url);
+            if state.checked_urls.insert(url) {
+                state.progress.checked += 1;
+                state.progress.hosts.entry(host).or_default().checked += 1;
+            }
+        }
+        let due = {
+            let mut last_saved = self.last_saved.lock().unwrap_or_else(|e| e.into_inner());
+            let due = last_saved.elapsed() >= SAVE_INTERVAL;
+            if due {
+                *last_saved = Instant::now();
+            }
+            due
+        };
+        if due {
+            self.save_logged();
+        }
+    }
+
+    /// 记录发现并立即保存
+    pub fn record_result(&self, result: &ScanResult) {
+        {
+            let mut state = self.lock();
+            if !state.results.iter().any(|saved| saved.url == result.url && saved.method == result.method) {
+                state.results.push(result.clone());
+                state.progress.findings += 1;
+                state.progress.hosts.entry(host_key(&result.url)).or_default().findings += 1;
+            }
+        }
+        self.save_logged();
+    }
+
+    /// 记录完成的目标并立即保存，该目标下已检查的URL不再需要
+    pub fn complete_target(&self, report: &TargetReport) {
+        {
+            let mut state = self.lock();
+            let base = report.scanned_url.as_deref().unwrap_or(&report.target).trim_end_matches('/').to_string();
+            state.checked_urls.retain(|url| !url.starts_with(&format!("{}/", base)));
+            if !state.completed.iter().any(|saved| saved.target == report.target) {
+                state.progress.hosts.entry(host_key(&report.target)).or_default().completed += 1;
+            }
+            state.completed.retain(|saved| saved.target != report.target);
+            state.completed.push(report.clone());
+        }
+        self.save_logged();
+    }
+
+    /// 写入状态文件，先写临时文件再重命名，中断时不会留下损坏的文件
+    pub fn save(&self) -> Result<()> {
+        let content = serde_json::to_string(&*self.lock())?;
+        let staged = self.path.with_extension("tmp");
+        fs::write(&staged, content)?;
+        fs::rename(&staged, &self.path)?;
+        Ok(())
+    }
+
+    /// 扫描全部完成后删除状态文件
+    pub fn remove(&self) -> Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn save_logged(&self) {
+        if let Err(e) = self.save() {
+            debug!("保存状态文件 {} 失败: {:?}", self.path.display(), e);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CheckpointState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,662 @@
+use crate::patterns::PatternGenerator;
+use crate::redact::save_redacted_results;
+use crate::scanner::Scanner;
+use crate::utils::{display_url, save_results};
+use crate::watch::SaveOptions;
+use crate::{BackerError, Coverage, ErrorPhase, Result, ResultExt, ScanResult, Target, TargetReport};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// 每个分片默认包含的候选数
+pub const DEFAULT_SHARD_SIZE: usize = 5000;
+
+/// 默认的分片租约时长，工作节点在此期间没有心跳时分片重新分配
+pub const DEFAULT_LEASE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// 协调节点默认监听地址
+pub const DEFAULT_COORDINATOR_LISTEN: &str = "0.0.0.0:7878";
+
+/// 分片最多分配的次数，超过后记为失败
+const MAX_SHARD_ATTEMPTS: u32 = 3;
+
+/// 请求头的最大读取大小(字节)
+const MAX_HEADER_SIZE: usize = 8192;
+
+/// 请求体的最大大小(字节)，工作节点提交的结果不应超过
+const MAX_BODY_SIZE: usize = 256 * 1024 * 1024;
+
+/// 暂无可分配分片时工作节点的等待时间
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 扫描完成后协调节点继续应答的时间，让轮询中的工作节点收到结束通知
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
+/// 工作节点连续无法连接协调节点的最大次数
+const MAX_CONNECT_FAILURES: u32 = 12;
+
+/// 分配给工作节点的分片：一个目标按模式生成顺序的第 `start..end` 个候选
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShardJob {
+    /// 分片编号
+    pub id: u64,
+    /// 扫描目标
+    pub target: Target,
+    /// 候选区间起点(含)
+    pub start: usize,
+    /// 候选区间终点(不含)
+    pub end: usize,
+    /// 租约时长(秒)，工作节点需在此期间内发送心跳
+    pub lease_secs: u64,
+}
+
+impl ShardJob {
+    /// 单行描述
+    pub fn describe(&self) -> String {
+        format!("#{} {} [{}..{})", self.id, display_url(&self.target.url), self.start, self.end)
+    }
+}
+
+/// 工作节点的请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerRequest {
+    /// 工作节点名称
+    pub worker: String,
+    /// 扫描计划指纹，见 [`plan_fingerprint`]
+    #[serde(default)]
+    pub plan: String,
+    /// 分片编号，申请分片时为空
+    #[serde(default)]
+    pub job: Option<u64>,
+    /// 分片失败的原因
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// 工作节点提交的分片结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShardOutcome {
+    /// 工作节点名称
+    pub worker: String,
+    /// 分片编号
+    pub job: u64,
+    /// 发现
+    pub results: Vec<ScanResult>,
+    /// 目标报告
+    pub reports: Vec<TargetReport>,
+}
+
+/// 扫描计划指纹：模式和目录组合深度决定候选的生成顺序，协调节点和工作节点必须一致
+pub fn plan_fingerprint(patterns: &[String], dir_depth: usize) -> String {
+    let mut hasher = Sha256::new();
+    for pattern in patterns {
+        hasher.update(pattern.as_bytes());
+        hasher.update(b"\n");
+    }
+    hasher.update(dir_depth.to_le_bytes());
+    hasher.finalize().iter().take(8).map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// 协调节点选项
+#[derive(Debug, Clone)]
+pub struct CoordinatorOptions {
+    /// 监听地址
+    pub listen: String,
+    /// 每个分片的候选数
+    pub shard_size: usize,
+    /// 分片租约时长
+    pub lease_timeout: Duration,
+    /// 工作节点需携带的访问令牌
+    pub token: Option<String>,
+    /// 模式文件的各行
+    pub patterns: Vec<String>,
+    /// 备份目录组合深度
+    pub dir_depth: usize,
+    /// 结果保存选项，全部分片完成后写入
+    pub save: SaveOptions,
+}
+
+/// 工作节点选项
+#[derive(Debug, Clone)]
+pub struct WorkerOptions {
+    /// 协调节点地址，如 `http://10.0.0.1:7878`
+    pub coordinator: String,
+    /// 工作节点名称
+    pub name: String,
+    /// 访问令牌
+    pub token: Option<String>,
+    /// 本节点的扫描计划指纹
+    pub plan: String,
+}
+
+/// 分片状态
+#[derive(Debug, Clone)]
+enum ShardState {
+    Pending,
+    Leased { worker: String, until: Instant },
+    Done,
+    Failed,
+}
+
+/// 分片及其分配情况
+struct Shard {
+    job: ShardJob,
+    state: ShardState,
+    attempts: u32,
+    errors: Vec<String>,
+}
+
+/// 协调节点的共享状态
+struct Queue {
+    plan: String,
+    shards: Vec<Shard>,
+    results: Vec<ScanResult>,
+    seen: HashSet<String>,
+    reports: Vec<TargetReport>,
+    workers: HashMap<String, Instant>,
+    lease_timeout: Duration,
+}
+
+impl Queue {
+    /// 按目标的预计候选数切分分片
+    fn new(targets: Vec<Target>, per_target: usize, options: &CoordinatorOptions) -> Self {
+        let shard_size = options.shard_size.max(1);
+        let mut shards = Vec::new();
+        for target in targets {
+            let mut start: usize = 0;
+            loop {
+                let end = start.saturating_add(shard_size);
+                shards.push(Shard {
+                    job: ShardJob {
+                        id: shards.len() as u64,
+                        target: target.clone(),
+                        start,
+                        end,
+                        lease_secs: options.lease_timeout.as_secs(),
+                    },
+                    state: ShardState::Pending,
+                    attempts: 0,
+                    errors: Vec::new(),
+                });
+                if end >= per_target {
+                    break;
+                }
+                start = end;
+            }
+        }
+        Self {
+            plan: plan_fingerprint(&options.patterns, options.dir_depth),
+            shards,
+            results: Vec::new(),
+            seen: HashSet::new(),
+            reports: Vec::new(),
+            workers: HashMap::new(),
+            lease_timeout: options.lease_timeout,
+        }
+    }
+
+    /// 所有分片都已完成或失败
+    fn finished(&self) -> bool {
+        self.shards.iter().all(|shard| matches!(shard.state, ShardState::Done | ShardState::Failed))
+    }
+
+    /// 已完成、失败和执行中的分片数
+    fn progress(&self) -> (usize, usize, usize) {
+        self.shards.iter().fold((0, 0, 0), |(done, failed, leased), shard| match shard.state {
+            ShardState::Done => (done + 1, failed, leased),
+            ShardState::Failed => (done, failed + 1, leased),
+            ShardState::Leased { .. } => (done, failed, leased + 1),
+            ShardState::Pending => (done, failed, leased),
+        })
+    }
+
+    /// 租约过期的分片重新排队，分配次数用尽时记为失败
+    fn expire_leases(&mut self) {
+        let now = Instant::now();
+        for shard in &mut self.shards {
+            if let ShardState::Leased { worker, until } = &shard.state {
+                if *until <= now {
+                    let error = format!("工作节点 {} 租约过期", worker);
+                    println!("分片 {} {}，重新分配", shard.job.describe(), error);
+                    shard.errors.push(error);
+                    shard.state = ShardState::Pending;
+                }
+            }
+            if matches!(shard.state, ShardState::Pending) && shard.attempts >= MAX_SHARD_ATTEMPTS {
+                shard.state = ShardState::Failed;
+            }
+        }
+    }
+
+    /// 为工作节点分配下一个待扫描的分片
+    fn lease(&mut self, worker: &str) -> Option<ShardJob> {
+        self.workers.insert(worker.to_string(), Instant::now());
+        let until = Instant::now() + self.lease_timeout;
+        let shard = self.shards.iter_mut()
+            .find(|shard| matches!(shard.state, ShardState::Pending) && shard.attempts < MAX_SHARD_ATTEMPTS)?;
+        shard.attempts += 1;
+        shard.state = ShardState::Leased { worker: worker.to_string(), until };
+        debug!("分片 {} 分配给 {}", shard.job.describe(), worker);
+        Some(shard.job.clone())
+    }
+
+    /// 查找租给该工作节点的分片
+    fn leased_to(&mut self, worker: &str, job: u64) -> Option<&mut Shard> {
+        self.workers.insert(worker.to_string(), Instant::now());
+        self.shards.get_mut(job as usize)
+            .filter(|shard| matches!(&shard.state, ShardState::Leased { worker: owner, .. } if owner == worker))
+    }
+
+    /// 延长租约，分片已不属于该工作节点时返回false
+    fn heartbeat(&mut self, worker: &str, job: u64) -> bool {
+        let until = Instant::now() + self.lease_timeout;
+        match self.leased_to(worker, job) {
+            Some(shard) => {
+                shard.state = ShardState::Leased { worker: worker.to_string(), until };
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 工作节点报告分片失败，分片重新排队
+    fn fail(&mut self, worker: &str, job: u64, error: &str) -> bool {
+        match self.leased_to(worker, job) {
+            Some(shard) => {
+                println!("分片 {} 在 {} 上失败: {}", shard.job.describe(), worker, error);
+                shard.errors.push(format!("{}: {}", worker, error));
+                shard.state = ShardState::Pending;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 汇总分片结果；租约过期后重新分配的分片以先提交的结果为准
+    fn complete(&mut self, outcome: ShardOutcome) -> bool {
+        let Some(shard) = self.shards.get_mut(outcome.job as usize) else {
+            return false;
+        };
+        if matches!(shard.state, ShardState::Done) {
+            debug!("分片 {} 已完成，忽略 {} 重复提交的结果", shard.job.describe(), outcome.worker);
+            return false;
+        }
+        shard.state = ShardState::Done;
+        self.workers.insert(outcome.worker.clone(), Instant::now());
+        for result in outcome.results {
+            if self.seen.insert(result.id.clone()) {
+                println!("发现: {} (工作节点 {})", display_url(&result.url), outcome.worker);
+                self.results.push(result);
+            }
+        }
+        for report in outcome.reports {
+            self.merge_report(report);
+        }
+        true
+    }
+
+    /// 合并同一目标各分片的报告
+    fn merge_report(&mut self, report: TargetReport) {
+        let Some(existing) = self.reports.iter_mut().find(|existing| existing.target == report.target) else {
+            self.reports.push(report);
+            return;
+        };
+        for error in report.errors {
+            if !existing.errors.contains(&error) {
+                existing.errors.push(error);
+            }
+        }
+        existing.robots_skipped.extend(report.robots_skipped);
+        existing.size_excluded.extend(report.size_excluded);
+        existing.known.extend(report.known);
+        existing.truncated = existing.truncated.take().or(report.truncated);
+        existing.slow_lane = existing.slow_lane.take().or(report.slow_lane);
+        existing.challenge = existing.challenge.take().or(report.challenge);
+        existing.coverage = match (existing.coverage.take(), report.coverage) {
+            (Some(mut coverage), Some(other)) => {
+                merge_coverage(&mut coverage, other);
+                Some(coverage)
+            }
+            (coverage, other) => coverage.or(other),
+        };
+    }
+
+    /// 失败的分片记入对应目标的报告
+    fn record_failures(&mut self) {
+        let failures: Vec<(String, String)> = self.shards.iter()
+            .filter(|shard| matches!(shard.state, ShardState::Failed))
+            .map(|shard| (shard.job.target.url.clone(), format!("候选 {}..{} 分配 {} 次均未完成: {}",
+                shard.job.start, shard.job.end, shard.attempts, shard.errors.join("; "))))
+            .collect();
+        for (target, error) in failures {
+            match self.reports.iter_mut().find(|report| report.target == target) {
+                Some(report) => report.errors.push(error),
+                None => eprintln!("目标 {} 的分片未完成: {}", target, error),
+            }
+        }
+    }
+}
+
+/// 合并两个分片的覆盖情况，校准状态以先完成的分片为准
+fn merge_coverage(coverage: &mut Coverage, other: Coverage) {
+    coverage.attempted += other.attempted;
+    for (reason, count) in other.skipped {
+        match coverage.skipped.iter_mut().find(|(existing, _)| *existing == reason) {
+            Some((_, total)) => *total += count,
+            None => coverage.skipped.push((reason, count)),
+        }
+    }
+}
+
+/// 运行协调节点：把各目标的候选切分为分片租给工作节点，全部分片完成后保存汇总结果
+///
+/// 协调节点本身不发送扫描请求。工作节点超过租约时长没有心跳或报告失败时，分片重新分配给
+/// 其他工作节点，最多分配 [`MAX_SHARD_ATTEMPTS`] 次
+pub async fn run_coordinator(targets: Vec<Target>, options: &CoordinatorOptions) -> Result<Vec<ScanResult>> {
+    let generator = PatternGenerator::from_patterns(&options.patterns, options.dir_depth);
+    let per_target = generator.estimate_candidates();
+    let total_targets = targets.len();
+    let queue = Queue::new(targets, per_target, options);
+    let total_shards = queue.shards.len();
+    let plan = queue.plan.clone();
+    let queue = Arc::new(Mutex::new(queue));
+
+    let listener = TcpListener::bind(&options.listen).await
+        .map_err(|e| BackerError::Other(format!("无法监听 {}: {}", options.listen, e)))
+        .during(ErrorPhase::Setup)?;
+    println!("协调节点已启动: http://{}/ (计划 {})", listener.local_addr().during(ErrorPhase::Setup)?, plan);
+    println!("  {} 个目标，每个目标最多约 {} 个候选，共 {} 个分片 (每片 {} 个候选，租约 {} 秒)",
+        total_targets, per_target, total_shards, options.shard_size.max(1), options.lease_timeout.as_secs());
+
+    let token = options.token.clone();
+    let server = {
+        let queue = queue.clone();
+        tokio::spawn(async move {
+            loop {
+                let (stream, peer) = match listener.accept().await {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        debug!("接受连接失败: {}", e);
+                        continue;
+                    }
+                };
+                let queue = queue.clone();
+                let token = token.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream, &queue, token.as_deref()).await {
+                        debug!("处理来自 {} 的请求失败: {:?}", peer, e);
+                    }
+                });
+            }
+        })
+    };
+
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    let mut last_progress = (0, 0, 0);
+    loop {
+        {
+            let mut queue = queue.lock().unwrap();
+            queue.expire_leases();
+            let progress = queue.progress();
+            if progress != last_progress {
+                last_progress = progress;
+                println!("进度: {}/{} 个分片完成，{} 个执行中，{} 个失败，{} 个工作节点，{} 个发现",
+                    progress.0, total_shards, progress.2, progress.1, queue.workers.len(), queue.results.len());
+            }
+            if queue.finished() {
+                break;
+            }
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(1)) => {},
+            _ = &mut ctrl_c => {
+                println!("协调节点已中断，保存已汇总的结果");
+                break;
+            }
+        }
+    }
+
+    // 继续应答一段时间，轮询中的工作节点收到结束通知后退出
+    if queue.lock().unwrap().finished() {
+        tokio::time::sleep(SHUTDOWN_GRACE).await;
+    }
+    server.abort();
+
+    let (results, reports) = {
+        let mut queue = queue.lock().unwrap();
+        queue.record_failures();
+        (std::mem::take(&mut queue.results), std::mem::take(&mut queue.reports))
+    };
+    println!("分布式扫描完成: {} 个发现", results.len());
+    save(&options.save, &results, &reports).during(ErrorPhase::Output)?;
+    Ok(results)
+}
+
+/// 保存汇总结果，按选项脱敏并签名
+fn save(options: &SaveOptions, results: &[ScanResult], reports: &[TargetReport]) -> Result<()> {
+    if options.redact {
+        save_redacted_results(results, reports, options.format, options.output.as_deref(), options.unredacted_output.as_deref())?;
+    } else {
+        save_results(results, reports, options.format, options.output.as_ref())?;
+    }
+    if let (Some(signer), Some(output)) = (&options.signer, &options.output) {
+        if !results.is_empty() {
+            signer.sign_file(output).at_path(ErrorPhase::Output, output)?;
+        }
+    }
+    Ok(())
+}
+
+/// 处理工作节点的单个请求，响应后关闭连接
+async fn handle_connection(mut stream: TcpStream, queue: &Mutex<Queue>, token: Option<&str>) -> Result<()> {
+    let (method, path, authorization, body) = read_request(&mut stream).await?;
+
+    let (status, body) = if token.is_some_and(|token| authorization.as_deref() != Some(&format!("Bearer {}", token))) {
+        ("401 Unauthorized", "访问令牌无效".to_string())
+    } else if method != "POST" {
+        ("405 Method Not Allowed", "仅支持POST请求".to_string())
+    } else if path == "/complete" {
+        match serde_json::from_slice::<ShardOutcome>(&body) {
+            Ok(outcome) => {
+                queue.lock().unwrap().complete(outcome);
+                ("200 OK", String::new())
+            }
+            Err(e) => ("400 Bad Request", format!("无效的结果: {}", e)),
+        }
+    } else {
+        match serde_json::from_slice::<WorkerRequest>(&body) {
+            Ok(request) => respond(&mut queue.lock().unwrap(), &path, &request)?,
+            Err(e) => ("400 Bad Request", format!("无效的请求: {}", e)),
+        }
+    };
+    debug!("{} {} -> {}", method, path, status);
+
+    let content_type = if status.starts_with("200") { "application/json" } else { "text/plain; charset=utf-8" };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// 处理分片申请、心跳和失败报告
+fn respond(queue: &mut Queue, path: &str, request: &WorkerRequest) -> Result<(&'static str, String)> {
+    Ok(match (path, request.job) {
+        ("/lease", _) if request.plan != queue.plan => ("409 Conflict",
+            format!("扫描计划不一致 (协调节点 {}，工作节点 {})，请使用相同的模式文件和 --dir-depth", queue.plan, request.plan)),
+        ("/lease", _) => match queue.lease(&request.worker) {
+            Some(job) => ("200 OK", serde_json::to_string(&job)?),
+            None if queue.finished() => ("410 Gone", "扫描已完成".to_string()),
+            None => ("204 No Content", String::new()),
+        },
+        ("/heartbeat", Some(job)) if queue.heartbeat(&request.worker, job) => ("200 OK", String::new()),
+        ("/fail", Some(job)) if queue.fail(&request.worker, job, request.error.as_deref().unwrap_or("-")) => ("200 OK", String::new()),
+        ("/heartbeat" | "/fail", _) => ("410 Gone", "分片已不属于该工作节点".to_string()),
+        _ => ("404 Not Found", "未找到".to_string()),
+    })
+}
+
+/// 读取请求行、Authorization请求头和按Content-Length读取的请求体
+async fn read_request(stream: &mut TcpStream) -> Result<(String, String, Option<String>, Vec<u8>)> {
+    let mut buffer = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 8192];
+    let header_end = loop {
+        if let Some(position) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break position + 4;
+        }
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 || buffer.len() + n > MAX_HEADER_SIZE + chunk.len() {
+            return Err(BackerError::Other("请求头不完整或过大".to_string()));
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = head.lines();
+    let mut parts = lines.next().unwrap_or("").split_whitespace();
+    let method = parts.next().unwrap_or("").to_string();
+    let path = parts.next().unwrap_or("/").to_string();
+    let mut content_length = 0;
+    let mut authorization = None;
+    for line in lines {
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            } else if name.eq_ignore_ascii_case("authorization") {
+                authorization = Some(value.trim().to_string());
+            }
+        }
+    }
+    if content_length > MAX_BODY_SIZE {
+        return Err(BackerError::Other(format!("请求体过大: {} 字节", content_length)));
+    }
+
+    let mut body = buffer.split_off(header_end);
+    while body.len() < content_length {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..n]);
+    }
+    body.truncate(content_length);
+    Ok((method, path, authorization, body))
+}
+
+/// 运行工作节点：从协调节点申请分片并扫描，提交结果后继续申请，协调节点通知扫描完成时退出
+///
+/// 扫描期间按租约时长的三分之一发送心跳；分片扫描出错时报告失败，由协调节点重新分配
+pub async fn run_worker(scanner: &mut Scanner, options: &WorkerOptions) -> Result<()> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(60))
+        .build()
+        .during(ErrorPhase::Setup)?;
+    let base = options.coordinator.trim_end_matches('/').to_string();
+    let post = |path: &str| {
+        let request = client.post(format!("{}{}", base, path));
+        match &options.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    };
+    let request = |job: Option<u64>, error: Option<String>| WorkerRequest {
+        worker: options.name.clone(),
+        plan: options.plan.clone(),
+        job,
+        error,
+    };
+
+    println!("工作节点 {} 已启动，协调节点 {} (计划 {})", options.name, base, options.plan);
+    let mut completed = 0;
+    let mut failures = 0;
+    loop {
+        let response = match post("/lease").json(&request(None, None)).send().await {
+            Ok(response) => response,
+            Err(e) => {
+                failures += 1;
+                if failures >= MAX_CONNECT_FAILURES {
+                    return Err(BackerError::Other(format!("无法连接协调节点 {}: {}", base, e)));
+                }
+                debug!("连接协调节点失败: {}，稍后重试", e);
+                tokio::time::sleep(POLL_INTERVAL).await;
+                continue;
+            }
+        };
+        failures = 0;
+        match response.status().as_u16() {
+            200 => {}
+            204 => {
+                tokio::time::sleep(POLL_INTERVAL).await;
+                continue;
+            }
+            410 => break,
+            status => {
+                let message = response.text().await.unwrap_or_default();
+                return Err(BackerError::Config(format!("协调节点拒绝请求 ({}): {}", status, message)));
+            }
+        }
+        let job: ShardJob = response.json().await.at_url(ErrorPhase::Load, &format!("{}/lease", base))?;
+        println!("扫描分片 {}", job.describe());
+
+        // 扫描期间定期发送心跳，分片被重新分配后心跳失败，结果仍然提交，由协调节点取舍
+        let heartbeat = {
+            let client = post("/heartbeat").json(&request(Some(job.id), None));
+            let interval = Duration::from_secs((job.lease_secs / 3).max(1));
+            let request = client.try_clone();
+            tokio::spawn(async move {
+                let Some(request) = request else { return };
+                loop {
+                    tokio::time::sleep(interval).await;
+                    let Some(request) = request.try_clone() else { return };
+                    match request.send().await {
+                        Ok(response) if response.status().as_u16() == 410 => {
+                            debug!("分片已被重新分配");
+                            return;
+                        }
+                        Ok(_) => {}
+                        Err(e) => debug!("发送心跳失败: {}", e),
+                    }
+                }
+            })
+        };
+
+        scanner.set_candidate_shard(Some(job.start..job.end));
+        let scanned = scanner.scan_targets(vec![job.target.clone()]).await;
+        heartbeat.abort();
+        match scanned {
+            Ok(results) => {
+                let outcome = ShardOutcome {
+                    worker: options.name.clone(),
+                    job: job.id,
+                    results,
+                    reports: scanner.get_target_reports(),
+                };
+                post("/complete").json(&outcome).send().await
+                    .and_then(|response| response.error_for_status())
+                    .at_url(ErrorPhase::Publish, &format!("{}/complete", base))?;
+                completed += 1;
+            }
+            Err(e) => {
+                let e = e.annotate(None, Some(&job.target.url), None);
+                eprintln!("分片 {} 扫描失败: {}", job.describe(), e);
+                post("/fail").json(&request(Some(job.id), Some(e.to_string()))).send().await
+                    .at_url(ErrorPhase::Publish, &format!("{}/fail", base))?;
+            }
+        }
+    }
+
+    scanner.set_candidate_shard(None);
+    println!("协调节点通知扫描已完成，本节点完成 {} 个分片", completed);
+    Ok(())
+}
@@ -0,0 +1,253 @@
+use crate::{BackerError, OutputFormat, Result};
+use crate::category::Category;
+use crate::http::{IpVersion, RequestTemplate, UaClass, UaStrategy};
+use crate::notify::NotifyConfig;
+use crate::preset::TargetType;
+use crate::proxy::ProxyRotation;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 未指定配置档案时使用的档案名
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// TOML配置文件
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ConfigFile {
+    /// 命名配置档案，如 `[profile.quick]`
+    #[serde(default)]
+    pub profile: HashMap<String, Profile>,
+    /// 按严重程度路由的通知渠道，所有档案共用
+    #[serde(default)]
+    pub notify: NotifyConfig,
+}
+
+/// 单个配置档案
+///
+/// 键名与命令行长选项相同(连字符换成下划线)，未设置的键使用命令行默认值；
+/// 命令行中显式指定的选项优先于档案中的值
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// 自定义备份文件模式列表
+    pub patterns: Option<PathBuf>,
+    /// 目标标签映射文件
+    pub tags: Option<PathBuf>,
+    /// 并发线程数量
+    pub threads: Option<usize>,
+    /// 请求超时时间(秒)
+    pub timeout: Option<u64>,
+    /// 请求失败重试次数
+    pub retry: Option<u32>,
+    /// 自定义User-Agent
+    pub user_agent: Option<String>,
+    /// User-Agent列表文件
+    pub user_agents_file: Option<PathBuf>,
+    /// User-Agent类别
+    pub ua_class: Option<UaClass>,
+    /// User-Agent轮换策略
+    pub ua_strategy: Option<UaStrategy>,
+    /// 授权扫描的标识请求头
+    pub identify: Option<String>,
+    /// 连接目标使用的IP协议版本，`"4"`、`"6"` 或 `"auto"`
+    pub ip_version: Option<IpVersion>,
+    /// 是否在扫描前预解析目标主机
+    pub dns_prefetch: Option<bool>,
+    /// 固定解析
+    pub resolve: Option<Vec<String>>,
+    /// 代理地址
+    pub proxy: Option<String>,
+    /// 代理列表文件
+    pub proxy_list: Option<PathBuf>,
+    /// 代理列表的轮换策略，`"round-robin"` 或 `"random"`
+    pub proxy_rotation: Option<ProxyRotation>,
+    /// 代理连续连接失败多少次后停用
+    pub proxy_max_failures: Option<u32>,
+    /// 是否使用随机请求头
+    pub random_headers: Option<bool>,
+    /// 伪装来源IP: `random`、固定值或文件
+    pub spoof_ip: Option<String>,
+    /// 伪装IP使用的请求头
+    pub spoof_headers: Option<Vec<String>>,
+    /// 输出格式
+    pub format: Option<OutputFormat>,
+    /// 结果输出文件
+    pub output: Option<PathBuf>,
+    /// 输出脱敏后的结果
+    pub redact: Option<bool>,
+    /// 脱敏时另存未脱敏结果的文件
+    pub unredacted_output: Option<PathBuf>,
+    /// 为结果签名的ed25519私钥文件
+    pub signing_key: Option<PathBuf>,
+    /// 扫描前导出的流量说明文件
+    pub noise_report: Option<PathBuf>,
+    /// 验证文件内容
+    pub verify: Option<bool>,
+    /// 发现的最小文件大小(字节)
+    pub min_size: Option<u64>,
+    /// 发现的最大文件大小(字节)
+    pub max_size: Option<u64>,
+    /// 内存解压扫描的最大压缩包大小(字节)
+    pub archive_scan_max_size: Option<u64>,
+    /// 下载 .git/index 重建文件列表
+    pub git_index: Option<bool>,
+    /// 内存解压读取的最大字节数
+    pub max_decompressed_size: Option<u64>,
+    /// 最大解压比
+    pub max_compression_ratio: Option<u64>,
+    /// soft-404校准请求数
+    pub soft_404_probes: Option<usize>,
+    /// 报告发现前复查
+    pub double_check: Option<bool>,
+    /// 对403结果尝试绕过
+    pub bypass_403: Option<bool>,
+    /// 是否检测质询页并跳过受保护的主机
+    pub challenge_detection: Option<bool>,
+    /// 是否在扫描内缓存响应
+    pub response_cache: Option<bool>,
+    /// 随机数种子
+    pub seed: Option<u64>,
+    /// 从证书透明度日志扩展子域名
+    pub ct_subdomains: Option<bool>,
+    /// 反查同IP站点
+    pub reverse_ip: Option<bool>,
+    /// 将同IP站点加入扫描目标
+    pub expand_neighbors: Option<bool>,
+    /// 通过favicon识别技术栈
+    pub favicon: Option<bool>,
+    /// 补充的favicon哈希库
+    pub favicon_db: Option<PathBuf>,
+    /// 识别CMS和框架
+    pub cms_fingerprint: Option<bool>,
+    /// 额外启用的模式分类
+    pub categories: Option<Vec<Category>>,
+    /// 单个目标按模式分类的请求预算，如 "archive=500,vcs=50"
+    pub budget: Option<String>,
+    /// 目标类型预设
+    pub target_type: Option<TargetType>,
+    /// SecLists目录
+    pub seclists: Option<PathBuf>,
+    /// 范围排除列表
+    pub scope_exclude: Option<PathBuf>,
+    /// 备份目录组合深度，0表示不生成目录候选
+    pub dir_depth: Option<usize>,
+    /// 遵守robots.txt
+    pub respect_robots: Option<bool>,
+    /// 实时发布发现的消息总线
+    pub sink: Option<String>,
+    /// Elasticsearch/OpenSearch地址
+    pub es_url: Option<String>,
+    /// 写入的Elasticsearch索引名，默认 `backer-findings`
+    pub es_index: Option<String>,
+    /// Telegram机器人令牌
+    pub telegram_token: Option<String>,
+    /// 接收Telegram通知的聊天ID
+    pub telegram_chat_id: Option<String>,
+    /// 主机指纹缓存文件
+    pub fingerprint_cache: Option<PathBuf>,
+    /// 忽略列表文件
+    pub ignore_list: Option<PathBuf>,
+    /// 基线结果文件
+    pub baseline: Option<PathBuf>,
+    /// Lua脚本文件
+    pub script: Option<PathBuf>,
+    /// 分阶段输出目录
+    pub stage_dir: Option<PathBuf>,
+    /// 模式统计文件
+    pub pattern_stats: Option<PathBuf>,
+    /// 模式效果报告文件
+    pub pattern_report: Option<PathBuf>,
+    /// 单个目标的确认发现达到该数量后跳过其剩余候选
+    pub stop_after_hits: Option<usize>,
+    /// 所有目标的发现上限
+    pub max_findings: Option<usize>,
+    /// 单个主机的发现上限
+    pub max_findings_per_host: Option<usize>,
+    /// 同时进行中的请求数上限
+    pub max_connections: Option<usize>,
+    /// 启动爬坡时长(秒)
+    pub ramp_up: Option<u64>,
+    /// 慢速主机阈值(毫秒)
+    pub slow_host_threshold: Option<u64>,
+    /// 慢速队列的并发线程数
+    pub slow_lane_threads: Option<usize>,
+    /// 发现必须匹配的内容类型
+    pub require_content_type: Option<Vec<String>>,
+    /// 排除的内容类型
+    pub exclude_content_type: Option<Vec<String>>,
+    /// 下载确认发现的保存目录
+    pub download_dir: Option<PathBuf>,
+    /// 单个下载文件的大小上限(字节)
+    pub download_max_size: Option<u64>,
+    /// 同时进行的下载数
+    pub download_concurrency: Option<usize>,
+    /// 加密保存下载文件的age接收方公钥
+    pub download_encrypt_to: Option<Vec<String>>,
+    /// 下载文件的保留天数
+    pub download_retention_days: Option<u64>,
+    /// 请求模板
+    #[serde(default)]
+    pub request: Option<RequestTemplate>,
+}
+
+impl Profile {
+    /// 将相对路径解析为相对于配置文件所在目录，团队共享的配置文件在任意工作目录下都指向同一组文件
+    fn resolve_paths(&mut self, base: &Path) {
+        let paths = [
+            &mut self.patterns,
+            &mut self.tags,
+            &mut self.user_agents_file,
+            &mut self.output,
+            &mut self.unredacted_output,
+            &mut self.signing_key,
+            &mut self.noise_report,
+            &mut self.favicon_db,
+            &mut self.seclists,
+            &mut self.scope_exclude,
+            &mut self.fingerprint_cache,
+            &mut self.ignore_list,
+            &mut self.baseline,
+            &mut self.script,
+            &mut self.stage_dir,
+            &mut self.pattern_stats,
+            &mut self.pattern_report,
+            &mut self.download_dir,
+            &mut self.proxy_list,
+        ];
+        for path in paths.into_iter().flatten() {
+            if path.is_relative() {
+                *path = base.join(&*path);
+            }
+        }
+    }
+}
+
+impl ConfigFile {
+    /// 获取指定名称的配置档案
+    ///
+    /// 未指定名称时使用 `default` 档案，不存在则返回空档案；
+    /// 显式指定的档案不存在时返回错误
+    pub fn profile(&self, name: Option<&str>) -> Result<Profile> {
+        match name {
+            Some(name) => self.profile.get(name).cloned().ok_or_else(|| {
+                BackerError::Config(format!("配置文件中不存在档案: {}", name))
+            }),
+            None => Ok(self.profile.get(DEFAULT_PROFILE).cloned().unwrap_or_default()),
+        }
+    }
+}
+
+/// 加载TOML配置文件，档案中的相对路径相对于配置文件所在目录
+pub fn load_config<P: AsRef<Path>>(path: P) -> Result<ConfigFile> {
+    let path = path.as_ref();
+    let content = fs::read_to_string(path)?;
+    let mut config: ConfigFile = toml::from_str(&content)
+        .map_err(|e| BackerError::Config(format!("配置文件解析失败: {}", e)))?;
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+    for profile in config.profile.values_mut() {
+        profile.resolve_paths(base);
+    }
+    config.notify.resolve_paths(base);
+    Ok(config)
+}
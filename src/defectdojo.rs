@@ -0,0 +1,148 @@
+use crate::{ScanResult, Severity};
+use chrono::NaiveDate;
+use serde::Serialize;
+use url::Url;
+
+/// CWE-530: 备份文件暴露给未授权方
+const BACKUP_EXPOSURE_CWE: u32 = 530;
+
+/// 修复建议
+const MITIGATION: &str = "从Web根目录删除备份文件和导出的数据，必要时轮换其中包含的凭据；在Web服务器上禁止访问备份类扩展名。";
+
+/// DefectDojo通用发现导入格式的顶层结构
+///
+/// 在DefectDojo中选择扫描类型 "Generic Findings Import" 导入
+#[derive(Debug, Serialize)]
+pub struct DojoReport {
+    pub findings: Vec<DojoFinding>,
+}
+
+/// 单个发现
+#[derive(Debug, Serialize)]
+pub struct DojoFinding {
+    pub title: String,
+    pub description: String,
+    /// Info、Low、Medium、High 或 Critical
+    pub severity: &'static str,
+    pub date: String,
+    pub cwe: u32,
+    pub mitigation: &'static str,
+    pub active: bool,
+    pub verified: bool,
+    pub static_finding: bool,
+    pub dynamic_finding: bool,
+    /// 稳定的发现ID，DefectDojo按此去重
+    pub unique_id_from_tool: String,
+    pub vuln_id_from_tool: &'static str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    pub endpoints: Vec<DojoEndpoint>,
+}
+
+/// 发现所在的端点
+#[derive(Debug, Serialize)]
+pub struct DojoEndpoint {
+    pub protocol: String,
+    pub host: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    pub path: String,
+}
+
+/// 将扫描结果转换为DefectDojo导入格式，`date` 为扫描日期
+pub fn to_dojo_report(results: &[ScanResult], date: NaiveDate) -> DojoReport {
+    let findings = results.iter()
+        .map(|result| to_dojo_finding(result, date))
+        .collect();
+    DojoReport { findings }
+}
+
+fn to_dojo_finding(result: &ScanResult, date: NaiveDate) -> DojoFinding {
+    let file_name = result.url.rsplit('/').next().unwrap_or(&result.url);
+    let title = match result.kind() {
+        "restricted" => format!("受限的备份文件: {}", file_name),
+        "suspicious" => format!("可疑响应: {}", file_name),
+        _ => format!("暴露的备份文件: {}", file_name),
+    };
+
+    let mut tags = vec![result.kind().to_string()];
+    tags.extend(result.tag.clone());
+
+    DojoFinding {
+        title,
+        description: describe(result),
+        severity: dojo_severity(result.severity()),
+        date: date.format("%Y-%m-%d").to_string(),
+        cwe: BACKUP_EXPOSURE_CWE,
+        mitigation: MITIGATION,
+        active: true,
+        verified: result.verified,
+        static_finding: false,
+        dynamic_finding: true,
+        unique_id_from_tool: if result.id.is_empty() { result.finding_id() } else { result.id.clone() },
+        vuln_id_from_tool: "backer-backup-file",
+        tags,
+        endpoints: Url::parse(&result.url).ok().map(|url| DojoEndpoint {
+            protocol: url.scheme().to_string(),
+            host: url.host_str().unwrap_or("").to_string(),
+            port: url.port_or_known_default(),
+            path: url.path().to_string(),
+        }).into_iter().collect(),
+    }
+}
+
+/// DefectDojo的严重程度名称
+fn dojo_severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Info => "Info",
+        Severity::Low => "Low",
+        Severity::Medium => "Medium",
+        Severity::High => "High",
+        Severity::Critical => "Critical",
+    }
+}
+
+/// Markdown格式的发现详情
+fn describe(result: &ScanResult) -> String {
+    let mut lines = vec![
+        format!("**URL:** {}", result.url),
+        format!("**状态码:** {}", result.status_code),
+    ];
+    if let Some(content_type) = &result.content_type {
+        lines.push(format!("**内容类型:** {}", content_type));
+    }
+    if result.content_type_mismatch {
+        lines.push("**内容类型与扩展名不符:** 是".to_string());
+    }
+    if let Some(length) = result.content_length {
+        lines.push(format!("**内容长度:** {} 字节", length));
+    }
+    if let Some(reason) = &result.suspicious {
+        lines.push(format!("**可疑原因:** {}", reason));
+    }
+    if let Some(note) = &result.verify_note {
+        lines.push(format!("**验证说明:** {}", note));
+    }
+    if let Some(technique) = &result.bypass {
+        lines.push(format!("**403绕过:** {}", technique));
+    }
+    if !result.indicators.is_empty() {
+        lines.push(format!("**压缩包内高价值文件:** {}", result.indicators.join(", ")));
+    }
+    if let Some(summary) = &result.sql_summary {
+        lines.push(format!("**SQL转储:** {}", summary.describe()));
+    }
+    if let Some(git) = &result.git {
+        lines.push(format!("**git仓库:** {}", git.describe()));
+    }
+    if let Some(listing) = &result.listing {
+        lines.push(format!("**文件列表:** {}", listing.describe()));
+    }
+    if let Some(summary) = &result.api_summary {
+        lines.push(format!("**API定义:** {}", summary));
+    }
+    if let Some(source) = &result.source {
+        lines.push(format!("**发现来源:** {}", source));
+    }
+    lines.join("\n\n")
+}
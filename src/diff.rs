@@ -0,0 +1,247 @@
+use crate::report::{escape_html, load_results};
+use crate::utils::{describe_content_type, display_url};
+use crate::{Result, ScanResult};
+use chrono::{DateTime, Local};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// 一次扫描的结果文件
+#[derive(Debug, Clone)]
+pub struct ScanSnapshot {
+    /// 结果文件名
+    pub name: String,
+    /// 扫描时间，取结果文件的修改时间
+    pub scanned_at: DateTime<Local>,
+    /// 扫描结果
+    pub results: Vec<ScanResult>,
+}
+
+impl ScanSnapshot {
+    /// 读取JSON格式的结果文件
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        Ok(Self {
+            name: path.display().to_string(),
+            scanned_at: fs::metadata(path)?.modified()?.into(),
+            results: load_results(path)?,
+        })
+    }
+}
+
+/// 两次扫描之间仍然存在的发现
+#[derive(Debug, Clone)]
+pub struct PersistingFinding {
+    /// 新扫描中的结果
+    pub result: ScanResult,
+    /// 至少已存在的天数，即两次扫描的间隔
+    pub age_days: i64,
+}
+
+/// 两次扫描之间状态码、大小或内容发生变化的发现
+#[derive(Debug, Clone)]
+pub struct ChangedFinding {
+    /// 旧扫描中的结果
+    pub old: ScanResult,
+    /// 新扫描中的结果
+    pub new: ScanResult,
+}
+
+impl ChangedFinding {
+    /// 两个结果不同时视为变化：状态码、内容长度(两边都已知时)或内容哈希(两边都已计算时)
+    fn between(old: &ScanResult, new: &ScanResult) -> Option<Self> {
+        let changed = old.status_code != new.status_code
+            || matches!((old.content_length, new.content_length), (Some(a), Some(b)) if a != b)
+            || matches!((&old.content_hash, &new.content_hash), (Some(a), Some(b)) if a != b);
+        changed.then(|| Self { old: old.clone(), new: new.clone() })
+    }
+
+    /// 变化说明，如 "状态码 403 → 200，大小 120 → 4096"
+    pub fn describe(&self) -> String {
+        let mut changes = Vec::new();
+        if self.old.status_code != self.new.status_code {
+            changes.push(format!("状态码 {} → {}", self.old.status_code, self.new.status_code));
+        }
+        if self.old.content_length != self.new.content_length {
+            let size = |len: Option<u64>| len.map_or("未知".to_string(), |len| len.to_string());
+            changes.push(format!("大小 {} → {}", size(self.old.content_length), size(self.new.content_length)));
+        }
+        if matches!((&self.old.content_hash, &self.new.content_hash), (Some(a), Some(b)) if a != b) {
+            changes.push("内容变化".to_string());
+        }
+        changes.join("，")
+    }
+}
+
+/// 两次扫描结果的比较，发现按ID对应
+#[derive(Debug, Clone)]
+pub struct ScanDiff {
+    /// 旧扫描
+    pub old: ScanSnapshot,
+    /// 新扫描
+    pub new: ScanSnapshot,
+    /// 新出现的发现
+    pub added: Vec<ScanResult>,
+    /// 已修复(新扫描中不再出现)的发现
+    pub fixed: Vec<ScanResult>,
+    /// 状态码、大小或内容发生变化的发现
+    pub changed: Vec<ChangedFinding>,
+    /// 仍然存在且没有变化的发现
+    pub persisting: Vec<PersistingFinding>,
+}
+
+impl ScanDiff {
+    /// 比较两次扫描，各组按严重程度从高到低、再按URL排序
+    ///
+    /// ID包含发现类型，状态码在403与200之间变化时ID也变化，这类ID不同但URL相同的发现
+    /// 同样算作变化，不计为一个新增和一个已修复
+    pub fn compare(old: ScanSnapshot, new: ScanSnapshot) -> Self {
+        let old_by_id: HashMap<&str, &ScanResult> = old.results.iter().map(|r| (r.id.as_str(), r)).collect();
+        let new_by_id: HashMap<&str, &ScanResult> = new.results.iter().map(|r| (r.id.as_str(), r)).collect();
+        let age_days = (new.scanned_at - old.scanned_at).num_days().max(0);
+
+        let mut unmatched_old: HashMap<&str, &ScanResult> = old_by_id.values()
+            .filter(|result| !new_by_id.contains_key(result.id.as_str()))
+            .map(|result| (result.url.as_str(), *result))
+            .collect();
+
+        let mut added = Vec::new();
+        let mut changed = Vec::new();
+        let mut persisting = Vec::new();
+        for result in new_by_id.values() {
+            let previous = old_by_id.get(result.id.as_str()).copied()
+                .or_else(|| unmatched_old.remove(result.url.as_str()));
+            match previous {
+                Some(previous) => match ChangedFinding::between(previous, result) {
+                    Some(change) => changed.push(change),
+                    None => persisting.push(PersistingFinding { result: (*result).clone(), age_days }),
+                },
+                None => added.push((*result).clone()),
+            }
+        }
+        let mut fixed: Vec<ScanResult> = unmatched_old.into_values().cloned().collect();
+
+        added.sort_by(by_severity);
+        fixed.sort_by(by_severity);
+        changed.sort_by(|a, b| by_severity(&a.new, &b.new));
+        persisting.sort_by(|a, b| by_severity(&a.result, &b.result));
+        Self { old, new, added, fixed, changed, persisting }
+    }
+
+    /// 比较摘要，如 "新增 3 个，已修复 1 个，有变化 2 个，仍存在 12 个"
+    pub fn summary(&self) -> String {
+        format!("新增 {} 个，已修复 {} 个，有变化 {} 个，仍存在 {} 个",
+            self.added.len(), self.fixed.len(), self.changed.len(), self.persisting.len())
+    }
+}
+
+fn by_severity(a: &ScanResult, b: &ScanResult) -> std::cmp::Ordering {
+    b.severity().cmp(&a.severity()).then_with(|| a.url.cmp(&b.url))
+}
+
+/// 存在时间的说明，如 "≥ 30 天"
+fn describe_age(days: i64) -> String {
+    if days == 0 { "不足1天".to_string() } else { format!("≥ {} 天", days) }
+}
+
+/// 在控制台输出比较结果
+pub fn print_diff(diff: &ScanDiff) {
+    println!("比较 {} ({}) → {} ({})",
+        diff.old.name, diff.old.scanned_at.format("%Y-%m-%d %H:%M"),
+        diff.new.name, diff.new.scanned_at.format("%Y-%m-%d %H:%M"));
+    println!("{}", diff.summary());
+    for (label, results) in [("新增", &diff.added), ("已修复", &diff.fixed)] {
+        if !results.is_empty() {
+            println!("\n{}:", label);
+            for result in results {
+                println!("  [{}] {} ({})", result.severity().name(), display_url(&result.url), result.status_code);
+            }
+        }
+    }
+    if !diff.changed.is_empty() {
+        println!("\n有变化:");
+        for change in &diff.changed {
+            println!("  [{}] {} ({})", change.new.severity().name(), display_url(&change.new.url), change.describe());
+        }
+    }
+    if !diff.persisting.is_empty() {
+        println!("\n仍存在:");
+        for finding in &diff.persisting {
+            let result = &finding.result;
+            println!("  [{}] {} ({}, {})", result.severity().name(), display_url(&result.url), result.status_code, describe_age(finding.age_days));
+        }
+    }
+}
+
+/// 渲染比较结果的HTML页面，可直接作为附件发送，无需JavaScript
+pub fn render_diff_html(diff: &ScanDiff) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>备份文件暴露面对比</title>\n<style>\n");
+    html.push_str("body{font-family:sans-serif;margin:2em}table{border-collapse:collapse;width:100%;margin-bottom:2em}");
+    html.push_str("th,td{border:1px solid #ccc;padding:4px 8px;text-align:left;font-size:14px}");
+    html.push_str("th{background:#f0f0f0}small{color:#666}.added{color:#b00}.fixed{color:#070}.changed{color:#c50}.persisting{color:#a60}\n");
+    html.push_str("</style>\n</head>\n<body>\n<h1>备份文件暴露面对比</h1>\n");
+    html.push_str(&format!(
+        "<p>旧扫描: <code>{}</code> ({})<br>新扫描: <code>{}</code> ({})</p>\n",
+        escape_html(&diff.old.name), diff.old.scanned_at.format("%Y-%m-%d %H:%M"),
+        escape_html(&diff.new.name), diff.new.scanned_at.format("%Y-%m-%d %H:%M"),
+    ));
+    html.push_str(&format!(
+        "<p><span class=\"added\">新增 {}</span> · <span class=\"fixed\">已修复 {}</span> · <span class=\"changed\">有变化 {}</span> · <span class=\"persisting\">仍存在 {}</span></p>\n",
+        diff.added.len(), diff.fixed.len(), diff.changed.len(), diff.persisting.len(),
+    ));
+
+    // 各组的附加列：变化说明或存在时间
+    let sections = [
+        ("added", "新增", None, diff.added.iter().map(|r| (r, None)).collect::<Vec<_>>()),
+        ("changed", "有变化", Some("变化"), diff.changed.iter().map(|c| (&c.new, Some(c.describe()))).collect()),
+        ("persisting", "仍存在", Some("存在时间"), diff.persisting.iter().map(|f| (&f.result, Some(describe_age(f.age_days)))).collect()),
+        ("fixed", "已修复", None, diff.fixed.iter().map(|r| (r, None)).collect()),
+    ];
+    for (class, title, extra, rows) in sections {
+        html.push_str(&format!("<h2 class=\"{}\">{} ({})</h2>\n", class, title, rows.len()));
+        if rows.is_empty() {
+            html.push_str("<p><small>无</small></p>\n");
+            continue;
+        }
+        html.push_str("<table>\n<tr><th>ID</th><th>严重程度</th><th>URL</th><th>状态码</th><th>内容类型</th><th>内容长度</th><th>标签</th>");
+        if let Some(extra) = extra {
+            html.push_str(&format!("<th>{}</th>", extra));
+        }
+        html.push_str("</tr>\n");
+        for (result, extra) in rows {
+            let mut url = escape_html(&display_url(&result.url));
+            if let Some(summary) = &result.sql_summary {
+                url.push_str(&format!("<br><small>SQL转储: {}</small>", escape_html(&summary.describe())));
+            }
+            if let Some(git) = &result.git {
+                url.push_str(&format!("<br><small>git仓库: {}</small>", escape_html(&git.describe())));
+            }
+            if let Some(listing) = &result.listing {
+                url.push_str(&format!("<br><small>文件列表: {}</small>", escape_html(&listing.describe())));
+            }
+            if !result.indicators.is_empty() {
+                url.push_str(&format!("<br><small>高价值文件: {}</small>", escape_html(&result.indicators.join(", "))));
+            }
+            html.push_str(&format!(
+                "<tr><td><code>{}</code></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>",
+                escape_html(&result.id),
+                result.severity().name(),
+                url,
+                result.status_code,
+                escape_html(&describe_content_type(result)),
+                result.content_length.map_or("未知".to_string(), |len| len.to_string()),
+                escape_html(result.tag.as_deref().unwrap_or("")),
+            ));
+            if let Some(extra) = extra {
+                html.push_str(&format!("<td>{}</td>", escape_html(&extra)));
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table>\n");
+    }
+    html.push_str("<p><small>存在时间按两次结果文件的修改时间计算，为下限。</small></p>\n");
+    html.push_str("</body>\n</html>\n");
+    html
+}
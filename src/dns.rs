@@ -0,0 +1,141 @@
+use crate::http::IpVersion;
+use dashmap::DashMap;
+use futures::stream::{self, StreamExt};
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use std::collections::HashSet;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::net::lookup_host;
+use tokio::time::timeout;
+
+/// 预解析时同时进行的DNS查询数
+pub const PREFETCH_CONCURRENCY: usize = 100;
+
+/// 单个主机的解析超时
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 主机名无法解析的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DnsFailure {
+    /// 域名不存在或解析出错(NXDOMAIN、SERVFAIL等)
+    NotFound,
+    /// 没有所选IP协议版本的地址
+    NoAddress,
+    /// 解析超时
+    Timeout,
+}
+
+impl DnsFailure {
+    /// 说明
+    pub fn describe(&self) -> &'static str {
+        match self {
+            DnsFailure::NotFound => "域名不存在或解析出错",
+            DnsFailure::NoAddress => "没有可用的地址",
+            DnsFailure::Timeout => "解析超时",
+        }
+    }
+}
+
+/// 主机名的解析结果
+#[derive(Debug, Clone)]
+pub enum DnsEntry {
+    /// 解析出的地址(已按IP协议版本过滤)
+    Resolved(Vec<SocketAddr>),
+    /// 无法解析
+    Failed(DnsFailure),
+}
+
+/// 扫描前批量解析的主机地址，作为reqwest的解析器使用，请求时不再逐个解析
+///
+/// `--resolve` 固定的主机优先使用固定地址；未预解析的主机(如重定向到的新域名)回退到系统解析
+#[derive(Debug, Default)]
+pub struct DnsCache {
+    entries: DashMap<String, DnsEntry>,
+    pinned: DashMap<String, Vec<IpAddr>>,
+}
+
+impl DnsCache {
+    /// 已记录的解析结果，固定的地址优先
+    pub fn get(&self, host: &str) -> Option<DnsEntry> {
+        let host = host.to_lowercase();
+        if let Some(addrs) = self.pinned.get(&host) {
+            return Some(DnsEntry::Resolved(addrs.iter().map(|addr| SocketAddr::new(*addr, 0)).collect()));
+        }
+        self.entries.get(&host).map(|entry| entry.clone())
+    }
+
+    /// 固定主机名的连接地址，同一主机可固定多个地址
+    pub fn pin(&self, host: &str, addr: IpAddr) {
+        let mut addrs = self.pinned.entry(host.to_lowercase()).or_default();
+        if !addrs.contains(&addr) {
+            addrs.push(addr);
+        }
+    }
+
+    /// 主机固定的第一个连接地址
+    pub fn pinned(&self, host: &str) -> Option<IpAddr> {
+        self.pinned.get(&host.to_lowercase()).and_then(|addrs| addrs.first().copied())
+    }
+
+    /// 并发解析所有主机并记录结果，IP地址和已解析过的主机跳过，返回无法解析的主机
+    pub async fn prefetch(&self, hosts: impl IntoIterator<Item = String>, ip_version: IpVersion) -> Vec<(String, DnsFailure)> {
+        let hosts: HashSet<String> = hosts.into_iter()
+            .map(|host| host.to_lowercase())
+            .filter(|host| host.trim_matches(|c| c == '[' || c == ']').parse::<IpAddr>().is_err())
+            .filter(|host| !self.entries.contains_key(host) && !self.pinned.contains_key(host))
+            .collect();
+
+        let lookups = stream::iter(hosts)
+            .map(|host| async move {
+                let entry = lookup(&host, ip_version).await;
+                (host, entry)
+            })
+            .buffer_unordered(PREFETCH_CONCURRENCY);
+        let mut failures: Vec<(String, DnsFailure)> = lookups
+            .filter_map(|(host, entry)| async move {
+                let failure = match &entry {
+                    DnsEntry::Failed(failure) => Some((host.clone(), *failure)),
+                    DnsEntry::Resolved(_) => None,
+                };
+                self.entries.insert(host, entry);
+                failure
+            })
+            .collect()
+            .await;
+        failures.sort();
+        failures
+    }
+}
+
+/// 解析单个主机
+async fn lookup(host: &str, ip_version: IpVersion) -> DnsEntry {
+    match timeout(LOOKUP_TIMEOUT, lookup_host((host, 0))).await {
+        Ok(Ok(addrs)) => {
+            let addrs: Vec<SocketAddr> = addrs.filter(|addr| ip_version.accepts(&addr.ip())).collect();
+            if addrs.is_empty() {
+                DnsEntry::Failed(DnsFailure::NoAddress)
+            } else {
+                DnsEntry::Resolved(addrs)
+            }
+        },
+        Ok(Err(_)) => DnsEntry::Failed(DnsFailure::NotFound),
+        Err(_) => DnsEntry::Failed(DnsFailure::Timeout),
+    }
+}
+
+impl Resolve for DnsCache {
+    fn resolve(&self, name: Name) -> Resolving {
+        let cached = match self.get(name.as_str()) {
+            Some(DnsEntry::Resolved(addrs)) => Some(addrs),
+            _ => None,
+        };
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = match cached {
+                Some(addrs) => addrs,
+                None => lookup_host((name.as_str(), 0)).await?.collect(),
+            };
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
@@ -0,0 +1,412 @@
+use crate::http::HttpClient;
+use crate::{BackerError, ErrorPhase, Result, ResultExt, ScanResult};
+use futures::stream::{self, StreamExt};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+use tokio::time::timeout;
+use url::Url;
+
+/// 校验和清单文件名，格式与 `sha256sum` 相同，可用 `sha256sum -c SHA256SUMS` 校验
+pub const MANIFEST_NAME: &str = "SHA256SUMS";
+
+/// 下载过程中连续没有收到数据的最长时间
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 加密文件的扩展名，可用 `age -d -i 私钥文件` 解密
+pub const ENCRYPTED_EXTENSION: &str = "age";
+
+/// 保存目录和子目录的权限，只有当前用户可访问
+#[cfg(unix)]
+const DIR_MODE: u32 = 0o700;
+
+/// 下载文件和清单的权限，只有当前用户可读写，不可执行
+#[cfg(unix)]
+const FILE_MODE: u32 = 0o600;
+
+/// 下载设置
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    /// 保存目录，文件按 `主机/路径` 存放
+    pub dir: PathBuf,
+    /// 单个文件的大小上限(字节)，超过时跳过或中止
+    pub max_size: u64,
+    /// 同时进行的下载数
+    pub concurrency: usize,
+    /// age接收方公钥(`age1...`)，非空时文件加密后保存为 `.age`
+    pub encrypt_to: Vec<String>,
+    /// 保留天数，下载前删除清单中超过该天数的文件
+    pub retention_days: Option<u64>,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("downloads"),
+            max_size: 100 * 1024 * 1024,
+            concurrency: 4,
+            encrypt_to: Vec::new(),
+            retention_days: None,
+        }
+    }
+}
+
+/// 已下载的文件
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadRecord {
+    /// 发现的URL
+    pub url: String,
+    /// 相对于保存目录的路径
+    pub path: PathBuf,
+    /// 文件大小(字节)
+    pub size: u64,
+    /// 保存的文件的SHA-256校验和，加密时为密文的校验和
+    pub sha256: String,
+    /// 是否加密保存
+    pub encrypted: bool,
+}
+
+/// 一次下载的汇总
+#[derive(Debug, Clone, Default)]
+pub struct DownloadSummary {
+    /// 下载成功的文件
+    pub downloaded: Vec<DownloadRecord>,
+    /// 未下载的发现及原因，如超过大小上限
+    pub skipped: Vec<(String, String)>,
+    /// 下载失败的发现及错误
+    pub failed: Vec<(String, String)>,
+    /// 超过保留天数而删除的文件
+    pub expired: Vec<PathBuf>,
+}
+
+/// 将确认的备份文件(2xx)流式下载到本地，并维护校验和清单
+///
+/// 下载的文件是客户的敏感数据：压缩包从不自动解压，目录和文件只有当前用户可访问，
+/// 可选加密保存和按保留天数清理
+pub struct Downloader {
+    client: HttpClient,
+    options: DownloadOptions,
+    recipients: Vec<age::x25519::Recipient>,
+}
+
+impl Downloader {
+    /// 使用扫描器的HTTP客户端创建下载器，下载请求同样受限速和连接数上限约束；接收方公钥无效时返回错误
+    pub fn new(client: HttpClient, options: DownloadOptions) -> Result<Self> {
+        let recipients = options.encrypt_to.iter()
+            .map(|key| age::x25519::Recipient::from_str(key.trim())
+                .map_err(|e| BackerError::Config(format!("age接收方公钥无效 {}: {}", key, e))))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { client, options, recipients })
+    }
+
+    /// 并发下载所有可直接访问的发现，下载完成后更新保存目录中的校验和清单
+    pub async fn download_all(&self, results: &[ScanResult]) -> Result<DownloadSummary> {
+        create_private_dir(&self.options.dir).at_path(ErrorPhase::Download, &self.options.dir)?;
+        let mut summary = DownloadSummary::default();
+        if let Some(days) = self.options.retention_days {
+            summary.expired = self.expire(days)?;
+        }
+
+        // 响应头中的大小已超过上限时不发起下载
+        let mut queue = Vec::new();
+        for result in results.iter().filter(|result| result.kind() == "exposed") {
+            match result.content_length {
+                Some(size) if size > self.options.max_size => summary.skipped.push((
+                    result.url.clone(),
+                    format!("大小 {} 字节超过上限 {} 字节", size, self.options.max_size),
+                )),
+                _ => queue.push(result),
+            }
+        }
+
+        let progress = MultiProgress::new();
+        let outcomes: Vec<(String, Result<DownloadRecord>)> = stream::iter(queue)
+            .map(|result| {
+                let bar = progress.add(ProgressBar::new(0));
+                async move { (result.url.clone(), self.download(result, &bar).await) }
+            })
+            .buffer_unordered(self.options.concurrency.max(1))
+            .collect()
+            .await;
+
+        for (url, outcome) in outcomes {
+            match outcome {
+                Ok(record) => summary.downloaded.push(record),
+                Err(e) => summary.failed.push((url, e.to_string())),
+            }
+        }
+        summary.downloaded.sort_by(|a, b| a.path.cmp(&b.path));
+        self.update_manifest(&summary.downloaded)?;
+        Ok(summary)
+    }
+
+    /// 清单文件路径
+    pub fn manifest_path(&self) -> PathBuf {
+        self.options.dir.join(MANIFEST_NAME)
+    }
+
+    /// 下载单个发现，先写入 `.part` 临时文件，完成后重命名；加密时写入的是age密文
+    async fn download(&self, result: &ScanResult, bar: &ProgressBar) -> Result<DownloadRecord> {
+        let mut relative = local_path(&result.url)
+            .ok_or_else(|| BackerError::Other(format!("无法确定保存路径: {}", result.url)))?;
+        let encrypted = !self.recipients.is_empty();
+        if encrypted {
+            relative = PathBuf::from(format!("{}.{}", relative.display(), ENCRYPTED_EXTENSION));
+        }
+        let path = self.options.dir.join(&relative);
+        if let Some(parent) = path.parent() {
+            create_private_dir(parent).at_path(ErrorPhase::Download, parent)?;
+        }
+
+        // 绕过403的发现从绕过时的URL下载
+        let url = result.bypass_url.as_deref().unwrap_or(&result.url);
+        let (mut response, _slot) = self.client.open_download(url).await?;
+        let total = response.content_length().or(result.content_length);
+        if let Some(total) = total {
+            if total > self.options.max_size {
+                bar.finish_and_clear();
+                return Err(BackerError::Other(format!("大小 {} 字节超过上限 {} 字节", total, self.options.max_size)));
+            }
+        }
+        bar.set_style(progress_style(total.is_some()));
+        bar.set_length(total.unwrap_or(0));
+        bar.set_message(relative.display().to_string());
+
+        let staged = PathBuf::from(format!("{}.part", path.display()));
+        let mut size = 0u64;
+        let streamed: Result<String> = async {
+            let file = HashingWriter::new(create_private_file(&staged).at_path(ErrorPhase::Download, &staged)?);
+            let mut sink = if encrypted {
+                let recipients = self.recipients.iter().map(|recipient| recipient as &dyn age::Recipient);
+                let encryptor = age::Encryptor::with_recipients(recipients)
+                    .map_err(|e| BackerError::Other(format!("无法创建加密器: {}", e)))?;
+                ArtifactSink::Encrypted(encryptor.wrap_output(file).at_path(ErrorPhase::Download, &staged)?)
+            } else {
+                ArtifactSink::Plain(file)
+            };
+            loop {
+                let chunk = timeout(IDLE_TIMEOUT, response.chunk()).await
+                    .map_err(|_| BackerError::Other(format!("{} 秒内没有收到数据", IDLE_TIMEOUT.as_secs())))?
+                    .at_url(ErrorPhase::Download, url)?;
+                let Some(chunk) = chunk else { break };
+                size += chunk.len() as u64;
+                if size > self.options.max_size {
+                    return Err(BackerError::Other(format!("下载超过大小上限 {} 字节", self.options.max_size)));
+                }
+                sink.write_all(&chunk).at_path(ErrorPhase::Download, &staged)?;
+                bar.inc(chunk.len() as u64);
+            }
+            sink.finish().at_path(ErrorPhase::Download, &staged)
+        }.await;
+
+        let sha256 = match streamed {
+            Ok(sha256) => sha256,
+            Err(e) => {
+                let _ = fs::remove_file(&staged);
+                bar.abandon_with_message(format!("{} 失败: {}", relative.display(), e));
+                return Err(e);
+            }
+        };
+        fs::rename(&staged, &path).at_path(ErrorPhase::Download, &path)?;
+        bar.finish_with_message(format!("{} 完成", relative.display()));
+
+        Ok(DownloadRecord {
+            url: result.url.clone(),
+            path: relative,
+            size,
+            sha256,
+            encrypted,
+        })
+    }
+
+    /// 删除清单中修改时间超过保留天数的文件，并从清单中移除；不在清单中的文件不处理
+    fn expire(&self, days: u64) -> Result<Vec<PathBuf>> {
+        let mut entries = self.read_manifest()?;
+        let cutoff = SystemTime::now() - Duration::from_secs(days * 24 * 3600);
+        let mut expired = Vec::new();
+        entries.retain(|file, _| {
+            let path = self.options.dir.join(file);
+            let stale = match fs::metadata(&path).and_then(|metadata| metadata.modified()) {
+                Ok(modified) => modified < cutoff,
+                // 文件已被手动删除
+                Err(e) => e.kind() == io::ErrorKind::NotFound,
+            };
+            if stale && fs::remove_file(&path).is_ok() {
+                expired.push(PathBuf::from(file));
+            }
+            !stale
+        });
+        if !expired.is_empty() {
+            self.write_manifest(&entries)?;
+        }
+        Ok(expired)
+    }
+
+    /// 合并已有清单和本次下载的文件，同一路径以本次为准，按路径排序写回
+    fn update_manifest(&self, records: &[DownloadRecord]) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let mut entries = self.read_manifest()?;
+        for record in records {
+            entries.insert(manifest_name(&record.path), record.sha256.clone());
+        }
+        self.write_manifest(&entries)
+    }
+
+    /// 读取清单，返回 文件名 -> 校验和
+    fn read_manifest(&self) -> Result<BTreeMap<String, String>> {
+        match fs::read_to_string(self.manifest_path()) {
+            Ok(content) => Ok(content.lines()
+                .filter_map(|line| line.split_once("  "))
+                .map(|(hash, file)| (file.to_string(), hash.to_string()))
+                .collect()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(e).at_path(ErrorPhase::Download, &self.manifest_path()),
+        }
+    }
+
+    fn write_manifest(&self, entries: &BTreeMap<String, String>) -> Result<()> {
+        let content: String = entries.iter().map(|(file, hash)| format!("{}  {}\n", hash, file)).collect();
+        let path = self.manifest_path();
+        let staged = path.with_extension("tmp");
+        create_private_file(&staged)
+            .and_then(|mut file| file.write_all(content.as_bytes()))
+            .at_path(ErrorPhase::Download, &staged)?;
+        fs::rename(&staged, &path).at_path(ErrorPhase::Download, &path)
+    }
+}
+
+/// 计算写入内容校验和的文件
+struct HashingWriter {
+    file: File,
+    hasher: Sha256,
+}
+
+impl HashingWriter {
+    fn new(file: File) -> Self {
+        Self { file, hasher: Sha256::new() }
+    }
+
+    /// 刷新到磁盘并返回十六进制校验和
+    fn finish(mut self) -> io::Result<String> {
+        self.file.flush()?;
+        self.file.sync_all()?;
+        Ok(self.hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect())
+    }
+}
+
+impl Write for HashingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.file.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// 下载内容的写入目标，明文或age加密
+enum ArtifactSink {
+    Plain(HashingWriter),
+    Encrypted(age::stream::StreamWriter<HashingWriter>),
+}
+
+impl ArtifactSink {
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        match self {
+            ArtifactSink::Plain(writer) => writer.write_all(buf),
+            ArtifactSink::Encrypted(writer) => writer.write_all(buf),
+        }
+    }
+
+    /// 结束写入(加密时写入最后一个数据块)，返回磁盘上文件的校验和
+    fn finish(self) -> io::Result<String> {
+        match self {
+            ArtifactSink::Plain(writer) => writer.finish(),
+            ArtifactSink::Encrypted(writer) => writer.finish()?.finish(),
+        }
+    }
+}
+
+/// 创建只有当前用户可访问的目录，已存在的目录收紧权限
+fn create_private_dir(dir: &Path) -> Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+        builder.mode(DIR_MODE);
+        builder.create(dir)?;
+        fs::set_permissions(dir, fs::Permissions::from_mode(DIR_MODE))?;
+    }
+    #[cfg(not(unix))]
+    builder.create(dir)?;
+    Ok(())
+}
+
+/// 创建只有当前用户可读写的文件，已存在时截断
+fn create_private_file(path: &Path) -> io::Result<File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(FILE_MODE);
+    }
+    options.open(path)
+}
+
+/// 下载进度条样式，大小未知时只显示已下载量
+fn progress_style(known_size: bool) -> ProgressStyle {
+    let template = if known_size {
+        "{msg} [{bar:30}] {bytes}/{total_bytes} ({bytes_per_sec})"
+    } else {
+        "{spinner} {msg} {bytes} ({bytes_per_sec})"
+    };
+    ProgressStyle::with_template(template).unwrap().progress_chars("=>")
+}
+
+/// 清单中的文件名，统一使用 `/` 分隔
+fn manifest_name(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// 按主机和路径生成相对保存位置，如 `example.com/backup/www.zip`，
+/// 端口并入主机名，过滤 `..` 等路径成分并替换不安全的字符
+fn local_path(url: &str) -> Option<PathBuf> {
+    let url = Url::parse(url).ok()?;
+    let host = url.host_str()?;
+    let host = match url.port() {
+        Some(port) => format!("{}_{}", host, port),
+        None => host.to_string(),
+    };
+    let segments: Vec<String> = url.path_segments()?
+        .filter(|segment| !segment.is_empty() && *segment != "." && *segment != "..")
+        .map(sanitize)
+        .collect();
+    if segments.is_empty() {
+        return None;
+    }
+    let mut path = PathBuf::from(sanitize(&host));
+    path.extend(segments);
+    Some(path)
+}
+
+/// 只保留字母、数字和 `._-`，其余字符替换为下划线
+fn sanitize(segment: &str) -> String {
+    segment.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') { c } else { '_' })
+        .collect()
+}
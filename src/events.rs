@@ -0,0 +1,41 @@
+use crate::checkpoint::ScanProgress;
+use crate::{ScanResult, TargetStatus};
+use serde::Serialize;
+
+/// 事件通道容量，订阅者处理过慢时最旧的事件会被丢弃
+pub const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// 单个URL的检查结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UrlOutcome {
+    /// 发现备份文件
+    Found,
+    /// 未发现
+    NotFound,
+    /// 请求出错
+    Error,
+    /// 请求超时
+    Timeout,
+}
+
+/// 扫描器通过广播通道发布的事件
+///
+/// 通过 [`crate::scanner::Scanner::subscribe`] 订阅，GUI、机器人等可以据此
+/// 展示进度和发现，而无需修改扫描器内部
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ScanEvent {
+    /// 从状态文件继续扫描，订阅者以此为起点继续累计进度，扫描开始前发布
+    ProgressRestored { progress: ScanProgress },
+    /// 开始处理目标
+    TargetStarted { target: String },
+    /// 检查完一个URL
+    UrlChecked { target: String, url: String, outcome: UrlOutcome },
+    /// 确认发现
+    FindingConfirmed { target: String, result: Box<ScanResult> },
+    /// 目标扫描出错，扫描会继续
+    TargetErrored { target: String, error: String },
+    /// 目标处理完毕，`findings` 为该目标的发现数
+    TargetFinished { target: String, status: TargetStatus, findings: usize },
+}
@@ -0,0 +1,130 @@
+use crate::Result;
+use crate::similarity::fnv1a;
+use chrono::Utc;
+use log::debug;
+use reqwest::header::HeaderMap;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// 缓存条目有效期(秒)，超过后重新校准
+const CACHE_TTL_SECS: i64 = 7 * 24 * 3600;
+
+/// 按响应头比较时允许超出校准长度范围的字节数，错误页中常嵌入请求路径，长度随路径略有变化
+const WILDCARD_LENGTH_SLACK: u64 = 256;
+
+/// 泛200(soft-404)主机对不存在路径的响应特征
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WildcardSignature {
+    /// 内容类型，各次校准不一致时为None
+    pub content_type: Option<String>,
+    /// 各次校准响应的最小和最大长度，有响应未给出长度时为None
+    pub length_range: Option<(u64, u64)>,
+    /// 响应开头内容的哈希，各次校准不一致(如页面嵌入了请求路径)时为None
+    pub body_hash: Option<u64>,
+}
+
+impl WildcardSignature {
+    /// 由多次校准响应的内容类型、长度和开头内容生成特征
+    pub fn from_samples(samples: &[(Option<String>, Option<u64>, Vec<u8>)]) -> Option<Self> {
+        let (first_type, _, first_body) = samples.first()?;
+        let content_type = first_type.clone()
+            .filter(|content_type| samples.iter().all(|(other, _, _)| other.as_ref() == Some(content_type)));
+        let lengths: Option<Vec<u64>> = samples.iter().map(|(_, length, _)| *length).collect();
+        let length_range = lengths.and_then(|lengths| Some((*lengths.iter().min()?, *lengths.iter().max()?)));
+        let body_hash = Some(fnv1a(first_body))
+            .filter(|hash| samples.iter().all(|(_, _, body)| fnv1a(body) == *hash));
+        Some(Self { content_type, length_range, body_hash })
+    }
+
+    /// 未下载内容时按响应头判断：内容类型相同且长度落在校准范围附近
+    pub fn matches_headers(&self, content_type: Option<&str>, content_length: Option<u64>) -> bool {
+        let (Some(expected_type), Some((min, max)), Some(length)) = (&self.content_type, self.length_range, content_length) else {
+            return false;
+        };
+        let slack = WILDCARD_LENGTH_SLACK.max(max - min);
+        content_type == Some(expected_type.as_str())
+            && length + slack >= min
+            && length <= max + slack
+    }
+
+    /// 开头内容与校准响应完全相同
+    pub fn matches_body(&self, preview: &[u8]) -> bool {
+        self.body_hash == Some(fnv1a(preview))
+    }
+}
+
+/// 主机校准指纹
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HostFingerprint {
+    /// 不存在路径返回200时错误页的simhash
+    pub error_page_hash: Option<u64>,
+    /// 不存在路径返回200时的响应特征，用于过滤泛200主机的"发现"
+    #[serde(default)]
+    pub wildcard: Option<WildcardSignature>,
+    /// HEAD请求的状态码是否与GET一致
+    pub head_reliable: Option<bool>,
+    /// 服务器使用的HTTP版本
+    pub http_version: Option<String>,
+    /// 检测到的WAF/CDN名称
+    pub waf: Option<String>,
+    /// 校准请求的平均延迟(毫秒)
+    pub avg_latency_ms: Option<u64>,
+    /// 校准时间(Unix时间戳)
+    pub calibrated_at: i64,
+}
+
+impl HostFingerprint {
+    /// 指纹是否仍在有效期内
+    pub fn is_fresh(&self) -> bool {
+        Utc::now().timestamp() - self.calibrated_at < CACHE_TTL_SECS
+    }
+}
+
+/// 从缓存文件加载主机指纹，文件不存在时返回空表，过期条目会被丢弃
+pub fn load_fingerprint_cache<P: AsRef<Path>>(path: P) -> Result<HashMap<String, HostFingerprint>> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let content = fs::read_to_string(path)?;
+    let mut cache: HashMap<String, HostFingerprint> = serde_json::from_str(&content)?;
+    cache.retain(|_, fingerprint| fingerprint.is_fresh());
+
+    debug!("从 {} 加载了 {} 个主机指纹", path.display(), cache.len());
+    Ok(cache)
+}
+
+/// 将主机指纹保存到缓存文件
+pub fn save_fingerprint_cache<P: AsRef<Path>>(path: P, cache: &HashMap<String, HostFingerprint>) -> Result<()> {
+    let json = serde_json::to_string_pretty(cache)?;
+    fs::write(path, json)?;
+    Ok(())
+}
+
+/// 根据响应头识别常见的WAF/CDN
+pub fn detect_waf(headers: &HeaderMap) -> Option<String> {
+    let server = headers.get(reqwest::header::SERVER)
+        .and_then(|h| h.to_str().ok())
+        .map(|s| s.to_lowercase())
+        .unwrap_or_default();
+
+    let signatures = [
+        ("cloudflare", "cf-ray"),
+        ("akamai", "x-akamai-transformed"),
+        ("sucuri", "x-sucuri-id"),
+        ("incapsula", "x-iinfo"),
+        ("aws", "x-amz-cf-id"),
+        ("f5", "x-wa-info"),
+    ];
+
+    for (name, header) in signatures {
+        if server.contains(name) || headers.contains_key(header) {
+            return Some(name.to_string());
+        }
+    }
+
+    None
+}
@@ -0,0 +1,309 @@
+/// 长篇帮助主题，`backer help <主题>` 输出，也用于生成man手册
+#[derive(Debug, Clone, Copy)]
+pub struct HelpTopic {
+    /// 主题名
+    pub name: &'static str,
+    /// 一行摘要
+    pub summary: &'static str,
+    /// 章节
+    pub sections: &'static [HelpSection],
+}
+
+/// 帮助主题中的一个章节
+#[derive(Debug, Clone, Copy)]
+pub struct HelpSection {
+    /// 章节标题
+    pub title: &'static str,
+    /// 说明段落
+    pub paragraphs: &'static [&'static str],
+    /// 条目列表，(名称, 说明)
+    pub items: &'static [(&'static str, &'static str)],
+    /// 示例，原样输出
+    pub example: Option<&'static str>,
+}
+
+/// 所有帮助主题
+pub const HELP_TOPICS: &[HelpTopic] = &[
+    HelpTopic {
+        name: "patterns",
+        summary: "模式文件语法、备份目录组合和模式分类",
+        sections: &[
+            HelpSection {
+                title: "模式文件",
+                paragraphs: &[
+                    "通过 -p/--patterns 指定，每行一个模式，# 开头的行为注释，空行忽略。文件可以是 gzip 或 zstd 压缩的，按文件头自动识别。",
+                    "行首可以用指令显式指定类型；未加指令的行按旧规则推断：不含 .、/ 和模板语法的纯名称视为前缀，其余视为模板。",
+                ],
+                items: &[
+                    ("file:<路径>", "完整路径，原样请求，不解析模板语法"),
+                    ("prefix:<名称>", "字面前缀，与每个内置后缀组合"),
+                    ("dir:<目录>", "额外的备份目录，其他模式也会在该目录下生成"),
+                    ("template:<模板>", "路径模板，语法见下节"),
+                    ("<方法> <模板>", "用指定方法在根目录下探测，如 PROPFIND /backup/、GET /.git/HEAD；可用 GET HEAD POST OPTIONS PROPFIND REPORT SEARCH"),
+                ],
+                example: Some("file:wp-config.php.bak\nprefix:www\ndir:old\ntemplate:{domain}[_{year}].{zip,tar.gz}"),
+            },
+            HelpSection {
+                title: "模板语法",
+                paragraphs: &["模板展开后的每个路径都原样请求，单个模板最多展开 4096 个路径。"],
+                items: &[
+                    ("{domain}", "域名部分，如 www.example.com 的 example"),
+                    ("{host}", "完整主机名"),
+                    ("{year}", "当前年份"),
+                    ("{ext}", "每个内置后缀：.rar .zip .tar.gz .tar .7z .bak"),
+                    ("{a,b,c}", "选择，每项各生成一个路径，项可为空"),
+                    ("[...]", "可选段，分别生成包含和不包含该段的路径"),
+                    ("\\{ \\[ \\, \\\\", "转义为字面字符，选择以外的逗号须写作 \\,"),
+                ],
+                example: None,
+            },
+            HelpSection {
+                title: "备份目录",
+                paragraphs: &[
+                    "文件、前缀和模板展开出的路径都会在根目录和每个备份目录(默认 backup/、backups/)下各请求一次。",
+                ],
+                items: &[
+                    ("--dir-depth <N>", "备份目录组合深度，2 时额外生成 backup/backups/ 等两级目录"),
+                    ("--no-dir-scan", "只扫描根目录"),
+                    ("--dry-run", "打印每行模式的解释方式和生成的候选URL，不发送请求；缺少协议的目标不探测，假定为 https://"),
+                ],
+                example: None,
+            },
+            HelpSection {
+                title: "用户脚本",
+                paragraphs: &[
+                    "--script 加载Lua脚本(需 --features lua 编译)，单次调用超过5秒或内存超过64MB时中止。",
+                ],
+                items: &[
+                    ("candidates(target)", "每个目标调用一次，target 含 url、host、technologies，返回的路径作为根目录候选优先请求"),
+                    ("review(finding)", "每个发现调用一次，返回 false 否决、true 确认(标记为已验证)、nil 保持原样，第二个返回值为说明"),
+                ],
+                example: Some("function review(finding)\n  if finding.content_length ~= nil and finding.content_length < 1024 then\n    return false, \"too small\"\n  end\nend"),
+            },
+            HelpSection {
+                title: "模式分类",
+                paragraphs: &[
+                    "archive 分类始终启用，其他分类通过 --category 启用，--seclists 导入的字典按文件名归入对应分类。",
+                    "分类和技术栈的专属路径来自模式库，backer update-patterns 下载的本地模式库优先于内置模式库。",
+                    "--budget archive=500,vcs=50 限制单个目标各分类的请求数，total=N 为合计上限，此时各分类轮流请求，备份类候选不会占满上限。",
+                    "模式库中的条件模式([[conditional]])在同一目标命中 after 中的路径或识别出 technology 技术栈后才请求，如 .git/HEAD 命中后再请求 .git/index。",
+                    "-v 时 .git/HEAD 或 .git/config 的发现会进一步分析：下载HEAD、config、当前分支引用和提交对象，确认仓库真实暴露，能解压提交对象时升级为critical；加 --git-index 还会下载 .git/index 重建文件列表。",
+                    "-v 时 .svn/wc.db、.svn/entries、.hg/store/fncache、.hg/dirstate 和 .DS_Store 会完整下载并列出服务器上的文件名，为其中的文件追加 .bak、~、.swp 等备份路径，为目录追加同名压缩包，.DS_Store 和 .svn/entries 还会继续请求子目录下的同名文件；每个目标最多追加1000个候选。",
+                ],
+                items: &[
+                    ("archive", "压缩包、数据库转储等备份文件"),
+                    ("config", ".env 等敏感配置文件"),
+                    ("vcs", ".git/.svn/.hg 等版本控制元数据"),
+                    ("api", "接口文档备份、GraphQL配置、Postman集合"),
+                ],
+                example: None,
+            },
+            HelpSection {
+                title: "目标类型预设",
+                paragraphs: &[
+                    "已知目标平台时用 --target-type 跳过技术栈识别：直接加入平台专属路径、启用相关分类，并过滤其他技术栈的路径(web.config、.war、.jsp 等)。-v 验证内容时平台配置文件须包含平台特征，否则不算发现。",
+                    "未知平台时用 --cms-fingerprint 逐个目标识别：请求首页、/wp-login.php 和 /composer.json，按 X-Pingback 等响应头、laravel_session/csrftoken 等Cookie、页面特征和 composer.json 的依赖判断 WordPress、Laravel、Django、Spring Boot 等，识别出后追加该技术栈的专属路径并触发其条件模式。",
+                ],
+                items: &[
+                    ("wordpress", "wp-config.php 备份，启用 config；须含 DB_PASSWORD、DB_NAME、table_prefix 或 AUTH_KEY"),
+                    ("magento", "app/etc/env.php、local.xml 备份，启用 config、vcs；须含 'crypt'、'connection' 或 <crypt>"),
+                    ("laravel", ".env 备份，启用 config、vcs；须含 APP_KEY=、APP_ENV= 或 DB_PASSWORD="),
+                    ("generic", "默认，不做任何假定"),
+                ],
+                example: Some("backer -t shops.txt --target-type magento -v"),
+            },
+        ],
+    },
+    HelpTopic {
+        name: "outputs",
+        summary: "输出格式、报告章节和实时输出",
+        sections: &[
+            HelpSection {
+                title: "输出格式",
+                paragraphs: &["通过 -f/--format 选择，-o/--output 指定输出文件；没有发现时不写文件。"],
+                items: &[
+                    ("json", "带版本号的报告对象 {version, generated_at, generator, results, targets}，results 为 ScanResult 数组，targets 为目标报告；可再交给 report、diff、triage 子命令处理，旧版本写入的 ScanResult 数组同样可以读取"),
+                    ("csv", "每个发现一行，包含状态码、内容类型、长度、验证结果、标签、SQL/API摘要、发现来源和所属目标"),
+                    ("markdown", "结果表格，按标签分组统计，并附结果截断、目标存活状态、扫描错误、同IP站点、技术识别、robots.txt跳过区域和扫描覆盖等章节"),
+                    ("defectdojo", "DefectDojo通用发现导入(Generic Findings Import)JSON，按内容和状态码映射严重程度"),
+                    ("ndjson", "每行一个发现的JSON对象(JSON Lines)，扫描中发现即追加到输出文件，可用 tail -f 跟踪；中断的扫描也留下完整的行。启用 --redact 时在扫描结束后统一写入"),
+                    ("sarif", "SARIF 2.1.0，可上传到GitHub Code Scanning；每个模式分类一条规则(backer/archive、backer/config、backer/vcs、backer/api)"),
+                ],
+                example: None,
+            },
+            HelpSection {
+                title: "JSON字段",
+                paragraphs: &["可选字段为空时省略。"],
+                items: &[
+                    ("id", "由主机、路径和发现类型计算的稳定ID，重复扫描和各输出格式中相同，用于去重"),
+                    ("url / status_code", "发现的URL和状态码"),
+                    ("content_type / content_length", "响应的内容类型和长度"),
+                    ("content_type_mismatch", "内容类型与扩展名预期不符时为 true，如 .zip 返回 text/html"),
+                    ("verified / verify_note", "-v 时的内容验证结论和说明"),
+                    ("tag / source", "目标标签和发现来源(如 crt.sh、reverse-ip)"),
+                    ("target / original_target", "规范化后扫描的目标URL和目标文件中的原始写法(保留端口、路径和大小写)，用于对应回资产清单"),
+                    ("indicators", "压缩包内的高价值文件"),
+                    ("sql_summary / api_summary", "SQL转储和API定义摘要"),
+                    ("git", "暴露的 .git 目录的分析结果：HEAD、当前提交、能否下载对象、远程地址(凭据已隐藏)和 --git-index 重建的文件列表"),
+                    ("listing", ".svn/wc.db、.svn/entries、.hg/store/fncache、.hg/dirstate、.DS_Store 中列出的文件名(最多500个，目录以 / 结尾)"),
+                    ("bypass", "403绕过使用的技巧"),
+                    ("attempt", "请求过程：探测请求次数(含重试)和方法、Retry-After 暂停次数及耗时毫秒数，用于复现"),
+                    ("suspicious", "可疑响应的原因，如解压比超过 --max-compression-ratio 的压缩炸弹；此类发现不会被 --download-dir 下载"),
+                ],
+                example: None,
+            },
+            HelpSection {
+                title: "报告与实时输出",
+                paragraphs: &[],
+                items: &[
+                    ("backer report <FILE>", "从JSON结果生成静态HTML报告，--serve 启动可筛选的本地报告服务"),
+                    ("backer diff <OLD> <NEW>", "按发现ID比较两次扫描，列出新增、已修复和状态码或大小有变化的发现，--html 另存包含存在时间的HTML对比报告"),
+                    ("--watch --interval 24h", "进程常驻定时扫描，发现记入 --history 历史文件，只通知新发现"),
+                    ("backer coordinate / worker <URL>", "分布式扫描：协调节点把各目标的候选分片租给多台工作节点，汇总结果后按 -o 保存"),
+                    ("--sink <URL>", "将每个发现实时发布到 NATS 或 Kafka"),
+                    ("--es-url <URL>", "将发现实时批量写入 Elasticsearch/OpenSearch，索引名由档案的 es_index 指定"),
+                    ("--telegram-token <TOKEN>", "与 --telegram-chat-id 一起使用，将已验证的发现和扫描汇总推送到Telegram"),
+                    ("--baseline <FILE>", "之前的结果文件作为基线，URL(及内容哈希)相同的已知发现不再报告"),
+                    ("--stage-dir <DIR>", "分阶段输出：验证前的候选 candidates.jsonl 和验证、去重、评分后的 findings.json"),
+                    ("--pattern-stats <FILE>", "按历史命中率排序模式，扫描后更新"),
+                    ("--pattern-report <FILE>", "本次扫描各模式的尝试、命中、命中率和未命中浪费的字节数"),
+                    ("--noise-report <FILE>", "扫描前导出各主机的请求数、请求头取值和速率，交给防守方加白或关联告警"),
+                ],
+                example: Some("backer -t targets.txt -f markdown -o report.md\nbacker report results.json --serve"),
+            },
+        ],
+    },
+    HelpTopic {
+        name: "profiles",
+        summary: "TOML配置文件中的命名档案",
+        sections: &[
+            HelpSection {
+                title: "选择档案",
+                paragraphs: &[
+                    "通过 --config 指定TOML配置文件，--profile 选择档案。未指定 --profile 时使用 default 档案，不存在则使用空档案；显式指定的档案不存在时报错。档案之间不继承。",
+                ],
+                items: &[],
+                example: None,
+            },
+            HelpSection {
+                title: "扫描选项",
+                paragraphs: &[
+                    "档案中的键名与命令行长选项相同，连字符换成下划线，如 --max-findings-per-host 写作 max_findings_per_host。命令行中显式指定的选项优先于档案中的值；开关类选项同样如此，档案开启的开关可以在命令行用 --verify=false 这种形式关闭。",
+                    "档案中的相对路径相对于配置文件所在目录，团队可以把配置文件和字典一起提交到仓库。未知的键会报错，避免拼写错误被静默忽略。",
+                ],
+                items: &[
+                    ("patterns, tags, output", "模式文件、标签映射文件、结果输出文件"),
+                    ("threads, timeout, retry", "并发线程数、超时秒数、重试次数"),
+                    ("format", "json、csv、markdown、defectdojo、sarif 或 ndjson"),
+                    ("verify, bypass_403, respect_robots", "开关类选项，true 时开启"),
+                    ("categories", "额外启用的模式分类列表，如 [\"config\", \"vcs\"]"),
+                    ("dir_depth", "备份目录组合深度，0 等同于 --no-dir-scan"),
+                    ("random_headers", "true 或 false，对应 --random-headers / --no-random-headers"),
+                    ("spoof_ip", "random、固定值或文件路径，等同于 --spoof-ip"),
+                    ("challenge_detection, response_cache", "默认开启，false 时等同于 --no-challenge-detection / --no-response-cache"),
+                    ("es_url, es_index", "Elasticsearch地址和索引名，es_index 只能在档案中指定，默认 backer-findings"),
+                    ("telegram_token, telegram_chat_id", "Telegram机器人令牌和聊天ID，等同于 --telegram-token 和 --telegram-chat-id，须同时指定"),
+                    ("identify", "标识请求头，等同于 --identify，与 random_headers = true、spoof_ip、ua_class 和 ua_strategy 互斥"),
+                    ("target_type", "wordpress、magento、laravel 或 generic，等同于 --target-type"),
+                    ("ua_class, ua_strategy, ip_version", "取值与命令行相同，ip_version 写作 \"4\"、\"6\" 或 \"auto\""),
+                ],
+                example: Some("[profile.quick]\nthreads = 20\ntimeout = 5\ndir_depth = 0\n\n[profile.thorough]\npatterns = \"wordlists/backup.txt\"\nverify = true\ncategories = [\"config\", \"vcs\", \"api\"]\nformat = \"markdown\"\noutput = \"reports/scan.md\""),
+            },
+            HelpSection {
+                title: "request 表",
+                paragraphs: &["定制请求形态，适用于要求特定请求格式的中间设备。"],
+                items: &[
+                    ("method", "探测请求方法，默认 HEAD"),
+                    ("http_version", "1.0、1.1 或 2"),
+                    ("replace_default_headers", "为 true 时只发送 headers 中列出的请求头"),
+                    ("headers", "请求头列表，每项为 [名称, 值]，覆盖同名的随机请求头"),
+                ],
+                example: Some("[profile.legacy-proxy.request]\nmethod = \"GET\"\nhttp_version = \"1.1\"\nreplace_default_headers = true\nheaders = [\n  [\"User-Agent\", \"Mozilla/5.0\"],\n  [\"Accept\", \"*/*\"],\n]"),
+            },
+            HelpSection {
+                title: "notify 段",
+                paragraphs: &[
+                    "按严重程度把发现发送到不同的通知渠道，所有档案共用。channels 定义命名渠道，routes 指定每个严重程度(info、low、medium、high、critical)使用的渠道，summary 指定扫描结束时接收汇总的渠道；没有路由的严重程度不发送通知，路由引用不存在的渠道时在扫描前报错。发送失败记入目标报告的扫描错误。",
+                ],
+                items: &[
+                    ("type = \"slack\"", "webhook: Slack Incoming Webhook地址"),
+                    ("type = \"telegram\"", "token、chat_id: 机器人令牌和聊天ID(写作字符串)，api: 自建Bot API服务器地址(可选)"),
+                    ("type = \"email\"", "smtp、from、to: SMTP服务器地址(smtp:// 或 smtps://)、发件人、收件人列表，需 --features email 编译"),
+                    ("type = \"file\"", "path: 每个发现追加一行JSON(含 time、severity)，相对路径相对于配置文件所在目录"),
+                ],
+                example: Some("[notify]\nsummary = [\"oncall\"]\n\n[notify.channels.oncall]\ntype = \"slack\"\nwebhook = \"https://hooks.slack.com/services/...\"\n\n[notify.channels.log]\ntype = \"file\"\npath = \"findings.log\"\n\n[notify.routes]\ncritical = [\"oncall\", \"log\"]\nmedium = [\"log\"]"),
+            },
+        ],
+    },
+];
+
+/// 按名称查找帮助主题
+pub fn find_topic(name: &str) -> Option<&'static HelpTopic> {
+    HELP_TOPICS.iter().find(|topic| topic.name == name)
+}
+
+/// 渲染为终端文本
+pub fn render_topic(topic: &HelpTopic) -> String {
+    let mut text = format!("{} - {}\n", topic.name, topic.summary);
+    for section in topic.sections {
+        text.push_str(&format!("\n{}\n", section.title));
+        for paragraph in section.paragraphs {
+            text.push_str(&format!("  {}\n", paragraph));
+        }
+        let width = section.items.iter().map(|(name, _)| name.chars().count()).max().unwrap_or(0);
+        for (name, description) in section.items {
+            let padding = " ".repeat(width - name.chars().count());
+            text.push_str(&format!("  {}{}  {}\n", name, padding, description));
+        }
+        if let Some(example) = section.example {
+            text.push_str("\n  示例:\n");
+            for line in example.lines() {
+                text.push_str(&format!("    {}\n", line));
+            }
+        }
+    }
+    text
+}
+
+/// 渲染man手册(roff格式)，`options` 为 (参数, 说明) 列表，包含所有帮助主题
+pub fn render_man(version: &str, about: &str, options: &[(String, String)]) -> String {
+    let mut man = format!(".TH BACKER 1 \"\" \"backer {}\"\n", roff_escape(version));
+    man.push_str(&format!(".SH NAME\nbacker \\- {}\n", roff_escape(about)));
+    man.push_str(".SH SYNOPSIS\n\\fBbacker\\fR [OPTIONS] \\-t <FILE>\n.br\n\\fBbacker\\fR [OPTIONS] <COMMAND>\n");
+
+    man.push_str(".SH OPTIONS\n");
+    for (flag, description) in options {
+        man.push_str(&format!(".TP\n\\fB{}\\fR\n{}\n", roff_escape(flag), roff_escape(description)));
+    }
+
+    for topic in HELP_TOPICS {
+        man.push_str(&format!(".SH {}\n{}\n", topic.name.to_uppercase(), roff_escape(topic.summary)));
+        for section in topic.sections {
+            man.push_str(&format!(".SS {}\n", roff_escape(section.title)));
+            for paragraph in section.paragraphs {
+                man.push_str(&format!(".PP\n{}\n", roff_escape(paragraph)));
+            }
+            for (name, description) in section.items {
+                man.push_str(&format!(".TP\n\\fB{}\\fR\n{}\n", roff_escape(name), roff_escape(description)));
+            }
+            if let Some(example) = section.example {
+                man.push_str(".PP\n.nf\n");
+                for line in example.lines() {
+                    man.push_str(&format!("{}\n", roff_escape(line)));
+                }
+                man.push_str(".fi\n");
+            }
+        }
+    }
+    man
+}
+
+/// 转义roff特殊字符，行首的 `.` 和 `'` 会被当作请求
+fn roff_escape(text: &str) -> String {
+    let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+    if escaped.starts_with('.') || escaped.starts_with('\'') {
+        format!("\\&{}", escaped)
+    } else {
+        escaped
+    }
+}
@@ -1,54 +1,592 @@
-use crate::{Result, ScanResult};
-use crate::utils::get_random_user_agent;
+use crate::tech::{detect_cms, CmsMatch, CMS_PROBE_PATHS, FAVICON_MAX_SIZE};
+use crate::robots::ROBOTS_MAX_SIZE;
+use crate::ratelimit::{parse_retry_after, ConnectionBudget, HostPauses, RateLimiter, RequestSlot, StartupRamp, MAX_HOST_PAUSES};
+use crate::api::{describe_api_leak, is_api_leak_url, API_PREVIEW_SIZE};
+use crate::{AttemptInfo, CalibrationStatus, ErrorPhase, Result, ResultExt, ScanResult, TargetReport, TargetStatus};
+use crate::liveness::{challenge_from_headers, classify_page, detect_challenge, is_challenge_status, resolve_host, scheme_fallback_url};
+use crate::bypass::bypass_variants;
+use crate::cache::ResponseCache;
+use crate::preset::TargetType;
+use crate::archive::{find_indicators, list_entries, DecompressionLimits, Listing};
+use crate::sqldump::{is_sql_dump_url, summarize as summarize_sql_dump, SQL_SUMMARY_SIZE};
+use crate::vcs::{self, FileListing, GitExposure, Head, ListingSource};
+use crate::mime::is_content_type_mismatch;
+use crate::verify::{check_magic, is_archive_url, is_binary_url, judge_samples, MagicVerdict, looks_like_html, sample_plan, SamplePosition, SampleVerdict, LARGE_FILE_THRESHOLD};
+use crate::utils::{random_user_agent, seeded_rng};
+use crate::similarity::{fnv1a, simhash, is_similar, DEFAULT_SIMILARITY_THRESHOLD};
+use crate::fingerprint::{detect_waf, HostFingerprint, WildcardSignature};
+use crate::proxy::ProxyPool;
+use crate::timing::{measure_tcp, ConnectTiming, RequestTiming};
+use crate::dns::{DnsCache, DnsEntry, DnsFailure};
 use log::{debug, warn};
 use rand::prelude::*;
+use rand::distributions::Alphanumeric;
 use rand::seq::SliceRandom;
-use reqwest::{Client, header::{HeaderMap, HeaderValue, USER_AGENT, HeaderName}, StatusCode};
+use reqwest::{Client, ClientBuilder, Method, RequestBuilder, Response, Version, header::{HeaderMap, HeaderValue, USER_AGENT, HeaderName}, StatusCode};
+use serde::{Serialize, Deserialize};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::str::FromStr;
 use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
 use tokio::time::timeout;
 use std::collections::HashMap;
 use url::Url;
-use std::sync::{Arc, Mutex};
+use dashmap::DashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// 验证时下载的预览内容大小(字节)
+const PREVIEW_SIZE: usize = 4096;
+
+/// 存活检测时读取的首页内容大小(字节)
+const LIVENESS_PREVIEW_SIZE: usize = 16384;
+
+/// 下载请求的总超时，大文件下载由调用方按读取间隔判断超时
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(3600);
+
+/// 探测请求的超时
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 默认的soft-404校准请求数
+pub const DEFAULT_SOFT_404_PROBES: usize = 3;
+
+/// 校准请求依次使用的扩展名，泛200规则有时只针对部分扩展名
+const CALIBRATION_EXTENSIONS: &[&str] = &["zip", "sql", "tar.gz", "bak"];
+
+/// 探测请求第一次重试前的等待时间，之后每次翻倍
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(250);
+
+/// 重试等待时间的上限
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(8);
+
+/// 主机第一个403/429/503响应不是质询页时，之后每隔若干个检查一次，发现扫描中途开始的质询
+const CHALLENGE_SAMPLE_EVERY: usize = 20;
+
+/// User-Agent轮换策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UaStrategy {
+    /// 整个扫描只使用 `-a` 指定或启动时选出的一个UA
+    Fixed,
+    /// 每个主机固定使用一个UA，不同主机之间轮换
+    PerHost,
+    /// 每次请求从UA列表中随机选择
+    PerRequest,
+}
+
+/// 桌面浏览器User-Agent
+const DESKTOP_USER_AGENTS: &[&str] = &[
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:125.0) Gecko/20100101 Firefox/125.0",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4 Safari/605.1.15",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36 Edg/124.0.2478.51",
+    "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36",
+];
+
+/// 随机请求头开启时按概率添加的浏览器常见请求头
+const BROWSER_HEADERS: &[(&str, &str)] = &[
+    ("accept", "text/html,application/xhtml+xml,application/xml;q=0.9,image/webp,*/*;q=0.8"),
+    ("accept-language", "en-US,en;q=0.9,zh-CN;q=0.8,zh;q=0.7"),
+    ("accept-encoding", "gzip, deflate, br"),
+    ("connection", "keep-alive"),
+    ("upgrade-insecure-requests", "1"),
+    ("pragma", "no-cache"),
+    ("cache-control", "no-cache"),
+];
+
+/// 每个浏览器常见请求头被添加的概率
+const BROWSER_HEADER_PROBABILITY: f64 = 0.8;
+
+/// 移动端浏览器User-Agent
+const MOBILE_USER_AGENTS: &[&str] = &[
+    "Mozilla/5.0 (iPhone; CPU iPhone OS 17_4 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4 Mobile/15E148 Safari/604.1",
+    "Mozilla/5.0 (Linux; Android 14; SM-S918B) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Mobile Safari/537.36",
+    "Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Mobile Safari/537.36",
+    "Mozilla/5.0 (iPhone; CPU iPhone OS 17_4 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) CriOS/124.0.6367.88 Mobile/15E148 Safari/604.1",
+    "Mozilla/5.0 (Linux; U; Android 13; zh-CN; V2227A) AppleWebKit/537.36 (KHTML, like Gecko) Version/4.0 Chrome/100.0.4896.58 UCBrowser/16.0.0.1 Mobile Safari/537.36",
+    "Mozilla/5.0 (iPhone; CPU iPhone OS 17_4 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Mobile/15E148 MicroMessenger/8.0.48 NetType/WIFI Language/zh_CN",
+];
+
+/// 搜索引擎爬虫User-Agent
+const BOT_USER_AGENTS: &[&str] = &[
+    "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)",
+    "Mozilla/5.0 (compatible; bingbot/2.0; +http://www.bing.com/bingbot.htm)",
+    "Mozilla/5.0 (compatible; Baiduspider/2.0; +http://www.baidu.com/search/spider.html)",
+    "Sogou web spider/4.0(+http://www.sogou.com/docs/help/webmasters.htm#07)",
+    "Mozilla/5.0 (compatible; YandexBot/3.0; +http://yandex.com/bots)",
+];
+
+/// User-Agent类别，部分站点对移动端或爬虫返回不同的站点根目录
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UaClass {
+    Desktop,
+    Mobile,
+    Bot,
+    /// 桌面、移动端和爬虫混合
+    Mixed,
+}
+
+impl UaClass {
+    /// 该类别的User-Agent列表
+    pub fn user_agents(&self) -> Vec<String> {
+        let pools: &[&[&str]] = match self {
+            UaClass::Desktop => &[DESKTOP_USER_AGENTS],
+            UaClass::Mobile => &[MOBILE_USER_AGENTS],
+            UaClass::Bot => &[BOT_USER_AGENTS],
+            UaClass::Mixed => &[DESKTOP_USER_AGENTS, MOBILE_USER_AGENTS, BOT_USER_AGENTS],
+        };
+        pools.iter().flat_map(|pool| pool.iter().map(|ua| ua.to_string())).collect()
+    }
+}
+
+/// 来源IP伪装方式
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum IpSpoof {
+    /// 不伪装
+    #[default]
+    Off,
+    /// 每次请求随机生成IP
+    Random,
+    /// 固定值，如 127.0.0.1
+    Fixed(String),
+    /// 每次请求从列表中随机选择
+    List(Vec<String>),
+}
+
+impl IpSpoof {
+    /// 解析 `--spoof-ip` 参数：`random`、文件路径(每行一个值)或固定值
+    pub fn parse(value: &str) -> Result<Self> {
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("random") {
+            return Ok(IpSpoof::Random);
+        }
+        if value.eq_ignore_ascii_case("off") {
+            return Ok(IpSpoof::Off);
+        }
+        
+        let path = std::path::Path::new(value);
+        if path.is_file() {
+            let values: Vec<String> = std::fs::read_to_string(path)?
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                .map(String::from)
+                .collect();
+            if values.is_empty() {
+                return Err(crate::BackerError::Config(format!("伪装IP文件为空: {}", value)));
+            }
+            return Ok(IpSpoof::List(values));
+        }
+        
+        Ok(IpSpoof::Fixed(value.to_string()))
+    }
+}
+
+/// 连接目标时使用的IP协议版本
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+pub enum IpVersion {
+    /// 同时尝试IPv4和IPv6(Happy Eyeballs)，先连接解析结果中排在前面的地址族
+    #[default]
+    #[serde(rename = "auto")]
+    Auto,
+    /// 只连接IPv4地址
+    #[serde(rename = "4")]
+    V4,
+    /// 只连接IPv6地址
+    #[serde(rename = "6")]
+    V6,
+}
+
+impl IpVersion {
+    /// 绑定的本地地址，连接器只尝试与之同一地址族的目标地址
+    pub(crate) fn local_address(self) -> Option<IpAddr> {
+        match self {
+            IpVersion::Auto => None,
+            IpVersion::V4 => Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+            IpVersion::V6 => Some(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+        }
+    }
+
+    /// 地址是否属于允许的地址族
+    pub fn accepts(self, addr: &IpAddr) -> bool {
+        match self {
+            IpVersion::Auto => true,
+            IpVersion::V4 => addr.is_ipv4(),
+            IpVersion::V6 => addr.is_ipv6(),
+        }
+    }
+}
+
+/// 可用于伪装来源IP的请求头
+pub const SPOOF_HEADERS: &[&str] = &["x-forwarded-for", "x-real-ip", "x-originating-ip", "forwarded"];
+
+/// 请求模板，用于需要特定请求形态的中间设备
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RequestTemplate {
+    /// 探测请求使用的方法，默认HEAD
+    #[serde(default)]
+    pub method: Option<String>,
+    /// 额外请求头，按顺序发送，如 `[["Accept", "*/*"], ["X-Custom", "1"]]`
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+    /// HTTP版本: "1.0"、"1.1" 或 "2"
+    #[serde(default)]
+    pub http_version: Option<String>,
+    /// 只发送模板中的请求头，不添加随机请求头
+    #[serde(default)]
+    pub replace_default_headers: bool,
+}
+
+/// 扫描发送的一个请求头及其取值，供噪声报告列出
+#[derive(Debug, Clone, Serialize)]
+pub struct HeaderUsage {
+    /// 请求头名称
+    pub name: String,
+    /// 可能发送的值
+    pub values: Vec<String>,
+    /// 发送方式，如 "每次请求"、"每次请求以80%的概率发送"
+    pub when: String,
+}
+
+/// 单个URL的检查结果
+#[derive(Debug, Clone, Default)]
+pub struct UrlCheck {
+    /// 发现的备份文件，未命中时为None
+    pub result: Option<ScanResult>,
+    /// 探测响应的 Content-Length，没有响应或未给出时为0
+    pub response_size: u64,
+    /// 因文件大小超出范围而排除的原因
+    pub size_excluded: Option<String>,
+    /// 主机返回429/503要求等待的时间(Retry-After)，URL应在主机恢复后重新检查
+    pub retry_after: Option<Duration>,
+    /// 响应是JS质询或验证码拦截页时的服务商，主机的剩余候选应跳过
+    pub challenge: Option<String>,
+    /// 探测请求的发送次数，含重试
+    pub attempts: u32,
+    /// 探测响应是正面信号(2xx、3xx、403)时的响应信息，无论之后是否通过验证
+    pub signal: Option<ProbeSignal>,
+}
+
+/// 验证之前的正面信号，写入候选文件 `candidates.jsonl`
+#[derive(Debug, Clone)]
+pub struct ProbeSignal {
+    pub status_code: u16,
+    pub content_type: Option<String>,
+    pub content_length: Option<u64>,
+}
+
+/// 请求目标：URL中的主机决定请求行、Host请求头和TLS的SNI，连接地址可以单独指定
+///
+/// 虚拟主机扫描时URL写虚拟主机名，由 `--resolve` 把连接地址指向实际的服务器；Host请求头和SNI
+/// 始终由URL得出，不拼接字符串改写，请求行、Host和SNI不会互相矛盾
+///
+/// ```
+/// use backer::http::RequestTarget;
+///
+/// let target = RequestTarget::parse("https://shop.example:8443/db.sql").unwrap()
+///     .connect_to("10.0.0.5".parse().unwrap());
+/// assert_eq!(target.host_header(), "shop.example:8443");
+/// assert_eq!(target.sni(), Some("shop.example"));
+/// assert_eq!(target.connect_addr(), Some("10.0.0.5:8443".parse().unwrap()));
+/// assert_eq!(target.describe(), "shop.example:8443 → 10.0.0.5:8443");
+///
+/// // 默认端口不写入Host，IP地址不发送SNI，IPv6地址加方括号
+/// let target = RequestTarget::parse("https://[2001:db8::1]/db.sql").unwrap();
+/// assert_eq!(target.host_header(), "[2001:db8::1]");
+/// assert_eq!(target.sni(), None);
+/// assert_eq!(target.connect_addr(), Some("[2001:db8::1]:443".parse().unwrap()));
+///
+/// let target = RequestTarget::parse("http://shop.example/db.sql").unwrap();
+/// assert_eq!(target.host_header(), "shop.example");
+/// assert_eq!(target.sni(), None);
+/// assert_eq!(target.connect_addr(), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestTarget {
+    url: Url,
+    /// 指定的连接地址，None时按主机名解析
+    connect: Option<IpAddr>,
+}
+
+impl RequestTarget {
+    /// 解析请求URL，要求包含主机
+    pub fn parse(url: &str) -> Result<Self> {
+        let url = Url::parse(url)?;
+        if url.host().is_none() {
+            return Err(crate::BackerError::Config(format!("URL缺少主机: {}", url)));
+        }
+        Ok(Self { url, connect: None })
+    }
+
+    /// 指定连接地址，Host请求头和SNI不变
+    pub fn connect_to(self, addr: IpAddr) -> Self {
+        Self { connect: Some(addr), ..self }
+    }
+
+    /// 指定的连接地址
+    pub fn connect_override(&self) -> Option<IpAddr> {
+        self.connect
+    }
+
+    /// 请求URL
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Host请求头的值：主机名，非默认端口时附带端口
+    pub fn host_header(&self) -> String {
+        let host = self.url.host().map(|host| host.to_string()).unwrap_or_default();
+        match self.url.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host,
+        }
+    }
+
+    /// TLS握手发送的SNI，只有HTTPS的域名才发送
+    pub fn sni(&self) -> Option<&str> {
+        match self.url.host()? {
+            url::Host::Domain(domain) if self.url.scheme() == "https" => Some(domain),
+            _ => None,
+        }
+    }
+
+    /// 连接地址：指定的地址或URL中的IP地址，需要DNS解析时为None
+    pub fn connect_addr(&self) -> Option<SocketAddr> {
+        let port = self.url.port_or_known_default()?;
+        let ip = match (self.connect, self.url.host()?) {
+            (Some(ip), _) => ip,
+            (None, url::Host::Ipv4(ip)) => IpAddr::V4(ip),
+            (None, url::Host::Ipv6(ip)) => IpAddr::V6(ip),
+            (None, url::Host::Domain(_)) => return None,
+        };
+        Some(SocketAddr::new(ip, port))
+    }
+
+    /// 说明，如 "shop.example:8443 → 10.0.0.5:8443"，连接地址与Host相同时只有Host
+    pub fn describe(&self) -> String {
+        match self.connect.and_then(|_| self.connect_addr()) {
+            Some(addr) => format!("{} → {}", self.host_header(), addr),
+            None => self.host_header(),
+        }
+    }
+}
+
+/// 待发送的请求，经代理池发送时在完成后记录代理的连接结果
+struct OutboundRequest {
+    builder: RequestBuilder,
+    /// 使用的代理池及代理位置
+    proxy: Option<(Arc<ProxyPool>, usize)>,
+}
+
+impl OutboundRequest {
+    fn headers(self, headers: HeaderMap) -> Self {
+        Self { builder: self.builder.headers(headers), ..self }
+    }
+    
+    fn header(self, name: HeaderName, value: String) -> Self {
+        Self { builder: self.builder.header(name, value), ..self }
+    }
+    
+    fn timeout(self, timeout: Duration) -> Self {
+        Self { builder: self.builder.timeout(timeout), ..self }
+    }
+    
+    /// 发送请求；连接代理失败时换用下一个代理重试，每个代理最多尝试一次
+    async fn send(self) -> reqwest::Result<Response> {
+        let Some((pool, mut index)) = self.proxy else { return self.builder.send().await };
+        let (mut client, request) = self.builder.build_split();
+        let request = request?;
+        let mut attempts = 1;
+        loop {
+            let Some(attempt) = request.try_clone() else { return client.execute(request).await };
+            let result = client.execute(attempt).await;
+            pool.record(index, &result);
+            match result {
+                Err(e) if e.is_connect() && attempts < pool.len() => {
+                    attempts += 1;
+                    (index, client) = pool.pick(None, request.url().as_str());
+                },
+                result => return result,
+            }
+        }
+    }
+}
+
+/// 默认的最小文件大小(字节)，更小的200响应多为错误页
+pub const DEFAULT_MIN_SIZE: u64 = 100;
+/// 默认的最大文件大小(字节)，更大的响应多为流媒体或误配置的下载接口
+pub const DEFAULT_MAX_SIZE: u64 = 1_000_000_000;
+
+/// 发现的文件大小范围，只检查响应头给出的 Content-Length
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeLimits {
+    /// 最小大小(字节)
+    pub min: u64,
+    /// 最大大小(字节)
+    pub max: u64,
+}
+
+impl Default for SizeLimits {
+    fn default() -> Self {
+        Self { min: DEFAULT_MIN_SIZE, max: DEFAULT_MAX_SIZE }
+    }
+}
+
+impl SizeLimits {
+    /// 超出范围时返回原因，如 `58 字节，小于下限 100 字节`；大小未知时不排除
+    pub fn exclusion(&self, size: Option<u64>) -> Option<String> {
+        match size? {
+            size if size < self.min => Some(format!("{} 字节，小于下限 {} 字节", size, self.min)),
+            size if size > self.max => Some(format!("{} 字节，超过上限 {} 字节", size, self.max)),
+            _ => None,
+        }
+    }
+}
 
 /// HTTP客户端包装器
 #[derive(Clone)]
 pub struct HttpClient {
     client: Client,
     timeout_secs: u64,
-    #[allow(dead_code)]
     retry_count: u32,
     user_agent: String,
     random_headers: bool,
-    random_ip: bool,
+    // 来源IP伪装方式及使用的请求头
+    ip_spoof: IpSpoof,
+    spoof_headers: Vec<HeaderName>,
     // 域名响应时间跟踪
     #[allow(dead_code)]
-    response_times: Arc<Mutex<HashMap<String, Vec<Duration>>>>,
+    response_times: Arc<DashMap<String, Vec<Duration>>>,
     // 连接预热状态
-    warmed_up_hosts: Arc<Mutex<HashMap<String, bool>>>,
+    warmed_up_hosts: Arc<DashMap<String, bool>>,
     // 429/503响应计数
     #[allow(dead_code)]
-    rate_limited_hosts: Arc<Mutex<HashMap<String, (usize, Instant)>>>,
-    // 请求节流控制
+    rate_limited_hosts: Arc<DashMap<String, (usize, Instant)>>,
+    // 按 Retry-After 暂停的主机
+    host_pauses: Arc<HostPauses>,
+    // 请求节流控制(f32的位表示)
     #[allow(dead_code)]
-    throttle_factor: Arc<Mutex<f32>>,
+    throttle_factor: Arc<AtomicU32>,
     debug: bool,
     // 自定义User-Agent列表
     custom_user_agents: Vec<String>,
+    // User-Agent轮换策略，未设置时随机请求头开启则逐请求轮换
+    ua_strategy: Option<UaStrategy>,
+    // 按主机选定的User-Agent
+    host_user_agents: Arc<DashMap<String, String>>,
+    // 主机校准指纹(错误页simhash、HEAD可靠性、WAF等)
+    host_fingerprints: Arc<DashMap<String, HostFingerprint>>,
+    // 请求模板
+    request_template: Option<RequestTemplate>,
+    // 报告发现前重新请求URL和随机后缀的同级路径进行复查
+    double_check: bool,
+    // 校准时请求的随机不存在路径数
+    soft_404_probes: usize,
+    // 是否对403结果尝试绕过
+    bypass_403: bool,
+    // 参与内存解压扫描的最大压缩包大小，0表示禁用
+    archive_scan_max_size: u64,
+    // 确认暴露的.git目录后下载索引重建文件列表
+    git_index: bool,
+    // 内存解压的大小和解压比上限
+    decompression_limits: DecompressionLimits,
+    // 随机数种子，指定后随机行为可复现
+    seed: Option<u64>,
+    // 全局限速器，由 ScanManager 在多个扫描任务间共享
+    rate_limiter: Option<Arc<RateLimiter>>,
+    // 全局连接数上限，所有克隆共享
+    connection_budget: Option<ConnectionBudget>,
+    // 启动爬坡，所有克隆共享
+    startup_ramp: Option<Arc<StartupRamp>>,
+    // 连接使用的IP协议版本
+    ip_version: IpVersion,
+    // 显式指定的代理，None时使用环境变量中的代理
+    proxy: Option<String>,
+    // 轮换使用的代理池，设置后代替 `client` 发送请求
+    proxy_pool: Option<Arc<ProxyPool>>,
+    // 发现的文件大小范围
+    size_limits: SizeLimits,
+    // 调试模式下测得的各主机建连耗时
+    connect_timings: Arc<DashMap<String, ConnectTiming>>,
+    // 预解析的主机地址，作为连接池的DNS解析器
+    dns_cache: Arc<DnsCache>,
+    // 授权扫描的标识请求头，每次请求都发送
+    identify: Option<(HeaderName, HeaderValue)>,
+    // 是否检测质询页并跳过受保护的主机
+    challenge_detection: bool,
+    // 检测到质询页的主机(含端口)及服务商，所有克隆共享
+    challenged_hosts: Arc<DashMap<String, String>>,
+    // 各主机第一个403/429/503响应的检查结果，同时到达的响应等待同一次检查
+    challenge_verdicts: Arc<DashMap<String, Arc<OnceCell<Option<String>>>>>,
+    // 各主机的403/429/503响应数，用于抽样检查质询页
+    challenge_responses: Arc<DashMap<String, usize>>,
+    // 扫描内的响应缓存，所有克隆共享
+    response_cache: Option<Arc<ResponseCache>>,
+    // 目标类型预设，验证内容时按平台特征确认配置文件
+    target_type: TargetType,
+}
+
+/// 编译时选择的TLS后端
+#[cfg(feature = "native-tls")]
+pub const TLS_BACKEND: &str = "native-tls";
+/// 编译时选择的TLS后端
+#[cfg(all(feature = "rustls", not(feature = "native-tls")))]
+pub const TLS_BACKEND: &str = "rustls";
+
+#[cfg(not(any(feature = "rustls", feature = "native-tls")))]
+compile_error!("需要启用 rustls 或 native-tls 特性之一");
+
+/// 为客户端启用编译时选择的TLS后端
+pub fn use_tls_backend(builder: ClientBuilder) -> ClientBuilder {
+    #[cfg(feature = "native-tls")]
+    let builder = builder.use_native_tls();
+    #[cfg(all(feature = "rustls", not(feature = "native-tls")))]
+    let builder = builder.use_rustls_tls();
+    builder
+}
+
+/// 支持的代理协议
+const PROXY_SCHEMES: &[&str] = &["http", "https", "socks5", "socks5h"];
+
+/// 为客户端设置代理，如 `http://127.0.0.1:8080`、`socks5h://127.0.0.1:1080`
+///
+/// 未指定代理时沿用reqwest的默认行为，读取 `HTTP_PROXY`、`HTTPS_PROXY`、`ALL_PROXY`
+/// 和 `NO_PROXY` 环境变量；指定后忽略这些环境变量
+pub fn use_proxy(builder: ClientBuilder, proxy: Option<&str>) -> Result<ClientBuilder> {
+    let Some(proxy) = proxy else { return Ok(builder) };
+    let url = Url::parse(proxy)
+        .map_err(|e| crate::BackerError::Config(format!("代理地址无效 {}: {}", proxy, e)))?;
+    if !PROXY_SCHEMES.contains(&url.scheme()) || url.host_str().is_none() {
+        return Err(crate::BackerError::Config(format!(
+            "代理地址无效 {}: 需要 {}://主机:端口 格式", proxy, PROXY_SCHEMES.join("|")
+        )));
+    }
+    Ok(builder.proxy(reqwest::Proxy::all(url.as_str())?))
+}
+
+/// 创建启用连接池的reqwest客户端，`ip_version` 限制连接使用的地址族，`proxy` 为None时使用环境变量中的代理，
+/// 域名通过 `dns_cache` 解析
+pub fn build_client(timeout_secs: u64, ip_version: IpVersion, proxy: Option<&str>, dns_cache: Arc<DnsCache>) -> Result<Client> {
+    let client = use_proxy(use_tls_backend(Client::builder()), proxy)?
+        .dns_resolver(dns_cache)
+        .timeout(Duration::from_secs(timeout_secs))
+        // 启用连接池
+        .pool_idle_timeout(Duration::from_secs(90))
+        .pool_max_idle_per_host(10)
+        .local_address(ip_version.local_address())
+        .build()?;
+    Ok(client)
 }
 
 #[allow(dead_code)]
 impl HttpClient {
     /// 创建新的HTTP客户端
     pub fn new(timeout_secs: u64, retry_count: u32, user_agent: String) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(timeout_secs))
-            // 启用TLS和连接池
-            .use_rustls_tls()
-            // 启用连接池
-            .pool_idle_timeout(Duration::from_secs(90))
-            .pool_max_idle_per_host(10)
-            .build()?;
+        debug!("TLS后端: {}", TLS_BACKEND);
+        let dns_cache = Arc::new(DnsCache::default());
+        let client = build_client(timeout_secs, IpVersion::Auto, None, dns_cache.clone())?;
             
         // 预定义一些现代浏览器的User-Agent
         let default_user_agents = vec![
@@ -70,13 +608,42 @@ impl HttpClient {
             retry_count,
             user_agent,
             random_headers: true, // 默认开启随机请求头
-            random_ip: true,      // 默认开启随机IP
-            response_times: Arc::new(Mutex::new(HashMap::new())),
-            warmed_up_hosts: Arc::new(Mutex::new(HashMap::new())),
-            rate_limited_hosts: Arc::new(Mutex::new(HashMap::new())),
-            throttle_factor: Arc::new(Mutex::new(1.0)),
+            ip_spoof: IpSpoof::Off, // 默认不伪装来源IP
+            spoof_headers: vec![HeaderName::from_static("x-forwarded-for")],
+            response_times: Arc::new(DashMap::new()),
+            warmed_up_hosts: Arc::new(DashMap::new()),
+            rate_limited_hosts: Arc::new(DashMap::new()),
+            host_pauses: Arc::new(HostPauses::default()),
+            throttle_factor: Arc::new(AtomicU32::new(1.0f32.to_bits())),
             debug: false,
             custom_user_agents: default_user_agents,
+            ua_strategy: None,
+            host_user_agents: Arc::new(DashMap::new()),
+            host_fingerprints: Arc::new(DashMap::new()),
+            request_template: None,
+            double_check: false,
+            soft_404_probes: DEFAULT_SOFT_404_PROBES,
+            bypass_403: false,
+            archive_scan_max_size: crate::archive::DEFAULT_ARCHIVE_SCAN_MAX_SIZE,
+            git_index: false,
+            decompression_limits: DecompressionLimits::default(),
+            seed: None,
+            rate_limiter: None,
+            connection_budget: None,
+            startup_ramp: None,
+            ip_version: IpVersion::Auto,
+            proxy: None,
+            proxy_pool: None,
+            size_limits: SizeLimits::default(),
+            connect_timings: Arc::new(DashMap::new()),
+            dns_cache,
+            identify: None,
+            challenge_detection: true,
+            challenged_hosts: Arc::new(DashMap::new()),
+            challenge_verdicts: Arc::new(DashMap::new()),
+            challenge_responses: Arc::new(DashMap::new()),
+            response_cache: None,
+            target_type: TargetType::Generic,
         })
     }
     
@@ -87,7 +654,327 @@ impl HttpClient {
     
     /// 设置是否使用随机IP
     pub fn set_random_ip(&mut self, enable: bool) {
-        self.random_ip = enable;
+        self.ip_spoof = if enable { IpSpoof::Random } else { IpSpoof::Off };
+    }
+    
+    /// 设置报告发现前是否复查
+    pub fn set_double_check(&mut self, enable: bool) {
+        self.double_check = enable;
+    }
+    
+    /// 设置校准时请求的随机不存在路径数
+    pub fn set_soft_404_probes(&mut self, probes: usize) {
+        self.soft_404_probes = probes;
+    }
+    
+    /// 设置是否对403结果尝试绕过技巧
+    pub fn set_bypass_403(&mut self, enable: bool) {
+        self.bypass_403 = enable;
+    }
+    
+    /// 设置连接使用的IP协议版本，重新创建连接池
+    pub fn set_ip_version(&mut self, ip_version: IpVersion) -> Result<()> {
+        if ip_version != self.ip_version {
+            self.client = build_client(self.timeout_secs, ip_version, self.proxy.as_deref(), self.dns_cache.clone())?;
+            self.ip_version = ip_version;
+        }
+        Ok(())
+    }
+    
+    /// 设置发现的文件大小范围，下限大于上限时返回错误
+    pub fn set_size_limits(&mut self, size_limits: SizeLimits) -> Result<()> {
+        if size_limits.min > size_limits.max {
+            return Err(crate::BackerError::Config(format!(
+                "最小文件大小 {} 字节大于最大文件大小 {} 字节", size_limits.min, size_limits.max
+            )));
+        }
+        self.size_limits = size_limits;
+        Ok(())
+    }
+    
+    /// 设置代理，重新创建连接池
+    pub fn set_proxy(&mut self, proxy: Option<String>) -> Result<()> {
+        if proxy != self.proxy {
+            self.client = build_client(self.timeout_secs, self.ip_version, proxy.as_deref(), self.dns_cache.clone())?;
+            self.proxy = proxy;
+        }
+        Ok(())
+    }
+    
+    /// 显式指定的代理
+    pub fn proxy(&self) -> Option<&str> {
+        self.proxy.as_deref()
+    }
+    
+    /// 设置轮换使用的代理池，设置后忽略 `set_proxy` 指定的单个代理和共享连接池
+    pub fn set_proxy_pool(&mut self, pool: Option<Arc<ProxyPool>>) {
+        self.proxy_pool = pool;
+    }
+    
+    /// 请求是否经过显式指定的代理或代理池
+    fn uses_proxy(&self) -> bool {
+        self.proxy.is_some() || self.proxy_pool.is_some()
+    }
+    
+    /// 扫描前并发解析目标主机，请求时直接使用解析出的地址，返回无法解析的主机及原因
+    ///
+    /// 经代理访问时由代理解析域名，不进行预解析
+    pub async fn prefetch_dns(&self, targets: &[String]) -> Vec<(String, DnsFailure)> {
+        if self.uses_proxy() {
+            return Vec::new();
+        }
+        let hosts = targets.iter()
+            .filter_map(|target| Url::parse(target).ok())
+            .filter_map(|url| url.host_str().map(str::to_string));
+        self.dns_cache.prefetch(hosts, self.ip_version).await
+    }
+    
+    /// 按 `--resolve` 固定主机名的连接地址，Host请求头和SNI仍使用URL中的主机名
+    ///
+    /// 经代理访问时由代理解析域名，固定的地址不会生效，因此与代理互斥
+    pub fn set_resolve_overrides(&self, specs: &[String]) -> Result<()> {
+        if !specs.is_empty() && self.uses_proxy() {
+            return Err(crate::BackerError::Config("--resolve 不能与代理同时使用，经代理访问时由代理解析域名".to_string()));
+        }
+        for spec in specs {
+            let (host, addr) = parse_resolve_override(spec)?;
+            self.dns_cache.pin(&host, addr);
+        }
+        Ok(())
+    }
+    
+    /// URL的请求目标，附带 `--resolve` 固定的连接地址
+    pub fn request_target(&self, url: &str) -> Result<RequestTarget> {
+        let target = RequestTarget::parse(url)?;
+        match target.url().host_str().and_then(|host| self.dns_cache.pinned(host)) {
+            Some(addr) => Ok(target.connect_to(addr)),
+            None => Ok(target),
+        }
+    }
+    
+    /// 连接使用的IP协议版本
+    pub fn ip_version(&self) -> IpVersion {
+        self.ip_version
+    }
+    
+    /// 使用共享的连接池，多个扫描任务复用同一组连接，连接池需使用相同的IP协议版本、代理和固定解析
+    ///
+    /// `dns_cache` 为连接池的DNS解析器，替换本客户端的缓存，预解析结果和固定解析写入同一缓存
+    pub fn set_connection_pool(&mut self, client: Client, dns_cache: Arc<DnsCache>) {
+        self.client = client;
+        self.dns_cache = dns_cache;
+    }
+    
+    /// 设置全局限速器，所有请求发出前等待
+    pub fn set_rate_limiter(&mut self, limiter: Option<Arc<RateLimiter>>) {
+        self.rate_limiter = limiter;
+    }
+    
+    /// 设置全局连接数上限，请求发出前取得名额，响应读取完毕后归还
+    pub fn set_connection_budget(&mut self, budget: Option<ConnectionBudget>) {
+        self.connection_budget = budget;
+    }
+    
+    /// 设置启动爬坡，爬坡期间请求发出前等待并发额度
+    pub fn set_startup_ramp(&mut self, ramp: Option<Arc<StartupRamp>>) {
+        self.startup_ramp = ramp;
+    }
+    
+    /// 记录请求结果供启动爬坡统计错误率，`error` 表示请求出错、超时或被限流
+    pub fn record_ramp_outcome(&self, error: bool) {
+        if let Some(ramp) = &self.startup_ramp {
+            ramp.record(error);
+        }
+    }
+    
+    /// 设置随机数种子，UA选择、请求头抽样、IP伪装和校准路径都由种子和请求URL确定
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+    }
+    
+    /// 为指定请求创建随机数生成器
+    fn rng(&self, key: &str) -> StdRng {
+        seeded_rng(self.seed, key)
+    }
+    
+    /// 第 `attempt` 次重试(从0开始)前的等待时间：指数退避，并在后一半区间内随机抖动，
+    /// 避免大量失败的请求同时重试
+    fn retry_delay(&self, url: &str, attempt: u32) -> Duration {
+        let backoff = RETRY_BASE_DELAY.saturating_mul(1 << attempt.min(16)).min(RETRY_MAX_DELAY);
+        let half = backoff / 2;
+        half + half.mul_f64(self.rng(&format!("{}#retry{}", url, attempt)).gen::<f64>())
+    }
+    
+    /// 探测请求重试的最长额外耗时，用于放宽整体超时
+    pub(crate) fn retry_allowance(&self) -> Duration {
+        (0..self.retry_count)
+            .map(|attempt| PROBE_TIMEOUT + RETRY_BASE_DELAY.saturating_mul(1 << attempt.min(16)).min(RETRY_MAX_DELAY))
+            .sum()
+    }
+    
+    /// 设置参与内存解压扫描的最大压缩包大小，0表示禁用
+    pub fn set_archive_scan_max_size(&mut self, max_size: u64) {
+        self.archive_scan_max_size = max_size;
+    }
+
+    /// 设置确认暴露的.git目录后是否下载索引重建文件列表
+    pub fn set_git_index(&mut self, enable: bool) {
+        self.git_index = enable;
+    }
+    
+    /// 设置内存解压的大小和解压比上限
+    pub fn set_decompression_limits(&mut self, limits: DecompressionLimits) {
+        self.decompression_limits = limits;
+    }
+    
+    /// 设置来源IP伪装方式
+    pub fn set_ip_spoof(&mut self, spoof: IpSpoof) {
+        self.ip_spoof = spoof;
+    }
+    
+    /// 设置伪装IP使用的请求头，`all` 表示全部支持的请求头
+    pub fn set_spoof_headers(&mut self, names: &[String]) -> Result<()> {
+        let mut headers = Vec::new();
+        for name in names {
+            let name = name.trim().to_lowercase();
+            if name == "all" {
+                headers = SPOOF_HEADERS.iter().map(|h| HeaderName::from_static(h)).collect();
+                break;
+            }
+            if !SPOOF_HEADERS.contains(&name.as_str()) {
+                return Err(crate::BackerError::Config(format!(
+                    "不支持的伪装请求头: {} (可选: {}, all)", name, SPOOF_HEADERS.join(", ")
+                )));
+            }
+            headers.push(HeaderName::from_str(&name).map_err(|e| crate::BackerError::Config(e.to_string()))?);
+        }
+        if !headers.is_empty() {
+            self.spoof_headers = headers;
+        }
+        Ok(())
+    }
+    
+    /// 设置授权扫描的标识请求头，格式为 `名称: 值`，每次请求都发送，包括只发送模板请求头时
+    pub fn set_identify(&mut self, header: Option<&str>) -> Result<()> {
+        self.identify = header.map(parse_identify_header).transpose()?;
+        Ok(())
+    }
+    
+    /// 设置是否检测质询页，关闭后受保护主机的403/503响应按普通响应处理
+    pub fn set_challenge_detection(&mut self, enable: bool) {
+        self.challenge_detection = enable;
+    }
+    
+    /// URL所在主机检测到的质询页服务商
+    pub fn host_challenge(&self, url: &str) -> Option<String> {
+        if self.challenged_hosts.is_empty() {
+            return None;
+        }
+        self.challenged_hosts.get(&challenge_key(url)?).map(|provider| provider.clone())
+    }
+    
+    /// 记录主机受质询保护
+    fn mark_challenged(&self, url: &str, provider: &str) {
+        if let Some(key) = challenge_key(url) {
+            debug!("主机 {} 返回 {} 质询页", key, provider);
+            self.challenged_hosts.entry(key).or_insert_with(|| provider.to_string());
+        }
+    }
+    
+    /// 检查探测得到的403/429/503响应是否为质询页：响应头能判断时直接判断，否则下载内容比对特征。
+    /// 每个主机的第一个此类响应一定检查，同时到达的响应等待其结果；不是质询页时之后抽样检查。
+    /// 检测到时记录主机并返回服务商
+    async fn check_challenge(&self, url: &str, status: StatusCode, response_headers: &HeaderMap, headers: &HeaderMap) -> Option<String> {
+        if !self.challenge_detection || !is_challenge_status(status.as_u16()) {
+            return None;
+        }
+        let key = challenge_key(url)?;
+        if let Some(provider) = self.challenged_hosts.get(&key) {
+            return Some(provider.clone());
+        }
+        let provider = match challenge_from_headers(status.as_u16(), response_headers) {
+            Some(provider) => provider,
+            None => {
+                let verdict = self.challenge_verdicts.entry(key.clone()).or_default().clone();
+                let first = verdict.get_or_init(|| self.fetch_challenge(url, headers)).await.clone();
+                match first {
+                    Some(provider) => provider,
+                    None => {
+                        let seen = {
+                            let mut count = self.challenge_responses.entry(key).or_insert(0);
+                            *count += 1;
+                            *count
+                        };
+                        if seen % CHALLENGE_SAMPLE_EVERY != 0 {
+                            return None;
+                        }
+                        self.fetch_challenge(url, headers).await?
+                    }
+                }
+            }
+        };
+        self.mark_challenged(url, &provider);
+        Some(provider)
+    }
+    
+    /// 用GET请求下载URL的开头内容，比对质询页特征
+    async fn fetch_challenge(&self, url: &str, headers: &HeaderMap) -> Option<String> {
+        let (status, response_headers, body) = self.fetch_error_page(url, headers.clone()).await?;
+        detect_challenge(status.as_u16(), &response_headers, &body)
+    }
+    
+    /// 下载URL开头内容，不论状态码，用于检查错误页
+    async fn fetch_error_page(&self, url: &str, headers: HeaderMap) -> Option<(StatusCode, HeaderMap, Vec<u8>)> {
+        if let Some(cached) = self.response_cache.as_ref().and_then(|cache| cache.get(url, PREVIEW_SIZE)) {
+            return Some((cached.status, cached.headers, cached.body));
+        }
+        let timeout_duration = Duration::from_secs(3);
+        let _slot = self.acquire_slot().await;
+        self.wait_rate_limit().await;
+        let future = self.request(Method::GET, url)
+            .headers(headers)
+            .header(reqwest::header::RANGE, format!("bytes=0-{}", PREVIEW_SIZE - 1))
+            .timeout(timeout_duration)
+            .send();
+        match timeout(timeout_duration, future).await {
+            Ok(Ok(response)) => {
+                let status = response.status();
+                let response_headers = response.headers().clone();
+                let body = Self::read_preview(response, PREVIEW_SIZE).await;
+                if let Some(cache) = &self.response_cache {
+                    cache.insert(url, PREVIEW_SIZE, status, &response_headers, &body);
+                }
+                Some((status, response_headers, body))
+            },
+            _ => None,
+        }
+    }
+    
+    /// 设置目标类型预设
+    pub fn set_target_type(&mut self, target_type: TargetType) {
+        self.target_type = target_type;
+    }
+    
+    /// 设置扫描内的响应缓存，None时每次都重新请求
+    pub fn set_response_cache(&mut self, cache: Option<Arc<ResponseCache>>) {
+        self.response_cache = cache;
+    }
+    
+    /// 响应缓存中同一URL的探测结果，未启用缓存或已失效时返回None
+    pub fn cached_check(&self, url: &str, verify_content: bool) -> Option<UrlCheck> {
+        self.response_cache.as_ref()?.check(url, verify_content)
+    }
+    
+    /// 清空响应缓存，开始新一次扫描时调用
+    pub fn clear_response_cache(&self) {
+        if let Some(cache) = &self.response_cache {
+            cache.clear();
+        }
+    }
+    
+    /// 命中响应缓存、省去的请求数
+    pub fn response_cache_hits(&self) -> usize {
+        self.response_cache.as_ref().map_or(0, |cache| cache.hits())
     }
     
     /// 设置是否启用调试输出
@@ -100,19 +987,105 @@ impl HttpClient {
         self.custom_user_agents = user_agents;
     }
     
+    /// 设置User-Agent轮换策略，`None` 表示由随机请求头开关决定
+    pub fn set_ua_strategy(&mut self, strategy: Option<UaStrategy>) {
+        self.ua_strategy = strategy;
+    }
+    
+    /// 按轮换策略为请求选择User-Agent
+    fn pick_user_agent(&self, strategy: UaStrategy, url: &str, rng: &mut StdRng) -> String {
+        let choose = |rng: &mut StdRng| match self.custom_user_agents.choose(rng) {
+            Some(user_agent) => user_agent.clone(),
+            None => random_user_agent(rng),
+        };
+        match strategy {
+            UaStrategy::Fixed => self.user_agent.clone(),
+            UaStrategy::PerRequest => choose(rng),
+            UaStrategy::PerHost => {
+                let host = Url::parse(url).ok()
+                    .and_then(|u| u.host_str().map(str::to_string))
+                    .unwrap_or_default();
+                self.host_user_agents
+                    .entry(host.clone())
+                    .or_insert_with(|| choose(&mut self.rng(&host)))
+                    .clone()
+            }
+        }
+    }
+    
     /// 添加单个自定义User-Agent
     pub fn add_custom_user_agent(&mut self, user_agent: String) {
         self.custom_user_agents.push(user_agent);
     }
     
+    /// 设置请求模板，模板中的方法、请求头和HTTP版本将应用到所有请求
+    pub fn set_request_template(&mut self, template: Option<RequestTemplate>) -> Result<()> {
+        if let Some(template) = &template {
+            if let Some(method) = &template.method {
+                Method::from_str(&method.to_uppercase()).map_err(|_| {
+                    crate::BackerError::Config(format!("无效的请求方法: {}", method))
+                })?;
+            }
+            if let Some(version) = &template.http_version {
+                parse_http_version(version).ok_or_else(|| {
+                    crate::BackerError::Config(format!("无效的HTTP版本: {}", version))
+                })?;
+            }
+        }
+        self.request_template = template;
+        Ok(())
+    }
+    
+    /// 创建请求，应用模板中的HTTP版本
+    fn request(&self, method: Method, url: &str) -> OutboundRequest {
+        // 配置了代理池时按轮换策略选择代理，否则使用共享连接池
+        let (client, proxy) = match &self.proxy_pool {
+            Some(pool) => {
+                let (index, client) = pool.pick(self.seed, &format!("proxy#{}", url));
+                (client, Some((pool.clone(), index)))
+            },
+            None => (self.client.clone(), None),
+        };
+        let builder = client.request(method, url);
+        let builder = match self.request_template.as_ref()
+            .and_then(|t| t.http_version.as_deref())
+            .and_then(parse_http_version) {
+            Some(version) => builder.version(version),
+            None => builder,
+        };
+        OutboundRequest { builder, proxy }
+    }
+    
+    /// 探测请求使用的方法，模板未指定时为HEAD
+    fn probe_method(&self) -> Method {
+        self.request_template.as_ref()
+            .and_then(|t| t.method.as_deref())
+            .and_then(|m| Method::from_str(&m.to_uppercase()).ok())
+            .unwrap_or(Method::HEAD)
+    }
+    
+    /// 设置已知的主机指纹(例如从缓存文件加载)，已有指纹的主机不再校准
+    pub fn set_host_fingerprints(&mut self, fingerprints: HashMap<String, HostFingerprint>) {
+        self.host_fingerprints.clear();
+        for (host, fingerprint) in fingerprints {
+            self.host_fingerprints.insert(host, fingerprint);
+        }
+    }
+    
+    /// 获取当前所有主机指纹
+    pub fn host_fingerprints(&self) -> HashMap<String, HostFingerprint> {
+        self.host_fingerprints.iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect()
+    }
+    
     /// 预热目标主机连接
     pub async fn warm_up_connection(&self, base_url: &str) -> Result<()> {
         // 尝试解析URL获取主机名
         if let Ok(url) = Url::parse(base_url) {
             if let Some(host) = url.host_str() {
                 // 检查是否已经预热过
-                let mut warmed_up = self.warmed_up_hosts.lock().unwrap();
-                if warmed_up.contains_key(host) {
+                if self.warmed_up_hosts.contains_key(host) {
                     return Ok(());
                 }
                 
@@ -120,13 +1093,14 @@ impl HttpClient {
                 let short_timeout = Duration::from_secs(3);
                 
                 // 发送HEAD请求预热连接
-                let headers = self.generate_random_headers();
+                let headers = self.generate_random_headers(base_url);
                 
-                match timeout(short_timeout, self.client.head(base_url).headers(headers).send()).await {
+                let _slot = self.acquire_slot().await;
+                match timeout(short_timeout, self.request(Method::HEAD, base_url).headers(headers).send()).await {
                     Ok(result) => {
                         if result.is_ok() {
                             // 连接成功预热
-                            warmed_up.insert(host.to_string(), true);
+                            self.warmed_up_hosts.insert(host.to_string(), true);
                         }
                         // 即使失败也继续处理
                         Ok(())
@@ -143,7 +1117,485 @@ impl HttpClient {
             Ok(())
         }
     }
-    
+    
+    /// 校准目标主机指纹
+    ///
+    /// 依次请求若干个随机的不存在路径(扩展名各不相同)，记录HTTP版本、WAF、延迟以及HEAD与GET是否一致。
+    /// 如果主机对其返回200，记录预览内容的simhash和响应特征(内容类型、长度范围、内容哈希)，
+    /// 之后与之近似或特征相符的"发现"将被视为模板化的错误页(soft-404)而忽略
+    pub async fn calibrate_host(&self, base_url: &str) -> CalibrationStatus {
+        let url = match Url::parse(base_url) {
+            Ok(url) => url,
+            Err(_) => return CalibrationStatus::Failed,
+        };
+        let host = match url.host_str() {
+            Some(host) => host.to_string(),
+            None => return CalibrationStatus::Failed,
+        };
+
+        if self.host_fingerprints.contains_key(&host) {
+            return CalibrationStatus::Cached;
+        }
+
+        let mut rng = self.rng(base_url);
+        let mut probe_urls = Vec::new();
+        for extension in CALIBRATION_EXTENSIONS.iter().cycle().take(self.soft_404_probes.max(1)) {
+            let random_name: String = (&mut rng)
+                .sample_iter(&Alphanumeric)
+                .take(16)
+                .map(char::from)
+                .collect();
+            match url.join(&format!("/{}.{}", random_name, extension)) {
+                Ok(probe) => probe_urls.push(probe.to_string()),
+                Err(_) => return CalibrationStatus::Failed,
+            }
+        }
+
+        let timeout_duration = Duration::from_secs(3);
+        let headers = self.generate_random_headers(&probe_urls[0]);
+
+        // 校准请求依次进行，共用一个连接名额
+        let _slot = self.acquire_slot().await;
+        let mut fingerprint = HostFingerprint {
+            calibrated_at: chrono::Utc::now().timestamp(),
+            ..Default::default()
+        };
+        let mut get_status = None;
+        let mut get_latency = Duration::ZERO;
+        let mut samples = Vec::new();
+        for (index, probe_url) in probe_urls.iter().enumerate() {
+            if index > 0 {
+                self.wait_rate_limit().await;
+            }
+            let start_time = Instant::now();
+            let get_future = self.request(Method::GET, probe_url)
+                .headers(headers.clone())
+                .header(reqwest::header::RANGE, format!("bytes=0-{}", PREVIEW_SIZE - 1))
+                .timeout(timeout_duration)
+                .send();
+            let response = match timeout(timeout_duration, get_future).await {
+                Ok(Ok(resp)) => resp,
+                _ => {
+                    debug!("主机 {} 校准请求失败: {}", host, probe_url);
+                    return CalibrationStatus::Failed;
+                }
+            };
+            let status = response.status();
+            if index == 0 {
+                get_latency = start_time.elapsed();
+                get_status = Some(status);
+                fingerprint.http_version = Some(format!("{:?}", response.version()));
+                fingerprint.waf = detect_waf(response.headers());
+                fingerprint.avg_latency_ms = Some(get_latency.as_millis() as u64);
+            }
+            if status.is_success() {
+                let content_type = response.headers()
+                    .get(reqwest::header::CONTENT_TYPE)
+                    .and_then(|h| h.to_str().ok())
+                    .map(String::from);
+                let length = full_length(status, response.headers());
+                let preview = Self::read_preview(response, PREVIEW_SIZE).await;
+                if fingerprint.error_page_hash.is_none() {
+                    fingerprint.error_page_hash = Some(simhash(&preview));
+                }
+                samples.push((content_type, length, preview));
+            }
+        }
+        fingerprint.wildcard = WildcardSignature::from_samples(&samples);
+        let get_status = get_status.unwrap_or_default();
+
+        // 比较HEAD与GET的结果，判断HEAD请求是否可信
+        self.wait_rate_limit().await;
+        let start_time = Instant::now();
+        let head_future = self.request(Method::HEAD, &probe_urls[0])
+            .headers(headers)
+            .timeout(timeout_duration)
+            .send();
+        if let Ok(Ok(head_response)) = timeout(timeout_duration, head_future).await {
+            let head_status = head_response.status();
+            let consistent = if get_status.is_success() || head_status.is_success() {
+                get_status.is_success() == head_status.is_success()
+            } else {
+                get_status == head_status
+            };
+            fingerprint.head_reliable = Some(consistent);
+            fingerprint.avg_latency_ms = Some(((get_latency + start_time.elapsed()) / 2).as_millis() as u64);
+        }
+
+        debug!("主机 {} 校准完成: {:?}", host, fingerprint);
+        let status = if fingerprint.error_page_hash.is_some() {
+            CalibrationStatus::Wildcard
+        } else {
+            CalibrationStatus::Calibrated
+        };
+        self.host_fingerprints.insert(host, fingerprint);
+        status
+    }
+
+    /// 使用Range请求下载URL内容的前几KB作为预览，仅在状态码为2xx时返回
+    async fn fetch_preview(&self, url: &str, headers: HeaderMap) -> Option<Vec<u8>> {
+        self.fetch_prefix(url, PREVIEW_SIZE, headers).await
+    }
+
+    /// 使用Range请求下载URL内容的前 `size` 字节，仅在状态码为2xx时返回
+    async fn fetch_prefix(&self, url: &str, size: usize, headers: HeaderMap) -> Option<Vec<u8>> {
+        if let Some(cached) = self.response_cache.as_ref().and_then(|cache| cache.get(url, size)) {
+            return cached.status.is_success().then_some(cached.body);
+        }
+        let timeout_duration = Duration::from_secs(3);
+        let _slot = self.acquire_slot().await;
+        let future = self.request(Method::GET, url)
+            .headers(headers)
+            .header(reqwest::header::RANGE, format!("bytes=0-{}", size - 1))
+            .timeout(timeout_duration)
+            .send();
+
+        let start_time = Instant::now();
+        match timeout(timeout_duration, future).await {
+            Ok(Ok(resp)) if resp.status().is_success() => {
+                let ttfb = start_time.elapsed();
+                let (status, response_headers) = (resp.status(), resp.headers().clone());
+                let preview = Self::read_preview(resp, size).await;
+                if let Some(cache) = &self.response_cache {
+                    cache.insert(url, size, status, &response_headers, &preview);
+                }
+                self.log_timing("下载开头", url, RequestTiming::since(start_time, ttfb));
+                Some(preview)
+            },
+            _ => None,
+        }
+    }
+
+    /// 复查发现：重新请求URL和加了随机后缀的同级路径(如 `backup-k3x9q2.zip`)，
+    /// URL不再返回200，或同级路径也返回与之相同的200响应时视为误报，返回false
+    async fn double_check(&self, url: &str, headers: &HeaderMap) -> bool {
+        let suffix: String = self.rng(&format!("{}#double-check", url))
+            .sample_iter(&Alphanumeric)
+            .take(8)
+            .map(|c| char::from(c).to_ascii_lowercase())
+            .collect();
+        let Some(sibling) = mutated_sibling(url, &suffix) else {
+            return true;
+        };
+        let (original, canary) = futures::future::join(
+            self.fetch_canary(url, headers.clone()),
+            self.fetch_canary(&sibling, headers.clone()),
+        ).await;
+        let Some(original) = original else {
+            debug!("复查时URL不再返回200: {}", url);
+            return false;
+        };
+        if canary.is_some_and(|canary| canary == original) {
+            debug!("复查时同级路径返回相同的200响应: {} ~ {}", url, sibling);
+            return false;
+        }
+        true
+    }
+
+    /// 请求URL的开头内容，返回2xx响应的完整长度和开头内容的哈希
+    async fn fetch_canary(&self, url: &str, headers: HeaderMap) -> Option<(Option<u64>, u64)> {
+        let timeout_duration = Duration::from_secs(3);
+        let _slot = self.acquire_slot().await;
+        let future = self.request(Method::GET, url)
+            .headers(headers)
+            .header(reqwest::header::RANGE, format!("bytes=0-{}", PREVIEW_SIZE - 1))
+            .timeout(timeout_duration)
+            .send();
+        match timeout(timeout_duration, future).await {
+            Ok(Ok(resp)) if resp.status().is_success() => {
+                let length = full_length(resp.status(), resp.headers());
+                let preview = Self::read_preview(resp, PREVIEW_SIZE).await;
+                Some((length, fnv1a(&preview)))
+            },
+            _ => None,
+        }
+    }
+
+    /// 读取响应的前几KB，服务器可能忽略Range头，读满预览大小后即停止
+    async fn read_preview(mut response: Response, limit: usize) -> Vec<u8> {
+        let timeout_duration = Duration::from_secs(3);
+        let mut preview = Vec::with_capacity(limit);
+        while preview.len() < limit {
+            match timeout(timeout_duration, response.chunk()).await {
+                Ok(Ok(Some(chunk))) => preview.extend_from_slice(&chunk),
+                _ => break,
+            }
+        }
+        preview.truncate(limit);
+        preview
+    }
+    
+    /// 下载站点根目录的 `/favicon.ico`，不存在或超过大小上限时返回None
+    pub async fn fetch_favicon(&self, base_url: &str) -> Option<Vec<u8>> {
+        let url = Url::parse(base_url).ok()?.join("/favicon.ico").ok()?.to_string();
+        let headers = self.generate_random_headers(&url);
+        self.fetch_full(&url, FAVICON_MAX_SIZE, headers).await
+            .filter(|content| !content.is_empty())
+    }
+    
+    /// 识别目标站点的CMS和框架：并发请求首页、`/wp-login.php` 和 `/composer.json` 的开头内容，
+    /// 按响应头、Cookie和内容特征判断，每个技术栈只保留第一条依据
+    pub async fn fingerprint_cms(&self, base_url: &str) -> Vec<CmsMatch> {
+        let Ok(base) = Url::parse(base_url) else {
+            return Vec::new();
+        };
+        let probes = CMS_PROBE_PATHS.iter().filter_map(|path| Some((*path, base.join(path).ok()?.to_string())))
+            .map(|(path, url)| async move {
+                let headers = self.generate_random_headers(&url);
+                self.fetch_error_page(&url, headers).await
+                    .map(|(status, headers, body)| detect_cms(path, status.as_u16(), &headers, &body))
+                    .unwrap_or_default()
+            });
+        let mut matches: Vec<CmsMatch> = Vec::new();
+        for found in futures::future::join_all(probes).await.into_iter().flatten() {
+            if !matches.iter().any(|known| known.technology == found.technology) {
+                matches.push(found);
+            }
+        }
+        matches
+    }
+    
+    /// 下载目标站点的robots.txt，不存在、非200或过大时返回None
+    pub async fn fetch_robots(&self, base_url: &str) -> Option<String> {
+        let url = Url::parse(base_url).ok()?.join("/robots.txt").ok()?.to_string();
+        let headers = self.generate_random_headers(&url);
+        self.fetch_full(&url, ROBOTS_MAX_SIZE, headers).await
+            .map(|content| String::from_utf8_lossy(&content).to_string())
+    }
+    
+    /// 存活检测：解析主机名并请求首页，识别停放域名和可被接管的服务
+    ///
+    /// HTTPS因TLS错误失败时自动改用HTTP重试，HTTP连接被重置时改用HTTPS重试，
+    /// 回退记录在目标报告中
+    pub async fn check_liveness(&self, target: &str) -> TargetReport {
+        let mut report = TargetReport {
+            target: target.to_string(),
+            status: TargetStatus::Live,
+            detail: None,
+            scanned_url: None,
+            scheme_fallback: None,
+            errors: Vec::new(),
+            failures: Vec::new(),
+            neighbors: Vec::new(),
+            technologies: Vec::new(),
+            robots_skipped: Vec::new(),
+            truncated: None,
+            slow_lane: None,
+            challenge: None,
+            size_excluded: Vec::new(),
+            known: Vec::new(),
+            coverage: None,
+        };
+        
+        let url = match Url::parse(target) {
+            Ok(url) => url,
+            Err(_) => return report,
+        };
+        
+        // 经代理访问时由代理解析域名，目标可能只在代理所在网络中可解析；
+        // --resolve 固定了连接地址时不需要解析
+        let request_target = self.request_target(target).ok();
+        if let Some(request_target) = request_target.as_ref().filter(|t| t.connect_override().is_some()) {
+            debug!("目标 {} 的连接地址: {}", target, request_target.describe());
+        } else if let Some(host) = url.host_str().filter(|_| !self.uses_proxy()) {
+            if let Some(DnsEntry::Failed(failure)) = self.dns_cache.get(host) {
+                debug!("主机 {} 预解析失败: {}", host, failure.describe());
+                report.status = TargetStatus::Unresolvable;
+                report.detail = Some(format!("DNS预解析失败: {}", failure.describe()));
+                return report;
+            }
+            let port = url.port_or_known_default().unwrap_or(80);
+            if !resolve_host(host, port, self.ip_version).await {
+                debug!("主机 {} 无法解析", host);
+                report.status = TargetStatus::Unresolvable;
+                report.detail = Some(match self.ip_version {
+                    IpVersion::Auto => "DNS解析失败".to_string(),
+                    IpVersion::V4 => "DNS解析失败或没有IPv4地址".to_string(),
+                    IpVersion::V6 => "DNS解析失败或没有IPv6地址".to_string(),
+                });
+                return report;
+            }
+        }
+        
+        // 首页请求和协议回退依次进行，共用一个连接名额，读取首页内容后归还
+        let slot = self.acquire_slot().await;
+        let mut start_time = Instant::now();
+        let response = match self.fetch_root(target).await {
+            Ok(resp) => resp,
+            Err(error) => {
+                // 根据错误类型尝试切换协议
+                let fallback_url = error.as_ref().and_then(|e| scheme_fallback_url(&url, e));
+                let fallback_response = match &fallback_url {
+                    Some(alt_url) => {
+                        debug!("{} 请求失败，尝试切换协议: {}", target, alt_url);
+                        self.wait_rate_limit().await;
+                        start_time = Instant::now();
+                        self.fetch_root(alt_url).await.ok()
+                    },
+                    None => None,
+                };
+                
+                match (fallback_url, fallback_response) {
+                    (Some(alt_url), Some(resp)) => {
+                        let alt_scheme = Url::parse(&alt_url).map(|u| u.scheme().to_string()).unwrap_or_default();
+                        report.scheme_fallback = Some(format!("{} -> {}", url.scheme(), alt_scheme));
+                        report.scanned_url = Some(alt_url);
+                        resp
+                    },
+                    _ => {
+                        debug!("存活检测请求失败: {} - {:?}", target, error);
+                        report.status = TargetStatus::Unreachable;
+                        report.detail = Some(error.map_or("请求超时".to_string(), |e| e.to_string()));
+                        return report;
+                    }
+                }
+            }
+        };
+        
+        let final_url = response.url().to_string();
+        let status = response.status();
+        let response_headers = response.headers().clone();
+        let ttfb = start_time.elapsed();
+        let body = Self::read_preview(response, LIVENESS_PREVIEW_SIZE).await;
+        let timing = RequestTiming::since(start_time, ttfb);
+        drop(slot);
+        
+        // 调试模式下测量建连耗时，之后该主机的请求日志附带DNS、连接和TLS耗时
+        if self.debug {
+            self.profile_connection(report.scanned_url.as_deref().unwrap_or(target)).await;
+            self.log_timing("首页", &final_url, timing);
+        }
+        
+        // 首页就是质询页时不扫描，否则所有候选都会得到同样的403/503
+        if let Some(provider) = detect_challenge(status.as_u16(), &response_headers, &body).filter(|_| self.challenge_detection) {
+            self.mark_challenged(&final_url, &provider);
+            report.status = TargetStatus::ChallengeProtected;
+            report.detail = Some(format!("{} 质询页 (首页返回 {})", provider, status.as_u16()));
+            return report;
+        }
+        
+        if let Some((status, provider)) = classify_page(&final_url, &body) {
+            report.status = status;
+            report.detail = Some(provider);
+        }
+        report
+    }
+    
+    /// 请求目标首页，超时返回Err(None)，调用方负责限速和连接名额
+    async fn fetch_root(&self, url: &str) -> std::result::Result<Response, Option<reqwest::Error>> {
+        let timeout_duration = Duration::from_secs(std::cmp::min(self.timeout_secs, 10));
+        let future = self.request(Method::GET, url)
+            .headers(self.generate_random_headers(url))
+            .timeout(timeout_duration)
+            .send();
+        
+        match timeout(timeout_duration, future).await {
+            Ok(Ok(resp)) => Ok(resp),
+            Ok(Err(e)) => Err(Some(e)),
+            Err(_) => Err(None),
+        }
+    }
+
+    /// 请求指定字节区间，服务器未返回206时视为不支持Range
+    async fn fetch_range(&self, url: &str, start: u64, end: u64, headers: HeaderMap) -> Option<Vec<u8>> {
+        let timeout_duration = Duration::from_secs(3);
+        let _slot = self.acquire_slot().await;
+        let future = self.request(Method::GET, url)
+            .headers(headers)
+            .header(reqwest::header::RANGE, format!("bytes={}-{}", start, end))
+            .timeout(timeout_duration)
+            .send();
+        
+        let start_time = Instant::now();
+        match timeout(timeout_duration, future).await {
+            Ok(Ok(resp)) if resp.status() == StatusCode::PARTIAL_CONTENT => {
+                let ttfb = start_time.elapsed();
+                let sample = Self::read_preview(resp, (end - start + 1) as usize).await;
+                self.log_timing("分段采样", url, RequestTiming::since(start_time, ttfb));
+                Some(sample)
+            },
+            _ => None,
+        }
+    }
+    
+    /// 完整下载URL内容，超过大小上限时返回None
+    async fn fetch_full(&self, url: &str, limit: u64, headers: HeaderMap) -> Option<Vec<u8>> {
+        let timeout_duration = Duration::from_secs(self.timeout_secs);
+        let _slot = self.acquire_slot().await;
+        let future = self.request(Method::GET, url)
+            .headers(headers)
+            .timeout(timeout_duration)
+            .send();
+        
+        let start_time = Instant::now();
+        let mut response = match timeout(timeout_duration, future).await {
+            Ok(Ok(resp)) if resp.status() == StatusCode::OK => resp,
+            _ => return None,
+        };
+        let ttfb = start_time.elapsed();
+        // 声明的大小已超过上限时不读取响应体
+        if response.content_length().is_some_and(|length| length > limit) {
+            return None;
+        }
+        
+        let mut content = Vec::new();
+        loop {
+            match timeout(timeout_duration, response.chunk()).await {
+                Ok(Ok(Some(chunk))) => {
+                    if (content.len() + chunk.len()) as u64 > limit {
+                        return None;
+                    }
+                    content.extend_from_slice(&chunk);
+                },
+                Ok(Ok(None)) => break,
+                _ => return None,
+            }
+        }
+        self.log_timing("完整下载", url, RequestTiming::since(start_time, ttfb));
+        Some(content)
+    }
+    
+    /// 对大文件分段采样并判断是否完整，服务器不支持Range时返回None
+    async fn sample_large_file(&self, url: &str, content_length: u64, headers: &HeaderMap) -> Option<SampleVerdict> {
+        let plan = sample_plan(url, content_length);
+        let fetches = plan.iter().map(|(position, start, end)| async move {
+            self.fetch_range(url, *start, *end, headers.clone()).await
+                .map(|content| (*position, content))
+        });
+        
+        let samples: Option<Vec<(SamplePosition, Vec<u8>)>> = futures::future::join_all(fetches).await
+            .into_iter()
+            .collect();
+        
+        samples.map(|samples| judge_samples(url, &samples))
+    }
+    
+    /// 判断预览内容是否与主机已校准的错误页相同或近似
+    fn matches_error_page(&self, url_str: &str, preview: &[u8]) -> bool {
+        let host = match Url::parse(url_str).ok().and_then(|u| u.host_str().map(String::from)) {
+            Some(host) => host,
+            None => return false,
+        };
+
+        let Some(fingerprint) = self.host_fingerprints.get(&host) else {
+            return false;
+        };
+        if fingerprint.wildcard.as_ref().is_some_and(|wildcard| wildcard.matches_body(preview)) {
+            return true;
+        }
+        fingerprint.error_page_hash.is_some_and(|hash| is_similar(simhash(preview), hash, DEFAULT_SIMILARITY_THRESHOLD))
+    }
+
+    /// 判断响应头是否与主机已校准的泛200响应特征相符，用于未下载内容时过滤soft-404
+    fn matches_wildcard_headers(&self, url_str: &str, content_type: Option<&str>, content_length: Option<u64>) -> bool {
+        let Some(host) = Url::parse(url_str).ok().and_then(|u| u.host_str().map(String::from)) else {
+            return false;
+        };
+        self.host_fingerprints.get(&host)
+            .and_then(|fingerprint| fingerprint.wildcard.as_ref().map(|wildcard| wildcard.matches_headers(content_type, content_length)))
+            .unwrap_or(false)
+    }
+
     /// 获取域名的自适应超时时间
     fn get_adaptive_timeout(&self, url_str: &str) -> Duration {
         let default_timeout = Duration::from_secs(self.timeout_secs);
@@ -151,9 +1603,7 @@ impl HttpClient {
         // 尝试解析URL获取主机名
         if let Ok(url) = Url::parse(url_str) {
             if let Some(host) = url.host_str() {
-                let response_times = self.response_times.lock().unwrap();
-                
-                if let Some(times) = response_times.get(host) {
+                if let Some(times) = self.response_times.get(host) {
                     if !times.is_empty() {
                         // 计算平均响应时间
                         let avg_time: Duration = times.iter().sum::<Duration>() / times.len() as u32;
@@ -182,9 +1632,7 @@ impl HttpClient {
     fn record_response_time(&self, url_str: &str, duration: Duration) {
         if let Ok(url) = Url::parse(url_str) {
             if let Some(host) = url.host_str() {
-                let mut response_times = self.response_times.lock().unwrap();
-                
-                let times = response_times.entry(host.to_string()).or_insert_with(Vec::new);
+                let mut times = self.response_times.entry(host.to_string()).or_default();
                 times.push(duration);
                 
                 // 只保留最近10次的响应时间
@@ -200,20 +1648,20 @@ impl HttpClient {
         if status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE {
             if let Ok(url) = Url::parse(url_str) {
                 if let Some(host) = url.host_str() {
-                    let mut rate_limited = self.rate_limited_hosts.lock().unwrap();
-                    
                     // 当前时间
                     let now = Instant::now();
                     
                     // 增加计数或插入新记录
-                    let entry = rate_limited.entry(host.to_string()).or_insert((0, now));
-                    entry.0 += 1;
-                    entry.1 = now;
+                    let count = {
+                        let mut entry = self.rate_limited_hosts.entry(host.to_string()).or_insert((0, now));
+                        entry.0 += 1;
+                        entry.1 = now;
+                        entry.0
+                    };
                     
                     // 如果短时间内多次被限制，增加节流因子
-                    if entry.0 >= 3 {
-                        let mut throttle_factor = self.throttle_factor.lock().unwrap();
-                        *throttle_factor = (*throttle_factor * 1.5).min(5.0);
+                    if count >= 3 {
+                        self.update_throttle_factor(|factor| (factor * 1.5).min(5.0));
                         return true;
                     }
                 }
@@ -221,60 +1669,221 @@ impl HttpClient {
         }
         
         // 检查是否可以降低节流因子
-        let mut can_decrease = true;
-        {
-            let rate_limited = self.rate_limited_hosts.lock().unwrap();
-            let now = Instant::now();
-            
-            // 检查最近5分钟内是否有被限制
-            for (_, (_, time)) in rate_limited.iter() {
-                if now.duration_since(*time) < Duration::from_secs(300) {
-                    can_decrease = false;
-                    break;
-                }
-            }
-        }
+        // 检查最近5分钟内是否有被限制
+        let now = Instant::now();
+        let can_decrease = !self.rate_limited_hosts.iter()
+            .any(|entry| now.duration_since(entry.value().1) < Duration::from_secs(300));
         
         // 如果5分钟内没有被限制，逐渐恢复节流因子
         if can_decrease {
-            let mut throttle_factor = self.throttle_factor.lock().unwrap();
-            if *throttle_factor > 1.0 {
-                *throttle_factor = (*throttle_factor * 0.9).max(1.0);
-            }
+            self.update_throttle_factor(|factor| if factor > 1.0 { (factor * 0.9).max(1.0) } else { factor });
         }
         
         false
     }
     
+    /// 原子地更新节流因子
+    fn update_throttle_factor(&self, update: impl Fn(f32) -> f32) {
+        let _ = self.throttle_factor.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+            Some(update(f32::from_bits(bits)).to_bits())
+        });
+    }
+    
     /// 获取当前节流延迟
     fn get_throttle_delay(&self) -> Duration {
-        let factor = *self.throttle_factor.lock().unwrap();
+        let factor = f32::from_bits(self.throttle_factor.load(Ordering::Relaxed));
         
         // 降低初始延迟值，从100ms降至30ms
         Duration::from_millis((30.0 * factor) as u64)
     }
     
-    /// 检查URL是否可能是备份文件
-    pub async fn check_url(&self, url: &str, verify_content: bool) -> Result<Option<ScanResult>> {
-        // 直接做一次请求，不进行预热或多次重试
+    /// 测量主机的DNS解析、TCP连接和TLS握手耗时
+    ///
+    /// TLS握手耗时由同一新客户端的两次请求估算：第一次新建连接，第二次复用连接，
+    /// 两者之差扣除DNS和TCP连接耗时即为握手耗时；经代理访问时直连测得的耗时没有意义，不测量
+    async fn profile_connection(&self, base_url: &str) {
+        if self.uses_proxy() {
+            return;
+        }
+        let Ok(url) = Url::parse(base_url) else { return };
+        let Some(host) = url.host_str() else { return };
+        if self.connect_timings.contains_key(host) {
+            return;
+        }
+        let port = url.port_or_known_default().unwrap_or(80);
+        // 与实际请求连接同一地址，`--resolve` 固定的主机不做系统解析
+        let pinned = self.request_target(base_url).ok().and_then(|target| target.connect_override());
+        let Some((dns, connect)) = measure_tcp(host, port, pinned, self.ip_version).await else {
+            debug!("主机 {} 建连耗时测量失败", host);
+            return;
+        };
+        
+        let mut tls = None;
+        if url.scheme() == "https" {
+            if let Ok(client) = build_client(std::cmp::min(self.timeout_secs, 10), self.ip_version, self.proxy.as_deref(), self.dns_cache.clone()) {
+                let mut elapsed = Vec::with_capacity(2);
+                for _ in 0..2 {
+                    let _slot = self.acquire_slot().await;
+                    let start_time = Instant::now();
+                    match client.head(base_url).headers(self.generate_random_headers(base_url)).send().await {
+                        Ok(_) => elapsed.push(start_time.elapsed()),
+                        Err(_) => break,
+                    }
+                }
+                if let [fresh, reused] = elapsed[..] {
+                    tls = Some(fresh.saturating_sub(reused + dns + connect));
+                }
+            }
+        }
+        
+        let timing = ConnectTiming { dns, connect, tls };
+        debug!("主机 {} 建连耗时: {}", host, timing.describe());
+        self.connect_timings.insert(host.to_string(), timing);
+    }
+    
+    /// URL所在主机的建连耗时，未测量时为None
+    fn connect_timing(&self, url: &str) -> Option<ConnectTiming> {
+        let url = Url::parse(url).ok()?;
+        self.connect_timings.get(url.host_str()?).map(|timing| *timing)
+    }
+    
+    /// 调试模式下输出请求的耗时明细
+    fn log_timing(&self, action: &str, url: &str, timing: RequestTiming) {
+        if self.debug {
+            debug!("{} {} 耗时: {}", action, url, timing.describe(self.connect_timing(url).as_ref()));
+        }
+    }
+    
+    /// 等待全局限速，未设置限速器时立即返回
+    pub async fn wait_rate_limit(&self) {
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire().await;
+        }
+    }
+    
+    /// 取得连接名额并等待全局限速，返回的名额在请求完成前不能释放
+    ///
+    /// 持有名额期间不能再次调用，否则名额耗尽时会互相等待
+    pub async fn acquire_slot(&self) -> RequestSlot {
+        let ramp = match &self.startup_ramp {
+            Some(ramp) => ramp.acquire().await,
+            None => None,
+        };
+        let mut slot = match &self.connection_budget {
+            Some(budget) => budget.acquire().await,
+            None => RequestSlot::default(),
+        };
+        slot.ramp = ramp;
+        self.wait_rate_limit().await;
+        slot
+    }
+    
+    /// 发起下载请求，返回状态码为200的响应和占用的连接名额，响应体由调用方流式读取
+    pub async fn open_download(&self, url: &str) -> Result<(Response, RequestSlot)> {
+        let headers = self.generate_random_headers(url);
+        let slot = self.acquire_slot().await;
+        let response = self.request(Method::GET, url)
+            .headers(headers)
+            .timeout(DOWNLOAD_TIMEOUT)
+            .send()
+            .await
+            .at_url(ErrorPhase::Download, url)?;
+        if response.status() != StatusCode::OK {
+            return Err(crate::BackerError::Other(format!("下载返回状态码 {}", response.status())))
+                .at_url(ErrorPhase::Download, url);
+        }
+        Ok((response, slot))
+    }
+    
+    /// 检查URL是否可能是备份文件，限速、连接名额和主机暂停的等待不计入超时
+    pub async fn check_url(&self, url: &str, verify_content: bool) -> Result<UrlCheck> {
+        if let Some(check) = self.cached_check(url, verify_content) {
+            return Ok(check);
+        }
+        let mut pauses = 0;
+        loop {
+            self.wait_for_host(url).await;
+            let slot = self.acquire_slot().await;
+            let mut check = self.probe_url(url, None, verify_content, slot).await?;
+            if check.retry_after.is_none() || pauses >= MAX_HOST_PAUSES {
+                if let Some(attempt) = check.result.as_mut().and_then(|result| result.attempt.as_mut()) {
+                    attempt.pauses = pauses;
+                }
+                return Ok(check);
+            }
+            pauses += 1;
+        }
+    }
+    
+    /// 等待URL所在主机的 Retry-After 暂停结束，未暂停时立即返回
+    pub async fn wait_for_host(&self, url: &str) {
+        if let Some(host) = Url::parse(url).ok().and_then(|url| url.host_str().map(String::from)) {
+            self.host_pauses.wait(&host).await;
+        }
+    }
+    
+    /// URL所在主机剩余的 Retry-After 暂停时间，未暂停时为None
+    pub fn host_pause_remaining(&self, url: &str) -> Option<Duration> {
+        let host = Url::parse(url).ok()?.host_str()?.to_string();
+        self.host_pauses.remaining(&host)
+    }
+    
+    /// URL所在主机累计的 Retry-After 暂停时长，包括尚未结束的暂停
+    pub fn host_paused_total(&self, url: &str) -> Duration {
+        Url::parse(url).ok().and_then(|url| url.host_str().map(|host| self.host_pauses.paused_total(host)))
+            .unwrap_or_default()
+    }
+    
+    /// 按 Retry-After 暂停URL所在主机的请求
+    fn pause_host(&self, url: &str, delay: Duration) {
+        let Some(host) = Url::parse(url).ok().and_then(|url| url.host_str().map(String::from)) else { return };
+        if self.host_pauses.pause(&host, delay) {
+            println!("主机 {} 要求等待 {} 秒 (Retry-After)，暂停该主机的请求", host, delay.as_secs());
+        }
+    }
+    
+    /// 使用已取得的名额检查URL，名额在读取探测响应头后归还，后续的验证请求各自取得名额
+    ///
+    /// `method` 为模式指定的请求方法，未指定时使用默认的探测方法
+    pub(crate) async fn probe_url(&self, url: &str, method: Option<&Method>, verify_content: bool, slot: RequestSlot) -> Result<UrlCheck> {
         debug!("检查URL: {}", url);
         
-        // 使用更短的超时时间
-        let short_timeout = std::cmp::min(self.timeout_secs, 5); // 最多5秒
+        // 使用更短的超时时间，验证模式需要下载内容，使用完整超时
+        let short_timeout = if verify_content {
+            self.timeout_secs
+        } else {
+            std::cmp::min(self.timeout_secs, 5) // 最多5秒
+        };
         
-        // 只尝试一次请求
+        // 探测请求遇到暂时性错误时在 make_request 中重试，整体超时相应放宽
+        let start_time = Instant::now();
+        let mut check = UrlCheck::default();
         let request_result = timeout(
-            Duration::from_secs(short_timeout),
-            self.make_request(url, verify_content)
+            Duration::from_secs(short_timeout) + self.retry_allowance(),
+            self.make_request(url, method, verify_content, slot, &mut check)
         ).await;
         
-        match request_result {
-            Ok(result) => result,
+        check.result = match request_result {
+            Ok(result) => result.at_url(ErrorPhase::Probe, url)?,
             Err(_) => {
                 debug!("请求超时: {}", url);
-                Ok(None)
+                return Ok(check);
             }
+        };
+        if let Some(result) = &mut check.result {
+            result.method = method.map(ToString::to_string);
+            result.attempt = Some(AttemptInfo {
+                attempts: check.attempts,
+                method: method.cloned().unwrap_or_else(|| self.probe_method()).to_string(),
+                pauses: 0,
+                elapsed_ms: start_time.elapsed().as_millis() as u64,
+            });
         }
+        // 缓存按URL查找，指定方法的结果不能代替默认探测的结果
+        if let Some(cache) = self.response_cache.as_ref().filter(|_| method.is_none()) {
+            cache.insert_check(url, verify_content, &check);
+        }
+        Ok(check)
     }
     
     /// 检查目录是否存在并返回状态码
@@ -282,16 +1891,17 @@ impl HttpClient {
         debug!("检查目录状态: {}", url);
         
         // 生成随机请求头
-        let headers = self.generate_random_headers();
+        let headers = self.generate_random_headers(url);
         
         // 设置超时
-        let future = self.client.get(url)
+        let _slot = self.acquire_slot().await;
+        let future = self.request(Method::GET, url)
             .headers(headers.clone())
             .send();
             
         let timeout_duration = Duration::from_secs(self.timeout_secs);
         let response = match timeout(timeout_duration, future).await {
-            Ok(result) => result?,
+            Ok(result) => result.at_url(ErrorPhase::Probe, url)?,
             Err(_) => {
                 warn!("请求 {} 超时", url);
                 return Ok(None);
@@ -304,17 +1914,29 @@ impl HttpClient {
         Ok(Some(status.as_u16()))
     }
     
-    /// 生成随机请求头
-    fn generate_random_headers(&self) -> HeaderMap {
+    /// 生成随机请求头，随机性由种子和请求URL确定
+    fn generate_random_headers(&self, url: &str) -> HeaderMap {
         let mut headers = HeaderMap::new();
-        let mut rng = rand::thread_rng();
+        let mut rng = self.rng(url);
+        
+        // 模板要求只发送指定请求头
+        if let Some(template) = self.request_template.as_ref().filter(|t| t.replace_default_headers) {
+            apply_template_headers(&mut headers, template);
+            self.apply_identify(&mut headers);
+            return headers;
+        }
         
         // 设置User-Agent
-        let user_agent = if self.user_agent.is_empty() {
-            get_random_user_agent()
+        let user_agent = if let Some(strategy) = self.ua_strategy {
+            self.pick_user_agent(strategy, url, &mut rng)
+        } else if self.user_agent.is_empty() {
+            random_user_agent(&mut rng)
         } else if self.random_headers {
             // 随机UA
-            self.custom_user_agents.choose(&mut rng).cloned().unwrap_or_else(get_random_user_agent)
+            match self.custom_user_agents.choose(&mut rng) {
+                Some(user_agent) => user_agent.clone(),
+                None => random_user_agent(&mut rng),
+            }
         } else {
             // 使用指定的User-Agent
             self.user_agent.clone()
@@ -328,18 +1950,8 @@ impl HttpClient {
         // 添加其他随机请求头
         if self.random_headers {
             // 添加其他常见请求头
-            let accept_headers = [
-                ("accept", "text/html,application/xhtml+xml,application/xml;q=0.9,image/webp,*/*;q=0.8"),
-                ("accept-language", "en-US,en;q=0.9,zh-CN;q=0.8,zh;q=0.7"),
-                ("accept-encoding", "gzip, deflate, br"),
-                ("connection", "keep-alive"),
-                ("upgrade-insecure-requests", "1"),
-                ("pragma", "no-cache"),
-                ("cache-control", "no-cache"),
-            ];
-            
-            for (name, value) in accept_headers {
-                if rng.gen_bool(0.8) { // 80%的概率添加这个头
+            for (name, value) in BROWSER_HEADERS {
+                if rng.gen_bool(BROWSER_HEADER_PROBABILITY) {
                     if let Ok(header_value) = HeaderValue::from_str(value) {
                         // 使用HeaderName::from_str需要导入FromStr trait
                         if let Ok(header_name) = HeaderName::from_str(name) {
@@ -350,63 +1962,215 @@ impl HttpClient {
             }
         }
         
-        // 随机X-Forwarded-For IP
-        if self.random_ip {
-            let ip = format!(
+        // 伪装来源IP
+        let spoofed_ip = match &self.ip_spoof {
+            IpSpoof::Off => None,
+            IpSpoof::Random => Some(format!(
                 "{}.{}.{}.{}", 
                 rng.gen_range(1..=254), 
                 rng.gen_range(1..=254),
                 rng.gen_range(1..=254),
                 rng.gen_range(1..=254)
-            );
-            
-            if let Ok(header_value) = HeaderValue::from_str(&ip) {
-                // 使用HeaderName::from_str需要导入FromStr trait
-                if let Ok(header_name) = HeaderName::from_str("x-forwarded-for") {
-                    headers.insert(header_name, header_value);
+            )),
+            IpSpoof::Fixed(value) => Some(value.clone()),
+            IpSpoof::List(values) => values.choose(&mut rng).cloned(),
+        };
+        
+        if let Some(ip) = spoofed_ip {
+            for header_name in &self.spoof_headers {
+                // Forwarded头使用RFC 7239格式
+                let value = if header_name.as_str() == "forwarded" {
+                    format!("for={}", ip)
+                } else {
+                    ip.clone()
+                };
+                if let Ok(header_value) = HeaderValue::from_str(&value) {
+                    headers.insert(header_name.clone(), header_value);
                 }
             }
         }
         
+        // 模板请求头覆盖同名的随机请求头
+        if let Some(template) = &self.request_template {
+            apply_template_headers(&mut headers, template);
+        }
+        self.apply_identify(&mut headers);
+        
+        headers
+    }
+    
+    /// 添加标识请求头，覆盖模板中的同名请求头
+    fn apply_identify(&self, headers: &mut HeaderMap) {
+        if let Some((name, value)) = &self.identify {
+            headers.insert(name.clone(), value.clone());
+        }
+    }
+    
+    /// 扫描请求携带的请求头及取值，与 `generate_random_headers` 的规则一致
+    pub fn header_usage(&self) -> Vec<HeaderUsage> {
+        let usage = |name: &str, values: Vec<String>, when: &str| HeaderUsage { name: name.to_string(), values, when: when.to_string() };
+        let template_headers = || self.request_template.iter()
+            .flat_map(|template| &template.headers)
+            .map(|(name, value)| usage(&name.to_lowercase(), vec![value.clone()], "每次请求(请求模板)"));
+        let identify = || self.identify.iter()
+            .map(|(name, value)| usage(name.as_str(), vec![value.to_str().unwrap_or_default().to_string()], "每次请求(扫描标识)"));
+        if self.request_template.as_ref().is_some_and(|t| t.replace_default_headers) {
+            let mut headers: Vec<HeaderUsage> = template_headers().collect();
+            for header in identify() {
+                headers.retain(|existing| existing.name != header.name);
+                headers.push(header);
+            }
+            return headers;
+        }
+        
+        let mut headers = Vec::new();
+        let all_user_agents = || if self.custom_user_agents.is_empty() { vec!["内置浏览器UA之一".to_string()] } else { self.custom_user_agents.clone() };
+        headers.push(match self.ua_strategy {
+            Some(UaStrategy::Fixed) => usage("user-agent", vec![self.user_agent.clone()], "每次请求"),
+            Some(UaStrategy::PerHost) => usage("user-agent", all_user_agents(), "每个主机固定其中一个"),
+            Some(UaStrategy::PerRequest) => usage("user-agent", all_user_agents(), "每次请求随机选择其中一个"),
+            None if self.user_agent.is_empty() => usage("user-agent", vec!["内置浏览器UA之一".to_string()], "每次请求随机选择"),
+            None if self.random_headers => usage("user-agent", all_user_agents(), "每次请求随机选择其中一个"),
+            None => usage("user-agent", vec![self.user_agent.clone()], "每次请求"),
+        });
+        if self.random_headers {
+            let when = format!("每次请求以{}%的概率发送", (BROWSER_HEADER_PROBABILITY * 100.0) as u32);
+            headers.extend(BROWSER_HEADERS.iter().map(|(name, value)| usage(name, vec![value.to_string()], &when)));
+        }
+        let spoofed = match &self.ip_spoof {
+            IpSpoof::Off => None,
+            IpSpoof::Random => Some((vec!["随机IPv4地址".to_string()], "每次请求随机生成")),
+            IpSpoof::Fixed(value) => Some((vec![value.clone()], "每次请求")),
+            IpSpoof::List(values) => Some((values.clone(), "每次请求随机选择其中一个")),
+        };
+        if let Some((values, when)) = spoofed {
+            for header_name in &self.spoof_headers {
+                let values = if header_name.as_str() == "forwarded" {
+                    values.iter().map(|ip| format!("for={}", ip)).collect()
+                } else {
+                    values.clone()
+                };
+                headers.push(usage(header_name.as_str(), values, when));
+            }
+        }
+        // 模板请求头覆盖同名的请求头，标识请求头覆盖两者
+        for header in template_headers().chain(identify()) {
+            headers.retain(|existing| existing.name != header.name);
+            headers.push(header);
+        }
         headers
     }
     
+    /// 探测候选URL使用的请求方法
+    pub fn probe_method_name(&self) -> String {
+        self.probe_method().to_string()
+    }
+    
     /// 执行HTTP请求并分析响应
-    async fn make_request(&self, url: &str, verify_content: bool) -> Result<Option<ScanResult>> {
+    ///
+    /// 模式指定了请求方法时改用该方法探测，并且不再要求备份文件扩展名；GET和HEAD以外的方法
+    /// 响应的不是文件本身，2xx即作为未验证的发现报告
+    async fn make_request(&self, url: &str, method: Option<&Method>, verify_content: bool, mut slot: RequestSlot, check: &mut UrlCheck) -> Result<Option<ScanResult>> {
         // 生成随机请求头
-        let headers = self.generate_random_headers();
-        
-        // 使用固定超时，避免复杂计算
-        let timeout_duration = Duration::from_secs(3); // 固定3秒，比check_url更短
-        
-        // 开始计时
-        let start_time = Instant::now();
-        
-        // 设置超时 - 使用HEAD请求快速检测
-        let future = self.client.head(url)
-            .headers(headers.clone())
-            .timeout(timeout_duration) // 设置请求自身的超时
-            .send();
+        let mut headers = self.generate_random_headers(url);
+        let explicit = method.is_some();
+        let method = method.cloned().unwrap_or_else(|| self.probe_method());
+        // PROPFIND默认深度为infinity，多数服务器会拒绝，只列出一层
+        if method.as_str() == "PROPFIND" {
+            headers.insert(HeaderName::from_static("depth"), HeaderValue::from_static("1"));
+        }
+        let backup_like = explicit || is_backup_file_extension(url) || is_technology_artifact(url);
         
-        let response = match timeout(timeout_duration, future).await {
-            Ok(result) => match result {
-                Ok(resp) => resp,
-                Err(e) => {
+        // 使用HEAD请求快速检测；超时、连接失败/重置和502/503按 --retry 次数指数退避重试，
+        // 等待期间归还连接名额，重试前重新取得名额并等待限速
+        let mut attempt = 0;
+        let (response, duration) = loop {
+            check.attempts = attempt + 1;
+            // 开始计时，限速等待由 check_url/probe_url 的调用方在超时之外完成
+            let start_time = Instant::now();
+            let future = self.request(method.clone(), url)
+                .headers(headers.clone())
+                .timeout(PROBE_TIMEOUT) // 设置请求自身的超时
+                .send();
+            
+            let failure = match timeout(PROBE_TIMEOUT, future).await {
+                // 主机要求稍后再试时暂停该主机，由调用方在暂停结束后重新检查，不消耗重试次数
+                Ok(Ok(resp)) if matches!(resp.status(), StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE)
+                    && resp.headers().contains_key(reqwest::header::RETRY_AFTER) => {
+                    let delay = resp.headers().get(reqwest::header::RETRY_AFTER)
+                        .and_then(|value| value.to_str().ok())
+                        .and_then(parse_retry_after);
+                    if let Some(delay) = delay {
+                        debug!("HTTP请求返回 {}，Retry-After {:?}: {}", resp.status(), delay, url);
+                        self.pause_host(url, delay);
+                        check.retry_after = Some(delay);
+                        return Ok(None);
+                    }
+                    break (resp, start_time.elapsed());
+                },
+                // 质询页重试也不会通过，交给下面的质询检查
+                Ok(Ok(resp)) if matches!(resp.status(), StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE) && attempt < self.retry_count
+                    && challenge_from_headers(resp.status().as_u16(), resp.headers()).is_none() => {
+                    format!("状态码 {}", resp.status())
+                },
+                Ok(Ok(resp)) => break (resp, start_time.elapsed()),
+                Ok(Err(e)) if e.is_timeout() || e.is_connect() || e.is_request() => format!("{:?}", e),
+                Ok(Err(e)) => {
                     debug!("HTTP请求错误: {} - {:?}", url, e);
                     return Ok(None);
-                }
-            },
-            Err(_) => {
-                debug!("HTTP请求超时: {}", url);
+                },
+                Err(_) => "请求超时".to_string(),
+            };
+            if attempt >= self.retry_count {
+                debug!("HTTP请求失败，已重试 {} 次: {} - {}", attempt, url, failure);
                 return Ok(None);
             }
+            let delay = self.retry_delay(url, attempt);
+            attempt += 1;
+            debug!("HTTP请求失败，{:?} 后第 {} 次重试: {} - {}", delay, attempt, url, failure);
+            drop(slot);
+            tokio::time::sleep(delay).await;
+            slot = self.acquire_slot().await;
         };
         
         let status = response.status();
-        let duration = start_time.elapsed();
+        // 后续只需要响应头，丢弃响应并归还连接名额，验证请求各自取得名额
+        let response_headers = response.headers().clone();
+        drop(response);
+        drop(slot);
+        check.response_size = response_headers
+            .get(reqwest::header::CONTENT_LENGTH)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(0);
+        
+        // 质询页说明主机拦截了扫描，该响应和之后的响应都没有意义
+        if let Some(provider) = self.check_challenge(url, status, &response_headers, &headers).await {
+            check.challenge = Some(provider);
+            return Ok(None);
+        }
+        
+        if explicit && !matches!(method, Method::GET | Method::HEAD) {
+            return Ok(method_probe_result(url, &method, status, &response_headers));
+        }
+        
+        if (status.is_success() || status.is_redirection() || status == StatusCode::FORBIDDEN) && backup_like {
+            check.signal = Some(ProbeSignal {
+                status_code: status.as_u16(),
+                content_type: response_headers.get(reqwest::header::CONTENT_TYPE)
+                    .and_then(|h| h.to_str().ok())
+                    .map(String::from),
+                content_length: response_headers.get(reqwest::header::CONTENT_LENGTH)
+                    .and_then(|h| h.to_str().ok())
+                    .and_then(|s| s.parse::<u64>().ok()),
+            });
+        }
         
-        // 只在调试模式下输出所有状态
-        if self.debug || status.is_success() || status == StatusCode::FORBIDDEN {
+        // 只在调试模式下输出所有状态，调试模式附带耗时明细
+        if self.debug {
+            let timing = RequestTiming { ttfb: duration, total: duration };
+            debug!("URL {} 响应状态码: {} ({})", url, status, timing.describe(self.connect_timing(url).as_ref()));
+        } else if status.is_success() || status == StatusCode::FORBIDDEN {
             debug!("URL {} 响应状态码: {} (耗时: {:?})", url, status, duration);
         }
         
@@ -414,75 +2178,259 @@ impl HttpClient {
         // 1. 优先判断是否为200状态码（明确的成功）
         if status == StatusCode::OK {
             // 检查是否是备份文件扩展名
-            if !is_backup_file_extension(url) {
+            if !backup_like {
                 debug!("状态码为200但不是备份文件扩展名: {}", url);
                 return Ok(None);
             }
             
             // 获取响应头信息
-            let content_type = response.headers()
+            let content_type = response_headers
                 .get(reqwest::header::CONTENT_TYPE)
                 .and_then(|h| h.to_str().ok())
                 .map(String::from);
                 
-            let content_length = response.headers()
+            let content_length = response_headers
                 .get(reqwest::header::CONTENT_LENGTH)
                 .and_then(|h| h.to_str().ok())
                 .and_then(|s| s.parse::<u64>().ok());
             
-            // 检查内容类型
-            if let Some(ref ct) = content_type {
-                // 检查是否匹配预期的备份文件类型
-                if !self.is_valid_backup_content_type(ct, url) {
-                    debug!("状态码为200但内容类型不匹配: {} ({})", url, ct);
-                    // 我们不立即返回，因为有些服务器可能设置了错误的Content-Type
-                }
+            // 检查内容类型，不立即返回，因为有些服务器可能设置了错误的Content-Type；
+            // 不符时在结果中标记
+            let content_type_mismatch = is_content_type_mismatch(content_type.as_deref(), url);
+            if content_type_mismatch {
+                debug!("状态码为200但内容类型不匹配: {} ({})", url, content_type.as_deref().unwrap_or("-"));
             }
             
-            // 检查文件大小
-            if let Some(size) = content_length {
-                // 排除过小的文件 (小于100字节的可能是404页面)
-                if size < 100 {
-                    debug!("状态码为200但文件太小: {} ({}字节)", url, size);
-                    return Ok(None);
+            // 检查文件大小：过小的多为错误页，过大的多为误配置的下载接口，在验证请求之前排除
+            if let Some(reason) = self.size_limits.exclusion(content_length) {
+                debug!("状态码为200但文件大小超出范围: {} ({})", url, reason);
+                check.size_excluded = Some(reason);
+                return Ok(None);
+            }
+            
+            // 未验证内容时只能按响应头与泛200主机的校准特征比较
+            if !verify_content && self.matches_wildcard_headers(url, content_type.as_deref(), content_length) {
+                debug!("状态码为200但响应头与泛200特征相符(soft-404): {}", url);
+                return Ok(None);
+            }
+            
+            // 验证模式下用Range请求下载文件开头：比较与主机错误页的相似度，过滤模板化的200错误页，
+            // 并检查文件头，只有文件头相符(或扩展名没有已知文件头)时才标记为已验证
+            let mut verified = verify_content;
+            let mut verify_note = None;
+            let mut content_hash = None;
+            if verify_content {
+                match self.fetch_preview(url, headers.clone()).await {
+                    Some(preview) => {
+                        content_hash = Some(format!("{:016x}", fnv1a(&preview)));
+                        if self.matches_error_page(url, &preview) {
+                            debug!("状态码为200但内容与错误页近似: {}", url);
+                            return Ok(None);
+                        }
+                        if is_binary_url(url) && looks_like_html(&preview) {
+                            debug!("状态码为200但二进制文件内容是HTML页面: {}", url);
+                            return Ok(None);
+                        }
+                        match check_magic(url, &preview) {
+                            // 二进制文件头不符说明不是真实文件，SQL转储等文本文件可能格式特殊，只标记为未验证
+                            MagicVerdict::Mismatch(kind) if is_binary_url(url) => {
+                                debug!("状态码为200但文件头不是{}: {}", kind, url);
+                                return Ok(None);
+                            },
+                            MagicVerdict::Mismatch(kind) => {
+                                verified = false;
+                                verify_note = Some(format!("文件开头不像{}内容", kind));
+                            },
+                            MagicVerdict::Match | MagicVerdict::Unknown => {}
+                        }
+                        // 目标类型预设的平台配置文件须包含平台特征，否则是模板化的错误页或无关文件
+                        match self.target_type.matches_content(url, &preview) {
+                            Some(false) => {
+                                debug!("状态码为200但内容不含{}配置特征: {}", self.target_type.name(), url);
+                                return Ok(None);
+                            },
+                            Some(true) => verify_note = Some(format!("内容含{}配置特征", self.target_type.name())),
+                            None => {}
+                        }
+                    },
+                    None => {
+                        verified = false;
+                        verify_note = Some("无法下载文件开头进行验证".to_string());
+                    }
                 }
                 
-                // 排除过大的文件，防止误报 (超过1GB)
-                if size > 1_000_000_000 {
-                    debug!("状态码为200但文件太大: {} ({}字节)", url, size);
-                    // 我们不立即返回，因为有些备份确实很大
+                // 大文件按开头、中间、结尾分段采样，检测截断或损坏
+                if let Some(size) = content_length.filter(|size| *size > LARGE_FILE_THRESHOLD) {
+                    match self.sample_large_file(url, size, &headers).await {
+                        Some(SampleVerdict::ErrorPage) => {
+                            debug!("采样发现大文件实际为HTML错误页: {}", url);
+                            return Ok(None);
+                        },
+                        Some(SampleVerdict::Corrupt(reason)) => {
+                            debug!("采样发现文件不完整: {} ({})", url, reason);
+                            verified = false;
+                            verify_note = Some(reason);
+                        },
+                        Some(SampleVerdict::Intact) if verified => {
+                            verify_note = Some("分段采样验证通过".to_string());
+                        },
+                        Some(SampleVerdict::Intact) => {},
+                        None => {
+                            debug!("服务器不支持Range请求，跳过分段采样: {}", url);
+                        }
+                    }
+                }
+            }
+            
+            // 小型压缩包完整下载后在内存中解压，查找高价值文件
+            let mut indicators = Vec::new();
+            if verify_content && is_archive_url(url) && self.archive_scan_max_size > 0
+                && content_length.is_none_or(|size| size <= self.archive_scan_max_size) {
+                if let Some(content) = self.fetch_full(url, self.archive_scan_max_size, headers.clone()).await {
+                    match list_entries(&content, self.decompression_limits) {
+                        Some(Listing::Entries(entries)) => {
+                            indicators = find_indicators(&entries);
+                            debug!("压缩包 {} 包含 {} 个文件，其中 {} 个高价值文件", url, entries.len(), indicators.len());
+                        },
+                        // 解压炸弹不是可用的备份，单独作为可疑响应报告
+                        Some(Listing::Bomb(reason)) => {
+                            debug!("压缩包 {} 疑似解压炸弹: {}", url, reason);
+                            return Ok(Some(ScanResult {
+                                url: url.to_string(),
+                                status_code: status.as_u16(),
+                                content_type,
+                                content_type_mismatch,
+                                content_length,
+                                verified: false,
+                                suspicious: Some(format!("疑似解压炸弹: {}", reason)),
+                                ..Default::default()
+                            }));
+                        },
+                        None => {}
+                    }
+                }
+            }
+            
+            // SQL转储只下载开头部分，提取数据库名、表名和转储工具
+            let mut sql_summary = None;
+            if verify_content && is_sql_dump_url(url) {
+                if let Some(content) = self.fetch_prefix(url, SQL_SUMMARY_SIZE, headers.clone()).await {
+                    sql_summary = summarize_sql_dump(&content);
+                    debug!("SQL转储 {} 摘要: {:?}", url, sql_summary);
+                }
+            }
+            
+            // 暴露的.git目录：下载HEAD、config、引用和提交对象确认仓库真实可用，可选从索引重建文件列表
+            let mut git = None;
+            if verify_content {
+                if let Some(git_dir) = vcs::git_dir_url(url) {
+                    match self.inspect_git(git_dir, &headers).await {
+                        Some((exposure, git_indicators)) => {
+                            debug!(".git目录 {} 分析结果: {}", git_dir, exposure.describe());
+                            indicators.extend(git_indicators);
+                            verified = true;
+                            verify_note = Some(if exposure.objects {
+                                "可下载git对象，仓库可还原".to_string()
+                            } else {
+                                "内容是有效的git元数据".to_string()
+                            });
+                            git = Some(exposure);
+                        },
+                        None => {
+                            verified = false;
+                            verify_note = Some("内容不是有效的git元数据".to_string());
+                        }
+                    }
+                }
+            }
+            
+            // SVN、Mercurial元数据和.DS_Store完整下载后列出服务器上的文件名
+            let mut listing = None;
+            if verify_content {
+                if let Some(source) = ListingSource::from_url(url) {
+                    match self.fetch_full(url, vcs::LISTING_MAX_SIZE, headers.clone()).await
+                        .and_then(|content| source.parse(&content)) {
+                        Some(files) => {
+                            debug!("{} 列出 {} 个文件", url, files.len());
+                            indicators.extend(find_indicators(&files));
+                            verified = true;
+                            verify_note = Some(format!("内容是有效的{}", source.name()));
+                            listing = Some(FileListing::new(source, files));
+                        },
+                        None => {
+                            verified = false;
+                            verify_note = Some(format!("内容不是有效的{}", source.name()));
+                        }
+                    }
+                }
+            }
+            
+            // API定义文件不应是HTML页面，识别规范类型和接口数量
+            let mut api_summary = None;
+            if verify_content && is_api_leak_url(url) {
+                if let Some(content) = self.fetch_prefix(url, API_PREVIEW_SIZE, headers.clone()).await {
+                    if looks_like_html(&content) {
+                        debug!("状态码为200但API定义文件内容是HTML页面: {}", url);
+                        return Ok(None);
+                    }
+                    api_summary = describe_api_leak(&content);
+                    debug!("API定义文件 {} 摘要: {:?}", url, api_summary);
                 }
             }
             
+            // 复查：排除对相近路径也返回相同内容的响应和不稳定的响应
+            if self.double_check && !self.double_check(url, &headers).await {
+                return Ok(None);
+            }
+            
             // 200状态码且通过了基本校验，确认为备份文件
             debug!("确认发现备份文件 [200]: {}", url);
             return Ok(Some(ScanResult {
                 url: url.to_string(),
                 status_code: status.as_u16(),
                 content_type,
+                content_type_mismatch,
                 content_length,
-                verified: verify_content,
+                verified,
+                verify_note,
+                content_hash,
+                indicators,
+                sql_summary,
+                api_summary,
+                git,
+                listing,
+                ..Default::default()
             }));
         }
         
         // 2. 如果是403，可能是限制访问的备份文件
         else if status == StatusCode::FORBIDDEN {
             // 检查是否是备份文件扩展名
-            if !is_backup_file_extension(url) {
+            if !backup_like {
                 return Ok(None);
             }
             
             // 获取响应头信息
-            let content_type = response.headers()
+            let content_type = response_headers
                 .get(reqwest::header::CONTENT_TYPE)
                 .and_then(|h| h.to_str().ok())
                 .map(String::from);
                 
-            let content_length = response.headers()
+            let content_length = response_headers
                 .get(reqwest::header::CONTENT_LENGTH)
                 .and_then(|h| h.to_str().ok())
                 .and_then(|s| s.parse::<u64>().ok());
             
+            // 尝试绕过技巧，成功则转为确认的发现；绕过得到的文件大小超出范围时多为错误页，仍按403报告
+            if self.bypass_403 {
+                if let Some(result) = self.try_403_bypass(url).await {
+                    match self.size_limits.exclusion(result.content_length) {
+                        Some(reason) => debug!("绕过403得到的文件大小超出范围: {} ({})", url, reason),
+                        None => return Ok(Some(result)),
+                    }
+                }
+            }
+            
             debug!("发现可能受限制的备份文件 [403]: {}", url);
             return Ok(Some(ScanResult {
                 url: url.to_string(),
@@ -490,28 +2438,30 @@ impl HttpClient {
                 content_type,
                 content_length,
                 verified: false, // 403状态无法验证内容
+                ..Default::default()
             }));
         }
         
         // 3. 其他状态码如301/302/307重定向，尝试跟随重定向
         else if status.is_redirection() {
             // 只有备份文件扩展名才尝试跟随重定向
-            if !is_backup_file_extension(url) {
+            if !backup_like {
                 return Ok(None);
             }
             
             // 获取重定向位置
-            if let Some(location) = response.headers().get(reqwest::header::LOCATION) {
+            if let Some(location) = response_headers.get(reqwest::header::LOCATION) {
                 if let Ok(location_str) = location.to_str() {
                     debug!("URL {} 重定向到 {}", url, location_str);
                     
                     // 尝试GET请求跟随重定向 (限制只跟随一次重定向)
-                    let redirect_future = self.client.get(location_str)
+                    let _slot = self.acquire_slot().await;
+                    let redirect_future = self.request(Method::GET, location_str)
                         .headers(headers)
-                        .timeout(timeout_duration)
+                        .timeout(PROBE_TIMEOUT)
                         .send();
                         
-                    match timeout(timeout_duration, redirect_future).await {
+                    match timeout(PROBE_TIMEOUT, redirect_future).await {
                         Ok(Ok(redirect_resp)) => {
                             let redirect_status = redirect_resp.status();
                             
@@ -527,13 +2477,21 @@ impl HttpClient {
                                     .and_then(|h| h.to_str().ok())
                                     .and_then(|s| s.parse::<u64>().ok());
                                 
+                                if let Some(reason) = self.size_limits.exclusion(content_length) {
+                                    debug!("重定向后的文件大小超出范围: {} -> {} ({})", url, location_str, reason);
+                                    check.size_excluded = Some(reason);
+                                    return Ok(None);
+                                }
+                                
                                 debug!("经重定向发现备份文件: {} -> {}", url, location_str);
                                 return Ok(Some(ScanResult {
                                     url: url.to_string(), // 保留原始URL
                                     status_code: redirect_status.as_u16(),
+                                    content_type_mismatch: is_content_type_mismatch(content_type.as_deref(), url),
                                     content_type,
                                     content_length,
                                     verified: false,
+                                    ..Default::default()
                                 }));
                             }
                         },
@@ -549,53 +2507,257 @@ impl HttpClient {
         Ok(None)
     }
     
-    /// 检查内容类型是否符合备份文件预期
-    fn is_valid_backup_content_type(&self, content_type: &str, url: &str) -> bool {
-        let ct = content_type.to_lowercase();
-        
-        // 检查URL扩展名
-        if url.ends_with(".zip") || url.ends_with(".rar") || url.ends_with(".7z") {
-            return ct.contains("application/") || 
-                   ct.contains("application/zip") || 
-                   ct.contains("application/x-rar") || 
-                   ct.contains("application/x-7z") || 
-                   ct.contains("application/octet-stream");
+    /// 分析暴露的.git目录，`git_dir` 为以 `/.git/` 结尾的URL
+    ///
+    /// HEAD或config至少一个内容有效才算确认；随后按HEAD找到当前提交并下载松散对象，
+    /// 能解压出提交说明时仓库可以还原。启用 `--git-index` 时还会下载索引，返回其中的高价值文件
+    async fn inspect_git(&self, git_dir: &str, headers: &HeaderMap) -> Option<(GitExposure, Vec<String>)> {
+        let head = self.fetch_prefix(&format!("{}HEAD", git_dir), vcs::GIT_REF_SIZE, headers.clone()).await
+            .and_then(|content| vcs::parse_head(&content));
+        let config = self.fetch_prefix(&format!("{}config", git_dir), vcs::GIT_CONFIG_SIZE, headers.clone()).await
+            .and_then(|content| vcs::parse_config(&content));
+        if head.is_none() && config.is_none() {
+            return None;
         }
-        
-        if url.ends_with(".gz") || url.ends_with(".tar") || url.ends_with(".tar.gz") || url.ends_with(".tgz") {
-            return ct.contains("application/") || 
-                   ct.contains("application/gzip") || 
-                   ct.contains("application/x-tar") || 
-                   ct.contains("application/octet-stream");
+
+        let mut exposure = GitExposure::default();
+        if let Some(remote) = config.and_then(|config| config.remote) {
+            let (remote, credentials) = vcs::mask_remote(&remote);
+            exposure.remote = Some(remote);
+            exposure.credentials = credentials;
         }
-        
-        if url.ends_with(".sql") || url.ends_with(".sql.gz") {
-            return ct.contains("text/") || 
-                   ct.contains("application/sql") || 
-                   ct.contains("application/octet-stream");
+
+        // 引用文件不存在时可能已打包到packed-refs
+        exposure.commit = match &head {
+            Some(Head::Ref(reference)) => {
+                exposure.head = Some(reference.clone());
+                match self.fetch_prefix(&format!("{}{}", git_dir, reference), vcs::GIT_REF_SIZE, headers.clone()).await
+                    .and_then(|content| vcs::parse_ref(&content)) {
+                    Some(sha) => Some(sha),
+                    None => self.fetch_prefix(&format!("{}packed-refs", git_dir), vcs::GIT_CONFIG_SIZE, headers.clone()).await
+                        .and_then(|content| vcs::find_packed_ref(&content, reference)),
+                }
+            },
+            Some(Head::Detached(sha)) => {
+                exposure.head = Some(sha.clone());
+                Some(sha.clone())
+            },
+            None => None,
+        };
+
+        if let Some(sha) = &exposure.commit {
+            let object_url = format!("{}{}", git_dir, vcs::object_path(sha));
+            if let Some(commit) = self.fetch_full(&object_url, vcs::GIT_OBJECT_MAX_SIZE, headers.clone()).await
+                .and_then(|content| vcs::parse_commit_object(&content)) {
+                exposure.objects = true;
+                exposure.author = commit.author;
+                exposure.message = commit.message;
+            }
         }
-        
-        if url.ends_with(".db") || url.ends_with(".sqlite") || url.ends_with(".mdb") {
-            return ct.contains("application/") || 
-                   ct.contains("application/octet-stream");
+
+        let mut indicators = Vec::new();
+        if self.git_index {
+            if let Some(files) = self.fetch_full(&format!("{}index", git_dir), vcs::GIT_INDEX_MAX_SIZE, headers.clone()).await
+                .and_then(|content| vcs::parse_index(&content)) {
+                indicators = find_indicators(&files);
+                exposure.set_files(files);
+            }
         }
-        
-        if url.ends_with(".bak") || url.contains(".backup") || url.ends_with(".old") {
-            // 这些通用后缀可能是任何文件类型
-            return true;
+        Some((exposure, indicators))
+    }
+    
+    /// 并发尝试403绕过技巧，返回第一个得到200的技巧对应的结果
+    ///
+    /// 重写请求头技巧请求的是根路径，响应与不带该请求头的根路径相似时说明服务器忽略了请求头，不算绕过
+    async fn try_403_bypass(&self, url: &str) -> Option<ScanResult> {
+        let timeout_duration = Duration::from_secs(3);
+        let attempts = bypass_variants(url).into_iter().map(|variant| async move {
+            let mut headers = self.generate_random_headers(&format!("{}#{}", variant.url, variant.technique));
+            if let Some((name, value)) = &variant.header {
+                if let Ok(header_value) = HeaderValue::from_str(value) {
+                    headers.insert(HeaderName::from_static(name), header_value);
+                }
+            }
+            // 重写请求头技巧需要读取响应内容与根路径对比
+            let method = if variant.header.is_some() { Method::GET } else { self.probe_method() };
+            
+            let _slot = self.acquire_slot().await;
+            let future = self.request(method, &variant.url)
+                .headers(headers)
+                .timeout(timeout_duration)
+                .send();
+            match timeout(timeout_duration, future).await {
+                Ok(Ok(resp)) if resp.status() == StatusCode::OK => {
+                    let response_headers = resp.headers().clone();
+                    let preview = match variant.header {
+                        Some(_) => Some(Self::read_preview(resp, PREVIEW_SIZE).await),
+                        None => None,
+                    };
+                    Some((variant, response_headers, preview))
+                },
+                _ => None,
+            }
+        });
+
+        // 按技巧顺序选择第一个成功的结果，根路径只在需要对比时请求一次
+        let mut root_hash = None;
+        let mut chosen = None;
+        for (variant, response_headers, preview) in futures::future::join_all(attempts).await.into_iter().flatten() {
+            if let Some(preview) = preview {
+                if root_hash.is_none() {
+                    let headers = self.generate_random_headers(&variant.url);
+                    root_hash = Some(self.fetch_preview(&variant.url, headers).await.map(|root| simhash(&root)));
+                }
+                if root_hash.flatten().is_some_and(|hash| is_similar(simhash(&preview), hash, DEFAULT_SIMILARITY_THRESHOLD)) {
+                    debug!("403绕过 [{}] 的响应与根路径相似，服务器忽略了重写请求头: {}", variant.technique, url);
+                    continue;
+                }
+            }
+            chosen = Some((variant, response_headers));
+            break;
         }
-        
-        // 临时文件
-        url.ends_with(".tmp") ||
-        url.ends_with(".temp") ||
-        url.ends_with(".swp") ||
-        url.ends_with(".save") ||
-        url.ends_with(".old.php")
+        let (variant, response_headers) = chosen?;
+
+        debug!("403绕过成功 [{}]: {} -> {}", variant.technique, url, variant.url);
+        let content_type = response_headers
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|h| h.to_str().ok())
+            .map(String::from);
+        Some(ScanResult {
+            url: url.to_string(),
+            status_code: StatusCode::OK.as_u16(),
+            content_type_mismatch: is_content_type_mismatch(content_type.as_deref(), url),
+            content_type,
+            content_length: response_headers
+                .get(reqwest::header::CONTENT_LENGTH)
+                .and_then(|h| h.to_str().ok())
+                .and_then(|s| s.parse::<u64>().ok()),
+            verified: false,
+            bypass: Some(variant.technique.to_string()),
+            bypass_url: Some(variant.url),
+            ..Default::default()
+        })
+    }
+}
+
+/// 质询检测按主机和端口区分，同一主机名的不同端口可能是不同的服务
+fn challenge_key(url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    Some(format!("{}:{}", url.host_str()?, url.port_or_known_default()?))
+}
+
+/// 解析 `--resolve` 的固定解析，格式为 `主机:地址`，如 `shop.example:10.0.0.5`、`shop.example:[2001:db8::1]`
+///
+/// DNS解析与端口无关，端口由URL决定
+pub fn parse_resolve_override(spec: &str) -> Result<(String, IpAddr)> {
+    let invalid = || crate::BackerError::Config(format!("无效的固定解析: {} (格式为 \"主机:地址\")", spec));
+    let (host, addr) = spec.split_once(':').ok_or_else(invalid)?;
+    let host = host.trim().to_lowercase();
+    let addr = addr.trim().trim_start_matches('[').trim_end_matches(']');
+    if host.is_empty() || host.parse::<IpAddr>().is_ok() {
+        return Err(invalid());
+    }
+    let addr = addr.parse::<IpAddr>().map_err(|_| invalid())?;
+    Ok((host, addr))
+}
+
+/// 解析 `--identify` 的标识请求头，格式为 `名称: 值`，如 `X-Scanner: backer/1.x contact@corp`
+pub fn parse_identify_header(header: &str) -> Result<(HeaderName, HeaderValue)> {
+    let invalid = || crate::BackerError::Config(format!("无效的标识请求头: {} (格式为 \"名称: 值\")", header));
+    let (name, value) = header.split_once(':').ok_or_else(invalid)?;
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(invalid());
+    }
+    let name = HeaderName::from_str(name.trim()).map_err(|_| invalid())?;
+    let value = HeaderValue::from_str(value).map_err(|_| invalid())?;
+    Ok((name, value))
+}
+
+/// 标识模式下未指定 `-a` 时使用的User-Agent
+pub fn identify_user_agent() -> String {
+    format!("backer/{}", env!("CARGO_PKG_VERSION"))
+}
+
+/// 按顺序写入模板请求头
+fn apply_template_headers(headers: &mut HeaderMap, template: &RequestTemplate) {
+    for (name, value) in &template.headers {
+        match (HeaderName::from_str(name), HeaderValue::from_str(value)) {
+            (Ok(header_name), Ok(header_value)) => {
+                headers.insert(header_name, header_value);
+            },
+            _ => warn!("忽略无效的模板请求头: {}: {}", name, value),
+        }
+    }
+}
+
+/// 在URL文件名的第一个 `.` 之前插入后缀，如 `/db/backup.tar.gz` -> `/db/backup-k3x9q2.tar.gz`，
+/// 路径以 `/` 结尾时返回None
+fn mutated_sibling(url: &str, suffix: &str) -> Option<String> {
+    let mut url = Url::parse(url).ok()?;
+    let (dir, name) = url.path().rsplit_once('/')?;
+    if name.is_empty() {
+        return None;
+    }
+    let name = match name.split_once('.') {
+        Some((stem, extension)) => format!("{}-{}.{}", stem, suffix, extension),
+        None => format!("{}-{}", name, suffix),
+    };
+    let path = format!("{}/{}", dir, name);
+    url.set_path(&path);
+    Some(url.to_string())
+}
+
+/// 模式指定的GET、HEAD以外方法的探测结果：响应不是文件本身，2xx(如PROPFIND的207)即作为未验证的发现，
+/// 说明中记录方法和 `Allow`、`DAV` 响应头
+fn method_probe_result(url: &str, method: &Method, status: StatusCode, headers: &HeaderMap) -> Option<ScanResult> {
+    if !status.is_success() {
+        debug!("{} {} 响应状态码: {}", method, url, status);
+        return None;
+    }
+    let header = |name: &str| headers.get(name).and_then(|h| h.to_str().ok());
+    let mut note = format!("{} 请求返回 {}", method, status);
+    for name in ["allow", "dav"] {
+        if let Some(value) = header(name) {
+            note.push_str(&format!("，{}: {}", name.to_uppercase(), value));
+        }
+    }
+    debug!("{} {} 返回 {}", method, url, status);
+    Some(ScanResult {
+        url: url.to_string(),
+        status_code: status.as_u16(),
+        content_type: header("content-type").map(String::from),
+        content_length: header("content-length").and_then(|length| length.parse().ok()),
+        verified: false,
+        verify_note: Some(note),
+        ..Default::default()
+    })
+}
+
+/// 响应对应的完整内容长度，Range请求返回206时取 Content-Range 中的总长度
+fn full_length(status: StatusCode, headers: &HeaderMap) -> Option<u64> {
+    let header = |name| headers.get(name).and_then(|h: &HeaderValue| h.to_str().ok());
+    if status == StatusCode::PARTIAL_CONTENT {
+        header(reqwest::header::CONTENT_RANGE)
+            .and_then(|range| range.rsplit_once('/'))
+            .and_then(|(_, total)| total.parse().ok())
+    } else {
+        header(reqwest::header::CONTENT_LENGTH).and_then(|length| length.parse().ok())
+    }
+}
+
+/// 解析HTTP版本字符串
+fn parse_http_version(version: &str) -> Option<Version> {
+    match version.trim().to_lowercase().trim_start_matches("http/") {
+        "1.0" => Some(Version::HTTP_10),
+        "1.1" => Some(Version::HTTP_11),
+        "2" | "2.0" => Some(Version::HTTP_2),
+        _ => None,
     }
 }
 
 /// 检查URL是否有备份文件扩展名
-fn is_backup_file_extension(url: &str) -> bool {
+pub fn is_backup_file_extension(url: &str) -> bool {
     let url_lower = url.to_lowercase();
     
     // 压缩文件常见格式
@@ -631,6 +2793,7 @@ fn is_backup_file_extension(url: &str) -> bool {
     url_lower.ends_with("/.git/config") ||
     url_lower.ends_with("/.git/HEAD") ||
     url_lower.ends_with("/.svn/entries") ||
+    url_lower.ends_with("/.ds_store") ||
     url_lower.ends_with("/.env") ||
     url_lower.ends_with("/.htpasswd") ||
     url_lower.ends_with("/wp-config.php.bak") ||
@@ -638,11 +2801,148 @@ fn is_backup_file_extension(url: &str) -> bool {
     url_lower.contains(".config.") ||
     url_lower.contains("/.git/") ||
     url_lower.contains("/.svn/") ||
+    url_lower.contains("/.hg/") ||
+    url_lower.contains("/.bzr/") ||
+    url_lower.contains("/cvs/") ||
     
     // 临时文件
     url_lower.ends_with(".tmp") ||
     url_lower.ends_with(".temp") ||
     url_lower.ends_with(".swp") ||
     url_lower.ends_with(".save") ||
-    url_lower.ends_with(".old.php")
-}
\ No newline at end of file
+    url_lower.ends_with(".old.php") ||
+    
+    // API定义文件和集合
+    is_api_leak_url(url)
+}
+
+/// 检查URL是否为技术栈专属路径中的部署包或编辑器备份
+///
+/// Tomcat、Spring Boot的 `.war`/`.jar` 部署包含有配置和源码，`wp-config.php~` 等编辑器备份保留原文件内容；
+/// 内置规则不生成这些后缀，只有模式库中的专属路径会请求
+pub fn is_technology_artifact(url: &str) -> bool {
+    let url_lower = url.to_lowercase();
+    url_lower.ends_with(".war") || url_lower.ends_with(".jar") || url_lower.ends_with('~')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// 并发任务数，模拟 `-j 256` 时同时进行的请求
+    const BENCH_TASKS: usize = 256;
+    /// 每个任务的请求数，每次请求查询一次自适应超时并记录一次响应时间
+    const BENCH_ROUNDS: usize = 20_000;
+    /// 主机数
+    const BENCH_HOSTS: usize = 64;
+
+    /// 改用DashMap之前的响应时间记录：所有主机共用一把锁
+    #[derive(Default)]
+    struct LockedResponseTimes(Mutex<HashMap<String, Vec<Duration>>>);
+
+    impl LockedResponseTimes {
+        fn adaptive_timeout(&self, url: &str) -> Option<Duration> {
+            let host = Url::parse(url).ok()?.host_str()?.to_string();
+            let times = self.0.lock().unwrap();
+            let times = times.get(&host).filter(|times| !times.is_empty())?;
+            Some(times.iter().sum::<Duration>() / times.len() as u32 * 3 + Duration::from_secs(2))
+        }
+
+        fn record(&self, url: &str, duration: Duration) {
+            let Some(host) = Url::parse(url).ok().and_then(|url| url.host_str().map(String::from)) else { return };
+            let mut times = self.0.lock().unwrap();
+            let times = times.entry(host).or_default();
+            times.push(duration);
+            if times.len() > 10 {
+                times.remove(0);
+            }
+        }
+    }
+
+    /// 启动 `BENCH_TASKS` 个任务，每个任务对各主机轮流执行 `request`，返回每秒请求数
+    async fn requests_per_second<F>(request: F) -> f64
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        let request = Arc::new(request);
+        let urls: Arc<Vec<String>> = Arc::new((0..BENCH_HOSTS).map(|i| format!("https://host{}.example/backup.zip", i)).collect());
+        let start = Instant::now();
+        let tasks: Vec<_> = (0..BENCH_TASKS).map(|task| {
+            let request = request.clone();
+            let urls = urls.clone();
+            tokio::spawn(async move {
+                for round in 0..BENCH_ROUNDS {
+                    request(&urls[(task + round) % BENCH_HOSTS]);
+                    if round % 64 == 0 {
+                        tokio::task::yield_now().await;
+                    }
+                }
+            })
+        }).collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+        (BENCH_TASKS * BENCH_ROUNDS) as f64 / start.elapsed().as_secs_f64()
+    }
+
+    /// 比较单锁HashMap和当前的DashMap在高并发下记录响应时间的吞吐量
+    ///
+    /// `cargo test --release --lib bench_response_times -- --ignored --nocapture`
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    #[ignore = "基准测试，需在release模式下手动运行"]
+    async fn bench_response_times() {
+        let locked = Arc::new(LockedResponseTimes::default());
+        let locked_rate = requests_per_second(move |url| {
+            let _ = locked.adaptive_timeout(url);
+            locked.record(url, Duration::from_millis(40));
+        }).await;
+
+        let client = Arc::new(HttpClient::new(10, 0, "backer-bench".to_string()).unwrap());
+        let sharded_rate = requests_per_second(move |url| {
+            let _ = client.get_adaptive_timeout(url);
+            client.record_response_time(url, Duration::from_millis(40));
+        }).await;
+
+        println!("{} 个任务 × {} 次请求，{} 个主机，{} 个CPU",
+            BENCH_TASKS, BENCH_ROUNDS, BENCH_HOSTS, std::thread::available_parallelism().map_or(1, usize::from));
+        println!("Mutex<HashMap>: {:.2}M 次/秒", locked_rate / 1e6);
+        println!("DashMap:        {:.2}M 次/秒 ({:.2}x)", sharded_rate / 1e6, sharded_rate / locked_rate);
+    }
+
+    /// 启动只对根路径返回200的服务器，`rewrite_page` 为Some时按 X-Original-URL 返回该内容
+    async fn rewrite_server(rewrite_page: Option<&'static str>) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+                let path = request.split_whitespace().nth(1).unwrap_or("").to_string();
+                let rewritten = request.contains("x-original-url:");
+                let (status, body) = match (path.as_str(), rewrite_page) {
+                    ("/", Some(page)) if rewritten => ("200 OK", page),
+                    ("/", _) => ("200 OK", "<html><head><title>Example Shop</title></head><body>welcome to the example shop home page</body></html>"),
+                    _ => ("403 Forbidden", "forbidden"),
+                };
+                let response = format!("HTTP/1.1 {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}", status, body.len(), body);
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        format!("http://{}/backup.zip", addr)
+    }
+
+    #[tokio::test]
+    async fn rewrite_header_bypass_requires_different_content() {
+        let client = HttpClient::new(5, 0, "backer-test".to_string()).unwrap();
+
+        let url = rewrite_server(None).await;
+        assert!(client.try_403_bypass(&url).await.is_none());
+
+        let url = rewrite_server(Some("PK\u{3}\u{4} backup archive listing: db.sql config.php wp-content uploads")).await;
+        let result = client.try_403_bypass(&url).await.unwrap();
+        assert_eq!(result.bypass.as_deref(), Some("x-original-url"));
+    }
+}
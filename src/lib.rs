@@ -2,8 +2,56 @@ pub mod scanner;
 pub mod patterns;
 pub mod http;
 pub mod utils;
+pub mod similarity;
+pub mod fingerprint;
+pub mod stats;
+pub mod triage;
+pub mod liveness;
+pub mod config;
+pub mod bypass;
+pub mod verify;
+pub mod archive;
+pub mod sqldump;
+pub mod report;
+pub mod candidate;
+pub mod events;
+pub mod sink;
+pub mod elastic;
+pub mod watch;
+pub mod recon;
+pub mod tech;
+pub mod api;
+pub mod category;
+pub mod seclists;
+pub mod robots;
+pub mod help;
+pub mod update;
+pub mod patterndb;
+pub mod defectdojo;
+pub mod sarif;
+pub mod ratelimit;
+pub mod timing;
+pub mod checkpoint;
+pub mod mime;
+pub mod download;
+pub mod proxy;
+pub mod redact;
+pub mod notify;
+pub mod signing;
+pub mod diff;
+pub mod noise;
+pub mod dns;
+pub mod manager;
+pub mod cache;
+pub mod preset;
+pub mod baseline;
+pub mod stage;
+pub mod cluster;
+pub mod script;
+pub mod vcs;
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use thiserror::Error;
 use serde::{Serialize, Deserialize};
 
@@ -32,10 +80,195 @@ pub enum BackerError {
     
     #[error("其它错误: {0}")]
     Other(String),
+    
+    /// [`ScanConfig::validate`] 检出的全部配置问题
+    #[error("配置无效: {}", describe_issues(.0))]
+    InvalidConfig(Vec<ConfigIssue>),
+    
+    /// 附带出错阶段、目标、URL和文件的错误，由 [`ResultExt`] 添加
+    #[error("{}", describe_context(*phase, target.as_deref(), url.as_deref(), path.as_deref(), source))]
+    Context {
+        phase: Option<ErrorPhase>,
+        target: Option<String>,
+        url: Option<String>,
+        path: Option<PathBuf>,
+        #[source]
+        source: Box<BackerError>,
+    },
 }
 
 pub type Result<T> = std::result::Result<T, BackerError>;
 
+/// 出错的扫描阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorPhase {
+    /// 创建扫描器：读取配置引用的文件、连接输出服务等
+    Setup,
+    /// 读取目标列表
+    Load,
+    /// 存活检测和校准
+    Liveness,
+    /// 探测候选URL
+    Probe,
+    /// 下载发现的文件
+    Download,
+    /// 用户脚本
+    Script,
+    /// 写入结果文件
+    Output,
+    /// 发布到消息队列、Elasticsearch等外部服务
+    Publish,
+}
+
+impl ErrorPhase {
+    /// 名称，如 "探测"
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorPhase::Setup => "初始化",
+            ErrorPhase::Load => "读取目标",
+            ErrorPhase::Liveness => "存活检测",
+            ErrorPhase::Probe => "探测",
+            ErrorPhase::Download => "下载",
+            ErrorPhase::Script => "脚本",
+            ErrorPhase::Output => "写入结果",
+            ErrorPhase::Publish => "发布结果",
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// 带上下文的错误说明，如 "探测失败 (目标 https://example.com，URL https://example.com/a.zip): HTTP错误: ..."
+fn describe_context(phase: Option<ErrorPhase>, target: Option<&str>, url: Option<&str>, path: Option<&Path>, source: &BackerError) -> String {
+    let mut location = Vec::new();
+    if let Some(target) = target {
+        location.push(format!("目标 {}", target));
+    }
+    // URL与目标相同时不重复
+    if let Some(url) = url.filter(|url| Some(*url) != target) {
+        location.push(format!("URL {}", url));
+    }
+    if let Some(path) = path {
+        location.push(format!("文件 {}", path.display()));
+    }
+    let mut text = phase.map_or_else(|| "失败".to_string(), |phase| format!("{}失败", phase));
+    if !location.is_empty() {
+        text.push_str(&format!(" ({})", location.join("，")));
+    }
+    format!("{}: {}", text, source)
+}
+
+impl BackerError {
+    /// 补充上下文：已带上下文的错误只填入尚未记录的字段，保留最先记录的(最具体的)阶段和URL
+    pub fn annotate(self, phase: Option<ErrorPhase>, target: Option<&str>, url: Option<&str>) -> Self {
+        match self {
+            BackerError::Context { phase: inner_phase, target: inner_target, url: inner_url, path, source } => BackerError::Context {
+                phase: inner_phase.or(phase),
+                target: inner_target.or_else(|| target.map(String::from)),
+                url: inner_url.or_else(|| url.map(String::from)),
+                path,
+                source,
+            },
+            error => BackerError::Context {
+                phase,
+                target: target.map(String::from),
+                url: url.map(String::from),
+                path: None,
+                source: Box::new(error),
+            },
+        }
+    }
+
+    /// 补充出错的阶段和读写的文件，已记录的文件保留
+    pub fn annotate_path(self, phase: Option<ErrorPhase>, path: &Path) -> Self {
+        match self.annotate(phase, None, None) {
+            BackerError::Context { phase, target, url, path: inner_path, source } => BackerError::Context {
+                phase,
+                target,
+                url,
+                path: inner_path.or_else(|| Some(path.to_path_buf())),
+                source,
+            },
+            error => error,
+        }
+    }
+
+    /// 出错的阶段
+    pub fn phase(&self) -> Option<ErrorPhase> {
+        match self {
+            BackerError::Context { phase, .. } => *phase,
+            _ => None,
+        }
+    }
+
+    /// 出错的目标
+    pub fn target(&self) -> Option<&str> {
+        match self {
+            BackerError::Context { target, .. } => target.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// 出错的URL
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            BackerError::Context { url, .. } => url.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// 出错时读写的文件
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            BackerError::Context { path, .. } => path.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// 去掉上下文后的原始错误
+    pub fn root(&self) -> &BackerError {
+        match self {
+            BackerError::Context { source, .. } => source.root(),
+            error => error,
+        }
+    }
+}
+
+/// 为错误附加出错阶段、目标和URL
+pub trait ResultExt<T> {
+    /// 记录出错的阶段
+    fn during(self, phase: ErrorPhase) -> Result<T>;
+    /// 记录出错的阶段和请求的URL
+    fn at_url(self, phase: ErrorPhase, url: &str) -> Result<T>;
+    /// 记录出错的阶段和读写的文件
+    fn at_path(self, phase: ErrorPhase, path: &Path) -> Result<T>;
+    /// 记录出错的目标
+    fn for_target(self, target: &str) -> Result<T>;
+}
+
+impl<T, E: Into<BackerError>> ResultExt<T> for std::result::Result<T, E> {
+    fn during(self, phase: ErrorPhase) -> Result<T> {
+        self.map_err(|e| e.into().annotate(Some(phase), None, None))
+    }
+
+    fn at_url(self, phase: ErrorPhase, url: &str) -> Result<T> {
+        self.map_err(|e| e.into().annotate(Some(phase), None, Some(url)))
+    }
+
+    fn at_path(self, phase: ErrorPhase, path: &Path) -> Result<T> {
+        self.map_err(|e| e.into().annotate_path(Some(phase), path))
+    }
+
+    fn for_target(self, target: &str) -> Result<T> {
+        self.map_err(|e| e.into().annotate(None, Some(target), None))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ScanConfig {
     /// 目标站点文件
@@ -52,19 +285,146 @@ pub struct ScanConfig {
     pub user_agent: String,
     /// 输出格式
     pub output_format: OutputFormat,
-    /// 输出文件
+    /// 输出文件，格式为 `ndjson` 时扫描中每个发现立即追加到该文件
     pub output_file: Option<PathBuf>,
     /// 是否验证文件内容
     pub verify_content: bool,
     /// 是否启用调试模式
     pub debug: bool,
+    /// 主机指纹缓存文件
+    pub fingerprint_cache: Option<PathBuf>,
+    /// 忽略列表文件(人工确认的误报URL)
+    pub ignore_list: Option<PathBuf>,
+    /// 基线结果文件，与其中URL(及内容哈希)相符的发现视为已知，不报告
+    pub baseline: Option<PathBuf>,
+    /// Lua脚本文件，生成额外候选并审核发现，见 [`script::ScriptHooks`]
+    pub script: Option<PathBuf>,
+    /// 分阶段输出目录，写入验证前的候选 `candidates.jsonl` 和验证后的发现 `findings.json`
+    pub stage_dir: Option<PathBuf>,
+    /// 模式统计文件，用于按历史命中率排序
+    pub pattern_stats: Option<PathBuf>,
+    /// 模式效果报告文件，扫描后写入本次各模式的尝试、命中和浪费字节数
+    pub pattern_report: Option<PathBuf>,
+    /// 单个目标的确认发现(200)达到该数量后跳过其剩余候选
+    pub stop_after_hits: Option<usize>,
+    /// 所有目标的发现上限，达到后丢弃新发现并跳过剩余候选
+    pub max_findings: Option<usize>,
+    /// 单个主机的发现上限，达到后丢弃该主机的新发现并跳过其剩余候选
+    pub max_findings_per_host: Option<usize>,
+    /// 同时进行中的请求数上限，覆盖探测、校准、验证下载等所有请求
+    pub max_connections: Option<usize>,
+    /// 启动爬坡时长(秒)：扫描开始时以目标并发的1/10发送请求，在该时长内逐步提高，错误率过高时暂停爬坡，0表示禁用
+    pub ramp_up: u64,
+    /// 断点续扫的状态文件，扫描中定期保存进度，重新启动时跳过已完成的工作
+    pub resume: Option<PathBuf>,
+    /// 慢速主机阈值(毫秒)，主机请求延迟的中位数超过该值时，剩余候选移入慢速队列，
+    /// 在其他目标扫描完成后处理
+    pub slow_host_threshold: Option<u64>,
+    /// 慢速队列的并发线程数
+    pub slow_lane_threads: usize,
+    /// 发现的内容类型必须匹配其中之一，如 `application/*`；为空时不限制
+    pub require_content_types: Vec<String>,
+    /// 内容类型匹配其中任一的响应不算发现，如 `text/html`
+    pub exclude_content_types: Vec<String>,
+    /// 请求模板(来自配置文件档案)
+    pub request_template: Option<http::RequestTemplate>,
+    /// 来源IP伪装方式
+    pub ip_spoof: http::IpSpoof,
+    /// 伪装IP使用的请求头
+    pub spoof_headers: Vec<String>,
+    /// 扫描前对每个主机请求的随机不存在路径数，识别soft-404主机并过滤与其错误页相符的发现，0表示禁用
+    pub soft_404_probes: usize,
+    /// 报告200发现前重新请求URL和加了随机后缀的同级路径，两者返回相同的200响应或URL不再返回200时视为误报
+    pub double_check: bool,
+    /// 对403结果尝试绕过技巧
+    pub bypass_403: bool,
+    /// 检测JS质询和验证码拦截页：首页是质询页的目标不扫描，扫描中遇到质询页时跳过该主机的剩余候选
+    pub challenge_detection: bool,
+    /// 在一次扫描内缓存响应，同一URL的重复请求(如不同阶段的预览下载、重复的目标)复用之前的响应
+    pub response_cache: bool,
+    /// 验证时参与内存解压扫描的最大压缩包大小(字节)，0表示禁用
+    pub archive_scan_max_size: u64,
+    /// 验证时确认暴露的.git目录后下载索引，重建服务器上的文件列表
+    pub git_index: bool,
+    /// 内存解压读取的最大字节数
+    pub max_decompressed_size: u64,
+    /// 最大解压比，超过时作为可疑响应(压缩炸弹)报告
+    pub max_compression_ratio: u64,
+    /// 随机数种子，指定后随机行为可复现
+    pub seed: Option<u64>,
+    /// 消息总线地址，每个发现实时发布为一条JSON消息
+    pub sink: Option<String>,
+    /// Elasticsearch/OpenSearch地址，发现实时批量写入索引
+    pub es_url: Option<String>,
+    /// 写入的Elasticsearch索引名
+    pub es_index: String,
+    /// 按严重程度路由的通知渠道，来自配置文件的 `[notify]` 段
+    pub notify: notify::NotifyConfig,
+    /// 扫描前从证书透明度日志扩展子域名目标
+    pub ct_subdomains: bool,
+    /// 范围排除列表文件，扩展出的子域名命中时不扫描
+    pub scope_exclude: Option<PathBuf>,
+    /// 扫描前反查各目标的同IP站点并列出
+    pub reverse_ip: bool,
+    /// 将反查到的同IP站点加入扫描目标
+    pub expand_neighbors: bool,
+    /// 通过favicon哈希识别技术栈，并追加技术栈专属路径
+    pub favicon_fingerprint: bool,
+    /// 补充的favicon哈希库文件
+    pub favicon_db: Option<PathBuf>,
+    /// 通过首页响应头、Cookie、`/wp-login.php` 和 `/composer.json` 识别CMS和框架，并追加技术栈专属路径
+    pub cms_fingerprint: bool,
+    /// 额外启用的内置模式分类
+    pub categories: Vec<category::Category>,
+    /// 单个目标按模式分类的请求预算
+    pub budget: Option<category::CategoryBudget>,
+    /// 目标类型预设：假定技术栈、启用相关分类、过滤其他技术栈的路径，并按平台特征验证配置文件
+    pub target_type: preset::TargetType,
+    /// SecLists目录，相关字典按分类导入
+    pub seclists: Option<PathBuf>,
+    /// 备份目录组合深度，0表示不生成目录候选
+    pub dir_depth: usize,
+    /// 自定义User-Agent列表，为空时使用内置列表
+    pub user_agents: Vec<String>,
+    /// User-Agent轮换策略，未设置时由随机请求头开关决定
+    pub ua_strategy: Option<http::UaStrategy>,
+    /// 授权扫描的标识请求头，如 `X-Scanner: backer/1.x contact@corp`，每次请求都发送
+    pub identify: Option<String>,
+    /// 遵守robots.txt，不请求被禁止路径下的候选
+    pub respect_robots: bool,
+    /// 连接目标时使用的IP协议版本
+    pub ip_version: http::IpVersion,
+    /// 扫描前并发解析所有目标主机，无法解析的主机立即报告，请求时直接使用解析出的地址
+    pub dns_prefetch: bool,
+    /// 固定解析，`主机:地址`，连接该地址但Host请求头和SNI仍使用主机名，用于虚拟主机扫描
+    pub resolve: Vec<String>,
+    /// 代理地址，支持 `http://`、`https://`、`socks5://` 和 `socks5h://`；
+    /// 未设置时使用 `HTTP_PROXY`/`HTTPS_PROXY` 环境变量
+    pub proxy: Option<String>,
+    /// 轮换使用的代理列表，非空时代替 `proxy`
+    pub proxies: Vec<String>,
+    /// 代理列表的轮换策略
+    pub proxy_rotation: proxy::ProxyRotation,
+    /// 连续连接失败多少次后停用代理
+    pub proxy_max_failures: u32,
+    /// 发现的最小文件大小(字节)，Content-Length 更小的200响应不算发现
+    pub min_size: u64,
+    /// 发现的最大文件大小(字节)，Content-Length 更大的200响应不算发现
+    pub max_size: u64,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum OutputFormat {
     Json,
     Csv,
     Markdown,
+    /// DefectDojo通用发现导入格式(Generic Findings Import)
+    DefectDojo,
+    /// SARIF 2.1.0，可上传到GitHub Code Scanning
+    Sarif,
+    /// 每行一个JSON对象(JSON Lines)，扫描时发现即追加
+    Ndjson,
 }
 
 impl Default for ScanConfig {
@@ -80,21 +440,533 @@ impl Default for ScanConfig {
             output_file: None,
             verify_content: false,
             debug: false,
+            fingerprint_cache: None,
+            ignore_list: None,
+            baseline: None,
+            script: None,
+            stage_dir: None,
+            pattern_stats: None,
+            pattern_report: None,
+            stop_after_hits: None,
+            max_findings: None,
+            max_findings_per_host: None,
+            max_connections: None,
+            ramp_up: ratelimit::DEFAULT_RAMP_UP_SECS,
+            resume: None,
+            slow_host_threshold: None,
+            slow_lane_threads: 2,
+            require_content_types: Vec::new(),
+            exclude_content_types: Vec::new(),
+            request_template: None,
+            ip_spoof: http::IpSpoof::Off,
+            spoof_headers: vec!["x-forwarded-for".to_string()],
+            soft_404_probes: http::DEFAULT_SOFT_404_PROBES,
+            double_check: false,
+            bypass_403: false,
+            challenge_detection: true,
+            response_cache: true,
+            archive_scan_max_size: archive::DEFAULT_ARCHIVE_SCAN_MAX_SIZE,
+            git_index: false,
+            max_decompressed_size: archive::DEFAULT_MAX_DECOMPRESSED_SIZE,
+            max_compression_ratio: archive::DEFAULT_MAX_COMPRESSION_RATIO,
+            seed: None,
+            sink: None,
+            es_url: None,
+            es_index: elastic::DEFAULT_ES_INDEX.to_string(),
+            notify: notify::NotifyConfig::default(),
+            ct_subdomains: false,
+            scope_exclude: None,
+            reverse_ip: false,
+            expand_neighbors: false,
+            favicon_fingerprint: false,
+            favicon_db: None,
+            cms_fingerprint: false,
+            categories: Vec::new(),
+            budget: None,
+            target_type: preset::TargetType::Generic,
+            seclists: None,
+            dir_depth: patterns::DEFAULT_DIR_DEPTH,
+            user_agents: Vec::new(),
+            ua_strategy: None,
+            identify: None,
+            respect_robots: false,
+            ip_version: http::IpVersion::Auto,
+            dns_prefetch: true,
+            resolve: Vec::new(),
+            proxy: None,
+            proxies: Vec::new(),
+            proxy_rotation: proxy::ProxyRotation::RoundRobin,
+            proxy_max_failures: proxy::DEFAULT_PROXY_MAX_FAILURES,
+            min_size: http::DEFAULT_MIN_SIZE,
+            max_size: http::DEFAULT_MAX_SIZE,
         }
     }
 }
 
-/// 扫描结果
+/// 扫描配置中的问题，由 [`ScanConfig::validate`] 检出
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigIssue {
+    /// 并发线程数为0
+    ZeroThreads,
+    /// 超时时间为0
+    ZeroTimeout,
+    /// 设置了慢速主机阈值但慢速队列线程数为0
+    ZeroSlowLaneThreads,
+    /// 设置为0的上限，如 `max_connections`
+    ZeroLimit(&'static str),
+    /// 最小文件大小大于最大文件大小
+    SizeRange { min: u64, max: u64 },
+    /// 无法解析的标识请求头
+    InvalidIdentify(String),
+    /// 标识请求头与伪装选项同时设置，列出冲突的字段
+    IdentifyConflict(Vec<&'static str>),
+    /// 引用的输入文件不存在
+    MissingFile { field: &'static str, path: PathBuf },
+    /// 应为目录的路径不是目录
+    NotADirectory { field: &'static str, path: PathBuf },
+    /// 输出文件所在的目录不存在
+    MissingParent { field: &'static str, path: PathBuf },
+}
+
+impl std::fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigIssue::ZeroThreads => f.write_str("threads 并发线程数不能为0"),
+            ConfigIssue::ZeroTimeout => f.write_str("timeout 超时时间不能为0"),
+            ConfigIssue::ZeroSlowLaneThreads => f.write_str("设置了 slow_host_threshold 时 slow_lane_threads 不能为0"),
+            ConfigIssue::ZeroLimit(field) => write!(f, "{} 不能为0，不限制时不设置", field),
+            ConfigIssue::SizeRange { min, max } => write!(f, "min_size {} 字节大于 max_size {} 字节", min, max),
+            ConfigIssue::InvalidIdentify(header) => write!(f, "identify 标识请求头无效: {} (格式为 \"名称: 值\")", header),
+            ConfigIssue::IdentifyConflict(fields) => write!(f, "identify 不能与伪装选项同时使用: {}", fields.join(", ")),
+            ConfigIssue::MissingFile { field, path } => write!(f, "{} 文件不存在: {}", field, path.display()),
+            ConfigIssue::NotADirectory { field, path } => write!(f, "{} 不是目录: {}", field, path.display()),
+            ConfigIssue::MissingParent { field, path } => write!(f, "{} 所在目录不存在: {}", field, path.display()),
+        }
+    }
+}
+
+fn describe_issues(issues: &[ConfigIssue]) -> String {
+    issues.iter().map(ToString::to_string).collect::<Vec<_>>().join("；")
+}
+
+impl ScanConfig {
+    /// 检查配置，一次返回所有问题，避免配置错误在扫描中表现为难以理解的行为
+    ///
+    /// 只检查必须事先存在的文件：指纹缓存、模式统计、忽略列表和断点续扫状态文件不存在时视为空，
+    /// 目标文件由调用方读取(监视模式下可以稍后创建)，都不检查
+    pub fn validate(&self) -> Result<()> {
+        let issues = self.issues();
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(BackerError::InvalidConfig(issues))
+        }
+    }
+
+    /// 配置中的所有问题，没有问题时为空
+    pub fn issues(&self) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+        if self.threads == 0 {
+            issues.push(ConfigIssue::ZeroThreads);
+        }
+        if self.timeout == 0 {
+            issues.push(ConfigIssue::ZeroTimeout);
+        }
+        if self.slow_host_threshold.is_some() && self.slow_lane_threads == 0 {
+            issues.push(ConfigIssue::ZeroSlowLaneThreads);
+        }
+        for (field, limit) in [
+            ("max_connections", self.max_connections),
+            ("stop_after_hits", self.stop_after_hits),
+        ] {
+            if limit == Some(0) {
+                issues.push(ConfigIssue::ZeroLimit(field));
+            }
+        }
+        if self.min_size > self.max_size {
+            issues.push(ConfigIssue::SizeRange { min: self.min_size, max: self.max_size });
+        }
+
+        // 标识模式声明扫描身份，与伪装来源IP或轮换User-Agent相矛盾
+        if let Some(identify) = &self.identify {
+            if http::parse_identify_header(identify).is_err() {
+                issues.push(ConfigIssue::InvalidIdentify(identify.clone()));
+            }
+            let conflicts: Vec<&'static str> = [
+                ("ip_spoof", self.ip_spoof != http::IpSpoof::Off),
+                ("user_agents", !self.user_agents.is_empty()),
+                ("ua_strategy", self.ua_strategy.is_some_and(|strategy| strategy != http::UaStrategy::Fixed)),
+            ].into_iter().filter(|(_, set)| *set).map(|(field, _)| field).collect();
+            if !conflicts.is_empty() {
+                issues.push(ConfigIssue::IdentifyConflict(conflicts));
+            }
+        }
+
+        for (field, path) in [
+            ("patterns_file", self.patterns_file.as_ref()),
+            ("baseline", self.baseline.as_ref()),
+            ("script", self.script.as_ref()),
+            ("scope_exclude", self.scope_exclude.as_ref()),
+            ("favicon_db", self.favicon_db.as_ref()),
+        ] {
+            if let Some(path) = path.filter(|path| !path.is_file()) {
+                issues.push(ConfigIssue::MissingFile { field, path: path.clone() });
+            }
+        }
+        if let Some(path) = self.seclists.as_ref().filter(|path| !path.is_dir()) {
+            issues.push(ConfigIssue::NotADirectory { field: "seclists", path: path.clone() });
+        }
+
+        // 输出文件在扫描结束后才写入，所在目录不存在时提前报告
+        for (field, path) in [
+            ("output_file", self.output_file.as_ref()),
+            ("pattern_report", self.pattern_report.as_ref()),
+            ("resume", self.resume.as_ref()),
+        ] {
+            let Some(path) = path else { continue };
+            if path.parent().is_some_and(|parent| !parent.as_os_str().is_empty() && !parent.is_dir()) {
+                issues.push(ConfigIssue::MissingParent { field, path: path.clone() });
+            }
+        }
+        issues
+    }
+}
+
+/// 扫描目标
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Target {
+    /// 目标URL
+    pub url: String,
+    /// 所属组织/客户标签
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// 发现来源，如 "crt.sh"，目标文件中的目标为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// 用户提供的原始目标字符串(规范化之前)，如 `WWW.Example.com:8443/shop`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original: Option<String>,
+}
+
+impl From<String> for Target {
+    fn from(url: String) -> Self {
+        Self { url, tag: None, source: None, original: None }
+    }
+}
+
+/// 发现的严重程度，按从低到高排序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// 受限(403)等仅供参考的发现
+    Info,
+    /// 其他状态码的可疑发现
+    Low,
+    /// 可下载但未验证内容
+    Medium,
+    /// 可下载且内容已验证
+    High,
+    /// 确认包含数据库转储或高价值文件
+    Critical,
+}
+
+impl Severity {
+    /// 名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+/// 目标存活状态分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetStatus {
+    /// 正常存活，已扫描
+    Live,
+    /// 域名停放页面
+    Parked,
+    /// 指向可被接管服务的默认错误页
+    TakeoverCandidate,
+    /// 主机名无法解析(可能是悬空DNS记录)
+    Unresolvable,
+    /// 无法建立连接
+    Unreachable,
+    /// 首页返回JS质询或验证码拦截页
+    ChallengeProtected,
+}
+
+/// 单个目标的扫描报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TargetReport {
+    /// 目标URL
+    pub target: String,
+    /// 存活状态
+    pub status: TargetStatus,
+    /// 补充说明，如停放服务商
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// 协议回退后实际扫描的URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scanned_url: Option<String>,
+    /// 协议回退说明，如 "https -> http"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheme_fallback: Option<String>,
+    /// 扫描过程中的错误，如任务异常终止或超时
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+    /// 与 `errors` 一一对应的原始错误，保留出错阶段、URL和文件，不写入报告；
+    /// 从报告文件读入或由工作节点汇总的报告为空
+    #[serde(skip)]
+    pub failures: Vec<Arc<BackerError>>,
+    /// 反查IP得到的同IP站点
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub neighbors: Vec<String>,
+    /// 识别出的技术栈
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub technologies: Vec<String>,
+    /// 因robots.txt跳过的区域，如 "Disallow: /admin/ (跳过 12 个候选)"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub robots_skipped: Vec<String>,
+    /// 发现被上限截断的说明，如 "已达到单主机发现上限 50，丢弃 3 个发现，剩余候选未扫描"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub truncated: Option<String>,
+    /// 移入慢速队列的说明，如 "中位延迟 2310.5ms 超过阈值 1000ms，剩余候选移入慢速队列"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slow_lane: Option<String>,
+    /// 扫描中遇到质询页的说明，如 "检测到 Cloudflare 质询页，跳过剩余 230 个候选"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub challenge: Option<String>,
+    /// 因文件大小超出范围排除的响应，如 "https://example.com/www.zip (58 字节，小于下限 100 字节)"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub size_excluded: Vec<String>,
+    /// 与基线相符、未报告的已知发现URL
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub known: Vec<String>,
+    /// 扫描覆盖情况，未扫描或扫描未完成(如域名超时)时为None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<Coverage>,
+}
+
+impl TargetReport {
+    /// 记录扫描错误，报告中写入错误说明，原始错误保留在 `failures` 中
+    pub fn record_error(&mut self, error: impl Into<Arc<BackerError>>) {
+        let error = error.into();
+        self.errors.push(error.to_string());
+        self.failures.push(error);
+    }
+}
+
+/// 主机校准状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CalibrationStatus {
+    /// 未启用校准(--soft-404-probes 为0，且未启用 -v 和 --fingerprint-cache)
+    Disabled,
+    /// 已校准，不存在的路径返回错误状态码
+    Calibrated,
+    /// 已校准，不存在的路径也返回200，已记录错误页用于过滤
+    Wildcard,
+    /// 使用指纹缓存，未重新校准
+    Cached,
+    /// 校准请求失败，无法过滤模板化的200错误页
+    Failed,
+}
+
+impl CalibrationStatus {
+    /// 说明
+    pub fn describe(&self) -> &'static str {
+        match self {
+            CalibrationStatus::Disabled => "未启用",
+            CalibrationStatus::Calibrated => "已校准",
+            CalibrationStatus::Wildcard => "已校准(泛200)",
+            CalibrationStatus::Cached => "使用缓存",
+            CalibrationStatus::Failed => "校准失败",
+        }
+    }
+}
+
+/// 单个目标的扫描覆盖情况，用于区分"没有发现"和"没能扫描"
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Coverage {
+    /// 实际请求的候选数
+    pub attempted: usize,
+    /// 按原因统计的跳过候选数，如 ("robots.txt", 12)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<(String, usize)>,
+    /// 主机校准状态
+    pub calibration: CalibrationStatus,
+}
+
+impl Coverage {
+    /// 跳过的候选总数
+    pub fn skipped_total(&self) -> usize {
+        self.skipped.iter().map(|(_, count)| count).sum()
+    }
+}
+
+/// 扫描结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ScanResult {
+    /// 稳定的发现ID，见 [`ScanResult::finding_id`]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub id: String,
     /// 发现的URL
     pub url: String,
     /// HTTP状态码
     pub status_code: u16,
     /// 内容类型（Content-Type）
     pub content_type: Option<String>,
+    /// 内容类型与扩展名预期不符，如 `.zip` 返回 `text/html`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub content_type_mismatch: bool,
     /// 内容长度（Content-Length）
     pub content_length: Option<u64>,
     /// 是否已验证文件内容
     pub verified: bool,
+    /// 验证说明，如分段采样发现文件被截断
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verify_note: Option<String>,
+    /// 验证时下载的文件开头内容的哈希，用于与基线比较内容是否变化
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// 目标所属组织/客户标签
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// 将403转为200的绕过技巧
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bypass: Option<String>,
+    /// 绕过时实际请求的URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bypass_url: Option<String>,
+    /// 压缩包中发现的高价值文件
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub indicators: Vec<String>,
+    /// SQL转储摘要
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sql_summary: Option<sqldump::SqlDumpSummary>,
+    /// 暴露的.git目录的分析结果
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git: Option<vcs::GitExposure>,
+    /// 从 `.svn/wc.db`、`.hg/store/fncache`、`.DS_Store` 等元数据文件列出的文件名
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub listing: Option<vcs::FileListing>,
+    /// API定义文件摘要，如 "OpenAPI 3.0.1 (42 个路径): 订单服务"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_summary: Option<String>,
+    /// 目标的发现来源，如 "crt.sh"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// 发现所属的目标(规范化后实际扫描的目标URL)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// 用户提供的原始目标字符串，保留端口、路径和大小写，便于对应回资产清单
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_target: Option<String>,
+    /// 可疑响应的原因，如解压比超过上限的压缩炸弹
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suspicious: Option<String>,
+    /// 得到发现的请求过程，便于复现
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attempt: Option<AttemptInfo>,
+    /// 模式行首指定的请求方法，如 `PROPFIND`，按默认流程探测的发现为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+}
+
+/// 得到发现的请求过程：探测请求的次数和方法、主机暂停次数和耗时
+///
+/// 绕过403使用的技巧另见 [`ScanResult::bypass`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AttemptInfo {
+    /// 探测请求的发送次数，含超时、连接失败和502/503后的重试
+    pub attempts: u32,
+    /// 探测请求方法，如 `HEAD`；验证内容和跟随重定向另用GET请求
+    pub method: String,
+    /// 主机返回 Retry-After 后暂停、重新检查的次数
+    pub pauses: usize,
+    /// 从发出探测请求到完成验证的耗时(毫秒)，不含连接名额、限速和主机暂停的等待
+    pub elapsed_ms: u64,
+}
+
+impl AttemptInfo {
+    /// 是否经过重试或主机暂停才得到发现
+    pub fn retried(&self) -> bool {
+        self.attempts > 1 || self.pauses > 0
+    }
+
+    /// 说明，如 "HEAD 请求 2 次 (重试)，暂停 1 次，耗时 340 ms"
+    pub fn describe(&self) -> String {
+        let mut text = format!("{} 请求 {} 次", self.method, self.attempts);
+        if self.attempts > 1 {
+            text.push_str(" (重试)");
+        }
+        if self.pauses > 0 {
+            text.push_str(&format!("，暂停 {} 次", self.pauses));
+        }
+        text.push_str(&format!("，耗时 {} ms", self.elapsed_ms));
+        text
+    }
+}
+
+impl ScanResult {
+    /// 发现类型：`suspicious` 可疑响应(如压缩炸弹)，`exposed` 可直接下载，`restricted` 受限(403)，其余为 `other`
+    pub fn kind(&self) -> &'static str {
+        if self.suspicious.is_some() {
+            return "suspicious";
+        }
+        match self.status_code {
+            200..=299 => "exposed",
+            403 => "restricted",
+            _ => "other",
+        }
+    }
+
+    /// 严重程度：包含SQL转储、高价值文件或可还原的git仓库为critical，可下载时按是否验证分为high/medium，403为info，可疑响应为low
+    pub fn severity(&self) -> Severity {
+        match self.kind() {
+            "exposed" if self.sql_summary.is_some() || !self.indicators.is_empty()
+                || self.git.as_ref().is_some_and(vcs::GitExposure::is_critical) => Severity::Critical,
+            "exposed" if self.verified => Severity::High,
+            "exposed" => Severity::Medium,
+            "restricted" => Severity::Info,
+            _ => Severity::Low,
+        }
+    }
+
+    /// 由主机、路径和发现类型计算的确定性ID，如 `bk-3f2a9c0d1e4b5a67`
+    ///
+    /// 不含协议、查询参数和扫描时间，同一文件在重复扫描和不同输出格式中ID相同，
+    /// 便于下游工单系统去重；模式指定了请求方法时方法也参与计算
+    pub fn finding_id(&self) -> String {
+        let (host, path) = match url::Url::parse(&self.url) {
+            Ok(url) => {
+                let host = url.host_str().unwrap_or("").to_lowercase();
+                let host = match url.port() {
+                    Some(port) => format!("{}:{}", host, port),
+                    None => host,
+                };
+                (host, url.path().to_string())
+            }
+            Err(_) => (String::new(), self.url.clone()),
+        };
+        let mut key = format!("{}\n{}\n{}", host, path, self.kind());
+        if let Some(method) = &self.method {
+            key.push_str(&format!("\n{}", method));
+        }
+        let digest = update::hex_digest(key.as_bytes());
+        format!("bk-{}", &digest[..16])
+    }
+
+    /// 补上缺失的ID，兼容旧版本写入的结果文件
+    pub fn ensure_id(&mut self) {
+        if self.id.is_empty() {
+            self.id = self.finding_id();
+        }
+    }
 }
@@ -0,0 +1,157 @@
+use crate::http::IpVersion;
+use crate::TargetStatus;
+use reqwest::header::HeaderMap;
+use std::error::Error;
+use tokio::net::lookup_host;
+use url::Url;
+
+/// 域名停放页面特征
+const PARKING_SIGNATURES: &[(&str, &str)] = &[
+    ("sedoparking.com", "Sedo"),
+    ("parkingcrew.net", "ParkingCrew"),
+    ("bodis.com", "Bodis"),
+    ("afternic.com", "Afternic"),
+    ("parklogic.com", "ParkLogic"),
+    ("godaddy.com/domainsearch", "GoDaddy"),
+    ("domain is for sale", "通用停放页"),
+    ("this domain may be for sale", "通用停放页"),
+    ("buy this domain", "通用停放页"),
+    ("domain has expired", "域名已过期"),
+];
+
+/// 可被接管的服务默认错误页特征
+const TAKEOVER_SIGNATURES: &[(&str, &str)] = &[
+    ("there isn't a github pages site here", "GitHub Pages"),
+    ("no such app", "Heroku"),
+    ("nosuchbucket", "Amazon S3"),
+    ("the specified bucket does not exist", "Amazon S3"),
+    ("404 web site not found", "Azure"),
+    ("fastly error: unknown domain", "Fastly"),
+    ("sorry, this shop is currently unavailable", "Shopify"),
+    ("the thing you were looking for is no longer here", "Tumblr"),
+    ("help center closed", "Zendesk"),
+    ("domain is not configured", "Pantheon/Netlify"),
+];
+
+/// JS质询和验证码拦截页特征，只在403/429/503响应中匹配，正常页面中嵌入的验证码(如登录页)不会误判
+const CHALLENGE_SIGNATURES: &[(&str, &str)] = &[
+    ("/cdn-cgi/challenge-platform/", "Cloudflare"),
+    ("cf-browser-verification", "Cloudflare"),
+    ("cf_chl_opt", "Cloudflare"),
+    ("<title>just a moment...</title>", "Cloudflare"),
+    ("attention required! | cloudflare", "Cloudflare"),
+    ("/_sec/cp_challenge/", "Akamai"),
+    ("bm-verify", "Akamai"),
+    ("_incapsula_resource", "Imperva"),
+    ("sucuri_cloudproxy_js", "Sucuri"),
+    ("check.ddos-guard.net", "DDoS-Guard"),
+    ("awswafintegration", "AWS WAF"),
+    ("captcha-delivery.com", "DataDome"),
+    ("px-captcha", "PerimeterX"),
+    ("g-recaptcha", "reCAPTCHA"),
+    ("h-captcha", "hCaptcha"),
+];
+
+/// 质询页使用的状态码
+pub fn is_challenge_status(status: u16) -> bool {
+    matches!(status, 403 | 429 | 503)
+}
+
+/// 响应头直接标明的质询，如Cloudflare的 `cf-mitigated: challenge`
+pub fn challenge_from_headers(status: u16, headers: &HeaderMap) -> Option<String> {
+    let mitigated = headers.get("cf-mitigated").and_then(|value| value.to_str().ok());
+    (is_challenge_status(status) && mitigated.is_some_and(|value| value.eq_ignore_ascii_case("challenge")))
+        .then(|| "Cloudflare".to_string())
+}
+
+/// 根据状态码、响应头和开头内容判断响应是否为JS质询或验证码拦截页，返回服务商
+pub fn detect_challenge(status: u16, headers: &HeaderMap, body: &[u8]) -> Option<String> {
+    if !is_challenge_status(status) {
+        return None;
+    }
+    if let Some(provider) = challenge_from_headers(status, headers) {
+        return Some(provider);
+    }
+    let body = String::from_utf8_lossy(body).to_lowercase();
+    CHALLENGE_SIGNATURES.iter()
+        .find(|(signature, _)| body.contains(signature))
+        .map(|(_, provider)| provider.to_string())
+}
+
+/// 检查主机名能否解析出指定IP协议版本的地址
+pub async fn resolve_host(host: &str, port: u16, ip_version: IpVersion) -> bool {
+    match lookup_host((host, port)).await {
+        Ok(mut addrs) => addrs.any(|addr| ip_version.accepts(&addr.ip())),
+        Err(_) => false,
+    }
+}
+
+/// 根据根页面内容和最终URL判断目标是否为停放域名或可被接管的服务
+pub fn classify_page(final_url: &str, body: &[u8]) -> Option<(TargetStatus, String)> {
+    let body = String::from_utf8_lossy(body).to_lowercase();
+
+    for (signature, provider) in TAKEOVER_SIGNATURES {
+        if body.contains(signature) {
+            return Some((TargetStatus::TakeoverCandidate, provider.to_string()));
+        }
+    }
+
+    for (signature, provider) in PARKING_SIGNATURES {
+        if body.contains(signature) {
+            return Some((TargetStatus::Parked, provider.to_string()));
+        }
+    }
+
+    // 部分停放服务会跳转到自己的落地页
+    let final_url = final_url.to_lowercase();
+    for (signature, provider) in PARKING_SIGNATURES {
+        if final_url.contains(signature) {
+            return Some((TargetStatus::Parked, provider.to_string()));
+        }
+    }
+
+    None
+}
+
+/// 根据请求错误决定是否切换协议重试
+///
+/// HTTPS出现TLS错误时改用HTTP，HTTP连接被重置或拒绝时改用HTTPS。
+/// 显式指定了端口的URL不切换，因为端口通常只对应一种协议
+pub fn scheme_fallback_url(url: &Url, error: &reqwest::Error) -> Option<String> {
+    if url.port().is_some() {
+        return None;
+    }
+
+    let alt_scheme = match url.scheme() {
+        "https" if is_tls_error(error) => "http",
+        "http" if is_connection_reset(error) => "https",
+        _ => return None,
+    };
+
+    let mut alt_url = url.clone();
+    alt_url.set_scheme(alt_scheme).ok()?;
+    Some(alt_url.to_string())
+}
+
+/// 判断错误是否由TLS握手或证书问题引起
+pub fn is_tls_error(error: &reqwest::Error) -> bool {
+    error_chain_contains(error, &["tls", "certificate", "handshake", "ssl", "invalidcontenttype", "corrupt message"])
+}
+
+/// 判断错误是否为连接被重置或拒绝
+pub fn is_connection_reset(error: &reqwest::Error) -> bool {
+    error_chain_contains(error, &["connection reset", "connection refused", "reset by peer", "connection closed"])
+}
+
+/// 在错误链的描述中查找关键字
+fn error_chain_contains(error: &reqwest::Error, keywords: &[&str]) -> bool {
+    let mut source: Option<&dyn Error> = Some(error);
+    while let Some(err) = source {
+        let message = format!("{} {:?}", err, err).to_lowercase();
+        if keywords.iter().any(|k| message.contains(k)) {
+            return true;
+        }
+        source = err.source();
+    }
+    false
+}
@@ -0,0 +1,269 @@
+use crate::events::ScanEvent;
+use crate::dns::DnsCache;
+use crate::http::{parse_resolve_override, use_proxy, use_tls_backend, IpVersion};
+use crate::ratelimit::{ConnectionBudget, RateLimiter};
+use crate::scanner::Scanner;
+use crate::{BackerError, Result, ScanConfig, ScanResult, Target, TargetReport};
+use log::debug;
+use reqwest::Client;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, Semaphore};
+use tokio::task::JoinHandle;
+
+/// 扫描管理器的共享设置
+#[derive(Debug, Clone)]
+pub struct ManagerOptions {
+    /// 同时运行的扫描任务数，超出的任务排队等待
+    pub max_concurrent_scans: usize,
+    /// 所有任务合计的每秒请求数上限，0表示不限速
+    pub requests_per_second: u32,
+    /// 所有任务合计的同时进行中请求数上限，0表示不限制；
+    /// 设置后替换各任务 `ScanConfig::max_connections` 的单独上限
+    pub max_connections: usize,
+    /// 共享连接池中每个主机保留的空闲连接数
+    pub pool_max_idle_per_host: usize,
+}
+
+impl Default for ManagerOptions {
+    fn default() -> Self {
+        Self {
+            max_concurrent_scans: 4,
+            requests_per_second: 0,
+            max_connections: 0,
+            pool_max_idle_per_host: 10,
+        }
+    }
+}
+
+/// 共享连接池的连接设置，全部相同的任务才能共用连接池
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct PoolKey {
+    ip_version: IpVersion,
+    proxy: Option<String>,
+    resolve: Vec<String>,
+    timeout: u64,
+}
+
+impl PoolKey {
+    fn new(config: &ScanConfig) -> Self {
+        let mut resolve = config.resolve.clone();
+        resolve.sort();
+        resolve.dedup();
+        Self { ip_version: config.ip_version, proxy: config.proxy.clone(), resolve, timeout: config.timeout }
+    }
+}
+
+/// 扫描任务编号，由管理器按提交顺序分配
+pub type ScanId = u64;
+
+/// 扫描任务的输出
+#[derive(Debug, Clone, Default)]
+pub struct ScanOutput {
+    /// 发现
+    pub results: Vec<ScanResult>,
+    /// 各目标的存活状态、覆盖情况和扫描错误
+    pub reports: Vec<TargetReport>,
+}
+
+/// 已提交的扫描任务
+pub struct ScanHandle {
+    id: ScanId,
+    events: broadcast::Receiver<ScanEvent>,
+    task: JoinHandle<Result<ScanOutput>>,
+}
+
+impl ScanHandle {
+    /// 任务编号
+    pub fn id(&self) -> ScanId {
+        self.id
+    }
+
+    /// 任务的事件流，从提交时开始，包含排队期间之后的所有事件
+    pub fn events(&mut self) -> &mut broadcast::Receiver<ScanEvent> {
+        &mut self.events
+    }
+
+    /// 新增一个事件订阅，只接收此后的事件
+    pub fn subscribe(&self) -> broadcast::Receiver<ScanEvent> {
+        self.events.resubscribe()
+    }
+
+    /// 任务是否已结束
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// 取消任务，已发出的请求不会撤回
+    pub fn abort(&self) {
+        self.task.abort();
+    }
+
+    /// 等待任务结束并取得输出
+    pub async fn join(self) -> Result<ScanOutput> {
+        match self.task.await {
+            Ok(output) => output,
+            Err(e) if e.is_cancelled() => Err(BackerError::Scan(format!("扫描任务 {} 已取消", self.id))),
+            Err(e) => Err(BackerError::Scan(format!("扫描任务 {} 异常终止: {}", self.id, e))),
+        }
+    }
+}
+
+/// 并发运行多个独立扫描任务的管理器，供多租户扫描服务嵌入
+///
+/// 每个任务使用各自的 [`ScanConfig`] 和 [`Scanner`]，结果、事件、统计互不影响；
+/// 连接设置（IP协议版本、代理、固定解析、超时）相同的任务共享同一个连接池和DNS缓存，
+/// 所有任务共享全局限速器和连接数上限，并受并发任务数限制
+///
+/// ```no_run
+/// # async fn run() -> backer::Result<()> {
+/// use backer::manager::{ManagerOptions, ScanManager};
+/// use backer::{ScanConfig, Target};
+///
+/// let manager = ScanManager::new(ManagerOptions { requests_per_second: 50, ..Default::default() })?;
+/// let tenant_a = manager.submit(ScanConfig::default(), vec![Target::from("https://a.example".to_string())]).await?;
+/// let tenant_b = manager.submit(ScanConfig::default(), vec![Target::from("https://b.example".to_string())]).await?;
+/// let (a, b) = (tenant_a.join().await?, tenant_b.join().await?);
+/// println!("{} / {} 个发现", a.results.len(), b.results.len());
+/// # Ok(())
+/// # }
+/// ```
+pub struct ScanManager {
+    options: ManagerOptions,
+    /// 按连接设置区分的共享连接池及其DNS缓存，设置相同的任务共用
+    pools: Mutex<HashMap<PoolKey, (Client, Arc<DnsCache>)>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    connection_budget: Option<ConnectionBudget>,
+    slots: Arc<Semaphore>,
+    next_id: AtomicU64,
+}
+
+impl ScanManager {
+    /// 创建管理器
+    pub fn new(options: ManagerOptions) -> Result<Self> {
+        if options.max_concurrent_scans == 0 {
+            return Err(BackerError::Config("并发扫描任务数必须大于0".to_string()));
+        }
+        let rate_limiter = (options.requests_per_second > 0)
+            .then(|| Arc::new(RateLimiter::new(options.requests_per_second)));
+        let connection_budget = (options.max_connections > 0)
+            .then(|| ConnectionBudget::new(options.max_connections));
+        Ok(Self {
+            pools: Mutex::new(HashMap::new()),
+            rate_limiter,
+            connection_budget,
+            slots: Arc::new(Semaphore::new(options.max_concurrent_scans)),
+            next_id: AtomicU64::new(1),
+            options,
+        })
+    }
+
+    /// 取得与任务连接设置相同的共享连接池及其DNS缓存，首次使用时创建
+    ///
+    /// 连接池通过DNS缓存解析域名，`--resolve` 固定的地址在创建时写入缓存
+    fn pool(&self, key: PoolKey) -> Result<(Client, Arc<DnsCache>)> {
+        let mut pools = self.pools.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(pool) = pools.get(&key) {
+            return Ok(pool.clone());
+        }
+        let dns_cache = Arc::new(DnsCache::default());
+        for spec in &key.resolve {
+            let (host, addr) = parse_resolve_override(spec)?;
+            dns_cache.pin(&host, addr);
+        }
+        let client = use_proxy(use_tls_backend(Client::builder()), key.proxy.as_deref())?
+            .dns_resolver(dns_cache.clone())
+            .timeout(Duration::from_secs(key.timeout))
+            .pool_idle_timeout(Duration::from_secs(90))
+            .pool_max_idle_per_host(self.options.pool_max_idle_per_host)
+            .local_address(key.ip_version.local_address())
+            .build()?;
+        pools.insert(key, (client.clone(), dns_cache.clone()));
+        Ok((client, dns_cache))
+    }
+
+    /// 提交扫描任务，立即返回；并发任务数已满时任务在后台排队
+    ///
+    /// 扫描器在提交时创建，配置错误(如模式文件无法读取)直接返回
+    pub async fn submit(&self, config: ScanConfig, targets: Vec<Target>) -> Result<ScanHandle> {
+        let key = PoolKey::new(&config);
+        let mut scanner = Scanner::new(config).await?;
+        let (pool, dns_cache) = self.pool(key)?;
+        scanner.set_connection_pool(pool, dns_cache);
+        scanner.set_rate_limiter(self.rate_limiter.clone());
+        if let Some(budget) = &self.connection_budget {
+            scanner.set_connection_budget(Some(budget.clone()));
+        }
+        let events = scanner.subscribe();
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let slots = self.slots.clone();
+        let task = tokio::spawn(async move {
+            let _slot = slots.acquire_owned().await
+                .map_err(|e| BackerError::Scan(format!("无法获取扫描槽位: {}", e)))?;
+            debug!("扫描任务 {} 开始: {} 个目标", id, targets.len());
+            let results = scanner.scan_targets(targets).await?;
+            debug!("扫描任务 {} 完成: {} 个发现", id, results.len());
+            Ok(ScanOutput { results, reports: scanner.get_target_reports() })
+        });
+
+        Ok(ScanHandle { id, events, task })
+    }
+
+    /// 正在运行的任务数，不含排队中的任务
+    pub fn running_scans(&self) -> usize {
+        self.options.max_concurrent_scans - self.slots.available_permits()
+    }
+
+    /// 所有任务正在使用的连接数，未设置连接数上限时为None
+    pub fn connections_in_use(&self) -> Option<usize> {
+        self.connection_budget.as_ref().map(ConnectionBudget::in_use)
+    }
+
+    /// 全局限速，未限速时为None
+    pub fn requests_per_second(&self) -> Option<u32> {
+        self.rate_limiter.as_ref().and_then(|limiter| limiter.requests_per_second())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn managed_scan_connects_to_pinned_address() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let hosts = Arc::new(Mutex::new(Vec::new()));
+        let seen = hosts.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+                if let Some(host) = request.lines().find_map(|line| line.strip_prefix("host: ")) {
+                    seen.lock().unwrap().push(host.trim().to_string());
+                }
+                let _ = stream.write_all(b"HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\nconnection: close\r\n\r\n").await;
+            }
+        });
+
+        let config = ScanConfig {
+            resolve: vec!["backer-pin.invalid:127.0.0.1".to_string()],
+            timeout: 5,
+            retry_count: 0,
+            ..Default::default()
+        };
+        let manager = ScanManager::new(ManagerOptions::default()).unwrap();
+        let target = Target::from(format!("http://backer-pin.invalid:{}", port));
+        manager.submit(config, vec![target]).await.unwrap().join().await.unwrap();
+
+        let hosts = hosts.lock().unwrap();
+        assert!(!hosts.is_empty(), "固定解析的地址没有收到请求");
+        assert!(hosts.iter().all(|host| host == &format!("backer-pin.invalid:{}", port)));
+    }
+}
@@ -0,0 +1,168 @@
+/// 备份文件类型及其可接受的内容类型
+#[derive(Debug)]
+pub struct FileKind {
+    /// 类型名称
+    pub name: &'static str,
+    /// 文件扩展名，含点号
+    pub extensions: &'static [&'static str],
+    /// 可接受的Content-Type(不含参数)
+    pub mime_types: &'static [&'static str],
+    /// 是否为压缩包，压缩包会尝试在内存中解压
+    pub archive: bool,
+    /// 内容是否为二进制，二进制文件不应是HTML页面
+    pub binary: bool,
+    /// 文件头签名，每项为 (偏移, 字节)，匹配任一即可；为空时不检查文件头
+    pub magic: &'static [(usize, &'static [u8])],
+}
+
+/// 二进制文件常用的通用内容类型，所有二进制类型都接受
+const GENERIC_BINARY: &[&str] = &["application/octet-stream", "binary/octet-stream", "application/x-download", "application/force-download"];
+
+/// 扩展名到可接受内容类型和文件头的映射，探测阶段判断内容类型是否相符，
+/// 验证阶段判断内容是否应为二进制、文件头是否相符
+pub const FILE_KINDS: &[FileKind] = &[
+    FileKind {
+        name: "zip",
+        extensions: &[".zip"],
+        mime_types: &["application/zip", "application/x-zip-compressed", "application/x-zip"],
+        archive: true,
+        binary: true,
+        magic: &[(0, b"PK\x03\x04"), (0, b"PK\x05\x06"), (0, b"PK\x07\x08")],
+    },
+    FileKind {
+        name: "rar",
+        extensions: &[".rar"],
+        mime_types: &["application/vnd.rar", "application/x-rar-compressed", "application/x-rar"],
+        archive: true,
+        binary: true,
+        magic: &[(0, b"Rar!\x1a\x07")],
+    },
+    FileKind {
+        name: "7z",
+        extensions: &[".7z"],
+        mime_types: &["application/x-7z-compressed"],
+        archive: true,
+        binary: true,
+        magic: &[(0, b"7z\xbc\xaf\x27\x1c")],
+    },
+    FileKind {
+        name: "tar",
+        extensions: &[".tar"],
+        mime_types: &["application/x-tar", "application/x-gtar"],
+        archive: true,
+        binary: true,
+        magic: &[(257, b"ustar")],
+    },
+    FileKind {
+        name: "gzip",
+        extensions: &[".tar.gz", ".tgz", ".sql.gz", ".gz"],
+        mime_types: &["application/gzip", "application/x-gzip", "application/x-gtar", "application/x-compressed-tar", "application/x-tar"],
+        archive: true,
+        binary: true,
+        magic: &[(0, b"\x1f\x8b")],
+    },
+    FileKind {
+        name: "bzip2",
+        extensions: &[".tar.bz2", ".tbz2", ".bz2"],
+        mime_types: &["application/x-bzip2", "application/x-bzip", "application/x-bzip-compressed-tar", "application/x-tar"],
+        archive: true,
+        binary: true,
+        magic: &[(0, b"BZh")],
+    },
+    FileKind {
+        name: "xz",
+        extensions: &[".tar.xz", ".txz", ".xz"],
+        mime_types: &["application/x-xz", "application/x-xz-compressed-tar", "application/x-tar"],
+        archive: true,
+        binary: true,
+        magic: &[(0, b"\xfd7zXZ\x00")],
+    },
+    FileKind {
+        name: "sql",
+        extensions: &[".sql"],
+        mime_types: &["text/plain", "text/x-sql", "application/sql", "application/x-sql", "application/octet-stream"],
+        archive: false,
+        binary: false,
+        magic: &[],
+    },
+    FileKind {
+        name: "sqlite",
+        extensions: &[".sqlite", ".sqlite3", ".db"],
+        mime_types: &["application/x-sqlite3", "application/vnd.sqlite3", "application/x-sqlite"],
+        archive: false,
+        binary: true,
+        magic: &[(0, b"SQLite format 3\x00")],
+    },
+    FileKind {
+        name: "access",
+        extensions: &[".mdb", ".accdb"],
+        mime_types: &["application/x-msaccess", "application/vnd.ms-access", "application/msaccess"],
+        archive: false,
+        binary: true,
+        magic: &[(4, b"Standard Jet DB"), (4, b"Standard ACE DB")],
+    },
+];
+
+/// 按URL路径的扩展名查找文件类型，多个扩展名匹配时取最长的；
+/// `.bak`、`.old` 等通用后缀没有对应类型
+pub fn file_kind(url: &str) -> Option<&'static FileKind> {
+    let path = url.split(['?', '#']).next().unwrap_or(url).to_lowercase();
+    FILE_KINDS.iter()
+        .filter_map(|kind| {
+            let matched = kind.extensions.iter().filter(|ext| path.ends_with(*ext)).map(|ext| ext.len()).max()?;
+            Some((matched, kind))
+        })
+        .max_by_key(|(matched, _)| *matched)
+        .map(|(_, kind)| kind)
+}
+
+/// 内容类型是否符合URL扩展名的预期，没有预期类型时视为相符
+pub fn content_type_matches(content_type: &str, url: &str) -> bool {
+    let Some(kind) = file_kind(url) else { return true };
+    let mime = content_type.split(';').next().unwrap_or("").trim().to_lowercase();
+    kind.mime_types.contains(&mime.as_str()) || (kind.binary && GENERIC_BINARY.contains(&mime.as_str()))
+}
+
+/// 响应带有内容类型且与扩展名预期不符
+pub fn is_content_type_mismatch(content_type: Option<&str>, url: &str) -> bool {
+    content_type.is_some_and(|content_type| !content_type_matches(content_type, url))
+}
+
+/// 内容类型过滤规则，如 `application/*`、`text/html`
+///
+/// 只比较去掉参数后的类型，`*` 可代替主类型或子类型
+#[derive(Debug, Clone, Default)]
+pub struct ContentTypeFilter {
+    /// 必须匹配其中之一，为空时不限制
+    require: Vec<String>,
+    /// 匹配其中任一即排除
+    exclude: Vec<String>,
+}
+
+impl ContentTypeFilter {
+    /// 创建过滤规则，规则不区分大小写
+    pub fn new(require: &[String], exclude: &[String]) -> Self {
+        let normalize = |patterns: &[String]| patterns.iter().map(|pattern| pattern.trim().to_lowercase()).collect();
+        Self { require: normalize(require), exclude: normalize(exclude) }
+    }
+
+    /// 是否没有任何规则
+    pub fn is_empty(&self) -> bool {
+        self.require.is_empty() && self.exclude.is_empty()
+    }
+
+    /// 内容类型是否通过过滤；设置了必需类型时，没有内容类型的响应不通过
+    pub fn allows(&self, content_type: Option<&str>) -> bool {
+        let Some(content_type) = content_type else { return self.require.is_empty() };
+        let mime = content_type.split(';').next().unwrap_or("").trim().to_lowercase();
+        let matches = |pattern: &String| mime_matches(&mime, pattern);
+        (self.require.is_empty() || self.require.iter().any(matches)) && !self.exclude.iter().any(matches)
+    }
+}
+
+/// 内容类型是否匹配规则，`*/*`、`application/*` 或完整类型
+fn mime_matches(mime: &str, pattern: &str) -> bool {
+    let (kind, subtype) = mime.split_once('/').unwrap_or((mime, ""));
+    let (pattern_kind, pattern_subtype) = pattern.split_once('/').unwrap_or((pattern, "*"));
+    (pattern_kind == "*" || pattern_kind == kind) && (pattern_subtype == "*" || pattern_subtype == subtype)
+}
@@ -0,0 +1,122 @@
+use crate::http::HeaderUsage;
+use crate::Result;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+/// 扫描将产生的流量说明，扫描前导出交给防守方，用于加白或关联告警
+#[derive(Debug, Clone, Serialize)]
+pub struct NoiseReport {
+    /// 生成时间(RFC 3339)
+    pub generated_at: String,
+    /// 扫描工具及版本
+    pub tool: String,
+    /// 出口：直连或代理
+    pub egress: String,
+    /// 探测候选URL使用的请求方法
+    pub probe_method: String,
+    /// 请求头及取值
+    pub headers: Vec<HeaderUsage>,
+    /// 并发和速率
+    pub rate: RateInfo,
+    /// 各主机的请求数
+    pub hosts: Vec<HostNoise>,
+    /// 所有主机的预计请求数
+    pub total_requests: usize,
+    /// 所有主机的请求数上限(含重试)
+    pub total_max_requests: usize,
+    /// 取决于扫描过程、未计入请求数的流量
+    pub notes: Vec<String>,
+}
+
+/// 并发和速率设置
+#[derive(Debug, Clone, Serialize)]
+pub struct RateInfo {
+    /// 同一主机同时检查的URL数
+    pub concurrency_per_host: usize,
+    /// 慢速队列的并发
+    pub slow_lane_concurrency: usize,
+    /// 全局同时进行中的请求数上限
+    pub max_connections: Option<usize>,
+    /// 启动爬坡时长(秒)，0表示不爬坡
+    pub ramp_up_secs: u64,
+    /// 单个请求的超时(秒)
+    pub timeout_secs: u64,
+    /// 失败请求的重试次数
+    pub retry_count: u32,
+}
+
+/// 单个主机的请求数
+#[derive(Debug, Clone, Serialize)]
+pub struct HostNoise {
+    /// 主机(含非默认端口)
+    pub host: String,
+    /// 该主机上的目标数
+    pub targets: usize,
+    /// 存活检测请求数，每个目标请求一次首页
+    pub liveness: usize,
+    /// 校准请求数(随机不存在的路径)
+    pub calibration: usize,
+    /// robots.txt、favicon等辅助请求数
+    pub auxiliary: usize,
+    /// 候选URL数，每个候选一次探测请求
+    pub candidates: usize,
+    /// 预计请求数
+    pub requests: usize,
+    /// 请求数上限：存活检测的协议回退和每个候选的全部重试
+    pub max_requests: usize,
+}
+
+/// 保存噪声报告，`.json` 结尾为JSON，否则为Markdown
+pub fn save_noise_report<P: AsRef<Path>>(report: &NoiseReport, path: P) -> Result<()> {
+    let path = path.as_ref();
+    let content = if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")) {
+        serde_json::to_string_pretty(report)?
+    } else {
+        render_markdown(report)
+    };
+    fs::write(path, content)?;
+    Ok(())
+}
+
+/// 渲染Markdown格式的噪声报告
+pub fn render_markdown(report: &NoiseReport) -> String {
+    let cell = |text: &str| text.replace('|', "\\|");
+    let mut md = String::new();
+    md.push_str("# 扫描流量说明\n\n");
+    md.push_str(&format!("- 生成时间: {}\n", report.generated_at));
+    md.push_str(&format!("- 扫描工具: {}\n", report.tool));
+    md.push_str(&format!("- 出口: {}\n", report.egress));
+    md.push_str(&format!("- 探测方法: {}（校准、验证和存活检测使用 GET）\n", report.probe_method));
+    md.push_str(&format!("- 预计请求总数: {}（上限 {}）\n\n", report.total_requests, report.total_max_requests));
+
+    md.push_str("## 并发和速率\n\n");
+    let rate = &report.rate;
+    md.push_str(&format!("- 同一主机同时检查的URL数: {}（慢速队列 {}）\n", rate.concurrency_per_host, rate.slow_lane_concurrency));
+    md.push_str(&format!("- 全局同时进行中的请求数上限: {}\n", rate.max_connections.map_or("不限制".to_string(), |n| n.to_string())));
+    if rate.ramp_up_secs > 0 {
+        md.push_str(&format!("- 启动爬坡: 开始时以1/10并发请求，{} 秒内逐步提高\n", rate.ramp_up_secs));
+    }
+    md.push_str(&format!("- 请求超时: {} 秒，失败重试: {} 次\n", rate.timeout_secs, rate.retry_count));
+    md.push_str("- 主机依次扫描，同一时间只请求一个主机（慢速队列除外）\n\n");
+
+    md.push_str("## 请求头\n\n| 请求头 | 取值 | 发送方式 |\n|---|---|---|\n");
+    for header in &report.headers {
+        md.push_str(&format!("| {} | {} | {} |\n",
+            cell(&header.name), header.values.iter().map(|value| cell(value)).collect::<Vec<_>>().join("<br>"), cell(&header.when)));
+    }
+
+    md.push_str("\n## 各主机请求数\n\n| 主机 | 目标数 | 存活检测 | 校准 | 辅助 | 候选 | 预计请求数 | 上限 |\n|---|---|---|---|---|---|---|---|\n");
+    for host in &report.hosts {
+        md.push_str(&format!("| {} | {} | {} | {} | {} | {} | {} | {} |\n",
+            cell(&host.host), host.targets, host.liveness, host.calibration, host.auxiliary, host.candidates, host.requests, host.max_requests));
+    }
+
+    if !report.notes.is_empty() {
+        md.push_str("\n## 未计入的流量\n\n");
+        for note in &report.notes {
+            md.push_str(&format!("- {}\n", note));
+        }
+    }
+    md
+}
@@ -0,0 +1,216 @@
+use crate::{BackerError, Result};
+use log::{debug, warn};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+/// 随程序发布的模式库
+const EMBEDDED_PATTERNS: &str = include_str!("../data/patterns.toml");
+
+/// 模式库文件名，也是发布附件名，签名附件为 `patterns.toml.minisig`
+pub const PATTERN_DB_FILE: &str = "patterns.toml";
+
+/// 分类和技术栈的专属路径库
+///
+/// 与代码分离的数据，版本号随内容更新递增；`backer update-patterns` 下载的
+/// 模式库保存在本地数据目录，版本不低于内置模式库时优先使用
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PatternDb {
+    /// 模式库版本
+    pub version: u64,
+    /// 分类名 -> 路径，如 `config`、`vcs`、`api`
+    #[serde(default)]
+    pub categories: HashMap<String, Vec<String>>,
+    /// 技术栈规范名称 -> 路径，如 `wordpress`、`spring-boot`
+    #[serde(default)]
+    pub technologies: HashMap<String, Vec<String>>,
+    /// 前提满足后才请求的条件模式
+    #[serde(default)]
+    pub conditional: Vec<ConditionalPatterns>,
+}
+
+/// 条件模式：同一目标命中某个路径或识别出某个技术栈后才请求的路径
+///
+/// 如 `.git/HEAD` 命中后再请求 `.git/index`，识别出WordPress后再请求 `wp-content/backups/`；
+/// `after` 和 `technology` 满足其一即触发，每个目标每条规则只触发一次
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ConditionalPatterns {
+    /// 命中其中任一路径(2xx)后触发
+    #[serde(default)]
+    pub after: Vec<String>,
+    /// 识别出该技术栈，或命中该技术栈的专属路径后触发
+    #[serde(default)]
+    pub technology: Option<String>,
+    /// 触发后请求的路径
+    pub paths: Vec<String>,
+}
+
+impl PatternDb {
+    /// 解析TOML格式的模式库
+    pub fn parse(content: &str) -> Result<Self> {
+        let db: Self = toml::from_str(content).map_err(|e| BackerError::Config(format!("无法解析模式库: {}", e)))?;
+        for (index, rule) in db.conditional.iter().enumerate() {
+            if rule.after.is_empty() && rule.technology.is_none() {
+                return Err(BackerError::Config(format!("模式库第 {} 条条件模式缺少 after 或 technology", index + 1)));
+            }
+            if rule.paths.is_empty() {
+                return Err(BackerError::Config(format!("模式库第 {} 条条件模式缺少 paths", index + 1)));
+            }
+        }
+        Ok(db)
+    }
+
+    /// 内置模式库
+    pub fn embedded() -> Self {
+        Self::parse(EMBEDDED_PATTERNS).expect("内置模式库格式错误")
+    }
+
+    /// 加载模式库，本地数据目录中的模式库可用且版本不低于内置版本时优先使用
+    pub fn load() -> Self {
+        let embedded = Self::embedded();
+        let Some(path) = local_path() else {
+            return embedded;
+        };
+        let Ok(content) = fs::read_to_string(&path) else {
+            return embedded;
+        };
+        match Self::parse(&content) {
+            Ok(local) if local.version >= embedded.version => {
+                debug!("使用本地模式库 {} (版本 {})", path.display(), local.version);
+                local
+            }
+            Ok(local) => {
+                debug!("本地模式库版本 {} 低于内置版本 {}，使用内置模式库", local.version, embedded.version);
+                embedded
+            }
+            Err(e) => {
+                warn!("忽略本地模式库 {}: {}", path.display(), e);
+                embedded
+            }
+        }
+    }
+
+    /// 分类的专属路径，未知分类返回空
+    pub fn category(&self, name: &str) -> &[String] {
+        self.categories.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// 技术栈的专属路径，未知技术栈返回空
+    pub fn technology(&self, name: &str) -> &[String] {
+        self.technologies.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// 专属路径属于的技术栈，如 `wp-config.php.bak` 属于 `wordpress`
+    pub fn technology_of(&self, path: &str) -> Option<&str> {
+        self.technologies.iter()
+            .find(|(_, paths)| paths.iter().any(|candidate| candidate == path))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// 路径总数，含条件模式的路径
+    pub fn len(&self) -> usize {
+        self.categories.values().chain(self.technologies.values()).map(Vec::len).sum::<usize>()
+            + self.conditional.iter().map(|rule| rule.paths.len()).sum::<usize>()
+    }
+
+    /// 是否没有任何路径
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// 单个目标的条件模式状态，记录已识别的技术栈和已触发的规则
+#[derive(Debug, Clone)]
+pub struct Followups {
+    db: std::sync::Arc<PatternDb>,
+    technologies: Vec<String>,
+    fired: Vec<bool>,
+}
+
+impl Followups {
+    /// 创建目标的条件模式状态，`technologies` 为扫描前识别出的技术栈
+    pub fn new(db: std::sync::Arc<PatternDb>, technologies: &[String]) -> Self {
+        let fired = vec![false; db.conditional.len()];
+        Self { db, technologies: technologies.to_vec(), fired }
+    }
+
+    /// 扫描前已满足的规则的路径，即技术栈已识别的规则
+    pub fn initial(&mut self) -> Vec<String> {
+        self.fire(|rule, technologies| rule.technology.as_ref().is_some_and(|name| technologies.contains(name)))
+    }
+
+    /// 命中路径后新满足的规则的路径；命中技术栈专属路径视为识别出该技术栈
+    pub fn on_hit(&mut self, path: &str) -> Vec<String> {
+        let path = path.trim_start_matches('/');
+        if let Some(technology) = self.db.technology_of(path) {
+            if !self.technologies.iter().any(|name| name == technology) {
+                debug!("命中 {} 的专属路径 {}", technology, path);
+                self.technologies.push(technology.to_string());
+            }
+        }
+        self.fire(|rule, technologies| {
+            rule.after.iter().any(|after| after.trim_start_matches('/') == path)
+                || rule.technology.as_ref().is_some_and(|name| technologies.contains(name))
+        })
+    }
+
+    /// 触发满足条件且尚未触发的规则，返回其路径
+    fn fire(&mut self, satisfied: impl Fn(&ConditionalPatterns, &[String]) -> bool) -> Vec<String> {
+        let mut paths = Vec::new();
+        for (rule, fired) in self.db.conditional.iter().zip(self.fired.iter_mut()) {
+            if !*fired && satisfied(rule, &self.technologies) {
+                *fired = true;
+                paths.extend(rule.paths.iter().map(|path| path.trim_start_matches('/').to_string()));
+            }
+        }
+        paths
+    }
+}
+
+/// 依次合并各组的模式库路径和导入路径，去除重复，保留首次出现的顺序
+///
+/// 分类和技术栈的专属路径都按此方式与SecLists导入的路径合并
+pub fn merge_paths<'a>(groups: impl IntoIterator<Item = (&'a [String], Option<&'a Vec<String>>)>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut paths = Vec::new();
+    for (builtin, imported) in groups {
+        for path in builtin.iter().chain(imported.into_iter().flatten()) {
+            if seen.insert(path.as_str()) {
+                paths.push(path.clone());
+            }
+        }
+    }
+    paths
+}
+
+/// 本地数据目录：`$BACKER_DATA_DIR`，否则 `$XDG_DATA_HOME/backer`，否则 `~/.local/share/backer`
+pub fn data_dir() -> Option<PathBuf> {
+    let non_empty = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+    non_empty("BACKER_DATA_DIR")
+        .or_else(|| non_empty("XDG_DATA_HOME").map(|dir| dir.join("backer")))
+        .or_else(|| non_empty("HOME").map(|dir| dir.join(".local/share/backer")))
+}
+
+/// 本地模式库路径
+pub fn local_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(PATTERN_DB_FILE))
+}
+
+/// 校验并保存下载的模式库，先写临时文件再重命名，返回保存路径
+pub fn install(content: &[u8]) -> Result<PathBuf> {
+    let text = std::str::from_utf8(content)
+        .map_err(|_| BackerError::Config("模式库不是有效的UTF-8文本".to_string()))?;
+    PatternDb::parse(text)?;
+
+    let path = local_path().ok_or_else(|| BackerError::Config(
+        "无法确定数据目录，请设置 BACKER_DATA_DIR".to_string()
+    ))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let staged = path.with_extension("toml.new");
+    fs::write(&staged, content)?;
+    fs::rename(&staged, &path)?;
+    Ok(path)
+}
@@ -1,31 +1,52 @@
+pub mod parser;
+
 use crate::Result;
-use log::debug;
+use crate::candidate::{base_url, rule, Candidate};
+use crate::similarity::fnv1a;
+use compact_str::{format_compact, CompactString};
+use chrono::{Datelike, Local};
+use log::{debug, warn};
+use parser::{ExpandContext, Pattern, PatternEntry};
+use reqwest::Method;
 use std::collections::HashSet;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::sync::Arc;
+use crate::utils::open_wordlist;
+use std::io::BufRead;
 use std::path::Path;
 use url::Url;
 
+/// 默认的备份目录组合深度，只生成 `backup/` 这样的一级目录
+pub const DEFAULT_DIR_DEPTH: usize = 1;
+
+/// 候选去重时精确记录路径哈希的上限，预计候选数更多时改用布隆过滤器
+const EXACT_DEDUP_LIMIT: usize = 1 << 20;
+
+/// 布隆过滤器每个路径占用的位数和哈希函数个数，误判(重复以外的路径被跳过)率约万分之五
+const BLOOM_BITS_PER_PATH: usize = 16;
+const BLOOM_HASHES: u64 = 11;
+
+/// 内置的备份文件后缀，前缀和模板中的 `{ext}` 与每个后缀组合
+pub const HARD_CODED_SUFFIXES: &[&str] = &[".rar", ".zip", ".tar.gz", ".tar", ".7z", ".bak"];
+
+/// 内置的通用备份文件名，在备份目录下与后缀组合
+const COMMON_NAMES: &[&str] = &["backup", "site", "www", "web", "database", "db"];
+
 /// 备份文件模式生成器
 pub struct PatternGenerator {
     pub prefixes: Vec<String>,        // 前缀，将与后缀组合
     pub full_paths: Vec<String>,      // 完整路径，不与后缀组合
+    pub templates: Arc<Vec<Pattern>>, // 路径模板，按目标在迭代时展开为完整路径，所有目标共用
+    pub probes: Arc<Vec<(Method, Pattern)>>, // 指定了请求方法的路径模板，只在根目录下展开
     pub hard_coded_suffixes: Vec<String>,  // 硬编码的后缀列表
     pub domain_placeholders: Vec<String>,  // 域名占位符模板
     pub backup_dirs: Vec<String>,     // 备份目录名称
+    pub dir_depth: usize,             // 备份目录组合深度，0表示不生成目录候选
 }
 
 impl PatternGenerator {
     /// 创建一个新的模式生成器
     pub fn new() -> Self {
-        let hard_coded_suffixes = vec![
-            ".rar".to_string(),
-            ".zip".to_string(),
-            ".tar.gz".to_string(),
-            ".tar".to_string(),
-            ".7z".to_string(),
-            ".bak".to_string(),
-        ];
+        let hard_coded_suffixes = HARD_CODED_SUFFIXES.iter().map(|suffix| suffix.to_string()).collect();
 
         let domain_placeholders = vec![
             "{domain}".to_string(),
@@ -56,41 +77,83 @@ impl PatternGenerator {
         Self {
             prefixes: Vec::new(),
             full_paths: Vec::new(),
+            templates: Arc::new(Vec::new()),
+            probes: Arc::new(Vec::new()),
             hard_coded_suffixes,
             domain_placeholders,
             backup_dirs,
+            dir_depth: DEFAULT_DIR_DEPTH,
+        }
+    }
+
+    /// 由模式文件的各行创建生成器，模式只解析一次，所有目标共用
+    pub fn from_patterns(patterns: &[String], dir_depth: usize) -> Self {
+        let mut generator = Self::new();
+        generator.dir_depth = dir_depth;
+        Arc::make_mut(&mut generator.templates).reserve(patterns.len());
+        for pattern in patterns {
+            match PatternEntry::parse(pattern) {
+                Ok(entry) => generator.add_entry(entry),
+                Err(e) => warn!("跳过无效模式: {}", e),
+            }
+        }
+        generator
+    }
+
+    /// 添加模式文件中的一行
+    pub fn add_entry(&mut self, entry: PatternEntry) {
+        match entry {
+            PatternEntry::File(path) => Arc::make_mut(&mut self.templates).push(Pattern::literal(&path)),
+            PatternEntry::Prefix(name) => Arc::make_mut(&mut self.templates).push(Pattern::prefix(&name)),
+            PatternEntry::Dir(dir) => {
+                if !self.backup_dirs.contains(&dir) {
+                    self.backup_dirs.push(dir);
+                }
+            }
+            PatternEntry::Template(pattern) => Arc::make_mut(&mut self.templates).push(pattern),
+            PatternEntry::Probe(method, pattern) => Arc::make_mut(&mut self.probes).push((method, pattern)),
         }
     }
+    
+    /// 每个目标最多生成的候选数(去重前)，用于在扫描前预估请求量
+    pub fn estimate_candidates(&self) -> usize {
+        let suffixes = self.hard_coded_suffixes.len();
+        // 模板展开的路径，备份目录下的 `.` 开头路径额外生成去掉前导点的版本，按上限计
+        let expanded = self.templates.iter()
+            .map(|template| template.expansion_count(suffixes))
+            .fold(self.full_paths.len(), usize::saturating_add);
+        let prefixes = self.prefixes.len().saturating_mul(suffixes);
+        // 域名本身、占位符变体和纯字母数字版本
+        let variants = (self.domain_placeholders.len() + 2).saturating_mul(suffixes);
+        let root = expanded.saturating_add(prefixes).saturating_add(suffixes).saturating_add(variants);
+        let per_dir = (1 + COMMON_NAMES.len()).saturating_mul(suffixes)
+            .saturating_add(prefixes)
+            .saturating_add(expanded.saturating_mul(2))
+            .saturating_add(variants);
+        let probes = self.probes.iter()
+            .map(|(_, pattern)| pattern.expansion_count(suffixes).max(1))
+            .fold(0, usize::saturating_add);
+        root.saturating_add(probes).saturating_add(self.dir_combinations().len().saturating_mul(per_dir))
+    }
 
-    /// 从文件加载自定义模式
+    /// 从文件加载自定义模式，每行按 [`PatternEntry::parse`] 解析
     pub fn load_custom_patterns<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        let mut loaded_prefixes = 0;
-        let mut loaded_full_paths = 0;
+        let reader = open_wordlist(path)?;
+        let loaded = self.templates.len();
         
-        for line in reader.lines() {
+        for (index, line) in reader.lines().enumerate() {
             let line = line?;
             let trimmed = line.trim();
             
             if !trimmed.is_empty() && !trimmed.starts_with('#') {
-                // 检查是否以.开头
-                if trimmed.starts_with('.') || trimmed.contains('/') {
-                    // 以.开头或含有/的作为完整路径，不再组合后缀
-                    self.full_paths.push(trimmed.to_string());
-                    loaded_full_paths += 1;
-                } else {
-                    // 不以.开头的作为前缀，将与后缀组合
-                    self.prefixes.push(trimmed.to_string());
-                    loaded_prefixes += 1;
-                }
+                self.add_entry(PatternEntry::parse_file_line(index + 1, trimmed)?);
             }
         }
         
-        debug!("从模式文件加载了 {} 个前缀和 {} 个完整路径", loaded_prefixes, loaded_full_paths);
+        debug!("从模式文件加载了 {} 个模式", self.templates.len() - loaded);
         
         // 如果没有加载任何模式，使用默认的一些值
-        if self.prefixes.is_empty() && self.full_paths.is_empty() {
+        if self.prefixes.is_empty() && self.full_paths.is_empty() && self.templates.is_empty() && self.probes.is_empty() {
             self.prefixes = vec![
                 "backup".to_string(),
                 "bak".to_string(),
@@ -103,8 +166,7 @@ impl PatternGenerator {
 
     /// 从文件加载自定义域名占位符模板
     pub fn load_custom_domain_placeholders<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
+        let reader = open_wordlist(path)?;
         
         for line in reader.lines() {
             let line = line?;
@@ -129,6 +191,19 @@ impl PatternGenerator {
 
     /// 为给定的URL生成所有可能的备份文件URL
     pub fn generate_urls(&self, target_url: &str) -> Result<Vec<String>> {
+        Ok(self.candidates(target_url)?.map(|c| c.url()).collect())
+    }
+
+    /// 为给定的URL生成所有备份文件候选，根目录候选在前
+    pub fn generate_candidates(&self, target_url: &str) -> Result<Vec<Candidate>> {
+        Ok(self.candidates(target_url)?.collect())
+    }
+
+    /// 惰性生成备份文件候选，根目录候选在前
+    ///
+    /// 模板在迭代时才逐个展开，调用方按需拉取，不按目标复制模式；去重只保留路径的哈希值，
+    /// 预计候选数超过 [`EXACT_DEDUP_LIMIT`] 时改用布隆过滤器，内存占用每个路径两个字节
+    pub fn candidates(&self, target_url: &str) -> Result<CandidateIter> {
         let url = Url::parse(target_url)?;
         let host = url.host_str().ok_or_else(|| {
             crate::BackerError::Config(format!("无效的URL: {}", target_url))
@@ -137,114 +212,120 @@ impl PatternGenerator {
         let domain = extract_domain(host);
         debug!("从 {} 提取的域名部分: {}", host, domain);
         
-        let base_url = format!("{}://{}", url.scheme(), host);
+        let base = base_url(&url);
+        let suffixes: Arc<[String]> = self.hard_coded_suffixes.clone().into();
+        let target = self.target_context(&domain, host);
+        let sources = self.path_sources(&domain, &target);
         
-        // 先生成根目录URL
-        let mut root_urls: HashSet<String> = HashSet::new();
-        self.generate_root_urls(&mut root_urls, &base_url, &domain);
-        
-        // 再生成子目录URL
-        let mut dir_urls: HashSet<String> = HashSet::new();
-        self.generate_backup_dir_urls(&mut dir_urls, &base_url, &domain);
-        
-        // 统计根目录URL数量
-        let root_urls_count = root_urls.len();
-        
-        // 将根目录URL放在前面
-        let mut result_vec = root_urls.into_iter().collect::<Vec<String>>();
-        result_vec.extend(dir_urls.into_iter());
+        // 指定了请求方法的模式在最前面，`PROPFIND /` 这样的根路径展开为空路径
+        let probes = self.probes.clone();
+        let probe_paths = (0..probes.len()).flat_map(move |i| {
+            let (method, pattern) = &probes[i];
+            let mut paths = pattern.expand(&target.expand_context());
+            if paths.is_empty() && pattern.source().trim_matches('/').is_empty() {
+                paths.push(String::new());
+            }
+            let method = method.clone();
+            paths.into_iter().map(move |path| (CompactString::from(path), rule::METHOD_PROBE, Some(method.clone())))
+        });
         
-        debug!("为目标 {} 生成了 {} 个备份文件URL (根目录: {})", 
-               target_url, result_vec.len(), root_urls_count);
+        let mut seen = PathDedup::new(self.estimate_candidates());
+        let candidates = probe_paths
+            .chain(sources.into_iter().flat_map(move |source| {
+                let suffixes = suffixes.clone();
+                let rule = source.rule;
+                let source = Arc::new(source);
+                source.names.iter_owned().flat_map(move |name| {
+                    source.expand(&name, &suffixes).into_iter().map(move |path| (path, rule, None))
+                })
+            }))
+            // 同一路径用不同方法请求不算重复
+            .filter(move |(path, _, method): &(CompactString, u16, Option<Method>)| match method {
+                Some(method) => seen.insert(fnv1a(format!("{} {}", method, path).as_bytes())),
+                None => seen.insert(fnv1a(path.as_bytes())),
+            })
+            .map(move |(path, rule, method)| {
+                let candidate = Candidate::new(&base, path, rule);
+                match method {
+                    Some(method) => candidate.with_method(method),
+                    None => candidate,
+                }
+            });
         
-        Ok(result_vec)
+        Ok(Box::new(candidates))
     }
     
-    /// 为根目录生成备份文件URL
-    fn generate_root_urls(&self, result: &mut HashSet<String>, base_url: &str, domain: &str) {
-        // 1. 添加完整路径（不添加后缀）
-        for path in &self.full_paths {
-            result.insert(format!("{}/{}", base_url, path));
-        }
+    /// 展开模板所需的目标信息
+    fn target_context(&self, domain: &str, host: &str) -> Arc<TargetContext> {
+        Arc::new(TargetContext {
+            domain: domain.to_string(),
+            host: host.to_string(),
+            year: Local::now().year(),
+            extensions: self.hard_coded_suffixes.clone(),
+        })
+    }
+    
+    /// 按生成顺序列出候选路径的来源，根目录在前，备份目录在后
+    fn path_sources(&self, domain: &str, target: &Arc<TargetContext>) -> Vec<PathSource> {
+        let full_paths = Names::Expanded {
+            full_paths: self.full_paths.clone().into(),
+            templates: self.templates.clone(),
+            target: target.clone(),
+        };
+        let prefixes = Names::List(self.prefixes.clone().into());
+        let domain_names = Names::List(vec![domain.to_string()].into());
+        let variants = Names::List(self.generate_domain_variants(domain).into());
+        let common_names = Names::List(COMMON_NAMES.iter().map(|name| name.to_string()).collect());
         
-        // 2. 前缀与硬编码后缀组合
-        for prefix in &self.prefixes {
-            // 检查前缀是否已经包含后缀（如 "backup.zip"）
-            if prefix.contains('.') {
-                // 如果已包含后缀，直接添加
-                result.insert(format!("{}/{}", base_url, prefix));
-            } else {
-                // 否则组合所有后缀
-                for suffix in &self.hard_coded_suffixes {
-                    result.insert(format!("{}/{}{}", base_url, prefix, suffix));
-                }
-            }
-        }
+        let source = |dir: Option<&String>, names: &Names, rule: u16, expansion: Expansion| PathSource {
+            dir: dir.cloned(),
+            names: names.clone(),
+            rule,
+            expansion,
+        };
         
-        // 3. 域名本身与硬编码后缀组合
-        for suffix in &self.hard_coded_suffixes {
-            result.insert(format!("{}/{}{}", base_url, domain, suffix));
-        }
+        // 根目录：完整路径、前缀、域名本身、域名变体
+        let mut sources = vec![
+            source(None, &full_paths, rule::FULL_PATH, Expansion::Verbatim),
+            source(None, &prefixes, rule::PREFIX, Expansion::Prefix),
+            source(None, &domain_names, rule::DOMAIN, Expansion::Suffixes),
+            source(None, &variants, rule::DOMAIN_VARIANT, Expansion::Suffixes),
+        ];
         
-        // 4. 域名变体与硬编码后缀组合
-        let domain_variants = self.generate_domain_variants(domain);
-        for variant in domain_variants {
-            for suffix in &self.hard_coded_suffixes {
-                result.insert(format!("{}/{}{}", base_url, variant, suffix));
-            }
+        // 备份目录：域名、通用备份名、前缀、完整路径、域名变体
+        for dir in &self.dir_combinations() {
+            sources.push(source(Some(dir), &domain_names, rule::DIR_DOMAIN, Expansion::Suffixes));
+            sources.push(source(Some(dir), &common_names, rule::DIR_COMMON_NAME, Expansion::Suffixes));
+            sources.push(source(Some(dir), &prefixes, rule::DIR_PREFIX, Expansion::Prefix));
+            sources.push(source(Some(dir), &full_paths, rule::DIR_FULL_PATH, Expansion::DirFullPath));
+            sources.push(source(Some(dir), &variants, rule::DIR_DOMAIN_VARIANT, Expansion::Suffixes));
         }
+        
+        sources
     }
     
-    /// 为备份目录生成备份文件URL
-    fn generate_backup_dir_urls(&self, result: &mut HashSet<String>, base_url: &str, domain: &str) {
-        for dir in &self.backup_dirs {
-            // 1. 目录下的域名与后缀组合
-            for suffix in &self.hard_coded_suffixes {
-                result.insert(format!("{}/{}/{}{}", base_url, dir, domain, suffix));
-            }
-            
-            // 2. 目录下的通用备份名
-            for common_name in &["backup", "site", "www", "web", "database", "db"] {
-                for suffix in &self.hard_coded_suffixes {
-                    result.insert(format!("{}/{}/{}{}", base_url, dir, common_name, suffix));
-                }
-            }
-            
-            // 3. 目录下前缀与后缀组合
-            for prefix in &self.prefixes {
-                // 检查前缀是否已经包含后缀
-                if prefix.contains('.') {
-                    // 如果已包含后缀，直接添加
-                    result.insert(format!("{}/{}/{}", base_url, dir, prefix));
-                } else {
-                    // 否则组合所有后缀
-                    for suffix in &self.hard_coded_suffixes {
-                        result.insert(format!("{}/{}/{}{}", base_url, dir, prefix, suffix));
-                    }
-                }
-            }
-            
-            // 4. 目录下完整路径
-            for path in &self.full_paths {
-                if path.starts_with('.') {
-                    // 对于.开头的路径，添加不带前导点的版本
-                    let no_dot = path.trim_start_matches('.');
-                    if !no_dot.is_empty() {
-                        result.insert(format!("{}/{}/{}", base_url, dir, no_dot));
-                    }
-                }
-                // 始终添加原始路径
-                result.insert(format!("{}/{}/{}", base_url, dir, path));
-            }
-            
-            // 5. 目录下的域名变体与后缀组合
-            let domain_variants = self.generate_domain_variants(domain);
-            for variant in domain_variants {
-                for suffix in &self.hard_coded_suffixes {
-                    result.insert(format!("{}/{}/{}{}", base_url, dir, variant, suffix));
-                }
-            }
+    /// 按组合深度列出备份目录，如深度2时生成 `backup`、`old`、`backup/old`、`old/backup`，
+    /// 同一目录在一条路径中只出现一次，浅层目录在前
+    fn dir_combinations(&self) -> Vec<String> {
+        let mut combinations = Vec::new();
+        let mut layer: Vec<Vec<&String>> = vec![Vec::new()];
+        for _ in 0..self.dir_depth.min(self.backup_dirs.len()) {
+            layer = layer.iter()
+                .flat_map(|path| {
+                    self.backup_dirs.iter()
+                        .filter(|dir| !path.contains(dir))
+                        .map(move |dir| {
+                            let mut next = path.clone();
+                            next.push(dir);
+                            next
+                        })
+                })
+                .collect();
+            combinations.extend(layer.iter().map(|path| {
+                path.iter().map(|dir| dir.as_str()).collect::<Vec<_>>().join("/")
+            }));
         }
+        combinations
     }
     
     /// 生成域名的各种变体
@@ -272,10 +353,144 @@ impl PatternGenerator {
     }
 }
 
+/// 惰性候选迭代器
+pub type CandidateIter = Box<dyn Iterator<Item = Candidate> + Send>;
+
+/// 名称展开为路径的方式
+#[derive(Debug, Clone, Copy)]
+enum Expansion {
+    /// 原样使用
+    Verbatim,
+    /// 与每个硬编码后缀组合
+    Suffixes,
+    /// 已含后缀的前缀原样使用，否则与后缀组合
+    Prefix,
+    /// 目录下的完整路径，`.`开头时额外生成去掉前导点的版本
+    DirFullPath,
+}
+
+/// 展开模板所需的目标信息
+struct TargetContext {
+    domain: String,
+    host: String,
+    year: i32,
+    extensions: Vec<String>,
+}
+
+impl TargetContext {
+    fn expand_context(&self) -> ExpandContext<'_> {
+        ExpandContext { domain: &self.domain, host: &self.host, year: self.year, extensions: &self.extensions }
+    }
+}
+
+/// 候选路径来源中的名称
+#[derive(Clone)]
+enum Names {
+    /// 固定的名称列表
+    List(Arc<[String]>),
+    /// 完整路径和在迭代时才按目标展开的路径模板
+    Expanded {
+        full_paths: Arc<[String]>,
+        templates: Arc<Vec<Pattern>>,
+        target: Arc<TargetContext>,
+    },
+}
+
+impl Names {
+    /// 依次产生各个名称，模板逐个展开，不一次性生成全部路径
+    fn iter_owned(&self) -> Box<dyn Iterator<Item = String> + Send + Sync> {
+        match self.clone() {
+            Names::List(names) => Box::new((0..names.len()).map(move |i| names[i].clone())),
+            Names::Expanded { full_paths, templates, target } => Box::new(
+                (0..full_paths.len()).map(move |i| full_paths[i].clone())
+                    .chain((0..templates.len()).flat_map(move |i| templates[i].expand(&target.expand_context())))
+            ),
+        }
+    }
+}
+
+/// 候选路径来源：在某个目录下按指定方式展开一组名称
+struct PathSource {
+    dir: Option<String>,
+    names: Names,
+    rule: u16,
+    expansion: Expansion,
+}
+
+/// 候选路径去重：预计路径不多时精确记录哈希，否则使用布隆过滤器
+///
+/// 布隆过滤器偶尔把未出现过的路径误判为重复而跳过，换取大模式文件下可控的内存占用
+enum PathDedup {
+    Exact(HashSet<u64>),
+    Bloom(Vec<u64>),
+}
+
+impl PathDedup {
+    /// 按预计的路径数选择去重方式
+    fn new(expected: usize) -> Self {
+        if expected <= EXACT_DEDUP_LIMIT {
+            PathDedup::Exact(HashSet::new())
+        } else {
+            PathDedup::Bloom(vec![0; expected.saturating_mul(BLOOM_BITS_PER_PATH).div_ceil(64)])
+        }
+    }
+
+    /// 记录路径哈希，之前未出现过时返回true
+    fn insert(&mut self, hash: u64) -> bool {
+        match self {
+            PathDedup::Exact(seen) => seen.insert(hash),
+            PathDedup::Bloom(bits) => {
+                // 由一个哈希值派生多个位置(Kirsch-Mitzenmacher)
+                let len = bits.len() as u64 * 64;
+                let step = hash.rotate_left(32) | 1;
+                let mut inserted = false;
+                for i in 0..BLOOM_HASHES {
+                    let bit = hash.wrapping_add(i.wrapping_mul(step)) % len;
+                    let (word, mask) = ((bit / 64) as usize, 1u64 << (bit % 64));
+                    if bits[word] & mask == 0 {
+                        bits[word] |= mask;
+                        inserted = true;
+                    }
+                }
+                inserted
+            }
+        }
+    }
+}
+
+impl PathSource {
+    /// 展开单个名称，结果数量不超过后缀数量
+    fn expand(&self, name: &str, suffixes: &[String]) -> Vec<CompactString> {
+        let path = match &self.dir {
+            Some(dir) => format_compact!("{}/{}", dir, name),
+            None => CompactString::from(name),
+        };
+        let with_suffixes = || suffixes.iter().map(|suffix| format_compact!("{}{}", path, suffix)).collect();
+        
+        match self.expansion {
+            Expansion::Verbatim => vec![path.clone()],
+            Expansion::Suffixes => with_suffixes(),
+            Expansion::Prefix if name.contains('.') => vec![path.clone()],
+            Expansion::Prefix => with_suffixes(),
+            Expansion::DirFullPath => {
+                let mut paths = Vec::with_capacity(2);
+                let no_dot = name.trim_start_matches('.');
+                if name.starts_with('.') && !no_dot.is_empty() {
+                    if let Some(dir) = &self.dir {
+                        paths.push(format_compact!("{}/{}", dir, no_dot));
+                    }
+                }
+                paths.push(path.clone());
+                paths
+            }
+        }
+    }
+}
+
 /// 从主机名提取域名部分
 fn extract_domain(host: &str) -> String {
     // 如果是IP地址，直接返回
-    if host.chars().all(|c| c.is_digit(10) || c == '.') {
+    if host.chars().all(|c| c.is_ascii_digit() || c == '.') {
         return host.to_string();
     }
 
@@ -295,14 +510,23 @@ fn extract_domain(host: &str) -> String {
     host.to_string()
 }
 
+impl Default for PatternGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Clone for PatternGenerator {
     fn clone(&self) -> Self {
         Self {
             prefixes: self.prefixes.clone(),
             full_paths: self.full_paths.clone(),
+            templates: Arc::clone(&self.templates),
+            probes: Arc::clone(&self.probes),
             hard_coded_suffixes: self.hard_coded_suffixes.clone(),
             domain_placeholders: self.domain_placeholders.clone(),
             backup_dirs: self.backup_dirs.clone(),
+            dir_depth: self.dir_depth,
         }
     }
 } 
\ No newline at end of file
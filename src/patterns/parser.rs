@@ -0,0 +1,470 @@
+use super::HARD_CODED_SUFFIXES;
+use crate::{BackerError, Result};
+use reqwest::Method;
+use std::collections::HashSet;
+use std::fmt;
+
+/// 单个模式最多展开的结果数，防止嵌套选择组合爆炸；`{ext}` 按内置后缀数计算
+pub const MAX_EXPANSIONS: usize = 4096;
+
+/// 模式行首可以指定的请求方法，都不修改服务器上的内容
+pub const PROBE_METHODS: &[&str] = &["GET", "HEAD", "POST", "OPTIONS", "PROPFIND", "REPORT", "SEARCH"];
+
+/// 会修改服务器内容的方法，出现在行首时报错而不是当作路径
+const UNSAFE_METHODS: &[&str] = &["PUT", "DELETE", "PATCH", "MKCOL", "COPY", "MOVE", "LOCK", "UNLOCK", "PROPPATCH"];
+
+/// 占位符
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placeholder {
+    /// 域名部分，如 `www.example.com` 的 `example`
+    Domain,
+    /// 完整主机名
+    Host,
+    /// 当前年份
+    Year,
+    /// 每个硬编码后缀，如 `.zip`
+    Ext,
+}
+
+impl Placeholder {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "domain" => Some(Placeholder::Domain),
+            "host" => Some(Placeholder::Host),
+            "year" => Some(Placeholder::Year),
+            "ext" => Some(Placeholder::Ext),
+            _ => None,
+        }
+    }
+}
+
+/// 模板语法树节点
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    Literal(String),
+    Placeholder(Placeholder),
+    Alternation(Vec<Vec<Node>>),
+    Optional(Vec<Node>),
+}
+
+/// 展开模板时使用的目标信息
+#[derive(Debug, Clone, Copy)]
+pub struct ExpandContext<'a> {
+    pub domain: &'a str,
+    pub host: &'a str,
+    pub year: i32,
+    pub extensions: &'a [String],
+}
+
+/// 解析后的路径模板
+///
+/// 每行模式是一个路径模板，展开后的每个结果都作为完整路径原样请求：
+///
+/// - `{domain}`、`{host}`、`{year}`、`{ext}`：占位符，分别替换为域名部分、完整主机名、
+///   当前年份和每个硬编码后缀
+/// - `{backup,bak,old}`：选择，逗号分隔的每一项各生成一个结果，项内可嵌套其他语法
+/// - `[_{year}]`：可选段，分别生成包含和不包含该段的结果
+/// - `\{`、`\}`、`\[`、`\]`、`\,`、`\\`：转义，按字面使用；`,` 只能出现在选择中，
+///   其他位置(包括可选段内)的逗号须写作 `\,`
+///
+/// 例如 `{domain}[_{year}].{zip,tar.gz}` 对 `www.example.com` 展开为
+/// `example.zip`、`example.tar.gz`、`example_2026.zip`、`example_2026.tar.gz`
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    source: String,
+    nodes: Vec<Node>,
+}
+
+impl Pattern {
+    /// 按模板语法解析
+    pub fn parse(source: &str) -> Result<Self> {
+        if source.is_empty() {
+            return Err(BackerError::Config("模式不能为空".to_string()));
+        }
+        let chars: Vec<char> = source.chars().collect();
+        let mut parser = Parser { chars: &chars, pos: 0 };
+        let nodes = parser.sequence(None)
+            .map_err(|(pos, message)| BackerError::Config(format!("模式 `{}` 第 {} 个字符: {}", source, pos + 1, message)))?;
+
+        let count = count_with_extensions(&nodes, HARD_CODED_SUFFIXES.len());
+        if count > MAX_EXPANSIONS {
+            return Err(BackerError::Config(format!(
+                "模式 `{}` 展开后超过 {} 个路径，请拆分为多行", source, MAX_EXPANSIONS
+            )));
+        }
+        Ok(Self { source: source.to_string(), nodes })
+    }
+
+    /// 原样使用的字面路径
+    pub fn literal(path: &str) -> Self {
+        Self {
+            source: path.to_string(),
+            nodes: vec![Node::Literal(path.to_string())],
+        }
+    }
+
+    /// 与每个内置后缀组合的字面前缀，等价于 `前缀{ext}`
+    pub fn prefix(name: &str) -> Self {
+        Self {
+            source: format!("{}{{ext}}", name),
+            nodes: vec![Node::Literal(name.to_string()), Node::Placeholder(Placeholder::Ext)],
+        }
+    }
+
+    /// 展开后的路径数上限，`{ext}` 按 `extensions` 个后缀计算
+    pub fn expansion_count(&self, extensions: usize) -> usize {
+        count_with_extensions(&self.nodes, extensions)
+    }
+
+    /// 模式原文
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// 展开为路径列表，结果按模板顺序排列，不含前导斜杠，重复的路径只保留第一次出现
+    pub fn expand(&self, context: &ExpandContext) -> Vec<String> {
+        let mut seen = HashSet::new();
+        expand_sequence(&self.nodes, context).into_iter()
+            .map(|path| path.trim_start_matches('/').to_string())
+            .filter(|path| !path.is_empty() && seen.insert(path.clone()))
+            .collect()
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// 模式文件中一行的解释方式
+///
+/// 行首可以用指令显式指定类型：
+///
+/// - `file:wp-config.php`：完整路径，原样请求，不解析模板语法
+/// - `prefix:backup`：字面前缀，与每个内置后缀组合
+/// - `dir:old`：额外的备份目录，其他模式也会在该目录下生成
+/// - `template:{domain}.zip`：路径模板，见 [`Pattern`]
+///
+/// 行首还可以用大写的请求方法和空格指定探测方法，如 `PROPFIND /backup/`、`GET /.git/HEAD`，
+/// 其后的路径按模板解析，只在根目录下请求，不与备份目录组合，可用的方法见 [`PROBE_METHODS`]
+///
+/// 未加指令的行按旧规则推断：不含 `.`、`/` 和模板语法的纯名称视为前缀，其余视为模板
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternEntry {
+    File(String),
+    Prefix(String),
+    Dir(String),
+    Template(Pattern),
+    Probe(Method, Pattern),
+}
+
+impl PatternEntry {
+    /// 解析一行模式
+    pub fn parse(line: &str) -> Result<Self> {
+        let line = line.trim();
+        if let Some((method, path)) = split_method(line)? {
+            return Pattern::parse(path).map(|pattern| PatternEntry::Probe(method, pattern));
+        }
+        let Some((directive, value)) = split_directive(line) else {
+            return if is_bare_name(line) {
+                Ok(PatternEntry::Prefix(line.to_string()))
+            } else {
+                Pattern::parse(line).map(PatternEntry::Template)
+            };
+        };
+
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(BackerError::Config(format!("`{}:` 指令后缺少内容", directive)));
+        }
+        match directive {
+            "file" => Ok(PatternEntry::File(value.trim_start_matches('/').to_string())),
+            "prefix" => Ok(PatternEntry::Prefix(value.to_string())),
+            "dir" => Ok(PatternEntry::Dir(value.trim_matches('/').to_string())),
+            _ => Pattern::parse(value).map(PatternEntry::Template),
+        }
+    }
+
+    /// 解析模式文件中的一行，错误信息带上行号，`number` 从1开始
+    pub fn parse_file_line(number: usize, line: &str) -> Result<Self> {
+        Self::parse(line).map_err(|e| match e {
+            BackerError::Config(message) => BackerError::Config(format!("模式文件第 {} 行: {}", number, message)),
+            other => other,
+        })
+    }
+
+    /// 描述该行的解释方式，用于 `--dry-run` 输出
+    pub fn describe(&self) -> String {
+        match self {
+            PatternEntry::File(path) => format!("文件 {}", path),
+            PatternEntry::Prefix(name) => format!("前缀 {} + 内置后缀", name),
+            PatternEntry::Dir(dir) => format!("备份目录 {}/", dir),
+            PatternEntry::Template(pattern) => format!("模板 {}", pattern),
+            PatternEntry::Probe(method, pattern) => format!("{} 请求 模板 {}", method, pattern),
+        }
+    }
+}
+
+/// 判断行首是否带有显式指令或请求方法
+pub fn has_directive(line: &str) -> bool {
+    let line = line.trim();
+    split_directive(line).is_some() || matches!(split_method(line), Ok(Some(_)) | Err(_))
+}
+
+/// 拆分行首的请求方法，如 `PROPFIND /backup/`；不是已知方法的大写单词(如 `DB backup.zip`)仍按路径处理
+fn split_method(line: &str) -> Result<Option<(Method, &str)>> {
+    let Some((token, path)) = line.split_once(char::is_whitespace) else {
+        return Ok(None);
+    };
+    if UNSAFE_METHODS.contains(&token) {
+        return Err(BackerError::Config(format!(
+            "不支持的请求方法 {}：只能使用不修改服务器内容的方法 ({})", token, PROBE_METHODS.join(", ")
+        )));
+    }
+    if !PROBE_METHODS.contains(&token) {
+        return Ok(None);
+    }
+    let path = path.trim();
+    if path.is_empty() {
+        return Err(BackerError::Config(format!("请求方法 {} 后缺少路径", token)));
+    }
+    let method = Method::from_bytes(token.as_bytes())
+        .map_err(|_| BackerError::Config(format!("无效的请求方法: {}", token)))?;
+    Ok(Some((method, path)))
+}
+
+/// 拆分行首的 `file:`、`prefix:`、`dir:`、`template:` 指令
+fn split_directive(line: &str) -> Option<(&str, &str)> {
+    let (directive, value) = line.split_once(':')?;
+    matches!(directive, "file" | "prefix" | "dir" | "template").then_some((directive, value))
+}
+
+/// 判断是否为按旧规则视为前缀的纯名称
+fn is_bare_name(line: &str) -> bool {
+    !line.is_empty() && !line.contains(['.', '/', '{', '}', '[', ']', '\\'])
+}
+
+/// 出错时返回字符位置和原因
+type ParseResult<T> = std::result::Result<T, (usize, String)>;
+
+struct Parser<'a> {
+    chars: &'a [char],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    /// 解析节点序列，直到遇到 `terminators` 中的字符或输入结束
+    fn sequence(&mut self, terminators: Option<&[char]>) -> ParseResult<Vec<Node>> {
+        let mut nodes = Vec::new();
+        let mut literal = String::new();
+
+        while let Some(&c) = self.chars.get(self.pos) {
+            if terminators.is_some_and(|t| t.contains(&c)) {
+                break;
+            }
+            match c {
+                '\\' => {
+                    let escaped = self.chars.get(self.pos + 1)
+                        .ok_or((self.pos, "转义符后缺少字符".to_string()))?;
+                    literal.push(*escaped);
+                    self.pos += 2;
+                }
+                '{' | '[' => {
+                    if !literal.is_empty() {
+                        nodes.push(Node::Literal(std::mem::take(&mut literal)));
+                    }
+                    nodes.push(if c == '{' { self.braces()? } else { self.optional()? });
+                }
+                '}' | ']' => return Err((self.pos, format!("多余的 `{}`", c))),
+                ',' if !terminators.is_some_and(|t| t.contains(&',')) => {
+                    return Err((self.pos, "`,` 只能用于 `{...}` 选择中，字面逗号请写作 `\\,`".to_string()));
+                }
+                _ => {
+                    literal.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+
+        if !literal.is_empty() {
+            nodes.push(Node::Literal(literal));
+        }
+        Ok(nodes)
+    }
+
+    /// 解析 `{...}`，单个名称为占位符，含逗号为选择
+    fn braces(&mut self) -> ParseResult<Node> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut branches = vec![self.sequence(Some(&[',', '}']))?];
+        loop {
+            match self.chars.get(self.pos) {
+                Some(',') => {
+                    self.pos += 1;
+                    branches.push(self.sequence(Some(&[',', '}']))?);
+                }
+                Some('}') => {
+                    self.pos += 1;
+                    break;
+                }
+                _ => return Err((start, "`{` 未闭合".to_string())),
+            }
+        }
+
+        if branches.len() > 1 {
+            return Ok(Node::Alternation(branches));
+        }
+        match branches[0].as_slice() {
+            [Node::Literal(name)] => Placeholder::parse(name)
+                .map(Node::Placeholder)
+                .ok_or((start, format!("未知占位符 `{{{}}}`，可用: domain、host、year、ext", name))),
+            [] => Err((start, "`{}` 为空".to_string())),
+            _ => Err((start, "选择至少需要两项，用 `,` 分隔".to_string())),
+        }
+    }
+
+    /// 解析 `[...]` 可选段
+    fn optional(&mut self) -> ParseResult<Node> {
+        let start = self.pos;
+        self.pos += 1;
+        let nodes = self.sequence(Some(&[']']))?;
+        if self.chars.get(self.pos) != Some(&']') {
+            return Err((start, "`[` 未闭合".to_string()));
+        }
+        self.pos += 1;
+        if nodes.is_empty() {
+            return Err((start, "`[]` 为空".to_string()));
+        }
+        Ok(Node::Optional(nodes))
+    }
+}
+
+/// 估算展开数量，`{ext}` 按 `extensions` 个后缀计算
+fn count_with_extensions(nodes: &[Node], extensions: usize) -> usize {
+    nodes.iter().fold(1usize, |count, node| {
+        let factor = match node {
+            Node::Literal(_) => 1,
+            Node::Placeholder(Placeholder::Ext) => extensions,
+            Node::Placeholder(_) => 1,
+            Node::Alternation(branches) => branches.iter().map(|b| count_with_extensions(b, extensions)).fold(0, usize::saturating_add),
+            Node::Optional(inner) => count_with_extensions(inner, extensions).saturating_add(1),
+        };
+        count.saturating_mul(factor)
+    })
+}
+
+fn expand_sequence(nodes: &[Node], context: &ExpandContext) -> Vec<String> {
+    let mut results = vec![String::new()];
+    for node in nodes {
+        let parts = expand_node(node, context);
+        results = results.iter()
+            .flat_map(|prefix| parts.iter().map(move |part| format!("{}{}", prefix, part)))
+            .collect();
+    }
+    results
+}
+
+fn expand_node(node: &Node, context: &ExpandContext) -> Vec<String> {
+    match node {
+        Node::Literal(text) => vec![text.clone()],
+        Node::Placeholder(Placeholder::Domain) => vec![context.domain.to_string()],
+        Node::Placeholder(Placeholder::Host) => vec![context.host.to_string()],
+        Node::Placeholder(Placeholder::Year) => vec![context.year.to_string()],
+        Node::Placeholder(Placeholder::Ext) => context.extensions.to_vec(),
+        Node::Alternation(branches) => branches.iter().flat_map(|b| expand_sequence(b, context)).collect(),
+        Node::Optional(inner) => {
+            let mut parts = vec![String::new()];
+            parts.extend(expand_sequence(inner, context));
+            parts
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(source: &str) -> Vec<String> {
+        let extensions = vec![".zip".to_string(), ".bak".to_string()];
+        let context = ExpandContext { domain: "example", host: "www.example.com", year: 2026, extensions: &extensions };
+        Pattern::parse(source).unwrap().expand(&context)
+    }
+
+    fn parse_error(source: &str) -> String {
+        Pattern::parse(source).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn reports_error_positions() {
+        assert!(parse_error("{domain").contains("第 1 个字符: `{` 未闭合"));
+        assert!(parse_error("db[_old").contains("第 3 个字符: `[` 未闭合"));
+        assert!(parse_error("db}.sql").contains("第 3 个字符: 多余的 `}`"));
+        assert!(parse_error("a.sql]").contains("第 6 个字符: 多余的 `]`"));
+        assert!(parse_error("a,b").contains("第 2 个字符: `,` 只能用于"));
+        assert!(parse_error("db.sql\\").contains("第 7 个字符: 转义符后缺少字符"));
+        assert!(parse_error("x{name}").contains("第 2 个字符: 未知占位符 `{name}`"));
+        assert!(parse_error("x{}").contains("第 2 个字符: `{}` 为空"));
+        assert!(parse_error("x[]").contains("第 2 个字符: `[]` 为空"));
+        assert!(parse_error("{a{year}}").contains("第 1 个字符: 选择至少需要两项"));
+        assert!(parse_error("").contains("模式不能为空"));
+    }
+
+    #[test]
+    fn rejects_commas_outside_alternation() {
+        assert!(parse_error("db[_a,b].sql").contains("第 6 个字符: `,` 只能用于"));
+        assert!(parse_error("{x,[a,b]}").contains("第 6 个字符: `,` 只能用于"));
+        assert_eq!(expand("db[_a\\,b].sql"), ["db.sql", "db_a,b.sql"]);
+    }
+
+    #[test]
+    fn expands_escapes_literally() {
+        assert_eq!(expand("a\\{b\\}\\[c\\]\\,d\\\\"), ["a{b}[c],d\\"]);
+        assert_eq!(expand("\\{domain\\}.zip"), ["{domain}.zip"]);
+    }
+
+    #[test]
+    fn expands_placeholders() {
+        assert_eq!(expand("{host}/{domain}_{year}{ext}"), [
+            "www.example.com/example_2026.zip",
+            "www.example.com/example_2026.bak",
+        ]);
+    }
+
+    #[test]
+    fn expands_alternation_and_optional_in_template_order() {
+        assert_eq!(expand("{domain}[_{year}].{zip,tar.gz}"), [
+            "example.zip",
+            "example.tar.gz",
+            "example_2026.zip",
+            "example_2026.tar.gz",
+        ]);
+        assert_eq!(expand("db{,_old}.sql"), ["db.sql", "db_old.sql"]);
+        assert_eq!(expand("{a,b[c]}"), ["a", "b", "bc"]);
+        assert_eq!(expand("/[old/]site.zip"), ["site.zip", "old/site.zip"]);
+        // 不相邻的重复路径也只保留第一次出现
+        assert_eq!(expand("{a,b,a}.{zip,sql}"), ["a.zip", "a.sql", "b.zip", "b.sql"]);
+        assert_eq!(expand("[/]x"), ["x"]);
+    }
+
+    #[test]
+    fn counts_expansions_with_extensions() {
+        let pattern = Pattern::parse("{a,b,c}[_{year}]{ext}").unwrap();
+        assert_eq!(pattern.expansion_count(6), 36);
+        assert_eq!(pattern.expansion_count(2), expand("{a,b,c}[_{year}]{ext}").len());
+    }
+
+    #[test]
+    fn caps_expansions() {
+        // 12 个二选一正好 4096 个
+        let pairs = "{a,b}".repeat(12);
+        assert_eq!(Pattern::parse(&pairs).unwrap().expansion_count(HARD_CODED_SUFFIXES.len()), MAX_EXPANSIONS);
+        assert!(parse_error(&format!("{}x", "{a,b}".repeat(13))).contains("超过 4096 个路径"));
+
+        // `{ext}` 按内置后缀数计算：1000 项 × 6 个后缀超过上限
+        let names: Vec<String> = (0..1000).map(|i| format!("n{}", i)).collect();
+        assert!(Pattern::parse(&format!("{{{}}}", names.join(","))).is_ok());
+        assert!(parse_error(&format!("{{{}}}{{ext}}", names.join(","))).contains("超过 4096 个路径"));
+    }
+}
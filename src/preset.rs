@@ -0,0 +1,122 @@
+use crate::category::Category;
+use crate::tech::Technology;
+use serde::{Deserialize, Serialize};
+
+/// 去掉备份后缀后匹配文件名
+const BACKUP_SUFFIXES: &[&str] = &[".bak", ".backup", ".old", ".orig", ".save", ".swp", "~"];
+
+/// PHP平台无关的其他技术栈路径特征，小写
+const NON_PHP_MARKERS: &[&str] = &[
+    "web.config", "settings.py", "application.properties", "application.yml", "application.yaml",
+    "application-prod.yml", "web-inf/", "tomcat-users", ".war", ".jar", ".aspx", ".jsp",
+];
+
+/// 验证匹配器：文件内容应包含的特征
+#[derive(Debug, Clone, Copy)]
+pub struct ContentMatcher {
+    /// 去掉备份后缀的文件名，如 `wp-config.php`
+    pub file: &'static str,
+    /// 内容包含其中任一特征时视为真实文件
+    pub markers: &'static [&'static str],
+}
+
+const WORDPRESS_MATCHERS: &[ContentMatcher] = &[
+    ContentMatcher { file: "wp-config.php", markers: &["DB_PASSWORD", "DB_NAME", "table_prefix", "AUTH_KEY"] },
+    ContentMatcher { file: "wp-config", markers: &["DB_PASSWORD", "DB_NAME", "table_prefix", "AUTH_KEY"] },
+];
+
+const MAGENTO_MATCHERS: &[ContentMatcher] = &[
+    ContentMatcher { file: "env.php", markers: &["'crypt'", "'connection'", "'backend'"] },
+    ContentMatcher { file: "local.xml", markers: &["<crypt>", "<connection>"] },
+];
+
+const LARAVEL_MATCHERS: &[ContentMatcher] = &[
+    ContentMatcher { file: ".env", markers: &["APP_KEY=", "APP_ENV=", "DB_PASSWORD="] },
+    ContentMatcher { file: "database.php", markers: &["'connections'"] },
+];
+
+/// 目标类型预设，`--target-type` 指定
+///
+/// 已知目标平台时跳过技术栈识别，直接加入平台专属路径并启用相关分类，过滤其他技术栈的路径，
+/// 验证内容时按平台特征确认配置文件是否真实
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetType {
+    /// 未知平台，不做任何假定
+    #[default]
+    Generic,
+    WordPress,
+    Magento,
+    Laravel,
+}
+
+impl TargetType {
+    /// 名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            TargetType::Generic => "generic",
+            TargetType::WordPress => "wordpress",
+            TargetType::Magento => "magento",
+            TargetType::Laravel => "laravel",
+        }
+    }
+
+    /// 假定的技术栈，扫描时不再通过favicon识别
+    pub fn technology(&self) -> Option<Technology> {
+        match self {
+            TargetType::Generic => None,
+            TargetType::WordPress => Some(Technology::WordPress),
+            TargetType::Magento => Some(Technology::Magento),
+            TargetType::Laravel => Some(Technology::Laravel),
+        }
+    }
+
+    /// 预设额外启用的模式分类
+    pub fn categories(&self) -> &'static [Category] {
+        match self {
+            TargetType::Generic => &[],
+            TargetType::WordPress => &[Category::Config],
+            TargetType::Magento | TargetType::Laravel => &[Category::Config, Category::Vcs],
+        }
+    }
+
+    /// 路径是否属于与该平台无关的技术栈，如PHP平台上的 `web.config`、`.war`
+    pub fn is_irrelevant(&self, path: &str) -> bool {
+        if *self == TargetType::Generic {
+            return false;
+        }
+        let path = path.to_lowercase();
+        NON_PHP_MARKERS.iter().any(|marker| path.contains(marker))
+    }
+
+    /// 平台配置文件的验证匹配器
+    pub fn matchers(&self) -> &'static [ContentMatcher] {
+        match self {
+            TargetType::Generic => &[],
+            TargetType::WordPress => WORDPRESS_MATCHERS,
+            TargetType::Magento => MAGENTO_MATCHERS,
+            TargetType::Laravel => LARAVEL_MATCHERS,
+        }
+    }
+
+    /// 按验证匹配器检查URL的内容：有对应匹配器时返回内容是否包含特征，否则返回None
+    pub fn matches_content(&self, url: &str, content: &[u8]) -> Option<bool> {
+        let path = url.split(['?', '#']).next().unwrap_or(url);
+        let name = path.rsplit('/').next().unwrap_or(path).to_lowercase();
+        let stem = BACKUP_SUFFIXES.iter().fold(name.as_str(), |name, suffix| name.strip_suffix(suffix).unwrap_or(name));
+        // vim交换文件以点开头，如 `.wp-config.php.swp`
+        let matcher = self.matchers().iter()
+            .find(|matcher| stem == matcher.file || stem.strip_prefix('.') == Some(matcher.file))?;
+        let text = String::from_utf8_lossy(content);
+        Some(matcher.markers.iter().any(|marker| text.contains(marker)))
+    }
+
+    /// 预设说明，如 "wordpress (技术栈 wordpress，启用分类 config)"
+    pub fn describe(&self) -> String {
+        let Some(technology) = self.technology() else {
+            return self.name().to_string();
+        };
+        let categories: Vec<&str> = self.categories().iter().map(Category::name).collect();
+        format!("{} (技术栈 {}，启用分类 {}，过滤其他技术栈的路径)", self.name(), technology.name(), categories.join(", "))
+    }
+}
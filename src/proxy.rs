@@ -0,0 +1,182 @@
+use crate::http::{use_proxy, use_tls_backend, IpVersion};
+use crate::utils::{open_wordlist, seeded_rng};
+use crate::{BackerError, Result};
+use log::debug;
+use rand::seq::SliceRandom;
+use reqwest::Client;
+use serde::Deserialize;
+use std::io::BufRead;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// 默认连续连接失败多少次后停用代理
+pub const DEFAULT_PROXY_MAX_FAILURES: u32 = 3;
+
+/// 连接代理的超时上限，挂起的代理按连接失败计数
+const PROXY_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 代理轮换策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProxyRotation {
+    /// 按列表顺序依次使用
+    #[default]
+    RoundRobin,
+    /// 每次请求随机选择
+    Random,
+}
+
+impl ProxyRotation {
+    /// 策略名称，与命令行取值相同
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProxyRotation::RoundRobin => "round-robin",
+            ProxyRotation::Random => "random",
+        }
+    }
+}
+
+/// 代理池中的单个代理
+#[derive(Debug)]
+struct PooledProxy {
+    url: String,
+    /// 使用该代理的客户端，各代理的连接池相互独立
+    client: Client,
+    /// 连续连接失败次数，成功一次即清零
+    failures: AtomicU32,
+    /// 是否已停用
+    dead: AtomicBool,
+}
+
+/// 轮换使用的出站代理池，连续连接失败的代理被停用
+///
+/// 只有连接代理失败(拒绝连接、连接超时、隧道建立失败)才计入失败，目标返回的错误状态码
+/// 和读取超时不算；所有代理都停用时仍在全部代理中轮换，不会退回直连暴露本机IP，
+/// 停用的代理再次连接成功后恢复
+#[derive(Debug)]
+pub struct ProxyPool {
+    proxies: Vec<PooledProxy>,
+    rotation: ProxyRotation,
+    max_failures: u32,
+    /// 轮询位置
+    next: AtomicUsize,
+    /// 是否已提示所有代理都停用
+    exhausted: AtomicBool,
+}
+
+impl ProxyPool {
+    /// 为每个代理创建客户端，代理地址无效时返回错误
+    pub fn new(proxies: &[String], rotation: ProxyRotation, max_failures: u32, timeout_secs: u64, ip_version: IpVersion) -> Result<Self> {
+        if proxies.is_empty() {
+            return Err(BackerError::Config("代理列表为空".to_string()));
+        }
+        let proxies = proxies.iter()
+            .map(|url| {
+                let client = use_proxy(use_tls_backend(Client::builder()), Some(url))?
+                    .timeout(Duration::from_secs(timeout_secs))
+                    .connect_timeout(PROXY_CONNECT_TIMEOUT.min(Duration::from_secs(timeout_secs)))
+                    .pool_idle_timeout(Duration::from_secs(90))
+                    .pool_max_idle_per_host(10)
+                    .local_address(ip_version.local_address())
+                    .build()?;
+                Ok(PooledProxy {
+                    url: url.clone(),
+                    client,
+                    failures: AtomicU32::new(0),
+                    dead: AtomicBool::new(false),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            proxies,
+            rotation,
+            max_failures: max_failures.max(1),
+            next: AtomicUsize::new(0),
+            exhausted: AtomicBool::new(false),
+        })
+    }
+
+    /// 代理数量
+    pub fn len(&self) -> usize {
+        self.proxies.len()
+    }
+
+    /// 代理池是否为空，创建时已保证至少有一个代理
+    pub fn is_empty(&self) -> bool {
+        self.proxies.is_empty()
+    }
+
+    /// 轮换策略
+    pub fn rotation(&self) -> ProxyRotation {
+        self.rotation
+    }
+
+    /// 已停用的代理
+    pub fn dead_proxies(&self) -> Vec<String> {
+        self.proxies.iter()
+            .filter(|proxy| proxy.dead.load(Ordering::Relaxed))
+            .map(|proxy| proxy.url.clone())
+            .collect()
+    }
+
+    /// 按轮换策略选择一个可用的代理，返回其位置和客户端；随机轮换时 `seed` 和 `key` 决定选择结果
+    pub fn pick(&self, seed: Option<u64>, key: &str) -> (usize, Client) {
+        let alive: Vec<usize> = (0..self.proxies.len())
+            .filter(|&index| !self.proxies[index].dead.load(Ordering::Relaxed))
+            .collect();
+        let candidates = if alive.is_empty() {
+            if !self.exhausted.swap(true, Ordering::Relaxed) {
+                println!("警告: 所有代理都已停用，继续在全部代理中轮换");
+            }
+            (0..self.proxies.len()).collect()
+        } else {
+            alive
+        };
+        let index = match self.rotation {
+            ProxyRotation::RoundRobin => candidates[self.next.fetch_add(1, Ordering::Relaxed) % candidates.len()],
+            ProxyRotation::Random => *candidates.choose(&mut seeded_rng(seed, key)).unwrap_or(&0),
+        };
+        (index, self.proxies[index].client.clone())
+    }
+
+    /// 记录请求结果，连接代理连续失败达到上限时停用该代理
+    pub fn record(&self, index: usize, result: &reqwest::Result<reqwest::Response>) {
+        let Some(proxy) = self.proxies.get(index) else { return };
+        match result {
+            Err(e) if e.is_connect() => {
+                let failures = proxy.failures.fetch_add(1, Ordering::Relaxed) + 1;
+                debug!("代理 {} 连接失败 ({} 次): {:?}", proxy.url, failures, e);
+                if failures >= self.max_failures && !proxy.dead.swap(true, Ordering::Relaxed) {
+                    println!("代理 {} 连续 {} 次连接失败，已停用", proxy.url, failures);
+                }
+            },
+            Err(_) => {}
+            // 所有代理都停用后仍会使用停用的代理，连接成功时恢复
+            Ok(_) => {
+                proxy.failures.store(0, Ordering::Relaxed);
+                if proxy.dead.swap(false, Ordering::Relaxed) {
+                    println!("代理 {} 已恢复", proxy.url);
+                    self.exhausted.store(false, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+}
+
+/// 加载代理列表文件，每行一个代理地址，忽略空行和 `#` 注释
+pub fn load_proxy_list<P: AsRef<Path>>(path: P) -> Result<Vec<String>> {
+    let path = path.as_ref();
+    let mut proxies = Vec::new();
+    for line in open_wordlist(path)?.lines() {
+        let line = line?;
+        let trimmed = line.trim();
+        if !trimmed.is_empty() && !trimmed.starts_with('#') {
+            proxies.push(trimmed.to_string());
+        }
+    }
+    if proxies.is_empty() {
+        return Err(BackerError::Config(format!("代理列表文件为空: {}", path.display())));
+    }
+    Ok(proxies)
+}
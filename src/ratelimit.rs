@@ -0,0 +1,297 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+
+/// 全局请求限速器，按固定间隔放行请求
+///
+/// 多个扫描任务共享同一个限速器时，所有请求合计不超过设定速率；
+/// 空闲后不会积攒额度，避免突发流量
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// 两个请求之间的最小间隔
+    interval: Duration,
+    /// 下一个请求可以发出的时间
+    next_slot: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// 创建每秒最多放行 `requests_per_second` 个请求的限速器，速率为0时不限速
+    pub fn new(requests_per_second: u32) -> Self {
+        let interval = match requests_per_second {
+            0 => Duration::ZERO,
+            rate => Duration::from_secs(1) / rate,
+        };
+        Self { interval, next_slot: Mutex::new(Instant::now()) }
+    }
+
+    /// 每秒放行的请求数，不限速时为None
+    pub fn requests_per_second(&self) -> Option<u32> {
+        (!self.interval.is_zero()).then(|| (Duration::from_secs(1).as_nanos() / self.interval.as_nanos()) as u32)
+    }
+
+    /// 预约下一个发送时间并等待到该时间
+    pub async fn acquire(&self) {
+        if self.interval.is_zero() {
+            return;
+        }
+        let slot = {
+            let mut next_slot = self.next_slot.lock().unwrap_or_else(|e| e.into_inner());
+            let slot = (*next_slot).max(Instant::now());
+            *next_slot = slot + self.interval;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+/// 全局连接数上限，限制同时进行中的请求数
+///
+/// 每个请求在发出前取得一个名额，响应读取完毕后归还；
+/// 同一扫描器的所有克隆、同一 `ScanManager` 下的所有扫描任务共享名额
+#[derive(Debug, Clone)]
+pub struct ConnectionBudget {
+    permits: Arc<Semaphore>,
+    max_connections: usize,
+}
+
+impl ConnectionBudget {
+    /// 创建最多允许 `max_connections` 个并发连接的名额池
+    pub fn new(max_connections: usize) -> Self {
+        Self { permits: Arc::new(Semaphore::new(max_connections)), max_connections }
+    }
+
+    /// 连接数上限
+    pub fn max_connections(&self) -> usize {
+        self.max_connections
+    }
+
+    /// 正在使用的连接数
+    pub fn in_use(&self) -> usize {
+        self.max_connections - self.permits.available_permits()
+    }
+
+    /// 等待并取得一个连接名额
+    pub async fn acquire(&self) -> RequestSlot {
+        // 名额池从不关闭，acquire_owned 只会在关闭后失败
+        RequestSlot { _permit: self.permits.clone().acquire_owned().await.ok(), ramp: None }
+    }
+}
+
+/// 单个请求占用的连接名额，释放时归还
+#[derive(Debug, Default)]
+pub struct RequestSlot {
+    _permit: Option<OwnedSemaphorePermit>,
+    pub(crate) ramp: Option<RampSlot>,
+}
+
+/// 默认的启动爬坡时长(秒)
+pub const DEFAULT_RAMP_UP_SECS: u64 = 45;
+
+/// 爬坡起始并发占目标并发的比例(分母)
+const RAMP_START_DIVISOR: usize = 10;
+
+/// 统计错误率的时间窗口
+const RAMP_WINDOW: Duration = Duration::from_secs(5);
+
+/// 窗口内至少有这么多请求时才判断错误率
+const RAMP_MIN_SAMPLES: usize = 10;
+
+/// 窗口内错误率超过该值时停止爬坡并回退
+const RAMP_MAX_ERROR_RATE: f64 = 0.2;
+
+/// 等待并发额度时重新检查的间隔，额度随时间增长
+const RAMP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 启动爬坡：扫描开始时以较低的并发发送请求，在爬坡时长内逐步提高到目标并发
+///
+/// 许多WAF根据开始时的突发流量封禁来源，逐步爬坡能让更多主机保持配合。
+/// 爬坡期间统计请求的错误率(请求出错、超时和429/503)，窗口内错误率过高时停止爬坡并回退一半进度，
+/// 错误率恢复后继续；爬坡完成后不再限制并发。计时从第一个请求开始
+#[derive(Debug)]
+pub struct StartupRamp {
+    /// 目标并发
+    target: usize,
+    /// 起始并发
+    start: usize,
+    /// 爬坡时长
+    duration: Duration,
+    /// 进行中的请求数
+    in_flight: AtomicUsize,
+    /// 爬坡是否已完成
+    finished: AtomicBool,
+    state: Mutex<RampState>,
+    /// 请求完成时唤醒等待额度的请求
+    released: Notify,
+}
+
+#[derive(Debug, Default)]
+struct RampState {
+    /// 已完成的爬坡进度，错误率过高时不增长
+    progress: Duration,
+    /// 上次更新进度的时间，第一个请求前为None
+    last_tick: Option<Instant>,
+    /// 当前窗口的开始时间
+    window_start: Option<Instant>,
+    /// 当前窗口的请求数
+    window_requests: usize,
+    /// 当前窗口的错误数
+    window_errors: usize,
+    /// 上一个窗口的错误率是否过高
+    degraded: bool,
+}
+
+impl StartupRamp {
+    /// 创建在 `duration` 内从目标并发的1/10爬升到 `target` 的启动爬坡
+    pub fn new(target: usize, duration: Duration) -> Self {
+        let target = target.max(1);
+        Self {
+            target,
+            start: (target / RAMP_START_DIVISOR).max(1),
+            duration,
+            in_flight: AtomicUsize::new(0),
+            finished: AtomicBool::new(duration.is_zero()),
+            state: Mutex::new(RampState::default()),
+            released: Notify::new(),
+        }
+    }
+
+    /// 当前允许的并发，爬坡完成后为None
+    pub fn limit(&self) -> Option<usize> {
+        if self.finished.load(Ordering::Relaxed) {
+            return None;
+        }
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let last_tick = *state.last_tick.get_or_insert(now);
+        if !state.degraded {
+            state.progress += now - last_tick;
+        }
+        state.last_tick = Some(now);
+        if state.progress >= self.duration {
+            self.finished.store(true, Ordering::Relaxed);
+            self.released.notify_waiters();
+            return None;
+        }
+        let ratio = state.progress.as_secs_f64() / self.duration.as_secs_f64();
+        Some(self.start + ((self.target - self.start) as f64 * ratio) as usize)
+    }
+
+    /// 等待并发额度，爬坡完成后立即返回None
+    pub async fn acquire(self: &Arc<Self>) -> Option<RampSlot> {
+        loop {
+            let limit = self.limit()?;
+            let acquired = self.in_flight
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |in_flight| (in_flight < limit).then_some(in_flight + 1))
+                .is_ok();
+            if acquired {
+                return Some(RampSlot { ramp: self.clone() });
+            }
+            let _ = tokio::time::timeout(RAMP_POLL_INTERVAL, self.released.notified()).await;
+        }
+    }
+
+    /// 记录一个请求的结果，窗口结束时按错误率决定是否停止爬坡
+    pub fn record(&self, error: bool) {
+        if self.finished.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let window_start = *state.window_start.get_or_insert(now);
+        state.window_requests += 1;
+        if error {
+            state.window_errors += 1;
+        }
+        if now - window_start < RAMP_WINDOW {
+            return;
+        }
+        let degraded = state.window_requests >= RAMP_MIN_SAMPLES
+            && state.window_errors as f64 / state.window_requests as f64 > RAMP_MAX_ERROR_RATE;
+        if degraded && !state.degraded {
+            state.progress /= 2;
+        }
+        state.degraded = degraded;
+        state.window_start = Some(now);
+        state.window_requests = 0;
+        state.window_errors = 0;
+    }
+}
+
+/// 爬坡期间单个请求占用的并发额度，释放时归还
+#[derive(Debug)]
+pub struct RampSlot {
+    ramp: Arc<StartupRamp>,
+}
+
+impl Drop for RampSlot {
+    fn drop(&mut self) {
+        self.ramp.in_flight.fetch_sub(1, Ordering::AcqRel);
+        self.ramp.released.notify_one();
+    }
+}
+
+/// Retry-After 等待时间的上限，避免异常的响应头让扫描长时间停滞
+pub const MAX_RETRY_AFTER: Duration = Duration::from_secs(600);
+
+/// 同一URL因 Retry-After 暂停后重新检查的最多次数
+pub const MAX_HOST_PAUSES: usize = 5;
+
+/// 解析 Retry-After 响应头，支持秒数和HTTP日期两种格式，超过 [`MAX_RETRY_AFTER`] 时取上限
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let delay = match value.parse::<u64>() {
+        Ok(seconds) => Duration::from_secs(seconds),
+        Err(_) => {
+            let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+            (date.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().unwrap_or(Duration::ZERO)
+        }
+    };
+    Some(delay.min(MAX_RETRY_AFTER))
+}
+
+/// 按主机暂停请求，主机返回429/503并带有 Retry-After 时，该主机的所有请求等到指定时间后再发出，
+/// 其他主机不受影响
+#[derive(Debug, Default)]
+pub struct HostPauses {
+    /// 主机 -> 恢复请求的时间
+    resume_at: dashmap::DashMap<String, Instant>,
+    /// 主机 -> 累计的暂停时长，用于把暂停时间排除在扫描超时之外
+    paused: dashmap::DashMap<String, Duration>,
+}
+
+impl HostPauses {
+    /// 暂停主机到 `delay` 之后，已有更晚的恢复时间时保持不变；返回主机此前是否未处于暂停状态
+    pub fn pause(&self, host: &str, delay: Duration) -> bool {
+        let now = Instant::now();
+        let resume_at = now + delay;
+        let mut entry = self.resume_at.entry(host.to_string()).or_insert(now);
+        let was_running = *entry <= now;
+        if resume_at > *entry {
+            // 只累计超出原有暂停的部分
+            *self.paused.entry(host.to_string()).or_default() += resume_at - (*entry).max(now);
+            *entry = resume_at;
+        }
+        was_running
+    }
+
+    /// 主机累计的暂停时长，包括尚未结束的暂停
+    pub fn paused_total(&self, host: &str) -> Duration {
+        self.paused.get(host).map_or(Duration::ZERO, |paused| *paused)
+    }
+
+    /// 主机剩余的暂停时间，未暂停时为None
+    pub fn remaining(&self, host: &str) -> Option<Duration> {
+        let resume_at = *self.resume_at.get(host)?;
+        let now = Instant::now();
+        (resume_at > now).then(|| resume_at - now)
+    }
+
+    /// 等待主机恢复，等待期间暂停被延长时继续等待
+    pub async fn wait(&self, host: &str) {
+        while let Some(remaining) = self.remaining(host) {
+            tokio::time::sleep(remaining).await;
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use crate::http::use_tls_backend;
+use crate::utils::extract_host;
+use crate::{BackerError, Result, Target};
+use log::{debug, warn};
+use reqwest::Client;
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::net::IpAddr;
+use tokio::net::lookup_host;
+use std::path::Path;
+use std::time::Duration;
+use url::Url;
+
+/// 证书透明度日志来源标识，记录在目标和结果的 `source` 字段
+pub const CT_SOURCE: &str = "crt.sh";
+
+/// 反查IP发现的同IP站点来源标识
+pub const REVERSE_IP_SOURCE: &str = "reverse-ip";
+
+/// 反查IP接口，返回每行一个域名
+const REVERSE_IP_API: &str = "https://api.hackertarget.com/reverseiplookup/?q=";
+
+/// 情报查询较慢，使用单独的超时
+const RECON_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 常见的二级公共后缀，用于推断可注册域名
+const SECOND_LEVEL_SUFFIXES: &[&str] = &[
+    "com.cn", "net.cn", "org.cn", "gov.cn", "edu.cn", "ac.cn",
+    "com.hk", "com.tw", "com.sg", "com.au", "net.au", "org.au",
+    "co.uk", "org.uk", "ac.uk", "gov.uk", "co.jp", "ne.jp", "or.jp",
+    "co.kr", "or.kr", "com.br", "co.in", "co.nz", "co.za",
+];
+
+/// crt.sh JSON接口返回的证书记录
+#[derive(Debug, Deserialize)]
+struct CtEntry {
+    /// 证书中的名称，多个名称以换行分隔
+    name_value: String,
+}
+
+/// 推断主机的可注册域名，如 `a.b.example.com.cn` -> `example.com.cn`，IP地址返回None
+pub fn registrable_domain(host: &str) -> Option<String> {
+    let host = host.trim_end_matches('.').to_lowercase();
+    if host.parse::<IpAddr>().is_ok() {
+        return None;
+    }
+    let labels: Vec<&str> = host.split('.').collect();
+    if labels.len() < 2 {
+        return None;
+    }
+    let last_two = labels[labels.len() - 2..].join(".");
+    let keep = if labels.len() >= 3 && SECOND_LEVEL_SUFFIXES.contains(&last_two.as_str()) { 3 } else { 2 };
+    if labels.len() < keep {
+        return None;
+    }
+    Some(labels[labels.len() - keep..].join("."))
+}
+
+/// 加载范围排除列表，每行一个主机名，`*.example.com` 匹配所有子域名
+pub fn load_scope_exclusions<P: AsRef<Path>>(path: P) -> Result<Vec<String>> {
+    let content = fs::read_to_string(path)?;
+    Ok(content.lines()
+        .map(|line| line.trim().to_lowercase())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect())
+}
+
+/// 判断主机是否被范围排除规则命中
+pub fn is_excluded(host: &str, exclusions: &[String]) -> bool {
+    exclusions.iter().any(|rule| match rule.strip_prefix("*.") {
+        Some(parent) => host.ends_with(&format!(".{}", parent)),
+        None => host == rule,
+    })
+}
+
+/// 从证书透明度日志查询域名的子域名，通配符名称和其他域名的名称被丢弃
+pub async fn ct_subdomains(client: &Client, domain: &str) -> Result<BTreeSet<String>> {
+    let url = format!("https://crt.sh/?q=%25.{}&output=json", domain);
+    let response = client.get(&url).send().await?;
+    if !response.status().is_success() {
+        return Err(BackerError::Other(format!("crt.sh 返回状态码 {}", response.status())));
+    }
+    let entries: Vec<CtEntry> = response.json().await?;
+    
+    let suffix = format!(".{}", domain);
+    Ok(entries.iter()
+        .flat_map(|entry| entry.name_value.lines())
+        .map(|name| name.trim().trim_end_matches('.').to_lowercase())
+        .filter(|name| name.ends_with(&suffix) || name == domain)
+        .filter(|name| !name.contains('*') && !name.contains('@'))
+        .collect())
+}
+
+/// 用证书透明度日志中的子域名扩展目标
+///
+/// 每个可注册域名只查询一次，新目标沿用原目标的协议和标签，
+/// 来源记为 [`CT_SOURCE`]。查询失败只记录警告，不影响原目标
+pub async fn expand_with_ct(targets: Vec<Target>, exclusions: &[String]) -> Result<Vec<Target>> {
+    let client = recon_client()?;
+    
+    let mut known_hosts: HashSet<String> = targets.iter()
+        .filter_map(|target| extract_host(&target.url))
+        .collect();
+    let mut queried: HashSet<String> = HashSet::new();
+    let mut discovered = Vec::new();
+    
+    for target in &targets {
+        let Some(url) = Url::parse(&target.url).ok() else { continue };
+        let Some(domain) = url.host_str().and_then(registrable_domain) else { continue };
+        if !queried.insert(domain.clone()) {
+            continue;
+        }
+        
+        let subdomains = match ct_subdomains(&client, &domain).await {
+            Ok(subdomains) => subdomains,
+            Err(e) => {
+                warn!("查询 {} 的证书透明度日志失败: {}", domain, e);
+                println!("警告: 查询 {} 的证书透明度日志失败，跳过子域名扩展", domain);
+                continue;
+            }
+        };
+        
+        let mut added = 0;
+        for host in subdomains {
+            if is_excluded(&host, exclusions) {
+                debug!("子域名 {} 不在范围内，跳过", host);
+                continue;
+            }
+            if !known_hosts.insert(host.clone()) {
+                continue;
+            }
+            discovered.push(Target {
+                url: format!("{}://{}", url.scheme(), host),
+                tag: target.tag.clone(),
+                source: Some(CT_SOURCE.to_string()),
+                original: None,
+            });
+            added += 1;
+        }
+        println!("证书透明度日志: {} 新增 {} 个子域名目标", domain, added);
+    }
+    
+    let mut targets = targets;
+    targets.extend(discovered);
+    Ok(targets)
+}
+
+/// 通过反查IP接口查询与该IP共享主机的域名
+pub async fn reverse_ip(client: &Client, ip: IpAddr) -> Result<Vec<String>> {
+    let body = client.get(format!("{}{}", REVERSE_IP_API, ip)).send().await?
+        .error_for_status()?
+        .text().await?;
+    if body.starts_with("No DNS") {
+        return Ok(Vec::new());
+    }
+    // 接口出错或超出配额时返回单行说明而非域名
+    if body.starts_with("error") || body.starts_with("API count exceeded") {
+        return Err(BackerError::Other(format!("反查IP失败: {}", body.trim())));
+    }
+    Ok(body.lines()
+        .map(|line| line.trim().trim_end_matches('.').to_lowercase())
+        .filter(|line| !line.is_empty() && !line.contains(' '))
+        .collect())
+}
+
+/// 查询各目标的同IP站点，返回 主机名 -> 同IP的其他域名
+///
+/// 每个IP只查询一次，解析或查询失败的目标只记录警告
+pub async fn discover_neighbors(targets: &[Target]) -> Result<HashMap<String, Vec<String>>> {
+    let client = recon_client()?;
+    let mut by_ip: HashMap<IpAddr, Vec<String>> = HashMap::new();
+    let mut neighbors = HashMap::new();
+    
+    for target in targets {
+        let Some(url) = Url::parse(&target.url).ok() else { continue };
+        let Some(host) = url.host_str().map(|h| h.to_lowercase()) else { continue };
+        if neighbors.contains_key(&host) {
+            continue;
+        }
+        
+        let ip = match host.parse::<IpAddr>() {
+            Ok(ip) => Some(ip),
+            Err(_) => lookup_host((host.as_str(), url.port_or_known_default().unwrap_or(80))).await
+                .ok()
+                .and_then(|mut addrs| addrs.next())
+                .map(|addr| addr.ip()),
+        };
+        let Some(ip) = ip else {
+            debug!("无法解析 {}，跳过反查IP", host);
+            continue;
+        };
+        
+        let co_hosted = match by_ip.get(&ip) {
+            Some(domains) => domains.clone(),
+            None => match reverse_ip(&client, ip).await {
+                Ok(domains) => {
+                    by_ip.insert(ip, domains.clone());
+                    domains
+                }
+                Err(e) => {
+                    warn!("反查 {} 失败: {}", ip, e);
+                    println!("警告: 反查 {} ({}) 的同IP站点失败", host, ip);
+                    continue;
+                }
+            },
+        };
+        
+        let domains: Vec<String> = co_hosted.into_iter()
+            .filter(|domain| *domain != host)
+            .collect();
+        if !domains.is_empty() {
+            println!("同IP站点: {} ({}) 共 {} 个: {}", host, ip, domains.len(), domains.join(", "));
+        }
+        neighbors.insert(host, domains);
+    }
+    
+    Ok(neighbors)
+}
+
+/// 将同IP站点转换为扫描目标，沿用原目标的协议和标签，来源记为 [`REVERSE_IP_SOURCE`]
+pub fn neighbor_targets(targets: &[Target], neighbors: &HashMap<String, Vec<String>>, exclusions: &[String]) -> Vec<Target> {
+    let mut known_hosts: HashSet<String> = targets.iter()
+        .filter_map(|target| extract_host(&target.url))
+        .collect();
+    let mut expanded = Vec::new();
+    
+    for target in targets {
+        let Some(url) = Url::parse(&target.url).ok() else { continue };
+        let Some(domains) = url.host_str().and_then(|host| neighbors.get(&host.to_lowercase())) else { continue };
+        for domain in domains {
+            if is_excluded(domain, exclusions) || !known_hosts.insert(domain.clone()) {
+                continue;
+            }
+            expanded.push(Target {
+                url: format!("{}://{}", url.scheme(), domain),
+                tag: target.tag.clone(),
+                source: Some(REVERSE_IP_SOURCE.to_string()),
+                original: None,
+            });
+        }
+    }
+    
+    expanded
+}
+
+/// 情报查询使用的HTTP客户端
+fn recon_client() -> Result<Client> {
+    Ok(use_tls_backend(Client::builder())
+        .timeout(RECON_TIMEOUT)
+        .build()?)
+}
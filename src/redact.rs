@@ -0,0 +1,178 @@
+use crate::utils::save_results;
+use crate::{OutputFormat, Result, ScanResult, TargetReport};
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// 替换敏感值的掩码
+pub const MASK: &str = "***";
+
+/// 脱敏后说明文字保留的最大字符数
+pub const PREVIEW_CHARS: usize = 120;
+
+/// 脱敏后SQL摘要保留的表名数
+const PREVIEW_TABLES: usize = 10;
+
+/// 脱敏后git和元数据文件列表保留的文件名数
+const PREVIEW_FILES: usize = 10;
+
+/// 值需要掩码的查询参数名(小写，包含即匹配)
+const SENSITIVE_PARAMS: &[&str] = &[
+    "token", "secret", "password", "passwd", "pwd", "key", "auth", "sig", "session", "credential", "jwt", "code",
+];
+
+/// 文本中的凭据，按顺序替换
+static SECRET_PATTERNS: LazyLock<Vec<(Regex, &'static str)>> = LazyLock::new(|| {
+    [
+        // PEM私钥
+        (r"-----BEGIN [A-Z ]*PRIVATE KEY-----[\s\S]*?(?:-----END [A-Z ]*PRIVATE KEY-----|$)", "-----PRIVATE KEY ***-----"),
+        // URL中的用户名和密码
+        (r"(?i)\b([a-z][a-z0-9+.-]*://)[^/\s:@]+(?::[^/\s@]*)?@", "${1}***@"),
+        // password=xxx、api_key: xxx 等键值对
+        (r#"(?i)\b((?:password|passwd|pwd|secret|token|api[_-]?key|access[_-]?key|auth)[\w-]*\s*[=:]\s*["']?)[^\s"',;&]+"#, "${1}***"),
+        (r"(?i)\b(bearer\s+)[\w.~+/-]+=*", "${1}***"),
+        // AWS访问密钥
+        (r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b", "***"),
+        // JWT
+        (r"\beyJ[\w-]+\.[\w-]+\.[\w-]+", "***"),
+    ]
+    .into_iter()
+    .map(|(pattern, replacement)| (Regex::new(pattern).expect("内置脱敏规则无效"), replacement))
+    .collect()
+});
+
+/// 生成可交给客户的脱敏结果：掩码URL和说明文字中的凭据，截断过长的说明
+pub fn redact_results(results: &[ScanResult]) -> Vec<ScanResult> {
+    results.iter().map(redact_result).collect()
+}
+
+/// 脱敏单个发现，发现ID保持不变，便于与未脱敏的结果对照
+pub fn redact_result(result: &ScanResult) -> ScanResult {
+    let mut result = result.clone();
+    result.url = redact_url(&result.url);
+    result.bypass_url = result.bypass_url.as_deref().map(redact_url);
+    result.target = result.target.as_deref().map(redact_url);
+    result.original_target = result.original_target.as_deref().map(redact_text);
+    for text in [&mut result.verify_note, &mut result.api_summary, &mut result.source, &mut result.suspicious].into_iter().flatten() {
+        *text = redact_preview(text);
+    }
+    for indicator in &mut result.indicators {
+        *indicator = redact_text(indicator);
+    }
+    if let Some(git) = &mut result.git {
+        git.author = git.author.as_deref().map(redact_text);
+        git.message = git.message.as_deref().map(redact_preview);
+        git.remote = git.remote.as_deref().map(redact_text);
+        git.files.truncate(PREVIEW_FILES);
+        for file in &mut git.files {
+            *file = redact_text(file);
+        }
+    }
+    if let Some(listing) = &mut result.listing {
+        listing.files.truncate(PREVIEW_FILES);
+        for file in &mut listing.files {
+            *file = redact_text(file);
+        }
+    }
+    if let Some(summary) = &mut result.sql_summary {
+        summary.tool = summary.tool.as_deref().map(redact_preview);
+        summary.database = summary.database.as_deref().map(redact_text);
+        if summary.tables.len() > PREVIEW_TABLES {
+            let omitted = summary.tables.len() - PREVIEW_TABLES;
+            summary.tables.truncate(PREVIEW_TABLES);
+            summary.tables.push(format!("…另有 {} 个", omitted));
+        }
+    }
+    result
+}
+
+/// 脱敏目标报告中的URL和说明文字
+pub fn redact_reports(targets: &[TargetReport]) -> Vec<TargetReport> {
+    targets.iter()
+        .map(|report| {
+            let mut report = report.clone();
+            report.target = redact_url(&report.target);
+            report.scanned_url = report.scanned_url.as_deref().map(redact_url);
+            for text in [&mut report.detail, &mut report.truncated, &mut report.slow_lane, &mut report.challenge].into_iter().flatten() {
+                *text = redact_preview(text);
+            }
+            for text in report.errors.iter_mut().chain(&mut report.size_excluded) {
+                *text = redact_preview(text);
+            }
+            for url in report.known.iter_mut() {
+                *url = redact_url(url);
+            }
+            report
+        })
+        .collect()
+}
+
+/// 掩码URL中的用户名、密码和敏感查询参数的值，其余部分保持原样
+pub fn redact_url(url: &str) -> String {
+    let url = redact_text(url);
+    let (rest, fragment) = match url.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (url.as_str(), None),
+    };
+    let Some((base, query)) = rest.split_once('?') else { return url };
+    let query: Vec<String> = query.split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((name, value)) if !value.is_empty() && is_sensitive_param(name) => format!("{}={}", name, MASK),
+            _ => pair.to_string(),
+        })
+        .collect();
+    let mut redacted = format!("{}?{}", base, query.join("&"));
+    if let Some(fragment) = fragment {
+        redacted.push('#');
+        redacted.push_str(fragment);
+    }
+    redacted
+}
+
+/// 掩码文本中的凭据
+pub fn redact_text(text: &str) -> String {
+    SECRET_PATTERNS.iter().fold(text.to_string(), |text, (pattern, replacement)| {
+        pattern.replace_all(&text, *replacement).into_owned()
+    })
+}
+
+/// 掩码凭据并截断到 [`PREVIEW_CHARS`] 个字符
+pub fn redact_preview(text: &str) -> String {
+    let text = redact_text(text);
+    match text.char_indices().nth(PREVIEW_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    }
+}
+
+fn is_sensitive_param(name: &str) -> bool {
+    let name = name.to_lowercase();
+    SENSITIVE_PARAMS.iter().any(|sensitive| name.contains(sensitive))
+}
+
+/// 保存脱敏后的结果；指定 `unredacted_output` 时另存一份未脱敏的结果，只有当前用户可读写
+pub fn save_redacted_results(
+    results: &[ScanResult],
+    targets: &[TargetReport],
+    format: OutputFormat,
+    output: Option<&Path>,
+    unredacted_output: Option<&Path>,
+) -> Result<()> {
+    if let Some(path) = unredacted_output.filter(|_| !results.is_empty()) {
+        // 先以私有权限创建文件，写入时沿用该权限
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+            options.mode(0o600);
+            options.open(path)?;
+            fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+        }
+        #[cfg(not(unix))]
+        options.open(path)?;
+        save_results(results, targets, format, Some(path))?;
+    }
+    save_results(&redact_results(results), &redact_reports(targets), format, output)
+}
@@ -0,0 +1,297 @@
+use crate::{BackerError, Result, ScanResult};
+use crate::utils::{describe_content_type, display_url, load_report};
+use log::debug;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// 请求头的最大读取大小(字节)
+const MAX_REQUEST_SIZE: usize = 8192;
+
+/// 每页默认显示的结果数
+const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// 报告排序字段
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SortKey {
+    #[default]
+    Url,
+    Status,
+    Size,
+    Tag,
+}
+
+impl SortKey {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "url" => Some(SortKey::Url),
+            "status" => Some(SortKey::Status),
+            "size" => Some(SortKey::Size),
+            "tag" => Some(SortKey::Tag),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            SortKey::Url => "url",
+            SortKey::Status => "status",
+            SortKey::Size => "size",
+            SortKey::Tag => "tag",
+        }
+    }
+}
+
+/// 报告过滤和排序条件，来自URL查询参数
+#[derive(Debug, Clone, Default)]
+pub struct ReportQuery {
+    /// URL包含的关键字
+    pub q: Option<String>,
+    /// 状态码
+    pub status: Option<u16>,
+    /// 标签
+    pub tag: Option<String>,
+    /// 是否已验证
+    pub verified: Option<bool>,
+    /// 排序字段
+    pub sort: SortKey,
+    /// 是否降序
+    pub desc: bool,
+    /// 跳过的结果数
+    pub offset: usize,
+    /// 返回的最大结果数，0表示不限制
+    pub limit: usize,
+}
+
+impl ReportQuery {
+    /// 从URL查询字符串解析条件，无法识别的参数被忽略
+    pub fn parse(query: &str) -> Self {
+        let mut parsed = ReportQuery { limit: DEFAULT_PAGE_SIZE, ..Default::default() };
+        for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+            if value.is_empty() {
+                continue;
+            }
+            match key.as_ref() {
+                "q" => parsed.q = Some(value.to_string()),
+                "status" => parsed.status = value.parse().ok(),
+                "tag" => parsed.tag = Some(value.to_string()),
+                "verified" => parsed.verified = value.parse().ok(),
+                "sort" => parsed.sort = SortKey::parse(&value).unwrap_or_default(),
+                "order" => parsed.desc = value == "desc",
+                "offset" => parsed.offset = value.parse().unwrap_or(0),
+                "limit" => parsed.limit = value.parse().unwrap_or(DEFAULT_PAGE_SIZE),
+                _ => {}
+            }
+        }
+        parsed
+    }
+
+    /// 判断结果是否满足过滤条件
+    fn matches(&self, result: &ScanResult) -> bool {
+        if let Some(q) = &self.q {
+            if !result.url.to_lowercase().contains(&q.to_lowercase()) {
+                return false;
+            }
+        }
+        if self.status.is_some_and(|status| status != result.status_code) {
+            return false;
+        }
+        if self.tag.is_some() && self.tag != result.tag {
+            return false;
+        }
+        if self.verified.is_some_and(|verified| verified != result.verified) {
+            return false;
+        }
+        true
+    }
+
+    /// 过滤并排序结果，返回满足条件的总数和当前页
+    pub fn apply<'a>(&self, results: &'a [ScanResult]) -> (usize, Vec<&'a ScanResult>) {
+        let mut matched: Vec<&ScanResult> = results.iter().filter(|r| self.matches(r)).collect();
+
+        matched.sort_by(|a, b| {
+            let ordering = match self.sort {
+                SortKey::Url => a.url.cmp(&b.url),
+                SortKey::Status => a.status_code.cmp(&b.status_code),
+                SortKey::Size => a.content_length.cmp(&b.content_length),
+                SortKey::Tag => a.tag.cmp(&b.tag),
+            };
+            if self.desc { ordering.reverse() } else { ordering }
+        });
+
+        let total = matched.len();
+        let page = matched.into_iter().skip(self.offset);
+        let page = if self.limit == 0 { page.collect() } else { page.take(self.limit).collect() };
+        (total, page)
+    }
+}
+
+/// 读取JSON(任意版本)或NDJSON格式的扫描结果文件中的发现
+pub fn load_results<P: AsRef<Path>>(path: P) -> Result<Vec<ScanResult>> {
+    Ok(load_report(path)?.results)
+}
+
+/// 渲染HTML报告，过滤表单以GET参数提交，无需JavaScript
+pub fn render_html(results: &[ScanResult], query: &ReportQuery) -> String {
+    let (total, page) = query.apply(results);
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>备份文件扫描结果</title>\n<style>\n");
+    html.push_str("body{font-family:sans-serif;margin:2em}table{border-collapse:collapse;width:100%}");
+    html.push_str("th,td{border:1px solid #ccc;padding:4px 8px;text-align:left;font-size:14px}");
+    html.push_str("th{background:#f0f0f0}form{margin-bottom:1em}small{color:#666}\n");
+    html.push_str("</style>\n</head>\n<body>\n<h1>备份文件扫描结果</h1>\n");
+
+    // 过滤表单
+    html.push_str("<form method=\"get\" action=\"/\">\n");
+    html.push_str(&format!(
+        "URL包含 <input name=\"q\" value=\"{}\"> 状态码 <input name=\"status\" size=\"4\" value=\"{}\"> 标签 <input name=\"tag\" value=\"{}\">\n",
+        escape_html(query.q.as_deref().unwrap_or("")),
+        query.status.map_or(String::new(), |s| s.to_string()),
+        escape_html(query.tag.as_deref().unwrap_or("")),
+    ));
+    html.push_str("已验证 <select name=\"verified\">");
+    for (value, label) in [("", "全部"), ("true", "是"), ("false", "否")] {
+        let selected = query.verified.map(|v| v.to_string()).unwrap_or_default() == value;
+        html.push_str(&format!("<option value=\"{}\"{}>{}</option>", value, if selected { " selected" } else { "" }, label));
+    }
+    html.push_str("</select>\n排序 <select name=\"sort\">");
+    for (value, label) in [("url", "URL"), ("status", "状态码"), ("size", "内容长度"), ("tag", "标签")] {
+        let selected = query.sort.as_str() == value;
+        html.push_str(&format!("<option value=\"{}\"{}>{}</option>", value, if selected { " selected" } else { "" }, label));
+    }
+    html.push_str("</select>\n<select name=\"order\">");
+    html.push_str(&format!("<option value=\"asc\">升序</option><option value=\"desc\"{}>降序</option>", if query.desc { " selected" } else { "" }));
+    html.push_str("</select>\n<button type=\"submit\">筛选</button>\n</form>\n");
+
+    html.push_str(&format!("<p>共 {} 条结果，显示第 {} 条起的 {} 条</p>\n", total, query.offset + 1, page.len()));
+
+    // 结果表格
+    html.push_str("<table>\n<tr><th>ID</th><th>URL</th><th>状态码</th><th>内容类型</th><th>内容长度</th><th>已验证</th><th>标签</th></tr>\n");
+    for result in page {
+        let mut url = format!("<a href=\"{}\">{}</a>", escape_html(&result.url), escape_html(&display_url(&result.url)));
+        if let Some(technique) = &result.bypass {
+            url.push_str(&format!("<br><small>绕过: {}</small>", escape_html(technique)));
+        }
+        if let Some(reason) = &result.suspicious {
+            url.push_str(&format!("<br><small>☣️ 可疑: {}</small>", escape_html(reason)));
+        }
+        if !result.indicators.is_empty() {
+            url.push_str(&format!("<br><small>高价值文件: {}</small>", escape_html(&result.indicators.join(", "))));
+        }
+        if let Some(summary) = &result.sql_summary {
+            url.push_str(&format!("<br><small>SQL转储: {}</small>", escape_html(&summary.describe())));
+        }
+        if let Some(git) = &result.git {
+            url.push_str(&format!("<br><small>git仓库: {}</small>", escape_html(&git.describe())));
+        }
+        if let Some(listing) = &result.listing {
+            url.push_str(&format!("<br><small>文件列表: {}</small>", escape_html(&listing.describe())));
+        }
+        if let Some(summary) = &result.api_summary {
+            url.push_str(&format!("<br><small>API定义: {}</small>", escape_html(summary)));
+        }
+        if let Some(source) = &result.source {
+            url.push_str(&format!("<br><small>发现来源: {}</small>", escape_html(source)));
+        }
+        let verified = match &result.verify_note {
+            Some(note) => format!("{} {}", if result.verified { "✅" } else { "❌" }, escape_html(note)),
+            None => if result.verified { "✅" } else { "❌" }.to_string(),
+        };
+        html.push_str(&format!(
+            "<tr><td><code>{}</code></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            escape_html(&result.id),
+            url,
+            result.status_code,
+            escape_html(&describe_content_type(result)),
+            result.content_length.map_or("未知".to_string(), |len| len.to_string()),
+            verified,
+            escape_html(result.tag.as_deref().unwrap_or("")),
+        ));
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+    html
+}
+
+/// 渲染JSON API响应
+pub fn render_json(results: &[ScanResult], query: &ReportQuery) -> Result<String> {
+    let (total, page) = query.apply(results);
+    let body = serde_json::json!({
+        "total": total,
+        "offset": query.offset,
+        "results": page,
+    });
+    Ok(serde_json::to_string(&body)?)
+}
+
+/// 在本地启动报告服务
+///
+/// `/` 返回HTML报告，`/api/results` 返回JSON，两者支持相同的查询参数：
+/// `q`、`status`、`tag`、`verified`、`sort`、`order`、`offset`、`limit`
+pub async fn serve(results: Vec<ScanResult>, listen: &str) -> Result<()> {
+    let listener = TcpListener::bind(listen).await
+        .map_err(|e| BackerError::Other(format!("无法监听 {}: {}", listen, e)))?;
+    println!("报告服务已启动: http://{}/ (共 {} 条结果，Ctrl+C 退出)", listener.local_addr()?, results.len());
+
+    let results = Arc::new(results);
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let results = results.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, &results).await {
+                debug!("处理来自 {} 的请求失败: {:?}", peer, e);
+            }
+        });
+    }
+}
+
+/// 处理单个HTTP请求，响应后关闭连接
+async fn handle_connection(mut stream: TcpStream, results: &[ScanResult]) -> Result<()> {
+    let mut buffer = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 1024];
+    while !buffer.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 || buffer.len() + n > MAX_REQUEST_SIZE {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+    }
+
+    let request = String::from_utf8_lossy(&buffer);
+    let mut parts = request.lines().next().unwrap_or("").split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let target = parts.next().unwrap_or("/");
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let (status, content_type, body) = if method != "GET" {
+        ("405 Method Not Allowed", "text/plain; charset=utf-8", "仅支持GET请求".to_string())
+    } else {
+        match path {
+            "/" => ("200 OK", "text/html; charset=utf-8", render_html(results, &ReportQuery::parse(query))),
+            "/api/results" => ("200 OK", "application/json", render_json(results, &ReportQuery::parse(query))?),
+            _ => ("404 Not Found", "text/plain; charset=utf-8", "未找到".to_string()),
+        }
+    };
+    debug!("{} {} -> {}", method, target, status);
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// 转义HTML特殊字符
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,98 @@
+/// robots.txt 的最大读取大小(字节)，超过时视为没有robots.txt
+pub const ROBOTS_MAX_SIZE: u64 = 512 * 1024;
+
+/// robots.txt 中适用于所有爬虫(`User-agent: *`)的规则
+#[derive(Debug, Clone, Default)]
+pub struct RobotsRules {
+    /// (是否允许, 路径模式)，模式支持 `*` 通配和结尾的 `$`
+    rules: Vec<(bool, String)>,
+}
+
+impl RobotsRules {
+    /// 解析robots.txt，只保留 `User-agent: *` 分组中的 Allow/Disallow 规则
+    pub fn parse(content: &str) -> Self {
+        let mut rules = Vec::new();
+        let mut group_matches = false;
+        // 连续的 User-agent 行属于同一分组，规则行之后的 User-agent 开始新分组
+        let mut in_agent_lines = false;
+
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((field, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match field.trim().to_lowercase().as_str() {
+                "user-agent" => {
+                    if !in_agent_lines {
+                        group_matches = false;
+                    }
+                    in_agent_lines = true;
+                    group_matches |= value == "*";
+                }
+                "allow" | "disallow" => {
+                    in_agent_lines = false;
+                    // 空的 Disallow 表示允许全部
+                    if group_matches && !value.is_empty() {
+                        rules.push((field.trim().eq_ignore_ascii_case("allow"), value.to_string()));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self { rules }
+    }
+
+    /// 是否没有任何规则
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// 返回禁止访问该路径的 Disallow 模式，允许访问时返回None
+    ///
+    /// 按最长匹配的规则判断，长度相同时 Allow 优先，`path` 需以 `/` 开头
+    pub fn disallowed_by(&self, path: &str) -> Option<&str> {
+        let mut best: Option<(bool, &str)> = None;
+        for (allow, pattern) in &self.rules {
+            if !matches_pattern(pattern, path) {
+                continue;
+            }
+            let better = match best {
+                None => true,
+                Some((best_allow, best_pattern)) => {
+                    pattern.len() > best_pattern.len() || (pattern.len() == best_pattern.len() && *allow && !best_allow)
+                }
+            };
+            if better {
+                best = Some((*allow, pattern));
+            }
+        }
+        best.filter(|(allow, _)| !allow).map(|(_, pattern)| pattern)
+    }
+}
+
+/// 判断路径是否匹配robots.txt路径模式
+fn matches_pattern(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        // 锚定时最后一段必须出现在路径末尾
+        if anchored && i == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
@@ -0,0 +1,142 @@
+use crate::category::Category;
+use crate::{ScanResult, Severity};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// SARIF格式版本
+const SARIF_VERSION: &str = "2.1.0";
+
+/// SARIF 2.1.0 的JSON Schema
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// 每个模式分类对应一条规则，规则ID如 `backer/archive`
+const RULES: &[(Category, &str, &str)] = &[
+    (Category::Archive, "ExposedBackupFile", "网站或数据库备份文件可被公开访问"),
+    (Category::Config, "ExposedConfigFile", "敏感配置文件或其备份可被公开访问"),
+    (Category::Vcs, "ExposedVcsMetadata", "版本控制元数据可被公开访问，可能泄露源代码"),
+    (Category::Api, "ExposedApiDefinition", "接口文档、GraphQL配置或API集合可被公开访问"),
+];
+
+/// 修复建议
+const HELP: &str = "从Web根目录删除备份文件、配置文件和版本控制目录，必要时轮换其中包含的凭据；在Web服务器上禁止访问这些路径。";
+
+/// SARIF日志的顶层结构，可上传到GitHub Code Scanning等SARIF消费端
+#[derive(Debug, Serialize)]
+pub struct SarifLog {
+    #[serde(rename = "$schema")]
+    pub schema: &'static str,
+    pub version: &'static str,
+    pub runs: Vec<SarifRun>,
+}
+
+/// 一次扫描
+#[derive(Debug, Serialize)]
+pub struct SarifRun {
+    pub tool: Value,
+    pub results: Vec<SarifResult>,
+}
+
+/// 单个发现
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifResult {
+    pub rule_id: String,
+    pub rule_index: usize,
+    /// error、warning 或 note
+    pub level: &'static str,
+    pub message: Value,
+    pub locations: Vec<Value>,
+    /// 稳定的发现ID，消费端按此跨扫描追踪同一发现
+    pub partial_fingerprints: Value,
+    pub properties: Value,
+}
+
+/// 将扫描结果转换为SARIF日志，规则按发现URL推断的模式分类划分
+pub fn to_sarif_log(results: &[ScanResult]) -> SarifLog {
+    let rules: Vec<Value> = RULES.iter()
+        .map(|(category, name, description)| json!({
+            "id": rule_id(*category),
+            "name": name,
+            "shortDescription": { "text": description },
+            "help": { "text": HELP },
+            "defaultConfiguration": { "level": "error" },
+            "properties": { "tags": ["security", category.name()] },
+        }))
+        .collect();
+    let tool = json!({
+        "driver": {
+            "name": "backer",
+            "version": env!("CARGO_PKG_VERSION"),
+            "rules": rules,
+        }
+    });
+    SarifLog {
+        schema: SARIF_SCHEMA,
+        version: SARIF_VERSION,
+        runs: vec![SarifRun { tool, results: results.iter().map(to_sarif_result).collect() }],
+    }
+}
+
+fn to_sarif_result(result: &ScanResult) -> SarifResult {
+    let category = Category::of_url(&result.url);
+    let mut message = format!("{} ({})，状态码 {}", result.url, result.kind(), result.status_code);
+    if result.verified {
+        message.push_str("，内容已验证");
+    }
+    if let Some(summary) = &result.sql_summary {
+        message.push_str(&format!("，SQL转储: {}", summary.describe()));
+    }
+    if let Some(git) = &result.git {
+        message.push_str(&format!("，git仓库: {}", git.describe()));
+    }
+    if let Some(listing) = &result.listing {
+        message.push_str(&format!("，文件列表: {}", listing.describe()));
+    }
+    if !result.indicators.is_empty() {
+        message.push_str(&format!("，高价值文件: {}", result.indicators.join(", ")));
+    }
+
+    let mut properties = json!({
+        "statusCode": result.status_code,
+        "verified": result.verified,
+        "kind": result.kind(),
+        "severity": result.severity().name(),
+        "category": category.name(),
+    });
+    for (key, value) in [
+        ("contentType", result.content_type.clone().map(Value::from)),
+        ("contentLength", result.content_length.map(Value::from)),
+        ("verifyNote", result.verify_note.clone().map(Value::from)),
+        ("tag", result.tag.clone().map(Value::from)),
+        ("target", result.target.clone().map(Value::from)),
+    ] {
+        if let Some(value) = value {
+            properties[key] = value;
+        }
+    }
+
+    SarifResult {
+        rule_id: rule_id(category),
+        rule_index: RULES.iter().position(|(c, _, _)| *c == category).unwrap_or(0),
+        level: sarif_level(result.severity()),
+        message: json!({ "text": message }),
+        locations: vec![json!({ "physicalLocation": { "artifactLocation": { "uri": result.url } } })],
+        partial_fingerprints: json!({
+            "findingId": if result.id.is_empty() { result.finding_id() } else { result.id.clone() },
+        }),
+        properties,
+    }
+}
+
+fn rule_id(category: Category) -> String {
+    format!("backer/{}", category.name())
+}
+
+/// SARIF的结果级别
+fn sarif_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical | Severity::High => "error",
+        Severity::Medium => "warning",
+        Severity::Low | Severity::Info => "note",
+    }
+}
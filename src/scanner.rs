@@ -1,51 +1,857 @@
-use crate::{Result, ScanConfig, ScanResult};
-use crate::http::HttpClient;
-use crate::utils::generate_backup_urls;
-use futures::future;
+use crate::{BackerError, CalibrationStatus, Coverage, ErrorPhase, OutputFormat, Result, ResultExt, ScanConfig, ScanResult, Target, TargetReport, TargetStatus};
+use crate::http::{HttpClient, SizeLimits, UrlCheck};
+use crate::cache::{ResponseCache, RESPONSE_CACHE_TTL};
+use crate::preset::TargetType;
+use crate::baseline::Baseline;
+use crate::script::{ScriptHooks, Verdict};
+use crate::stage::StageOutput;
+use crate::ratelimit::{ConnectionBudget, RateLimiter, StartupRamp, MAX_HOST_PAUSES};
+use crate::archive::DecompressionLimits;
+use crate::checkpoint::Checkpoint;
+use crate::dns::DnsCache;
+use crate::proxy::ProxyPool;
+use crate::mime::ContentTypeFilter;
+use crate::fingerprint::{load_fingerprint_cache, save_fingerprint_cache};
+use crate::stats::{load_ignore_list, load_pattern_stats, pattern_key, save_pattern_report, save_pattern_stats, PatternEffect, PatternStat};
+use crate::candidate::{base_url, rule, Candidate};
+use crate::sink::MessageSink;
+use crate::elastic::ElasticSink;
+use crate::notify::Notifier;
+use crate::noise::{HostNoise, NoiseReport, RateInfo};
+use crate::recon::{discover_neighbors, expand_with_ct, load_scope_exclusions, neighbor_targets};
+use crate::events::{ScanEvent, UrlOutcome, EVENT_CHANNEL_CAPACITY};
+use crate::patterns::{CandidateIter, PatternGenerator};
+use crate::tech::{technology_patterns, FaviconDb, CMS_PROBE_PATHS};
+use crate::category::category_patterns;
+use crate::patterndb::{Followups, PatternDb};
+use crate::seclists::{import_seclists, SeclistsImport};
+use crate::robots::RobotsRules;
+use crate::vcs::{FileListing, MAX_LISTING_CANDIDATES};
+use crate::utils::{describe_coverage, generator_stream, display_url, extract_host, lock, root_candidates, NdjsonWriter};
 use indicatif::{ProgressBar, ProgressStyle};
 use log::debug;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use dashmap::DashMap;
 use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tokio::sync::mpsc::{self, UnboundedSender};
 use tokio::sync::Semaphore;
+use tokio::task::{JoinError, JoinSet};
+use std::ops::Range;
 use std::time::{Instant, Duration};
-use std::fs::File;
-use std::io::Write;
-use serde_json;
+
+/// 每次从候选生成器拉取的URL数量
+const GENERATION_BATCH_SIZE: usize = 200;
+
+/// 每个目标的预计候选数超过该值时，在扫描前提示模式文件过大
+const LARGE_CANDIDATE_WARNING: usize = 1_000_000;
+
+/// 计算慢速主机延迟中位数使用的最近请求数
+const SLOW_HOST_WINDOW: usize = 50;
+
+/// 判定慢速主机前至少需要的请求数
+const SLOW_HOST_MIN_SAMPLES: usize = 10;
+/// 目标报告中保留的因文件大小排除的响应示例数
+const SIZE_EXCLUDED_EXAMPLES: usize = 20;
+
+/// 扫描任务的结果，通过通道汇总到扫描器
+enum TaskOutcome {
+    /// 发现备份文件
+    Found(Box<ScanResult>),
+    /// 扫描过程中出错，记录到对应目标的报告中
+    Failed { target: String, error: BackerError },
+}
+
+/// 跳过目标剩余候选的原因
+#[derive(Debug, Clone)]
+enum StopReason {
+    /// 主机返回质询页
+    Challenge(String),
+    /// 达到全局发现上限
+    MaxFindings(usize),
+    /// 达到单主机发现上限
+    MaxFindingsPerHost(usize),
+    /// 确认发现达到 `--stop-after-hits`
+    StopAfterHits(usize),
+}
+
+impl std::fmt::Display for StopReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StopReason::Challenge(provider) => write!(f, "检测到 {} 质询页", provider),
+            StopReason::MaxFindings(limit) => write!(f, "已达到全局发现上限 {}", limit),
+            StopReason::MaxFindingsPerHost(limit) => write!(f, "已达到单主机发现上限 {}", limit),
+            StopReason::StopAfterHits(limit) => write!(f, "已有 {} 个确认发现", limit),
+        }
+    }
+}
+
+/// 目标所在的扫描队列
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Lane {
+    /// 正常扫描，持续检测延迟
+    Normal,
+    /// 延迟中位数超过阈值，暂缓剩余候选
+    Deferring(Duration),
+    /// 在慢速队列中扫描，不再检测延迟
+    Slow,
+}
+
+/// 移入慢速队列的目标，在其他目标扫描完成后继续
+struct SlowTarget {
+    /// 目标报告在报告列表中的位置
+    report_index: usize,
+    /// 实际扫描的URL
+    target: String,
+    /// 剩余候选
+    candidates: CandidateIter,
+    context: Arc<BatchContext>,
+    robots_skipped: Arc<DashMap<String, usize>>,
+    calibration: CalibrationStatus,
+}
+
+/// 不计入主机暂停时间的截止时间
+///
+/// 主机返回 Retry-After 后其请求等到暂停结束才发出，这段时间按主机累计的暂停时长顺延截止时间，
+/// 长暂停不会使等待中的候选被当作超时取消
+struct HostDeadline {
+    client: HttpClient,
+    /// 用于确定主机的URL
+    url: String,
+    deadline: tokio::time::Instant,
+    /// 开始计时时主机已累计的暂停时长
+    paused: Duration,
+}
+
+impl HostDeadline {
+    fn new(client: &HttpClient, url: &str, duration: Duration) -> Self {
+        Self {
+            client: client.clone(),
+            url: url.to_string(),
+            deadline: tokio::time::Instant::now() + duration,
+            paused: client.host_paused_total(url),
+        }
+    }
+
+    /// 按此后的暂停时长顺延的截止时间
+    fn current(&self) -> tokio::time::Instant {
+        self.deadline + self.client.host_paused_total(&self.url).saturating_sub(self.paused)
+    }
+
+    /// 在截止时间前完成 `future`，超时返回None；到期时主机又暂停过则继续等待
+    async fn run<F: std::future::Future>(&self, future: F) -> Option<F::Output> {
+        tokio::pin!(future);
+        loop {
+            let deadline = self.current();
+            match tokio::time::timeout_at(deadline, &mut future).await {
+                Ok(output) => return Some(output),
+                Err(_) if self.current() > deadline => continue,
+                Err(_) => return None,
+            }
+        }
+    }
+}
+
+/// 单个目标的扫描上下文，由该目标的所有扫描任务共享
+struct BatchContext {
+    /// 目标URL，用于将错误归入目标报告
+    target: String,
+    /// 用户提供的原始目标字符串，记录到发现中
+    original_target: Option<String>,
+    /// 结果发送端
+    outcomes: UnboundedSender<TaskOutcome>,
+    /// 已提交的发现，扫描出错时仍可通过 [`Scanner::get_partial_results`] 取得
+    partial: Arc<Mutex<Vec<ScanResult>>>,
+    /// 事件发送端
+    events: broadcast::Sender<ScanEvent>,
+    /// 消息总线
+    sink: Option<Arc<MessageSink>>,
+    /// Elasticsearch索引
+    elastic: Option<Arc<ElasticSink>>,
+    /// 按严重程度路由的通知
+    notifier: Option<Arc<Notifier>>,
+    /// 已通知过、不再通知的发现ID
+    notified: Option<Arc<HashSet<String>>>,
+    /// 实时追加发现的NDJSON输出文件
+    ndjson: Option<Arc<NdjsonWriter>>,
+    /// 分阶段输出
+    stage: Option<Arc<StageOutput>>,
+    /// 发现数
+    found: AtomicUsize,
+    /// 确认的发现数(200)
+    confirmed: AtomicUsize,
+    /// 确认发现达到该数量后跳过剩余候选
+    stop_after_hits: Option<usize>,
+    /// 同一主机所有目标的发现数
+    host_findings: Arc<AtomicUsize>,
+    /// 单个主机的发现上限
+    max_findings_per_host: Option<usize>,
+    /// 所有目标的发现数
+    total_findings: Arc<AtomicUsize>,
+    /// 全局发现上限
+    max_findings: Option<usize>,
+    /// 因达到上限而丢弃的发现数
+    dropped: AtomicUsize,
+    /// 实际请求的候选数
+    attempted: AtomicUsize,
+    /// 因忽略列表跳过的候选数
+    ignored: AtomicUsize,
+    /// 因发现上限或 `--stop-after-hits` 跳过的候选数
+    skipped_by_limit: AtomicUsize,
+    /// 超出分类预算跳过的候选数
+    over_budget: Arc<AtomicUsize>,
+    /// 批次超时时尚未开始、被取消的候选数
+    cancelled: AtomicUsize,
+    /// 请求出错数
+    request_errors: AtomicUsize,
+    /// 第一个请求错误，附带出错的URL，写入目标报告
+    first_request_error: Mutex<Option<BackerError>>,
+    /// 请求超时数
+    timeouts: AtomicUsize,
+    /// 断点续扫检查点
+    checkpoint: Option<Arc<Checkpoint>>,
+    /// 因上次扫描已检查而跳过的候选数
+    resumed: AtomicUsize,
+    /// 慢速主机阈值
+    slow_threshold: Option<Duration>,
+    /// 最近的请求延迟
+    latencies: Mutex<VecDeque<Duration>>,
+    /// 所在的扫描队列
+    lane: Mutex<Lane>,
+    /// 移入慢速队列时已拉取但暂缓的候选
+    deferred: Mutex<Vec<Candidate>>,
+    /// 内容类型过滤规则
+    content_type_filter: Arc<ContentTypeFilter>,
+    /// 因内容类型过滤排除的响应数
+    content_type_filtered: AtomicUsize,
+    /// 因文件大小超出范围排除的响应数
+    size_excluded: AtomicUsize,
+    /// 因文件大小排除的响应示例，最多保留 `SIZE_EXCLUDED_EXAMPLES` 个
+    size_excluded_examples: Mutex<Vec<String>>,
+    /// 主机返回质询页时的服务商
+    challenge: Mutex<Option<String>>,
+    /// 已接受风险的发现基线
+    baseline: Option<Arc<Baseline>>,
+    /// 与基线相符、未报告的发现URL
+    known: Mutex<Vec<String>>,
+    /// 审核发现的用户脚本
+    script: Option<Arc<ScriptHooks>>,
+    /// 被脚本否决的发现数
+    vetoed: AtomicUsize,
+    /// 条件模式状态
+    followups: Mutex<Followups>,
+    /// 条件模式触发的候选，在下一批之前请求
+    pending: Mutex<Vec<Candidate>>,
+    /// 条件模式追加的候选数
+    conditional: AtomicUsize,
+    /// 元数据文件列出的文件名生成的候选，用于去重和限制数量
+    listed: Mutex<HashSet<String>>,
+    /// robots.txt规则和按规则统计的跳过数，条件模式的候选同样遵守
+    robots: Option<Arc<RobotsRules>>,
+    robots_skipped: Arc<DashMap<String, usize>>,
+}
+
+impl BatchContext {
+    /// 命中候选后触发条件模式，新的候选加入待请求列表；robots.txt禁止的候选同样跳过
+    fn follow_up(&self, base: &Arc<str>, path: &str) {
+        let paths = self.followups.lock().unwrap_or_else(|e| e.into_inner()).on_hit(path);
+        if paths.is_empty() {
+            return;
+        }
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        for next in paths {
+            if let Some(rule) = self.robots.as_ref().and_then(|robots| robots.disallowed_by(&format!("/{}", next))) {
+                *self.robots_skipped.entry(rule.to_string()).or_default() += 1;
+                continue;
+            }
+            debug!("{}/{} 命中，条件模式追加 {}", base, path, next);
+            self.conditional.fetch_add(1, Ordering::Relaxed);
+            pending.push(Candidate::new(base, next, rule::CONDITIONAL));
+        }
+    }
+    
+    /// 按元数据文件列出的文件名生成备份候选，加入待请求列表；每个目标最多追加 `MAX_LISTING_CANDIDATES` 个
+    fn list_files(&self, base: &Arc<str>, path: &str, listing: &FileListing) {
+        let paths = listing.candidates(listing.source.root(path));
+        let mut listed = self.listed.lock().unwrap_or_else(|e| e.into_inner());
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        for next in paths {
+            if listed.len() >= MAX_LISTING_CANDIDATES {
+                debug!("目标 {} 由文件列表生成的候选已达上限 {}", self.target, MAX_LISTING_CANDIDATES);
+                break;
+            }
+            if !listed.insert(next.clone()) {
+                continue;
+            }
+            if let Some(rule) = self.robots.as_ref().and_then(|robots| robots.disallowed_by(&format!("/{}", next))) {
+                *self.robots_skipped.entry(rule.to_string()).or_default() += 1;
+                continue;
+            }
+            pending.push(Candidate::new(base, next, rule::LISTING));
+        }
+    }
+    
+    /// 取出条件模式触发的候选
+    fn take_pending(&self) -> Vec<Candidate> {
+        std::mem::take(&mut *self.pending.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+    
+    /// 记录因文件大小排除的响应
+    fn exclude_by_size(&self, url: &str, reason: &str) {
+        self.size_excluded.fetch_add(1, Ordering::Relaxed);
+        let mut examples = self.size_excluded_examples.lock().unwrap_or_else(|e| e.into_inner());
+        if examples.len() < SIZE_EXCLUDED_EXAMPLES {
+            examples.push(format!("{} ({})", url, reason));
+        }
+    }
+    
+    /// 因文件大小排除的响应说明，超出示例数时附带总数
+    fn size_exclusions(&self) -> Vec<String> {
+        let total = self.size_excluded.load(Ordering::Relaxed);
+        let mut examples = self.size_excluded_examples.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        if total > examples.len() {
+            examples.push(format!("另有 {} 个响应因大小排除", total - examples.len()));
+        }
+        examples
+    }
+    
+    /// 记录目标的扫描错误
+    fn fail(&self, error: BackerError) {
+        let error = error.annotate(None, Some(&self.target), None);
+        debug!("{}", error);
+        self.emit(ScanEvent::TargetErrored { target: self.target.clone(), error: error.to_string() });
+        let _ = self.outcomes.send(TaskOutcome::Failed { target: self.target.clone(), error });
+    }
+    
+    /// 记录主机返回质询页，之后跳过剩余候选
+    fn mark_challenge(&self, provider: String) {
+        self.challenge.lock().unwrap_or_else(|e| e.into_inner()).get_or_insert(provider);
+    }
+    
+    /// 需要跳过剩余候选时返回原因，质询页优先，发现数上限优先于 `--stop-after-hits`
+    fn stop_reason(&self) -> Option<StopReason> {
+        if let Some(provider) = self.challenge.lock().unwrap_or_else(|e| e.into_inner()).clone() {
+            return Some(StopReason::Challenge(provider));
+        }
+        let reached = |counter: &AtomicUsize, limit: Option<usize>| {
+            limit.filter(|limit| counter.load(Ordering::Relaxed) >= *limit)
+        };
+        reached(&self.total_findings, self.max_findings).map(StopReason::MaxFindings)
+            .or_else(|| reached(&self.host_findings, self.max_findings_per_host).map(StopReason::MaxFindingsPerHost))
+            .or_else(|| reached(&self.confirmed, self.stop_after_hits).map(StopReason::StopAfterHits))
+    }
+    
+    /// 为发现占用主机和全局配额，达到任一上限时返回false，该发现将被丢弃
+    fn accept_finding(&self) -> bool {
+        let take = |counter: &AtomicUsize, limit: Option<usize>| {
+            counter.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+                limit.is_none_or(|limit| count < limit).then_some(count + 1)
+            }).is_ok()
+        };
+        if !take(&self.host_findings, self.max_findings_per_host) {
+            return false;
+        }
+        if !take(&self.total_findings, self.max_findings) {
+            self.host_findings.fetch_sub(1, Ordering::Relaxed);
+            return false;
+        }
+        true
+    }
+    
+    /// 发现被上限截断时的说明，用于目标报告
+    fn truncation(&self) -> Option<String> {
+        let reason = self.stop_reason().filter(|reason| matches!(reason, StopReason::MaxFindings(_) | StopReason::MaxFindingsPerHost(_)))?;
+        let dropped = self.dropped.load(Ordering::Relaxed);
+        Some(if dropped > 0 {
+            format!("{}，丢弃 {} 个发现，剩余候选未扫描", reason, dropped)
+        } else {
+            format!("{}，剩余候选未扫描", reason)
+        })
+    }
+    
+    /// 发布扫描事件，没有订阅者时直接丢弃
+    fn emit(&self, event: ScanEvent) {
+        let _ = self.events.send(event);
+    }
+    
+    /// 记录请求延迟，最近延迟的中位数超过阈值时暂缓该目标的剩余候选
+    fn record_latency(&self, latency: Duration) {
+        let Some(threshold) = self.slow_threshold else { return };
+        let mut lane = self.lane.lock().unwrap_or_else(|e| e.into_inner());
+        if *lane != Lane::Normal {
+            return;
+        }
+        let mut latencies = self.latencies.lock().unwrap_or_else(|e| e.into_inner());
+        latencies.push_back(latency);
+        if latencies.len() > SLOW_HOST_WINDOW {
+            latencies.pop_front();
+        }
+        if latencies.len() < SLOW_HOST_MIN_SAMPLES {
+            return;
+        }
+        let mut sorted: Vec<Duration> = latencies.iter().copied().collect();
+        sorted.sort();
+        let median = sorted[sorted.len() / 2];
+        if median > threshold {
+            debug!("目标 {} 延迟中位数 {:?} 超过阈值 {:?}", self.target, median, threshold);
+            *lane = Lane::Deferring(median);
+        }
+    }
+    
+    /// 目标正在移入慢速队列时暂缓候选，否则原样返回
+    fn defer_if_slow(&self, candidate: Candidate) -> Option<Candidate> {
+        if !matches!(*self.lane.lock().unwrap_or_else(|e| e.into_inner()), Lane::Deferring(_)) {
+            return Some(candidate);
+        }
+        self.deferred.lock().unwrap_or_else(|e| e.into_inner()).push(candidate);
+        None
+    }
+    
+    /// 移入慢速队列时的延迟中位数
+    fn slow_median(&self) -> Option<Duration> {
+        match *self.lane.lock().unwrap_or_else(|e| e.into_inner()) {
+            Lane::Deferring(median) => Some(median),
+            _ => None,
+        }
+    }
+    
+    /// 是否在慢速队列中扫描
+    fn in_slow_lane(&self) -> bool {
+        *self.lane.lock().unwrap_or_else(|e| e.into_inner()) == Lane::Slow
+    }
+    
+    /// 发布单个URL的检查结果
+    fn checked(&self, url: String, outcome: UrlOutcome) {
+        if let Some(checkpoint) = &self.checkpoint {
+            checkpoint.record_url(url.clone());
+        }
+        self.emit(ScanEvent::UrlChecked { target: self.target.clone(), url, outcome });
+    }
+}
 
 /// 扫描器核心
 pub struct Scanner {
     config: ScanConfig,
     client: HttpClient,
     // 模式成功率追踪
-    pattern_success_rates: Arc<Mutex<HashMap<String, (usize, usize)>>>, // (成功数, 总尝试数)
+    pattern_success_rates: Arc<DashMap<String, PatternStat>>,
+    // 本次扫描各模式的效果
+    pattern_effects: Arc<DashMap<String, PatternEffect>>,
+    // 所有目标的发现数，用于全局发现上限
+    total_findings: Arc<AtomicUsize>,
+    // 忽略列表中的URL(人工确认的误报)
+    ignored_urls: Arc<HashSet<String>>,
+    // 范围排除规则，用于过滤扩展出的子域名
+    scope_exclusions: Arc<Vec<String>>,
+    // favicon哈希库
+    favicon_db: Arc<FaviconDb>,
+    // 从SecLists导入的字典
+    seclists: Arc<SeclistsImport>,
+    // 分类和技术栈的专属路径库
+    pattern_db: Arc<PatternDb>,
     // 当前动态线程数
-    current_threads: Arc<Mutex<usize>>,
-    // 部分结果存储 - 即使在超时的情况下也可以保存已发现的结果
+    current_threads: Arc<AtomicUsize>,
+    // 部分结果存储 - 扫描中发现即追加，即使扫描出错也可以保存已发现的结果
     partial_results: Arc<Mutex<Vec<ScanResult>>>,
+    // 各目标的存活状态和扫描错误报告
+    target_reports: Vec<TargetReport>,
+    // 扫描事件广播通道
+    events: broadcast::Sender<ScanEvent>,
+    // 发现的实时发布目标
+    sink: Option<Arc<MessageSink>>,
+    // 发现批量写入的Elasticsearch索引
+    elastic: Option<Arc<ElasticSink>>,
+    // 按严重程度路由的通知
+    notifier: Option<Arc<Notifier>>,
+    // 定时扫描历史中已通知过的发现ID
+    notified: Option<Arc<HashSet<String>>>,
+    /// 分布式扫描中本节点负责的候选区间，按模式生成顺序计数
+    shard: Option<Range<usize>>,
+    // 实时追加发现的NDJSON输出文件
+    ndjson: Option<Arc<NdjsonWriter>>,
+    // 验证前的候选和验证后的发现
+    stage: Option<Arc<StageOutput>>,
+    // 断点续扫检查点
+    checkpoint: Option<Arc<Checkpoint>>,
+    // 发现的内容类型过滤规则
+    content_type_filter: Arc<ContentTypeFilter>,
+    // 已接受风险的发现基线
+    baseline: Option<Arc<Baseline>>,
+    // 生成额外候选和审核发现的用户脚本
+    script: Option<Arc<ScriptHooks>>,
 }
 
 #[allow(dead_code)]
 impl Scanner {
-    /// 创建新的扫描器
+    /// 创建新的扫描器，出错时标注为初始化阶段
     pub async fn new(config: ScanConfig) -> Result<Self> {
-        let client = HttpClient::new(
+        Self::setup(config).await.during(ErrorPhase::Setup)
+    }
+    
+    /// 检查配置后创建HTTP客户端，加载配置引用的文件并连接输出服务
+    async fn setup(config: ScanConfig) -> Result<Self> {
+        config.validate()?;
+        
+        let mut client = HttpClient::new(
             config.timeout,
             config.retry_count,
             config.user_agent.clone(),
         )?;
+        
+        client.set_request_template(config.request_template.clone())?;
+        client.set_ip_spoof(config.ip_spoof.clone());
+        client.set_spoof_headers(&config.spoof_headers)?;
+        client.set_soft_404_probes(config.soft_404_probes);
+        client.set_double_check(config.double_check);
+        client.set_bypass_403(config.bypass_403);
+        client.set_challenge_detection(config.challenge_detection);
+        client.set_target_type(config.target_type);
+        if config.response_cache {
+            client.set_response_cache(Some(Arc::new(ResponseCache::new(RESPONSE_CACHE_TTL))));
+        }
+        client.set_archive_scan_max_size(config.archive_scan_max_size);
+        client.set_git_index(config.git_index);
+        client.set_decompression_limits(DecompressionLimits {
+            max_size: config.max_decompressed_size,
+            max_ratio: config.max_compression_ratio,
+        });
+        client.set_seed(config.seed);
+        if !config.user_agents.is_empty() {
+            client.set_custom_user_agents(config.user_agents.clone());
+        }
+        client.set_ua_strategy(config.ua_strategy);
+        client.set_identify(config.identify.as_deref())?;
+        client.set_ip_version(config.ip_version)?;
+        client.set_proxy(config.proxy.clone())?;
+        if !config.proxies.is_empty() {
+            let pool = ProxyPool::new(&config.proxies, config.proxy_rotation, config.proxy_max_failures, config.timeout, config.ip_version)?;
+            client.set_proxy_pool(Some(Arc::new(pool)));
+        }
+        client.set_resolve_overrides(&config.resolve)?;
+        client.set_size_limits(SizeLimits { min: config.min_size, max: config.max_size })?;
+        client.set_connection_budget(config.max_connections.map(ConnectionBudget::new));
+        if config.ramp_up > 0 {
+            let target = config.max_connections.unwrap_or(config.threads);
+            client.set_startup_ramp(Some(Arc::new(StartupRamp::new(target, Duration::from_secs(config.ramp_up)))));
+        }
+        
+        // 加载主机指纹缓存，已缓存的主机跳过校准
+        if let Some(path) = &config.fingerprint_cache {
+            client.set_host_fingerprints(load_fingerprint_cache(path)?);
+        }
 
+        // 加载历史模式统计和忽略列表
+        let pattern_stats = match &config.pattern_stats {
+            Some(path) => load_pattern_stats(path)?,
+            None => HashMap::new(),
+        };
+        let ignored_urls = match &config.ignore_list {
+            Some(path) => load_ignore_list(path)?,
+            None => HashSet::new(),
+        };
+        let scope_exclusions = match &config.scope_exclude {
+            Some(path) => load_scope_exclusions(path)?,
+            None => Vec::new(),
+        };
+        let seclists = match &config.seclists {
+            Some(dir) => {
+                let import = import_seclists(dir)?;
+                println!("SecLists: 导入 {} 个字典 ({})", import.files.len(), import.summary());
+                import
+            }
+            None => SeclistsImport::default(),
+        };
+        let pattern_db = PatternDb::load();
+        let mut favicon_db = FaviconDb::bundled();
+        if let Some(path) = &config.favicon_db {
+            favicon_db.extend_from_file(path)?;
+        }
+        
         // 复制线程数
         let threads = config.threads;
         
+        let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        
+        // 连接消息总线，连接失败时直接报错，避免扫描结束后才发现结果未被投递
+        let sink = match &config.sink {
+            Some(address) => Some(Arc::new(MessageSink::connect(address).await?)),
+            None => None,
+        };
+        let elastic = match &config.es_url {
+            Some(address) => Some(ElasticSink::connect(address, &config.es_index).await?),
+            None => None,
+        };
+        
+        // 通知渠道配置无效时同样在扫描前报错
+        let notifier = if config.notify.is_empty() {
+            None
+        } else {
+            Some(Arc::new(Notifier::new(&config.notify)?))
+        };
+        
+        let checkpoint = match &config.resume {
+            Some(path) => Some(Arc::new(Checkpoint::open(path)?)),
+            None => None,
+        };
+        
+        // 输出格式为ndjson时发现即写入输出文件，从状态文件继续时保留上次写入的发现
+        let ndjson = match &config.output_file {
+            Some(path) if config.output_format == OutputFormat::Ndjson => {
+                let resumed = checkpoint.as_ref().is_some_and(|checkpoint| checkpoint.is_resumed());
+                Some(Arc::new(NdjsonWriter::open(path, resumed)?))
+            }
+            _ => None,
+        };
+        let stage = match &config.stage_dir {
+            Some(dir) => {
+                let resumed = checkpoint.as_ref().is_some_and(|checkpoint| checkpoint.is_resumed());
+                Some(Arc::new(StageOutput::open(dir, resumed)?))
+            }
+            None => None,
+        };
+        
+        let content_type_filter = Arc::new(ContentTypeFilter::new(&config.require_content_types, &config.exclude_content_types));
+        
+        // 加载基线，首次扫描时基线文件可能还不存在
+        let baseline = match &config.baseline {
+            Some(path) if path.exists() => {
+                let baseline = Baseline::load(path)?;
+                println!("基线: {} 个已知发现 ({})", baseline.len(), path.display());
+                Some(Arc::new(baseline))
+            }
+            Some(path) => {
+                println!("基线文件 {} 不存在，所有发现都将报告", path.display());
+                None
+            }
+            None => None,
+        };
+        
+        let script = match &config.script {
+            Some(path) => {
+                let script = ScriptHooks::load(path)?;
+                println!("脚本: {} ({})", path.display(), script.describe());
+                Some(Arc::new(script))
+            }
+            None => None,
+        };
+        
         Ok(Self {
             config: config.clone(),
             client,
-            pattern_success_rates: Arc::new(Mutex::new(HashMap::new())),
-            current_threads: Arc::new(Mutex::new(threads)),
+            pattern_success_rates: Arc::new(pattern_stats.into_iter().collect()),
+            pattern_effects: Arc::new(DashMap::new()),
+            total_findings: Arc::new(AtomicUsize::new(0)),
+            ignored_urls: Arc::new(ignored_urls),
+            scope_exclusions: Arc::new(scope_exclusions),
+            favicon_db: Arc::new(favicon_db),
+            seclists: Arc::new(seclists),
+            pattern_db: Arc::new(pattern_db),
+            current_threads: Arc::new(AtomicUsize::new(threads)),
             partial_results: Arc::new(Mutex::new(Vec::new())),
+            target_reports: Vec::new(),
+            events,
+            sink,
+            elastic,
+            notifier,
+            notified: None,
+            shard: None,
+            ndjson,
+            stage,
+            checkpoint,
+            content_type_filter,
+            baseline,
+            script,
         })
     }
     
+    /// 设置已通知过的发现ID(定时扫描的历史)，这些发现不再通知，扫描汇总只包含其余的新发现
+    /// 且没有新发现时不发送；None时通知所有发现
+    pub fn set_notified_findings(&mut self, ids: Option<HashSet<String>>) {
+        self.notified = ids.map(Arc::new);
+    }
+    
+    /// 只扫描模式生成的第 `range` 个候选(从0开始，按生成顺序)，供分布式扫描的工作节点使用；
+    /// 技术栈和分类的专属路径只在区间从0开始时扫描。None时扫描全部候选
+    pub fn set_candidate_shard(&mut self, range: Option<Range<usize>>) {
+        self.shard = range;
+    }
+    
+    /// 扫描使用的HTTP客户端，共享连接池、限速器和连接数上限，供下载等后续请求使用
+    pub fn http_client(&self) -> HttpClient {
+        self.client.clone()
+    }
+    
+    /// 已启用分类(含目标类型预设启用的分类)的路径，去掉与目标类型无关的路径
+    fn category_paths(&self) -> Vec<String> {
+        let target_type = self.config.target_type;
+        let mut categories = self.config.categories.clone();
+        categories.extend(target_type.categories().iter().filter(|category| !self.config.categories.contains(category)));
+        category_patterns(&categories, &self.pattern_db, &self.seclists.categories)
+            .into_iter()
+            .filter(|path| !target_type.is_irrelevant(path))
+            .collect()
+    }
+    
+    /// 目标类型预设的技术栈专属路径，未指定目标类型时为空
+    fn preset_technology_paths(&self) -> Vec<String> {
+        let names: Vec<String> = self.config.target_type.technology().map(|technology| technology.name().to_string()).into_iter().collect();
+        technology_patterns(&names, &self.pattern_db, &self.seclists.technologies)
+    }
+    
+    /// 按当前配置统计扫描将对各主机发送的请求，不发送请求
+    ///
+    /// 候选数与扫描时生成的候选一致；证书透明度和同IP扩展出的目标、命中后的验证请求等
+    /// 取决于扫描过程的流量列在说明中
+    pub fn noise_report(&self, targets: &[Target]) -> Result<NoiseReport> {
+        let patterns = match &self.config.patterns_file {
+            Some(path) => crate::utils::load_patterns(path)?,
+            None => Vec::new(),
+        };
+        let generator = PatternGenerator::from_patterns(&patterns, self.config.dir_depth);
+        let category_paths = self.category_paths();
+        let technology_paths = self.preset_technology_paths();
+        let preset: Vec<String> = self.config.target_type.technology()
+            .map(|technology| vec![technology.name().to_string()])
+            .unwrap_or_default();
+        let calibrates = self.config.soft_404_probes > 0 || self.config.verify_content || self.config.fingerprint_cache.is_some();
+        let fingerprints = self.config.favicon_fingerprint && self.config.target_type.technology().is_none();
+        let cms_fingerprints = self.config.cms_fingerprint && self.config.target_type.technology().is_none();
+        let auxiliary = usize::from(self.config.respect_robots) + usize::from(fingerprints)
+            + if cms_fingerprints { CMS_PROBE_PATHS.len() } else { 0 };
+        let retries = self.config.retry_count as usize;
+        
+        // 与扫描时相同，按主机(含端口)分组
+        let mut hosts: Vec<HostNoise> = Vec::new();
+        for target in targets {
+            let host = target.url.split("://").nth(1)
+                .and_then(|rest| rest.split('/').next())
+                .unwrap_or(&target.url)
+                .to_string();
+            let target_type = self.config.target_type;
+            let mut priority = root_candidates(&target.url, &technology_paths, rule::TECHNOLOGY);
+            priority.extend(root_candidates(&target.url, &category_paths, rule::CATEGORY));
+            priority.extend(root_candidates(&target.url, &Followups::new(self.pattern_db.clone(), &preset).initial(), rule::CONDITIONAL));
+            // 统计时不识别技术栈，脚本按未识别技术栈生成候选
+            if let Some(script) = &self.script {
+                priority.extend(root_candidates(&target.url, &script.candidates(&target.url, &[]).during(ErrorPhase::Script).for_target(&target.url)?, rule::SCRIPT));
+            }
+            let candidates: CandidateIter = Box::new(priority.into_iter().chain(generator_stream(&target.url, &generator, &patterns)
+                .filter(move |candidate| !target_type.is_irrelevant(&candidate.path))));
+            let candidates = match &self.config.budget {
+                Some(budget) => budget.apply(candidates, Arc::new(AtomicUsize::new(0))).count(),
+                None => candidates.count(),
+            };
+            let index = match hosts.iter().position(|noise| noise.host == host) {
+                Some(index) => index,
+                None => {
+                    let calibration = if calibrates { self.config.soft_404_probes.max(1) + 1 } else { 0 };
+                    hosts.push(HostNoise {
+                        host, targets: 0, liveness: 0, calibration, auxiliary: 0, candidates: 0, requests: 0, max_requests: 0,
+                    });
+                    hosts.len() - 1
+                }
+            };
+            let noise = &mut hosts[index];
+            noise.targets += 1;
+            noise.liveness += 1;
+            noise.auxiliary += auxiliary;
+            noise.candidates += candidates;
+            noise.requests = noise.liveness + noise.calibration + noise.auxiliary + noise.candidates;
+            noise.max_requests = noise.liveness * 2 + noise.calibration + noise.auxiliary + noise.candidates * (1 + retries);
+        }
+        
+        let mut notes = Vec::new();
+        if self.config.dns_prefetch {
+            notes.push("扫描前对每个主机进行一次DNS查询".to_string());
+        }
+        if self.config.verify_content {
+            notes.push("命中的候选追加GET请求(带Range头)读取文件头验证内容，大文件分段采样".to_string());
+            notes.push(format!(
+                "命中的 .git/HEAD、.git/config 追加数个请求分析仓库；.svn/wc.db、.hg/store/fncache、.DS_Store 等列出的文件名每个目标最多追加 {} 个备份候选，未列入各主机请求数",
+                MAX_LISTING_CANDIDATES
+            ));
+        }
+        if self.config.double_check {
+            notes.push("报告200发现前重新请求该URL和一个随机后缀的同级路径".to_string());
+        }
+        if self.config.bypass_403 {
+            notes.push("返回403的候选追加若干路径变体和请求头变体的绕过请求".to_string());
+        }
+        if fingerprints {
+            notes.push("通过favicon识别出技术栈后追加该技术栈的专属路径".to_string());
+        }
+        if cms_fingerprints {
+            notes.push(format!("请求 {} 识别CMS和框架，识别出技术栈后追加该技术栈的专属路径", CMS_PROBE_PATHS.join("、")));
+        }
+        if !self.pattern_db.conditional.is_empty() {
+            notes.push("条件模式在前提路径命中，或通过favicon、CMS特征、专属路径命中识别出技术栈后追加其路径，这部分未列入各主机请求数".to_string());
+        }
+        if self.config.ct_subdomains || self.config.expand_neighbors {
+            notes.push("证书透明度或同IP反查扩展出的目标在扫描时确定，未列入各主机请求数".to_string());
+        }
+        if self.config.challenge_detection {
+            notes.push("每个主机的第一个及之后每20个403/429/503响应追加一次GET请求(带Range头)检查是否为质询页，检测到后不再请求该主机".to_string());
+        }
+        notes.push("主机返回429/503并带有Retry-After时暂停该主机，恢复后重新请求".to_string());
+        
+        let egress = if !self.config.proxies.is_empty() {
+            format!("代理池轮换 ({} 个代理)", self.config.proxies.len())
+        } else {
+            match &self.config.proxy {
+                Some(proxy) => format!("代理 {}", crate::redact::redact_url(proxy)),
+                None => format!("直连 (IP协议版本: {:?})", self.config.ip_version),
+            }
+        };
+        
+        Ok(NoiseReport {
+            generated_at: chrono::Local::now().to_rfc3339(),
+            tool: format!("backer {}", env!("CARGO_PKG_VERSION")),
+            egress,
+            probe_method: self.client.probe_method_name(),
+            headers: self.client.header_usage(),
+            rate: RateInfo {
+                concurrency_per_host: std::cmp::min(self.config.threads, 10),
+                slow_lane_concurrency: self.config.slow_lane_threads,
+                max_connections: self.config.max_connections,
+                ramp_up_secs: self.config.ramp_up,
+                timeout_secs: self.config.timeout,
+                retry_count: self.config.retry_count,
+            },
+            total_requests: hosts.iter().map(|noise| noise.requests).sum(),
+            total_max_requests: hosts.iter().map(|noise| noise.max_requests).sum(),
+            hosts,
+            notes,
+        })
+    }
+    
+    /// 断点续扫检查点，用于在中断时保存进度
+    pub fn checkpoint(&self) -> Option<Arc<Checkpoint>> {
+        self.checkpoint.clone()
+    }
+    
+    /// 目标完成后写入检查点
+    fn complete_target(&self, report: &TargetReport) {
+        if let Some(checkpoint) = &self.checkpoint {
+            checkpoint.complete_target(report);
+        }
+    }
+    
+    /// 使用共享的连接池，见 [`crate::manager::ScanManager`]
+    pub fn set_connection_pool(&mut self, client: reqwest::Client, dns_cache: Arc<DnsCache>) {
+        self.client.set_connection_pool(client, dns_cache);
+    }
+    
+    /// 设置全局限速器，多个扫描器共享时合计限速
+    pub fn set_rate_limiter(&mut self, limiter: Option<Arc<RateLimiter>>) {
+        self.client.set_rate_limiter(limiter);
+    }
+    
+    /// 设置连接数上限，多个扫描器共享时合计计数，替换 `max_connections` 创建的名额池
+    pub fn set_connection_budget(&mut self, budget: Option<ConnectionBudget>) {
+        self.client.set_connection_budget(budget);
+    }
+    
     /// 设置是否使用随机请求头
     pub fn set_random_headers(&mut self, enable: bool) {
         self.client.set_random_headers(enable);
@@ -63,13 +869,109 @@ impl Scanner {
     
     /// 获取部分扫描结果
     pub fn get_partial_results(&self) -> Option<Vec<ScanResult>> {
-        let guard = self.partial_results.lock().ok()?;
-        Some(guard.clone())
+        Some(lock(&self.partial_results).clone())
+    }
+    
+    /// 获取各目标的存活状态和扫描错误报告
+    pub fn get_target_reports(&self) -> Vec<TargetReport> {
+        self.target_reports.clone()
+    }
+    
+    /// 订阅扫描事件
+    ///
+    /// 订阅只接收之后发布的事件，处理过慢的订阅者会收到 `Lagged` 错误并丢失最旧的事件
+    pub fn subscribe(&self) -> broadcast::Receiver<ScanEvent> {
+        self.events.subscribe()
+    }
+    
+    /// 发布扫描事件，没有订阅者时直接丢弃
+    fn emit(&self, event: ScanEvent) {
+        let _ = self.events.send(event);
+    }
+    
+    /// 启用 `respect_robots` 时获取目标的robots.txt规则，没有可用规则时返回None
+    async fn respect_robots(&self, target: &str) -> Option<RobotsRules> {
+        if !self.config.respect_robots {
+            return None;
+        }
+        let robots = RobotsRules::parse(&self.client.fetch_robots(target).await?);
+        if robots.is_empty() {
+            return None;
+        }
+        Some(robots)
     }
     
     /// 扫描目标站点
     pub async fn scan(&mut self, targets: Vec<String>) -> Result<Vec<ScanResult>> {
-        let mut all_results = Vec::new();
+        self.scan_targets(targets.into_iter().map(Target::from).collect()).await
+    }
+    
+    /// 扫描带标签的目标站点，结果会继承目标的标签
+    pub async fn scan_targets(&mut self, targets: Vec<Target>) -> Result<Vec<ScanResult>> {
+        let mut reports: Vec<TargetReport> = Vec::new();
+        self.client.clear_response_cache();
+        // 从状态文件继续时，上次扫描的发现也算已有结果
+        *lock(&self.partial_results) = self.checkpoint.as_ref()
+            .map(|checkpoint| checkpoint.results())
+            .unwrap_or_default();
+        
+        // 扫描任务通过通道提交发现和错误，扫描结束后统一汇总
+        let (outcomes, mut outcome_rx) = mpsc::unbounded_channel();
+        
+        // 从证书透明度日志扩展子域名目标
+        let mut targets = if self.config.ct_subdomains {
+            expand_with_ct(targets, &self.scope_exclusions).await?
+        } else {
+            targets
+        };
+        
+        // 反查同IP站点，默认只列出，指定扩展时才加入扫描
+        let neighbors = if self.config.reverse_ip || self.config.expand_neighbors {
+            discover_neighbors(&targets).await?
+        } else {
+            HashMap::new()
+        };
+        if self.config.expand_neighbors {
+            let expanded = neighbor_targets(&targets, &neighbors, &self.scope_exclusions);
+            println!("同IP站点: 新增 {} 个扫描目标", expanded.len());
+            targets.extend(expanded);
+        }
+        
+        // 记录主机对应的标签和来源，用于给结果打标签
+        let host_tags: HashMap<String, String> = targets.iter()
+            .filter_map(|t| Some((extract_host(&t.url)?, t.tag.clone()?)))
+            .collect();
+        let host_sources: HashMap<String, String> = targets.iter()
+            .filter_map(|t| Some((extract_host(&t.url)?, t.source.clone()?)))
+            .collect();
+        let target_originals: HashMap<String, String> = targets.iter()
+            .filter_map(|t| Some((t.url.clone(), t.original.clone()?)))
+            .collect();
+        let targets: Vec<String> = targets.into_iter().map(|t| t.url).collect();
+        let total_targets = targets.len();
+        
+        // 批量预解析目标主机，无法解析的主机在存活检测时直接跳过
+        if self.config.dns_prefetch {
+            let start_time = Instant::now();
+            let failures = self.client.prefetch_dns(&targets).await;
+            println!("DNS预解析: 用时 {:.1} 秒，{} 个主机无法解析", start_time.elapsed().as_secs_f64(), failures.len());
+            for (host, failure) in &failures {
+                println!("  无法解析: {} ({})", host, failure.describe());
+            }
+        }
+        
+        // 从状态文件继续时，之前的发现计入发现上限
+        if let Some(checkpoint) = self.checkpoint.as_ref().filter(|checkpoint| checkpoint.is_resumed()) {
+            let (completed, checked, found) = checkpoint.progress();
+            println!("从状态文件 {} 继续: {} 个目标已完成，{} 个URL已检查，{} 个发现",
+                checkpoint.path().display(), completed, checked, found);
+            self.total_findings.fetch_add(found, Ordering::Relaxed);
+            let progress = checkpoint.scan_progress();
+            for (host, host_progress) in progress.hosts.iter().filter(|(_, host)| host.completed < host.targets && host.checked > 0) {
+                println!("  {}: {}/{} 个目标已完成，{} 个URL已检查", host, host_progress.completed, host_progress.targets, host_progress.checked);
+            }
+            self.emit(ScanEvent::ProgressRestored { progress });
+        }
         
         // 创建进度条，修改为用户需要的样式
         let progress_bar = ProgressBar::new(targets.len() as u64)
@@ -86,6 +988,20 @@ impl Scanner {
             None => Vec::new(),
         };
         
+        // 模式只解析一次，候选在扫描各目标时按需展开
+        let generator = PatternGenerator::from_patterns(&patterns, self.config.dir_depth);
+        let per_target = generator.estimate_candidates();
+        if !patterns.is_empty() && self.shard.is_none() {
+            println!("模式: {} 个，每个目标最多约 {} 个候选，共约 {} 个请求",
+                patterns.len(), per_target, per_target.saturating_mul(total_targets));
+        }
+        if per_target > LARGE_CANDIDATE_WARNING {
+            println!("警告: 模式展开后每个目标约 {} 个候选，扫描耗时和请求量会很大，可拆分模式文件或降低 --dir-depth", per_target);
+        }
+        
+        // 已启用分类的内置路径和导入路径，所有目标共用
+        let category_paths = self.category_paths();
+        
         // 按域名分组处理，避免同时请求过多相同域名
         let mut domain_targets: HashMap<String, Vec<String>> = HashMap::new();
         
@@ -99,75 +1015,481 @@ impl Scanner {
                 None => target.clone(),
             };
             
-            domain_targets.entry(domain).or_insert_with(Vec::new).push(target);
+            domain_targets.entry(domain).or_default().push(target);
+        }
+        if let Some(checkpoint) = &self.checkpoint {
+            for (domain, targets) in &domain_targets {
+                checkpoint.plan_host(domain, targets.len());
+            }
         }
         
         // 总任务数
         let total_domains = domain_targets.len();
         progress_bar.set_length(total_domains as u64);
         
+        // 为每个域名设置单独的超时控制，避免一个域名拖慢整个扫描
+        let domain_timeout = std::cmp::max(self.config.timeout * 3, 30); // 单个域名的超时时间
+        
+        // 延迟过高而移入慢速队列的目标
+        let mut slow_lane: Vec<SlowTarget> = Vec::new();
+        
         // 对每个域名进行处理
         for (domain, domain_targets) in domain_targets {
+            let domain_target_list = domain_targets.clone();
+            // 单主机发现上限由同一主机的所有目标共享
+            let host_findings = Arc::new(AtomicUsize::new(0));
             progress_bar.set_message(format!("域名: {}", domain));
             debug!("开始扫描域名: {}", domain);
             
-            // 为每个域名设置单独的超时控制，避免一个域名拖慢整个扫描
-            let domain_timeout = std::cmp::max(self.config.timeout * 3, 30); // 单个域名的超时时间
             let domain_scan_future = async {
                 for target in domain_targets {
-                    // 为每个目标生成备份文件URL
-                    let urls = generate_backup_urls(&target, &patterns);
-                    debug!("为目标 {} 生成了 {} 个URL", target, urls.len());
+                    // 上次扫描已完成的目标直接沿用其报告
+                    if let Some(report) = self.checkpoint.as_ref().and_then(|checkpoint| checkpoint.completed_report(&target)) {
+                        debug!("目标 {} 已在上次扫描中完成，跳过", target);
+                        reports.push(report);
+                        continue;
+                    }
+                    
+                    // 存活检测：停放域名、可被接管或无法访问的目标不再扫描
+                    self.emit(ScanEvent::TargetStarted { target: target.clone() });
+                    let report = self.client.check_liveness(&target).await;
+                    let status = report.status;
+                    if status != TargetStatus::Live {
+                        println!("跳过目标 {}: {:?} ({})", target, status, report.detail.as_deref().unwrap_or("-"));
+                    }
+                    reports.push(report.clone());
+                    if status != TargetStatus::Live {
+                        self.complete_target(&report);
+                        self.emit(ScanEvent::TargetFinished { target: report.target, status, findings: 0 });
+                        continue;
+                    }
+                    
+                    // 协议回退后使用实际可访问的URL
+                    if let Some(fallback) = &report.scheme_fallback {
+                        println!("目标 {} 协议回退: {}", target, fallback);
+                    }
+                    let target = report.scanned_url.clone().unwrap_or(target);
+                    
+                    // 先校准主机，用于过滤近似的200错误页(soft-404)
+                    let calibration = if self.config.soft_404_probes > 0 || self.config.verify_content || self.config.fingerprint_cache.is_some() {
+                        self.client.calibrate_host(&target).await
+                    } else {
+                        CalibrationStatus::Disabled
+                    };
+                    
+                    // 指定了目标类型时直接使用其技术栈，否则通过favicon哈希和CMS/框架特征识别
+                    let mut technologies: Vec<String> = self.config.target_type.technology()
+                        .map(|technology| vec![technology.name().to_string()])
+                        .unwrap_or_default();
+                    let preset = !technologies.is_empty();
+                    if !preset && self.config.favicon_fingerprint {
+                        if let Some(technology) = self.client.fetch_favicon(&target).await
+                            .and_then(|favicon| self.favicon_db.lookup(&favicon).map(str::to_string)) {
+                            println!("技术识别: {} -> {} (favicon)", target, technology);
+                            technologies.push(technology);
+                        }
+                    }
+                    if !preset && self.config.cms_fingerprint {
+                        for found in self.client.fingerprint_cms(&target).await {
+                            let name = found.technology.name().to_string();
+                            if !technologies.contains(&name) {
+                                println!("技术识别: {} -> {} ({})", target, name, found.evidence);
+                                technologies.push(name);
+                            }
+                        }
+                    }
+                    
+                    // 为每个目标惰性生成备份文件URL，扫描时按批拉取，
+                    // 技术栈专属路径和额外启用分类的路径优先
+                    let technology_paths = technology_patterns(&technologies, &self.pattern_db, &self.seclists.technologies);
+                    let mut priority = root_candidates(&target, &technology_paths, rule::TECHNOLOGY);
+                    priority.extend(root_candidates(&target, &category_paths, rule::CATEGORY));
+                    // 条件模式：已识别技术栈的规则直接请求，其余在命中前提路径后追加
+                    let mut followups = Followups::new(self.pattern_db.clone(), &technologies);
+                    let conditional = root_candidates(&target, &followups.initial(), rule::CONDITIONAL);
+                    let mut initial_conditional = conditional.len();
+                    priority.extend(conditional);
+                    if let Some(script) = &self.script {
+                        match script.candidates(&target, &technologies) {
+                            Ok(paths) => priority.extend(root_candidates(&target, &paths, rule::SCRIPT)),
+                            Err(e) => {
+                                let e = e.annotate(Some(ErrorPhase::Script), Some(&target), None);
+                                println!("{}", e);
+                                if let Some(last) = reports.last_mut() {
+                                    last.record_error(e);
+                                }
+                            }
+                        }
+                    }
+                    let candidates = generator_stream(&target, &generator, &patterns);
+                    let candidates: CandidateIter = match self.shard.clone() {
+                        Some(range) => {
+                            if range.start > 0 {
+                                priority.clear();
+                                initial_conditional = 0;
+                            }
+                            Box::new(candidates.skip(range.start).take(range.len()))
+                        }
+                        None => candidates,
+                    };
+                    let target_type = self.config.target_type;
+                    let candidates: CandidateIter = if target_type == TargetType::Generic {
+                        candidates
+                    } else {
+                        Box::new(candidates.filter(move |candidate| !target_type.is_irrelevant(&candidate.path)))
+                    };
+                    let candidates: CandidateIter = if priority.is_empty() {
+                        candidates
+                    } else {
+                        Box::new(priority.into_iter().chain(candidates))
+                    };
+                    if let Some(last) = reports.last_mut() {
+                        last.technologies = technologies;
+                    }
                     
-                    // 对URL模式按历史成功率排序
-                    let sorted_urls = self.sort_urls_by_success_rate(urls);
+                    // 遵守robots.txt时过滤被禁止路径下的候选，按规则统计跳过数量
+                    let robots_skipped: Arc<DashMap<String, usize>> = Arc::new(DashMap::new());
+                    let robots = self.respect_robots(&target).await.map(Arc::new);
+                    let candidates: CandidateIter = match robots.clone() {
+                        Some(robots) => {
+                            let skipped = robots_skipped.clone();
+                            Box::new(candidates.filter(move |candidate| {
+                                match robots.disallowed_by(&format!("/{}", candidate.path)) {
+                                    Some(rule) => {
+                                        *skipped.entry(rule.to_string()).or_default() += 1;
+                                        false
+                                    }
+                                    None => true,
+                                }
+                            }))
+                        }
+                        None => candidates,
+                    };
+                    
+                    // 按分类预算筛选和调度候选，robots.txt禁止的候选不占预算
+                    let over_budget = Arc::new(AtomicUsize::new(0));
+                    let candidates: CandidateIter = match &self.config.budget {
+                        Some(budget) => budget.apply(candidates, over_budget.clone()),
+                        None => candidates,
+                    };
                     
                     // 扫描URL
-                    let results = self.scan_urls(&self.client, sorted_urls, self.config.verify_content, progress_bar.clone()).await;
+                    let context = Arc::new(BatchContext {
+                        target: report.target.clone(),
+                        original_target: target_originals.get(&report.target).cloned(),
+                        outcomes: outcomes.clone(),
+                        partial: self.partial_results.clone(),
+                        events: self.events.clone(),
+                        sink: self.sink.clone(),
+                        elastic: self.elastic.clone(),
+                        notifier: self.notifier.clone(),
+                        notified: self.notified.clone(),
+                        ndjson: self.ndjson.clone(),
+                        stage: self.stage.clone(),
+                        found: AtomicUsize::new(0),
+                        confirmed: AtomicUsize::new(0),
+                        stop_after_hits: self.config.stop_after_hits,
+                        host_findings: host_findings.clone(),
+                        max_findings_per_host: self.config.max_findings_per_host,
+                        total_findings: self.total_findings.clone(),
+                        max_findings: self.config.max_findings,
+                        dropped: AtomicUsize::new(0),
+                        attempted: AtomicUsize::new(0),
+                        ignored: AtomicUsize::new(0),
+                        skipped_by_limit: AtomicUsize::new(0),
+                        over_budget,
+                        cancelled: AtomicUsize::new(0),
+                        request_errors: AtomicUsize::new(0),
+                        first_request_error: Mutex::new(None),
+                        timeouts: AtomicUsize::new(0),
+                        checkpoint: self.checkpoint.clone(),
+                        resumed: AtomicUsize::new(0),
+                        slow_threshold: self.config.slow_host_threshold.map(Duration::from_millis),
+                        latencies: Mutex::new(VecDeque::new()),
+                        lane: Mutex::new(Lane::Normal),
+                        deferred: Mutex::new(Vec::new()),
+                        content_type_filter: self.content_type_filter.clone(),
+                        content_type_filtered: AtomicUsize::new(0),
+                        size_excluded: AtomicUsize::new(0),
+                        size_excluded_examples: Mutex::new(Vec::new()),
+                        challenge: Mutex::new(None),
+                        baseline: self.baseline.clone(),
+                        known: Mutex::new(Vec::new()),
+                        script: self.script.clone().filter(|script| script.reviews()),
+                        vetoed: AtomicUsize::new(0),
+                        followups: Mutex::new(followups),
+                        pending: Mutex::new(Vec::new()),
+                        conditional: AtomicUsize::new(initial_conditional),
+                        listed: Mutex::new(HashSet::new()),
+                        robots,
+                        robots_skipped: robots_skipped.clone(),
+                    });
+                    // 断点续扫时已保存的发现不会重新请求，按这些发现触发条件模式和文件列表
+                    if let (Some(checkpoint), Ok(url)) = (&self.checkpoint, url::Url::parse(&target)) {
+                        let base = base_url(&url);
+                        for result in checkpoint.results().iter().filter(|result| (200..300).contains(&result.status_code)) {
+                            if let Some(path) = result.url.strip_prefix(&*base).and_then(|rest| rest.strip_prefix('/')) {
+                                context.follow_up(&base, path);
+                                if let Some(listing) = &result.listing {
+                                    context.list_files(&base, path, listing);
+                                }
+                            }
+                        }
+                    }
+                    let remaining = self.scan_urls(&self.client, candidates, self.config.verify_content, progress_bar.clone(), context.clone()).await;
                     
-                    // 合并结果
-                    all_results.extend(results);
+                    let report_index = reports.len() - 1;
+                    match remaining {
+                        // 延迟过高，剩余候选留到慢速队列，目标在慢速队列完成后才计入覆盖
+                        Some(candidates) => {
+                            let median = context.slow_median().unwrap_or_default();
+                            let note = format!("中位延迟 {}ms 超过阈值 {}ms，剩余候选移入慢速队列",
+                                median.as_millis(), self.config.slow_host_threshold.unwrap_or_default());
+                            println!("目标 {} {}", target, note);
+                            reports[report_index].slow_lane = Some(note);
+                            slow_lane.push(SlowTarget { report_index, target, candidates, context, robots_skipped, calibration });
+                        }
+                        None => self.finish_target(&mut reports[report_index], &target, &context, &robots_skipped, calibration),
+                    }
                 }
                 Ok::<_, crate::BackerError>(())
             };
             
-            // 使用超时包装域名扫描过程
-            match tokio::time::timeout(
-                std::time::Duration::from_secs(domain_timeout),
-                domain_scan_future
-            ).await {
-                Ok(result) => {
-                    if let Err(e) = result {
-                        debug!("域名 {} 扫描出错: {:?}", domain, e);
-                    }
+            // 使用超时包装域名扫描过程，主机 Retry-After 暂停的时间不计入
+            let deadline = HostDeadline::new(&self.client, domain_target_list.first().map_or(domain.as_str(), String::as_str), Duration::from_secs(domain_timeout));
+            let domain_result = deadline.run(domain_scan_future).await;
+            let domain_error = match domain_result {
+                Some(Ok(())) => None,
+                Some(Err(e)) => {
+                    debug!("域名 {} 扫描出错: {:?}", domain, e);
+                    Some(e)
                 },
-                Err(_) => {
+                None => {
                     // 域名扫描超时，记录日志但继续下一个
                     debug!("域名 {} 扫描超时，继续执行下一个域名", domain);
                     println!("警告: 域名 {} 扫描超时，跳过并继续下一个", domain);
+                    Some(BackerError::Scan(format!("域名扫描超时 ({}秒)", domain_timeout)).annotate(Some(ErrorPhase::Probe), None, None))
+                }
+            };
+            
+            // 域名级错误记录到该域名下所有存活目标
+            if let Some(error) = domain_error.map(Arc::new) {
+                for report in reports.iter_mut()
+                    .filter(|r| r.status == TargetStatus::Live && domain_target_list.contains(&r.target)) {
+                    self.emit(ScanEvent::TargetErrored { target: report.target.clone(), error: error.to_string() });
+                    report.record_error(error.clone());
                 }
             }
             
             progress_bar.inc(1);
         }
         
+        // 慢速队列：其他目标全部完成后，以低并发逐个扫描慢速主机的剩余候选
+        if !slow_lane.is_empty() {
+            println!("慢速队列: {} 个目标 (线程数: {})", slow_lane.len(), self.config.slow_lane_threads);
+        }
+        for slow in slow_lane {
+            *slow.context.lane.lock().unwrap_or_else(|e| e.into_inner()) = Lane::Slow;
+            progress_bar.set_message(format!("慢速队列: {}", slow.target));
+            let scan = self.scan_urls(&self.client, slow.candidates, self.config.verify_content, progress_bar.clone(), slow.context.clone());
+            let finished = HostDeadline::new(&self.client, &slow.target, Duration::from_secs(domain_timeout)).run(scan).await.is_some();
+            let report = &mut reports[slow.report_index];
+            if finished {
+                self.finish_target(report, &slow.target, &slow.context, &slow.robots_skipped, slow.calibration);
+            } else {
+                println!("警告: 目标 {} 慢速队列扫描超时", slow.target);
+                let error = BackerError::Scan(format!("慢速队列扫描超时 ({}秒)", domain_timeout))
+                    .annotate(Some(ErrorPhase::Probe), Some(&slow.target), None);
+                self.emit(ScanEvent::TargetErrored { target: report.target.clone(), error: error.to_string() });
+                report.record_error(error);
+            }
+        }
+        
         progress_bar.finish();
         
-        // 如果在部分结果中有更多，也合并到最终结果
-        if let Ok(partial) = self.partial_results.lock() {
-            for result in partial.iter() {
-                if !all_results.iter().any(|r| r.url == result.url) {
-                    all_results.push(result.clone());
+        // 保存模式统计，供后续扫描排序
+        if let Some(path) = &self.config.pattern_stats {
+            let stats: HashMap<String, PatternStat> = self.pattern_success_rates.iter()
+                .map(|entry| (entry.key().clone(), *entry.value()))
+                .collect();
+            if let Err(e) = save_pattern_stats(path, &stats) {
+                debug!("保存模式统计失败: {:?}", e);
+            }
+        }
+        
+        // 保存本次扫描的模式效果报告
+        if let Some(path) = &self.config.pattern_report {
+            let effects: HashMap<String, PatternEffect> = self.pattern_effects.iter()
+                .map(|entry| (entry.key().clone(), *entry.value()))
+                .collect();
+            match save_pattern_report(path, &effects) {
+                Ok(()) => println!("模式效果报告已保存到: {} ({} 个模式)", path.display(), effects.len()),
+                Err(e) => eprintln!("保存模式效果报告失败: {}", e),
+            }
+        }
+        
+        // 保存主机指纹缓存，供后续扫描复用
+        if let Some(path) = &self.config.fingerprint_cache {
+            if let Err(e) = save_fingerprint_cache(path, &self.client.host_fingerprints()) {
+                debug!("保存主机指纹缓存失败: {:?}", e);
+            }
+        }
+        
+        // 写入Elasticsearch中剩余的缓冲发现
+        if let Some(elastic) = &self.elastic {
+            if let Err(e) = elastic.flush().await {
+                eprintln!("写入Elasticsearch失败: {}", e);
+            }
+        }
+        
+        // 汇总通道中的发现和错误，超时后仍在运行的任务已提交的发现也会被收集
+        drop(outcomes);
+        let mut all_results: Vec<ScanResult> = self.checkpoint.as_ref()
+            .map(|checkpoint| checkpoint.results())
+            .unwrap_or_default();
+        while let Ok(outcome) = outcome_rx.try_recv() {
+            match outcome {
+                TaskOutcome::Found(result) => {
+                    if !all_results.iter().any(|r| r.url == result.url && r.method == result.method) {
+                        all_results.push(*result);
+                    }
+                },
+                TaskOutcome::Failed { target, error } => {
+                    if let Some(report) = reports.iter_mut().find(|r| r.target == target) {
+                        report.record_error(error);
+                    }
                 }
             }
         }
         
+        // 按主机继承目标标签和来源
+        for result in all_results.iter_mut() {
+            let Some(host) = extract_host(&result.url) else { continue };
+            if let Some(tag) = host_tags.get(&host) {
+                result.tag = Some(tag.clone());
+            }
+            if let Some(source) = host_sources.get(&host) {
+                result.source = Some(source.clone());
+            }
+        }
+        
+        // 同IP站点记录到对应目标的报告
+        for report in reports.iter_mut() {
+            if let Some(domains) = extract_host(&report.target).and_then(|host| neighbors.get(&host)) {
+                report.neighbors = domains.clone();
+            }
+        }
+        
+        if !reports.is_empty() {
+            println!("{}", describe_coverage(&reports));
+        }
+        let cache_hits = self.client.response_cache_hits();
+        if cache_hits > 0 {
+            println!("响应缓存: 复用 {} 次之前的响应", cache_hits);
+        }
+        
+        if let Some(stage) = &self.stage {
+            stage.save_findings(&all_results).during(ErrorPhase::Output)?;
+            println!("分阶段输出: 候选和发现已写入 {}", stage.dir().display());
+        }
+        
+        // 向汇总渠道发送本次扫描的汇总，失败不影响扫描结果；定时扫描时只汇总新发现，没有新发现时不发送
+        if let Some(notifier) = &self.notifier {
+            let fresh: Vec<ScanResult> = match &self.notified {
+                Some(ids) => all_results.iter().filter(|result| !ids.contains(&result.id)).cloned().collect(),
+                None => all_results.clone(),
+            };
+            if self.notified.is_none() || !fresh.is_empty() {
+                for (channel, e) in notifier.notify_summary(&fresh, &reports).await {
+                    eprintln!("通知渠道 {} 发送扫描汇总失败: {}", channel, e);
+                }
+            }
+        }
+        
+        // 全部目标完成后删除状态文件，否则保留供下次继续
+        if let Some(checkpoint) = &self.checkpoint {
+            if checkpoint.progress().0 >= total_targets {
+                checkpoint.remove().during(ErrorPhase::Output)?;
+            } else {
+                checkpoint.save().during(ErrorPhase::Output)?;
+                println!("扫描未全部完成，进度已保存到 {}，使用相同的 --resume 继续", checkpoint.path().display());
+            }
+        }
+        
+        *lock(&self.partial_results) = all_results.clone();
+        self.target_reports = reports;
+        
         Ok(all_results)
     }
     
+    /// 目标扫描结束后记录截断、robots.txt跳过和覆盖情况，写入检查点并发布完成事件
+    fn finish_target(&self, report: &mut TargetReport, target: &str, context: &BatchContext,
+                     robots_skipped: &DashMap<String, usize>, calibration: CalibrationStatus) {
+        if let Some(truncated) = context.truncation() {
+            report.truncated = Some(truncated);
+        }
+        if let Some(StopReason::Challenge(provider)) = context.stop_reason() {
+            let skipped = context.skipped_by_limit.load(Ordering::Relaxed);
+            report.challenge = Some(format!("检测到 {} 质询页，跳过剩余 {} 个候选", provider, skipped));
+        }
+        
+        let known = std::mem::take(&mut *context.known.lock().unwrap_or_else(|e| e.into_inner()));
+        if !known.is_empty() {
+            println!("基线: {} 有 {} 个已知发现，未报告", target, known.len());
+            report.known = known;
+        }
+        let size_excluded = context.size_excluded.load(Ordering::Relaxed);
+        if size_excluded > 0 {
+            println!("文件大小: {} 排除 {} 个响应", target, size_excluded);
+            report.size_excluded = context.size_exclusions();
+        }
+        
+        let mut robots_total = 0;
+        if !robots_skipped.is_empty() {
+            let mut skipped: Vec<(String, usize)> = robots_skipped.iter()
+                .map(|entry| (entry.key().clone(), *entry.value()))
+                .collect();
+            skipped.sort();
+            robots_total = skipped.iter().map(|(_, count)| count).sum();
+            println!("robots.txt: {} 跳过 {} 个候选", target, robots_total);
+            report.robots_skipped = skipped.into_iter()
+                .map(|(rule, count)| format!("Disallow: {} (跳过 {} 个候选)", rule, count))
+                .collect();
+        }
+        
+        // 记录覆盖情况，区分"没有发现"和"没能扫描"
+        let limit_reason = match context.stop_reason() {
+            Some(StopReason::Challenge(_)) => "质询页",
+            Some(StopReason::StopAfterHits(_)) => "--stop-after-hits",
+            _ => "发现上限",
+        };
+        let skipped = [
+            ("上次扫描已检查", context.resumed.load(Ordering::Relaxed)),
+            ("忽略列表", context.ignored.load(Ordering::Relaxed)),
+            ("robots.txt", robots_total),
+            ("分类预算", context.over_budget.load(Ordering::Relaxed)),
+            ("批次超时", context.cancelled.load(Ordering::Relaxed)),
+            (limit_reason, context.skipped_by_limit.load(Ordering::Relaxed)),
+        ];
+        report.coverage = Some(Coverage {
+            attempted: context.attempted.load(Ordering::Relaxed),
+            skipped: skipped.into_iter()
+                .filter(|(_, count)| *count > 0)
+                .map(|(reason, count)| (reason.to_string(), count))
+                .collect(),
+            calibration,
+        });
+        self.complete_target(report);
+        self.emit(ScanEvent::TargetFinished {
+            target: report.target.clone(),
+            status: report.status,
+            findings: context.found.load(Ordering::Relaxed),
+        });
+    }
+    
     /// 根据历史成功率排序URL
-    fn sort_urls_by_success_rate(&self, urls: Vec<String>) -> Vec<String> {
-        let success_rates = self.pattern_success_rates.lock().unwrap();
+    fn sort_urls_by_success_rate(&self, urls: Vec<Candidate>) -> Vec<Candidate> {
+        let success_rates = &self.pattern_success_rates;
         
         // 如果没有历史数据，直接返回原始顺序
         if success_rates.is_empty() {
@@ -175,22 +1497,14 @@ impl Scanner {
         }
         
         // 计算每个URL的得分
-        let mut url_scores: Vec<(String, f64)> = urls
+        let mut url_scores: Vec<(Candidate, f64)> = urls
             .into_iter()
             .map(|url| {
-                // 提取模式
-                let pattern = self.extract_pattern_from_url(&url);
-                
-                // 计算成功率
-                let score = if let Some((successes, attempts)) = success_rates.get(&pattern) {
-                    if *attempts > 0 {
-                        (*successes as f64) / (*attempts as f64)
-                    } else {
-                        0.0
-                    }
-                } else {
+                // 计算成功率，模式即路径最后一段
+                let score = match success_rates.get(url.file_name()) {
+                    Some(stat) => stat.success_rate(),
                     // 默认得分 (0.1表示新模式有一定的探索机会)
-                    0.1
+                    None => 0.1,
                 };
                 
                 (url, score)
@@ -207,175 +1521,393 @@ impl Scanner {
     /// 从URL中提取模式
     fn extract_pattern_from_url(&self, url: &str) -> String {
         // 从URL中提取模式，例如从 http://example.com/backup.zip 提取 backup.zip
-        
-        let parts: Vec<&str> = url.split('/').collect();
-        if let Some(last) = parts.last() {
-            return last.to_string();
-        }
-        
-        url.to_string()
+        pattern_key(url)
     }
     
     /// 更新模式成功率
-    fn update_pattern_success_rate(&self, url: &str, success: bool) {
-        // 提取模式
-        let pattern = if let Some(pattern) = url.split('/').last() {
-            pattern.to_string()
-        } else {
-            return;
-        };
+    fn update_pattern_success_rate(rates: &DashMap<String, PatternStat>, url: &str, success: bool) {
+        let pattern = pattern_key(url);
         
         // 更新成功率
-        let mut rates = self.pattern_success_rates.lock().unwrap();
-        let entry = rates.entry(pattern).or_insert((0, 0));
+        let mut entry = rates.entry(pattern).or_default();
         
         if success {
-            entry.0 += 1;  // 成功数+1
+            entry.hits += 1;  // 成功数+1
         }
-        entry.1 += 1;  // 总数+1
+        entry.attempts += 1;  // 总数+1
     }
     
-    /// 将单个扫描结果保存为JSON文件
-    fn save_result_to_json(&self, result: &ScanResult, path: &str) -> Result<()> {
-        // 创建包含单个结果的数组
-        let results = vec![result];
-        
-        // 序列化为JSON
-        let json = serde_json::to_string_pretty(&results)?;
-        
-        // 写入文件
-        let mut file = File::create(path)?;
-        file.write_all(json.as_bytes())?;
-        
-        Ok(())
+    /// 记录模式在本次扫描中的效果，未命中时累计响应大小
+    fn record_pattern_effect(effects: &DashMap<String, PatternEffect>, url: &str, hit: bool, response_size: u64) {
+        let mut entry = effects.entry(pattern_key(url)).or_default();
+        entry.attempts += 1;
+        if hit {
+            entry.hits += 1;
+        } else {
+            entry.wasted_bytes += response_size;
+        }
     }
     
     /// 动态调整线程数
     fn adjust_concurrency(&self, status_code: u16) {
-        let mut current_threads = self.current_threads.lock().unwrap();
-        
-        // 如果遇到限制，减少线程数
-        if status_code == 429 || status_code == 503 {
-            *current_threads = (*current_threads * 3 / 4).max(1);
-        } 
-        // 如果运行平稳，可以考虑增加线程数，但不超过配置的最大值
-        else if *current_threads < self.config.threads && status_code < 400 {
-            *current_threads = (*current_threads * 5 / 4).min(self.config.threads);
-        }
+        let max_threads = self.config.threads;
+        let _ = self.current_threads.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current_threads| {
+            // 如果遇到限制，减少线程数
+            if status_code == 429 || status_code == 503 {
+                Some((current_threads * 3 / 4).max(1))
+            }
+            // 如果运行平稳，可以考虑增加线程数，但不超过配置的最大值
+            else if current_threads < max_threads && status_code < 400 {
+                Some((current_threads * 5 / 4).min(max_threads))
+            } else {
+                None
+            }
+        });
     }
     
     /// 获取当前线程数
     fn get_current_threads(&self) -> usize {
-        let current_threads = self.current_threads.lock().unwrap();
-        *current_threads
+        self.current_threads.load(Ordering::Relaxed)
     }
     
-    /// 扫描指定URL列表
-    async fn scan_urls(&self, client: &HttpClient, urls: Vec<String>, verify_content: bool, progress_bar: ProgressBar) -> Vec<ScanResult> {
-        let results = Arc::new(Mutex::new(Vec::new()));
-        
+    /// 扫描候选URL
+    ///
+    /// 候选按批从惰性迭代器中拉取，每批扫描完成后才生成下一批，
+    /// 内存占用与模式数量无关。发现和错误通过上下文中的通道提交。
+    /// 目标延迟过高需要移入慢速队列时提前返回剩余候选
+    async fn scan_urls(&self, client: &HttpClient, mut candidates: CandidateIter, verify_content: bool, progress_bar: ProgressBar, context: Arc<BatchContext>) -> Option<CandidateIter> {
         // 开始计时
         let start_time = Instant::now();
         
-        // 使用固定线程数，避免动态调整造成的复杂性
-        let threads = std::cmp::min(self.config.threads, 10); // 放宽限制到10个线程 
-        let semaphore = Arc::new(Semaphore::new(threads));
-        
-        // 统计根目录URL数量（假设PatternGenerator正确将根目录URL放在前面）
-        let root_url_count = std::cmp::min(urls.len(), 200); // 假设前200个是根目录URL
-        let backup_urls = if urls.len() > root_url_count {
-            urls[root_url_count..].to_vec()
+        // 使用固定线程数，避免动态调整造成的复杂性；慢速队列使用单独的低并发
+        let threads = if context.in_slow_lane() {
+            self.config.slow_lane_threads
         } else {
-            Vec::new()
+            std::cmp::min(self.config.threads, 10) // 放宽限制到10个线程
         };
-        let root_urls = urls[0..root_url_count].to_vec();
+        let semaphore = Arc::new(Semaphore::new(threads));
         
-        // 设置根目录进度条
-        progress_bar.set_length(root_url_count as u64);
-        progress_bar.set_message(format!("扫描根目录 (线程数: {})", threads));
+        // 进度条长度随拉取的批次增长
+        progress_bar.set_position(0);
+        progress_bar.set_length(0);
         progress_bar.set_style(ProgressStyle::default_bar()
             .template("{msg} [{elapsed_precise}] [{bar:50}] {pos}/{len} ({eta})")
             .unwrap()
             .progress_chars("=>")); // 使用"=>"，这会显示为[==============>    ]
         
-        debug!("开始扫描根目录: {} 个URL", root_url_count);
-        
-        // 1. 先扫描根目录
-        let _root_results = self.scan_url_batch(client, root_urls, verify_content, progress_bar.clone(), results.clone(), semaphore.clone()).await;
-        
-        // 等待一小段时间再继续
-        tokio::time::sleep(Duration::from_millis(500)).await;
-        
-        // 如果有子目录，继续扫描
-        if !backup_urls.is_empty() {
-            debug!("开始扫描备份目录: {} 个URL", backup_urls.len());
+        // 生成器先产出根目录候选，再产出备份目录候选
+        let mut batch_index = 0;
+        let mut scanned = 0;
+        loop {
+            // 上一批命中触发的条件模式候选先于剩余候选请求
+            let pending = context.take_pending();
+            if !pending.is_empty() {
+                debug!("目标 {} 条件模式追加 {} 个候选", context.target, pending.len());
+                candidates = Box::new(pending.into_iter().chain(candidates));
+            }
+            if let Some(reason) = context.stop_reason() {
+                println!("目标 {} {}，跳过剩余候选", context.target, reason);
+                context.skipped_by_limit.fetch_add(candidates.by_ref().count(), Ordering::Relaxed);
+                break;
+            }
+            let batch: Vec<Candidate> = candidates.by_ref()
+                .filter(|candidate| {
+                    if self.ignored_urls.is_empty() && context.checkpoint.is_none() {
+                        return true;
+                    }
+                    let url = candidate.url();
+                    if self.ignored_urls.contains(&url) {
+                        context.ignored.fetch_add(1, Ordering::Relaxed);
+                        return false;
+                    }
+                    if context.checkpoint.as_ref().is_some_and(|checkpoint| checkpoint.was_checked(&url)) {
+                        // 进度条从中断前的位置继续
+                        context.resumed.fetch_add(1, Ordering::Relaxed);
+                        progress_bar.inc_length(1);
+                        progress_bar.inc(1);
+                        return false;
+                    }
+                    true
+                })
+                .take(GENERATION_BATCH_SIZE)
+                .collect();
+            if batch.is_empty() {
+                break;
+            }
             
-            // 重置进度条
-            progress_bar.set_position(0);
-            progress_bar.set_length(backup_urls.len() as u64);
-            progress_bar.set_message(format!("扫描备份目录 (线程数: {})", threads));
+            batch_index += 1;
+            scanned += batch.len();
+            debug!("开始扫描第 {} 批候选: {} 个URL", batch_index, batch.len());
+            progress_bar.inc_length(batch.len() as u64);
+            progress_bar.set_message(format!("扫描第 {} 批 (线程数: {})", batch_index, threads));
             
-            // 2. 再扫描备份目录 - 每个URL都设置短超时，防止卡住
-            let _backup_results = self.scan_url_batch(client, backup_urls, verify_content, progress_bar.clone(), results.clone(), semaphore.clone()).await;
+            // 批内按历史成功率排序
+            let batch = self.sort_urls_by_success_rate(batch);
+            self.scan_url_batch(client, batch, verify_content, progress_bar.clone(), context.clone(), semaphore.clone()).await;
+            
+            // 暂缓的候选排在剩余候选之前，在慢速队列中按原顺序继续
+            if context.slow_median().is_some() {
+                let deferred = std::mem::take(&mut *context.deferred.lock().unwrap_or_else(|e| e.into_inner()));
+                debug!("目标 {} 移入慢速队列，已扫描 {} 个URL，暂缓 {} 个", context.target, scanned - deferred.len(), deferred.len());
+                let pending = context.take_pending();
+                return Some(Box::new(deferred.into_iter().chain(pending).chain(candidates)));
+            }
         }
+        debug!("目标 {} 共扫描 {} 个URL", context.target, scanned);
         
         // 打印扫描耗时
         let duration = start_time.elapsed();
         debug!("扫描完成，耗时: {:?}", duration);
         
-        // 获取最终结果
-        let result_clone = {
-            let guard = results.lock().unwrap();
-            let cloned = guard.clone();
-            
-            // 如果没有找到任何结果，显示提示信息
-            if cloned.is_empty() {
-                println!("未发现任何备份文件");
-            } else {
-                println!("总共发现 {} 个备份文件", cloned.len());
+        // 单个URL的请求错误很常见，只汇总数量
+        let request_errors = context.request_errors.load(Ordering::Relaxed);
+        if request_errors > 0 {
+            context.fail(BackerError::Scan(format!("{} 个请求出错", request_errors)).annotate(Some(ErrorPhase::Probe), None, None));
+            // 首个请求错误单独记录，保留原始错误和出错的URL
+            if let Some(error) = context.first_request_error.lock().unwrap_or_else(|e| e.into_inner()).take() {
+                context.fail(error);
             }
-            
-            cloned
-        };
+        }
+        let timeouts = context.timeouts.load(Ordering::Relaxed);
+        if timeouts > 0 {
+            context.fail(BackerError::Scan(format!("{} 个请求超时", timeouts)).annotate(Some(ErrorPhase::Probe), None, None));
+        }
+        
+        let filtered = context.content_type_filtered.load(Ordering::Relaxed);
+        if filtered > 0 {
+            println!("内容类型过滤排除 {} 个响应", filtered);
+        }
+        let vetoed = context.vetoed.load(Ordering::Relaxed);
+        if vetoed > 0 {
+            println!("脚本否决 {} 个发现", vetoed);
+        }
+        let conditional = context.conditional.load(Ordering::Relaxed);
+        if conditional > 0 {
+            println!("条件模式追加 {} 个候选", conditional);
+        }
+        let listed = context.listed.lock().unwrap_or_else(|e| e.into_inner()).len();
+        if listed > 0 {
+            println!("文件列表追加 {} 个候选", listed);
+        }
         
-        result_clone
+        // 如果没有找到任何结果，显示提示信息
+        let found = context.found.load(Ordering::Relaxed);
+        if found == 0 {
+            println!("未发现任何备份文件");
+        } else {
+            println!("总共发现 {} 个备份文件", found);
+        }
+        None
     }
     
     /// 扫描一批URL
-    async fn scan_url_batch(&self, client: &HttpClient, urls: Vec<String>, verify_content: bool, 
-                           progress_bar: ProgressBar, results: Arc<Mutex<Vec<ScanResult>>>, semaphore: Arc<Semaphore>) -> bool {
-        // 对每个URL进行处理
-        let mut tasks = Vec::with_capacity(urls.len());
+    ///
+    /// 先取得并发许可再创建任务，同时存在的任务数不超过并发数；任务由 `JoinSet` 管理，
+    /// 结束时逐个收集异常终止的任务，批次超时后取消尚未完成的任务并计入覆盖情况
+    async fn scan_url_batch(&self, client: &HttpClient, urls: Vec<Candidate>, verify_content: bool, 
+                           progress_bar: ProgressBar, context: Arc<BatchContext>, semaphore: Arc<Semaphore>) -> bool {
         let urls_count = urls.len();
+        let mut tasks = JoinSet::new();
         
         // 每URL设置短的超时，防止慢速URL拖慢整个扫描
-        let url_timeout = std::cmp::min(self.config.timeout, 5); // 单个URL最多5秒
+        // 验证模式需要下载内容，使用完整超时
+        let url_timeout = if verify_content {
+            self.config.timeout
+        } else {
+            std::cmp::min(self.config.timeout, 5) // 单个URL最多5秒
+        };
         
-        for url in urls {
-            let semaphore = semaphore.clone();
+        // 探测请求的重试在单个URL的超时之外另计
+        let probe_timeout = Duration::from_secs(url_timeout) + client.retry_allowance();
+        
+        // 批次超时按并发数下每个URL的超时估算，至少30秒，避免批次卡住；主机暂停的时间不计入
+        let threads = semaphore.available_permits().max(1);
+        let batch_timeout = (probe_timeout * urls_count.div_ceil(threads) as u32).max(Duration::from_secs(30));
+        let deadline = HostDeadline::new(client, &context.target, batch_timeout);
+        
+        let mut unstarted = 0;
+        let mut timed_out = false;
+        let mut urls = urls.into_iter();
+        while let Some(candidate) = urls.next() {
+            // 收集已结束的任务
+            while let Some(joined) = tasks.try_join_next() {
+                Self::record_join(&context, joined);
+            }
+            // 主机暂停期间不创建新任务，也不占用并发许可
+            let acquire = async {
+                client.wait_for_host(&candidate.base).await;
+                semaphore.clone().acquire_owned().await
+            };
+            let permit = match deadline.run(acquire).await {
+                Some(Ok(permit)) => permit,
+                Some(Err(e)) => {
+                    context.fail(BackerError::Scan(format!("无法获取并发许可: {}", e)).annotate(Some(ErrorPhase::Probe), None, None));
+                    unstarted = 1 + urls.len();
+                    break;
+                }
+                None => {
+                    timed_out = true;
+                    unstarted = 1 + urls.len();
+                    break;
+                }
+            };
             let client = client.clone();
-            let results = results.clone();
+            let context = context.clone();
             let progress_bar = progress_bar.clone();
-            let self_ref = self.clone();
+            let success_rates = self.pattern_success_rates.clone();
+            let pattern_effects = self.pattern_effects.clone();
+            let semaphore = semaphore.clone();
             
-            let task = tokio::spawn(async move {
-                let _permit = semaphore.acquire().await.expect("信号量错误");
+            tasks.spawn(async move {
+                let mut permit = Some(permit);
                 
-                // 添加整体超时保护 - 使用较小的超时值，确保不会单个请求卡住太久
-                let timeout_duration = Duration::from_secs(url_timeout);
-                let url_check = tokio::time::timeout(
-                    timeout_duration,
-                    client.check_url(&url, verify_content)
-                ).await;
+                // 同一主机的其他目标已遇到质询页时同样跳过
+                if let Some(provider) = client.host_challenge(&candidate.base) {
+                    context.mark_challenge(provider);
+                }
+                
+                // 同一目标的确认发现已足够、发现数达到上限或主机返回质询页时跳过，不计入模式统计
+                if context.stop_reason().is_some() {
+                    context.skipped_by_limit.fetch_add(1, Ordering::Relaxed);
+                    progress_bar.inc(1);
+                    return;
+                }
+                
+                // 目标移入慢速队列后，尚未请求的候选留到慢速队列
+                let Some(candidate) = context.defer_if_slow(candidate) else {
+                    progress_bar.inc(1);
+                    return;
+                };
+                context.attempted.fetch_add(1, Ordering::Relaxed);
+                
+                // 只在请求时拼接完整URL
+                let url = candidate.url();
+                
+                // 同一URL已在本次扫描中检查过(如重复的目标)时复用结果，不计入延迟和爬坡统计
+                let mut pauses = 0;
+                // 指定了请求方法的候选不复用默认探测的缓存结果
+                let cached = match candidate.method {
+                    Some(_) => None,
+                    None => client.cached_check(&url, verify_content),
+                };
+                let url_check = match cached {
+                    Some(check) => Ok(Ok(check)),
+                    None => loop {
+                        // 主机要求等待(Retry-After)时暂停该主机的请求，暂停结束后重新检查同一URL；
+                        // 暂停期间归还并发许可，暂停、连接名额和全局限速的等待不计入单个URL的超时
+                        if client.host_pause_remaining(&url).is_some() {
+                            drop(permit.take());
+                            client.wait_for_host(&url).await;
+                            match semaphore.clone().acquire_owned().await {
+                                Ok(reacquired) => permit.replace(reacquired),
+                                Err(_) => {
+                                    progress_bar.inc(1);
+                                    return;
+                                }
+                            };
+                        }
+                        let slot = client.acquire_slot().await;
+                    
+                        // 添加整体超时保护 - 使用较小的超时值，确保不会单个请求卡住太久，重试另计
+                        let probe_start = Instant::now();
+                        let url_check = tokio::time::timeout(
+                            probe_timeout,
+                            client.probe_url(&url, candidate.method.as_ref(), verify_content, slot)
+                        ).await;
+                        client.record_ramp_outcome(!matches!(url_check, Ok(Ok(UrlCheck { retry_after: None, .. }))));
+                        if matches!(url_check, Ok(Ok(UrlCheck { retry_after: Some(_), .. }))) && pauses < MAX_HOST_PAUSES {
+                            pauses += 1;
+                            continue;
+                        }
+                    
+                        // 请求出错不代表主机慢，只记录有响应或超时的请求
+                        if !matches!(url_check, Ok(Err(_))) {
+                            context.record_latency(probe_start.elapsed());
+                        }
+                        break url_check;
+                    },
+                };
+                
+                // 分阶段输出记录验证前的全部正面信号，包括未通过验证的
+                if let (Some(stage), Ok(Ok(check))) = (&context.stage, &url_check) {
+                    if let Some(signal) = &check.signal {
+                        let note = check.size_excluded.as_deref()
+                            .or_else(|| check.result.as_ref().and_then(|result| result.verify_note.as_deref()));
+                        if let Err(e) = stage.record_candidate(&context.target, &url, signal, check.result.is_some(), note).at_path(ErrorPhase::Output, stage.dir()) {
+                            context.fail(e.annotate(None, None, Some(&url)));
+                        }
+                    }
+                }
                 
                 match url_check {
                     Ok(check_result) => match check_result {
-                        Ok(Some(result)) => {
+                        // 2xx响应的内容类型未通过过滤时不算发现；403等响应的内容类型属于错误页，不参与过滤
+                        Ok(UrlCheck { result: Some(result), response_size, .. })
+                            if (200..300).contains(&result.status_code)
+                                && !context.content_type_filter.allows(result.content_type.as_deref()) => {
+                            debug!("内容类型 {} 未通过过滤: {}", result.content_type.as_deref().unwrap_or("-"), url);
+                            context.content_type_filtered.fetch_add(1, Ordering::Relaxed);
+                            Self::update_pattern_success_rate(&success_rates, &url, false);
+                            Self::record_pattern_effect(&pattern_effects, &url, false, response_size);
+                            context.checked(url, UrlOutcome::NotFound);
+                        },
+                        Ok(UrlCheck { result: Some(mut result), .. }) => {
+                            if let Some(attempt) = &mut result.attempt {
+                                attempt.pauses = pauses;
+                            }
+                            // 脚本否决的发现不报告，确认的发现标记为已验证
+                            if let Some(script) = &context.script {
+                                match script.review(&result) {
+                                    Ok(Verdict::Keep) => {}
+                                    Ok(Verdict::Confirm(note)) => {
+                                        result.verified = true;
+                                        result.verify_note = Some(note.unwrap_or_else(|| "脚本确认".to_string()));
+                                    }
+                                    Ok(Verdict::Veto(note)) => {
+                                        debug!("脚本否决发现: {} ({})", url, note.as_deref().unwrap_or("-"));
+                                        context.vetoed.fetch_add(1, Ordering::Relaxed);
+                                        Self::update_pattern_success_rate(&success_rates, &url, false);
+                                        Self::record_pattern_effect(&pattern_effects, &url, false, 0);
+                                        context.checked(url, UrlOutcome::NotFound);
+                                        return;
+                                    }
+                                    Err(e) => context.fail(e.annotate(Some(ErrorPhase::Script), None, Some(&url))),
+                                }
+                            }
+                            
+                            // 命中的路径触发依赖它的条件模式
+                            if (200..300).contains(&result.status_code) {
+                                context.follow_up(&candidate.base, &candidate.path);
+                            }
+                            // 元数据文件列出的文件名生成该主机的备份候选
+                            if let Some(listing) = &result.listing {
+                                context.list_files(&candidate.base, &candidate.path, listing);
+                            }
                             // 更新模式成功率
-                            self_ref.update_pattern_success_rate(&url, true);
+                            Self::update_pattern_success_rate(&success_rates, &url, true);
+                            Self::record_pattern_effect(&pattern_effects, &url, true, 0);
+                            
+                            // 基线中的已知发现不输出、不保存、不发布，也不占用发现上限
+                            if context.baseline.as_ref().is_some_and(|baseline| baseline.is_known(&result)) {
+                                debug!("基线中的已知发现: {}", url);
+                                context.known.lock().unwrap_or_else(|e| e.into_inner()).push(url.clone());
+                                context.checked(url, UrlOutcome::Found);
+                                return;
+                            }
+                            
+                            // 达到发现上限后丢弃，不输出、不保存、不发布，避免泛解析主机产生海量结果
+                            if !context.accept_finding() {
+                                context.dropped.fetch_add(1, Ordering::Relaxed);
+                                debug!("达到发现上限，丢弃: {}", url);
+                                return;
+                            }
+                            result.ensure_id();
+                            result.target = Some(context.target.clone());
+                            result.original_target = context.original_target.clone();
                             
                             // 根据不同状态码提供不同提示
                             let discovery_type = match result.status_code {
+                                _ if result.suspicious.is_some() => format!("☣️ 可疑响应 [{}]", result.status_code),
                                 200 => {
                                     // 获取文件大小的可读格式
                                     let size_info = if let Some(size) = result.content_length {
@@ -384,7 +1916,10 @@ impl Scanner {
                                         String::from("(未知大小)")
                                     };
                                     
-                                    format!("✅ 确认备份文件 [200] {}", size_info)
+                                    match &result.bypass {
+                                        Some(technique) => format!("🔓 绕过403确认备份文件 [200] {} (技巧: {})", size_info, technique),
+                                        None => format!("✅ 确认备份文件 [200] {}", size_info),
+                                    }
                                 },
                                 403 => "🔒 受限备份文件 [403]".to_string(),
                                 301 | 302 | 307 | 308 => "🔄 重定向备份文件".to_string(),
@@ -392,72 +1927,146 @@ impl Scanner {
                             };
                             
                             // 确保显示发现的备份文件URL
-                            println!("发现: {} - {}", url, discovery_type);
+                            println!("发现: {} - {}", display_url(&url), discovery_type);
+                            if let Some(reason) = &result.suspicious {
+                                println!("  {}", reason);
+                            }
+                            if let (Some(_), Some(note)) = (&result.method, &result.verify_note) {
+                                println!("  {}", note);
+                            }
+                            if result.content_type_mismatch {
+                                println!("  内容类型与扩展名不符: {}", result.content_type.as_deref().unwrap_or("-"));
+                            }
+                            if !result.indicators.is_empty() {
+                                println!("  压缩包内高价值文件: {}", result.indicators.join(", "));
+                            }
+                            if let Some(summary) = &result.sql_summary {
+                                println!("  SQL转储: {}", summary.describe());
+                            }
+                            if let Some(git) = &result.git {
+                                println!("  git仓库: {}", git.describe());
+                            }
+                            if let Some(listing) = &result.listing {
+                                println!("  文件列表: {}", listing.describe());
+                            }
+                            if let Some(summary) = &result.api_summary {
+                                println!("  API定义: {}", summary);
+                            }
                             
-                            // 将结果立即保存到临时JSON文件
-                            if let Some(output_file) = &self_ref.config.output_file {
-                                let temp_file = format!("{}.part", output_file.display());
-                                if let Err(e) = self_ref.save_result_to_json(&result, &temp_file) {
-                                    debug!("保存结果到临时文件失败: {:?}", e);
-                                } else {
-                                    debug!("成功保存发现的备份文件到: {}", temp_file);
-                                    // 复制到最终结果文件
-                                    let _ = std::fs::copy(&temp_file, output_file);
+                            // 立即追加到NDJSON输出文件
+                            if let Some(ndjson) = &context.ndjson {
+                                if let Err(e) = ndjson.append(&result).at_path(ErrorPhase::Output, ndjson.path()) {
+                                    context.fail(e.annotate(None, None, Some(&url)));
                                 }
                             }
                             
-                            // 添加到结果集
-                            let mut results_guard = results.lock().unwrap();
-                            results_guard.push(result.clone());
+                            // 实时发布到消息总线，失败时记录到目标报告
+                            if let Some(sink) = &context.sink {
+                                if let Err(e) = sink.publish(&result).await {
+                                    context.fail(e.annotate(Some(ErrorPhase::Publish), None, Some(&url)));
+                                }
+                            }
+                            if let Some(elastic) = &context.elastic {
+                                if let Err(e) = elastic.index(&result).await {
+                                    context.fail(e.annotate(Some(ErrorPhase::Publish), None, Some(&url)));
+                                }
+                            }
+                            // 定时扫描时历史中已有的发现不再通知
+                            let notified = context.notified.as_ref().is_some_and(|ids| ids.contains(&result.id));
+                            if let Some(notifier) = context.notifier.as_ref().filter(|_| !notified) {
+                                for (channel, e) in notifier.notify(&result).await {
+                                    debug!("通知渠道 {} 发送失败: {}", channel, e);
+                                    context.fail(e.annotate(Some(ErrorPhase::Publish), None, Some(&url)));
+                                }
+                            }
                             
-                            // 同时添加到部分结果中，以便在超时时能够获取
-                            if let Ok(mut partial_results) = self_ref.partial_results.lock() {
-                                partial_results.push(result);
+                            // 提交到结果通道，接收端已关闭时丢弃
+                            context.found.fetch_add(1, Ordering::Relaxed);
+                            if result.status_code == 200 && result.suspicious.is_none() {
+                                context.confirmed.fetch_add(1, Ordering::Relaxed);
+                            }
+                            if let Some(checkpoint) = &context.checkpoint {
+                                checkpoint.record_result(&result);
                             }
+                            context.checked(url.clone(), UrlOutcome::Found);
+                            context.emit(ScanEvent::FindingConfirmed { target: context.target.clone(), result: Box::new(result.clone()) });
+                            lock(&context.partial).push(result.clone());
+                            if context.outcomes.send(TaskOutcome::Found(Box::new(result))).is_err() {
+                                debug!("结果通道已关闭，丢弃发现: {}", url);
+                            }
+                        },
+                        // 质询页与模式无关，不计入模式统计，也不记为已检查，断点续扫时重新请求
+                        Ok(UrlCheck { challenge: Some(provider), .. }) => {
+                            context.mark_challenge(provider);
+                            context.emit(ScanEvent::UrlChecked { target: context.target.clone(), url, outcome: UrlOutcome::NotFound });
                         },
-                        Ok(None) => {
+                        Ok(UrlCheck { result: None, response_size, size_excluded, .. }) => {
+                            if let Some(reason) = size_excluded {
+                                context.exclude_by_size(&url, &reason);
+                            }
                             // 更新模式失败率
-                            self_ref.update_pattern_success_rate(&url, false);
+                            Self::update_pattern_success_rate(&success_rates, &url, false);
+                            Self::record_pattern_effect(&pattern_effects, &url, false, response_size);
+                            context.checked(url, UrlOutcome::NotFound);
                         },
                         Err(e) => {
                             // 错误也计入失败率
-                            self_ref.update_pattern_success_rate(&url, false);
-                            debug!("请求错误: {:?}", e);
+                            Self::update_pattern_success_rate(&success_rates, &url, false);
+                            Self::record_pattern_effect(&pattern_effects, &url, false, 0);
+                            context.request_errors.fetch_add(1, Ordering::Relaxed);
+                            debug!("请求错误: {}", e);
+                            context.first_request_error.lock().unwrap_or_else(|e| e.into_inner()).get_or_insert(e);
+                            context.checked(url, UrlOutcome::Error);
                         }
                     },
                     Err(_) => {
                         // 整体超时，记录失败
-                        self_ref.update_pattern_success_rate(&url, false);
+                        Self::update_pattern_success_rate(&success_rates, &url, false);
+                        Self::record_pattern_effect(&pattern_effects, &url, false, 0);
+                        context.timeouts.fetch_add(1, Ordering::Relaxed);
                         debug!("请求超时: {}", url);
+                        context.checked(url, UrlOutcome::Timeout);
                     }
                 }
                 
                 progress_bar.inc(1);
             });
-            
-            tasks.push(task);
         }
         
-        // 设置批次超时 - 避免批量请求卡住
-        // 最多给每个URL分配3秒，总时间不超过30秒
-        let batch_timeout_secs = std::cmp::min(
-            urls_count * 3, // 每个URL最多3秒
-            30              // 批次最多30秒
-        );
-        
-        let batch_timeout = Duration::from_secs(batch_timeout_secs as u64);
+        // 等待剩余任务，超时后取消
+        while !tasks.is_empty() {
+            match deadline.run(tasks.join_next()).await {
+                Some(Some(joined)) => Self::record_join(&context, joined),
+                Some(None) => break,
+                None => {
+                    timed_out = true;
+                    break;
+                }
+            }
+        }
+        let cancelled = unstarted + tasks.len();
+        tasks.shutdown().await;
+        progress_bar.inc(cancelled as u64);
         
-        match tokio::time::timeout(batch_timeout, future::join_all(tasks)).await {
-            Ok(_) => {
-                // 正常完成
-                progress_bar.finish_with_message("批次扫描完成");
-                true
-            },
-            Err(_) => {
-                // 超时，但继续处理部分结果
-                progress_bar.finish_with_message("批次扫描部分完成（超时）");
-                println!("警告: 批次扫描超时，部分URL未完成检查");
-                false
+        if timed_out {
+            // 已开始的任务计入请求数，覆盖情况中只把未开始的候选记为跳过
+            context.cancelled.fetch_add(unstarted, Ordering::Relaxed);
+            progress_bar.finish_with_message("批次扫描部分完成（超时）");
+            println!("警告: 批次扫描超时，取消 {} 个未完成的URL", cancelled);
+            context.fail(BackerError::Scan(format!("批次扫描超时 ({}秒)，取消 {} 个未完成的URL (其中 {} 个未开始)", batch_timeout.as_secs(), cancelled, unstarted))
+                .annotate(Some(ErrorPhase::Probe), None, None));
+            false
+        } else {
+            progress_bar.finish_with_message("批次扫描完成");
+            true
+        }
+    }
+    
+    /// 任务panic时记录到目标报告，不影响其他任务的结果
+    fn record_join(context: &BatchContext, joined: std::result::Result<(), JoinError>) {
+        if let Err(error) = joined {
+            if !error.is_cancelled() {
+                context.fail(BackerError::Scan(format!("扫描任务异常终止: {}", error)).annotate(Some(ErrorPhase::Probe), None, None));
             }
         }
     }
@@ -469,8 +2078,29 @@ impl Clone for Scanner {
             config: self.config.clone(),
             client: self.client.clone(),
             pattern_success_rates: self.pattern_success_rates.clone(),
+            pattern_effects: self.pattern_effects.clone(),
+            total_findings: self.total_findings.clone(),
+            ignored_urls: self.ignored_urls.clone(),
+            scope_exclusions: self.scope_exclusions.clone(),
+            favicon_db: self.favicon_db.clone(),
+            seclists: self.seclists.clone(),
+            pattern_db: self.pattern_db.clone(),
             current_threads: self.current_threads.clone(),
-            partial_results: self.partial_results.clone(),
+            // 各副本分别记录自己扫描的部分结果
+            partial_results: Arc::new(Mutex::new(self.get_partial_results().unwrap_or_default())),
+            target_reports: self.target_reports.clone(),
+            events: self.events.clone(),
+            sink: self.sink.clone(),
+            elastic: self.elastic.clone(),
+            notifier: self.notifier.clone(),
+            notified: self.notified.clone(),
+            shard: self.shard.clone(),
+            ndjson: self.ndjson.clone(),
+            stage: self.stage.clone(),
+            checkpoint: self.checkpoint.clone(),
+            content_type_filter: self.content_type_filter.clone(),
+            baseline: self.baseline.clone(),
+            script: self.script.clone(),
         }
     }
 } 
\ No newline at end of file
@@ -0,0 +1,170 @@
+use crate::{BackerError, Result, ScanResult};
+#[cfg(feature = "lua")]
+use crate::{ErrorPhase, ResultExt};
+use std::path::{Path, PathBuf};
+#[cfg(feature = "lua")]
+use std::sync::Mutex;
+#[cfg(feature = "lua")]
+use std::time::{Duration, Instant};
+
+/// 单次调用脚本函数的最长执行时间，超过后中止，防止脚本死循环卡住扫描
+#[cfg(feature = "lua")]
+const CALL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 脚本可使用的最大内存
+#[cfg(feature = "lua")]
+const MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+
+/// 脚本可以定义的全局函数
+const HOOKS: &[&str] = &["candidates", "review"];
+
+/// 脚本对发现的审核结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    /// 保持原样
+    Keep,
+    /// 确认为真实发现，附带说明
+    Confirm(Option<String>),
+    /// 否决，不报告该发现
+    Veto(Option<String>),
+}
+
+/// 用户提供的Lua脚本，在扫描时为目标生成额外候选并审核发现
+///
+/// 脚本可以定义以下全局函数，都是可选的：
+///
+/// - `candidates(target)`：`target` 含 `url`、`host` 和识别出的 `technologies`，
+///   返回路径数组，作为根目录候选优先请求
+/// - `review(finding)`：`finding` 为发现的各字段(同JSON输出)，返回 `false` 否决、`true` 确认、
+///   `nil` 保持原样，第二个返回值为说明
+///
+/// 需要通过 `lua` 特性启用
+pub struct ScriptHooks {
+    path: PathBuf,
+    /// 脚本定义的函数
+    hooks: Vec<&'static str>,
+    #[cfg(feature = "lua")]
+    lua: Mutex<mlua::Lua>,
+    /// 当前调用的开始时间，由指令计数钩子检查是否超时
+    #[cfg(feature = "lua")]
+    started: std::sync::Arc<Mutex<Instant>>,
+}
+
+impl ScriptHooks {
+    /// 加载并执行脚本文件，记录其中定义的函数
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        #[cfg(feature = "lua")]
+        {
+            let source = std::fs::read_to_string(path).at_path(ErrorPhase::Setup, path)?;
+            let lua = mlua::Lua::new();
+            let started = std::sync::Arc::new(Mutex::new(Instant::now()));
+            let script_error = |e: mlua::Error| BackerError::Config(format!("脚本 {} 加载失败: {}", path.display(), e));
+            lua.set_memory_limit(MEMORY_LIMIT).map_err(script_error)?;
+            let clock = started.clone();
+            lua.set_hook(mlua::HookTriggers::new().every_nth_instruction(10_000), move |_, _| {
+                let started = *clock.lock().unwrap_or_else(|e| e.into_inner());
+                if started.elapsed() > CALL_TIMEOUT {
+                    return Err(mlua::Error::RuntimeError(format!("执行超过 {} 秒", CALL_TIMEOUT.as_secs())));
+                }
+                Ok(())
+            });
+            lua.load(&source).set_name(path.display().to_string()).exec().map_err(script_error)?;
+            let globals = lua.globals();
+            let hooks = HOOKS.iter()
+                .copied()
+                .filter(|name| matches!(globals.get::<_, mlua::Value>(*name), Ok(mlua::Value::Function(_))))
+                .collect();
+            drop(globals);
+            Ok(Self { path: path.to_path_buf(), hooks, lua: Mutex::new(lua), started })
+        }
+        #[cfg(not(feature = "lua"))]
+        Err(BackerError::Config(format!(
+            "无法加载脚本 {}: Lua脚本未编译，请使用 --features lua 重新编译", path.display()
+        )))
+    }
+
+    /// 脚本文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 说明脚本定义了哪些函数，如 "candidates, review"
+    pub fn describe(&self) -> String {
+        if self.hooks.is_empty() {
+            format!("未定义 {} 函数，脚本不起作用", HOOKS.join("/"))
+        } else {
+            self.hooks.join(", ")
+        }
+    }
+
+    /// 是否定义了审核发现的 `review` 函数
+    pub fn reviews(&self) -> bool {
+        self.hooks.contains(&"review")
+    }
+
+    /// 调用 `candidates` 生成目标的额外候选路径，未定义时返回空列表
+    #[cfg_attr(not(feature = "lua"), allow(unused_variables))]
+    pub fn candidates(&self, target: &str, technologies: &[String]) -> Result<Vec<String>> {
+        if !self.hooks.contains(&"candidates") {
+            return Ok(Vec::new());
+        }
+        #[cfg(feature = "lua")]
+        {
+            let host = url::Url::parse(target).ok()
+                .and_then(|url| url.host_str().map(String::from))
+                .unwrap_or_default();
+            let paths = self.call("candidates", |lua, function| {
+                let table = lua.create_table()?;
+                table.set("url", target)?;
+                table.set("host", host)?;
+                table.set("technologies", technologies.to_vec())?;
+                function.call::<_, Option<Vec<String>>>(table).map(Option::unwrap_or_default)
+            })?;
+            Ok(paths.into_iter()
+                .map(|path| path.trim().trim_start_matches('/').to_string())
+                .filter(|path| !path.is_empty())
+                .collect())
+        }
+        #[cfg(not(feature = "lua"))]
+        Ok(Vec::new())
+    }
+
+    /// 调用 `review` 审核发现，未定义时保持原样
+    #[cfg_attr(not(feature = "lua"), allow(unused_variables))]
+    pub fn review(&self, result: &ScanResult) -> Result<Verdict> {
+        if !self.reviews() {
+            return Ok(Verdict::Keep);
+        }
+        #[cfg(feature = "lua")]
+        {
+            use mlua::LuaSerdeExt;
+            self.call("review", |lua, function| {
+                let finding = lua.to_value_with(result, mlua::SerializeOptions::new().serialize_none_to_null(false))?;
+                let (verdict, note) = function.call::<_, (Option<bool>, Option<String>)>(finding)?;
+                Ok(match verdict {
+                    Some(true) => Verdict::Confirm(note),
+                    Some(false) => Verdict::Veto(note),
+                    None => Verdict::Keep,
+                })
+            })
+        }
+        #[cfg(not(feature = "lua"))]
+        Ok(Verdict::Keep)
+    }
+
+    /// 调用脚本中的全局函数，`invoke` 构造参数、调用并转换返回值；同一时间只执行一个调用
+    #[cfg(feature = "lua")]
+    fn call<T>(
+        &self,
+        name: &str,
+        invoke: impl for<'lua> FnOnce(&'lua mlua::Lua, mlua::Function<'lua>) -> mlua::Result<T>,
+    ) -> Result<T> {
+        let lua = self.lua.lock().unwrap_or_else(|e| e.into_inner());
+        *self.started.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+        let result = lua.globals().get::<_, mlua::Function>(name)
+            .and_then(|function| invoke(&lua, function));
+        result.map_err(|e| BackerError::Other(format!("{} 函数出错: {}", name, e)))
+            .at_path(ErrorPhase::Script, &self.path)
+    }
+}
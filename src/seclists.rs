@@ -0,0 +1,160 @@
+use crate::category::Category;
+use crate::http::is_backup_file_extension;
+use crate::tech::Technology;
+use crate::utils::open_wordlist;
+use crate::{BackerError, Result};
+use log::debug;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+
+/// 导入的SecLists字典，按分类和技术栈归并
+#[derive(Debug, Clone, Default)]
+pub struct SeclistsImport {
+    /// 分类 -> 路径
+    pub categories: HashMap<Category, Vec<String>>,
+    /// 技术栈名称 -> 路径，只在识别出对应技术栈时使用
+    pub technologies: HashMap<String, Vec<String>>,
+    /// 导入的文件及其归类
+    pub files: Vec<(PathBuf, String)>,
+}
+
+impl SeclistsImport {
+    /// 各分类和技术栈的路径数量，用于输出导入摘要
+    pub fn summary(&self) -> String {
+        let mut parts: Vec<String> = self.categories.iter()
+            .map(|(category, paths)| format!("{} {} 条", category.name(), paths.len()))
+            .chain(self.technologies.iter().map(|(name, paths)| format!("{} {} 条", name, paths.len())))
+            .collect();
+        parts.sort();
+        parts.join(", ")
+    }
+}
+
+/// 字典文件的归类
+#[derive(Debug, PartialEq)]
+enum Mapping {
+    Category(Category),
+    Technology(String),
+}
+
+/// 从SecLists目录导入相关字典
+///
+/// 目录下存在 `Discovery/Web-Content` 时只扫描该子目录，否则扫描整个目录。
+/// 按文件名归类：备份类、敏感文件类、版本控制类、API类，以及 `CMS` 目录下
+/// 对应已知技术栈的字典，其余文件忽略。同一分类内的路径去重
+pub fn import_seclists<P: AsRef<Path>>(dir: P) -> Result<SeclistsImport> {
+    let dir = dir.as_ref();
+    if !dir.is_dir() {
+        return Err(BackerError::Config(format!("SecLists目录不存在: {}", dir.display())));
+    }
+    let web_content = dir.join("Discovery").join("Web-Content");
+    let root = if web_content.is_dir() { web_content } else { dir.to_path_buf() };
+
+    let mut files = Vec::new();
+    collect_files(&root, &mut files)?;
+    files.sort();
+
+    let mut import = SeclistsImport::default();
+    let mut seen: HashMap<String, HashSet<String>> = HashMap::new();
+    for file in files {
+        let relative = file.strip_prefix(&root).unwrap_or(&file).to_string_lossy().to_lowercase();
+        let Some(mapping) = classify(&relative) else { continue };
+        let (key, target) = match mapping {
+            Mapping::Category(category) => (category.name().to_string(), import.categories.entry(category).or_default()),
+            Mapping::Technology(name) => (name.clone(), import.technologies.entry(name).or_default()),
+        };
+
+        let seen = seen.entry(key.clone()).or_default();
+        let mut added = 0;
+        for line in open_wordlist(&file)?.lines() {
+            let line = line?;
+            if let Some(path) = normalize(&line) {
+                if seen.insert(path.clone()) {
+                    target.push(path);
+                    added += 1;
+                }
+            }
+        }
+        debug!("导入SecLists字典 {} -> {} ({} 条)", file.display(), key, added);
+        import.files.push((file, key));
+    }
+
+    Ok(import)
+}
+
+/// 递归收集字典文件
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "txt" || ext == "gz" || ext == "zst") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// 按相对路径归类字典文件
+fn classify(relative: &str) -> Option<Mapping> {
+    let name = relative.rsplit(['/', '\\']).next().unwrap_or(relative);
+
+    if relative.starts_with("cms/") || relative.contains("/cms/") {
+        let stem = name.split(['.', '-', '_']).next().unwrap_or(name);
+        let technology = match stem {
+            "wp" => Some(Technology::WordPress),
+            _ => Technology::parse(stem),
+        }?;
+        return Some(Mapping::Technology(technology.name().to_string()));
+    }
+
+    // 按整词匹配目录名和文件名，digital.txt、github-actions.txt 之类的名称不会被误判
+    let tokens: HashSet<&str> = relative.split(['/', '\\', '.', '-', '_', ' ']).collect();
+    let has = |words: &[&str]| words.iter().any(|word| tokens.contains(word));
+    if has(&["backup", "backups", "bak"]) {
+        return Some(Mapping::Category(Category::Archive));
+    }
+    if has(&["versioning", "metafiles", "svn", "git"]) {
+        return Some(Mapping::Category(Category::Vcs));
+    }
+    if has(&["swagger", "graphql", "openapi"]) || relative.starts_with("api/") {
+        return Some(Mapping::Category(Category::Api));
+    }
+    if has(&["quickhits", "sensitive", "dotfiles", "config"]) {
+        return Some(Mapping::Category(Category::Config));
+    }
+    None
+}
+
+/// 规范化字典行，去掉前导斜杠，跳过注释、空行和带模糊测试占位符的行
+///
+/// 扫描器不会报告的路径(如普通页面)也在导入时丢弃，避免无效请求
+fn normalize(line: &str) -> Option<String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') || line.contains("FUZZ") || line.contains(' ') {
+        return None;
+    }
+    let path = line.trim_start_matches('/');
+    if path.is_empty() || !is_backup_file_extension(&format!("/{}", path)) {
+        None
+    } else {
+        Some(path.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_matches_whole_words() {
+        assert_eq!(classify("discovery/web-content/versioning_metafiles.txt"), Some(Mapping::Category(Category::Vcs)));
+        assert_eq!(classify("discovery/web-content/common-db-backups.txt"), Some(Mapping::Category(Category::Archive)));
+        assert_eq!(classify("discovery/web-content/git/refs.txt"), Some(Mapping::Category(Category::Vcs)));
+        assert_eq!(classify("discovery/web-content/digital.txt"), None);
+        assert_eq!(classify("discovery/web-content/github-actions.txt"), None);
+        assert_eq!(classify("discovery/web-content/kubernetes-configmap.txt"), None);
+    }
+}
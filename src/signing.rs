@@ -0,0 +1,150 @@
+use crate::{BackerError, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::Local;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// 签名文件的扩展名，追加在报告文件名之后，如 `results.json.sig`
+pub const SIGNATURE_EXTENSION: &str = "sig";
+
+/// 签名算法名称
+const ALGORITHM: &str = "ed25519";
+
+/// 签名内容的前缀，防止同一密钥的签名被挪作他用
+const SIGNATURE_CONTEXT: &str = "backer-report-v1";
+
+/// 报告签名文件内容，与报告放在一起作为证据提交
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportSignature {
+    /// 签名算法，固定为 `ed25519`
+    pub algorithm: String,
+    /// 报告文件名
+    pub file: String,
+    /// 报告内容的SHA-256校验和
+    pub sha256: String,
+    /// 签名时间(RFC 3339)
+    pub signed_at: String,
+    /// 签名公钥(base64)
+    pub public_key: String,
+    /// 对 校验和和签名时间 的签名(base64)
+    pub signature: String,
+}
+
+impl ReportSignature {
+    /// 被签名的内容
+    fn message(&self) -> String {
+        format!("{}:{}:{}", SIGNATURE_CONTEXT, self.sha256, self.signed_at)
+    }
+}
+
+/// 用ed25519私钥为最终报告签名
+#[derive(Debug, Clone)]
+pub struct ReportSigner {
+    key: SigningKey,
+}
+
+impl ReportSigner {
+    /// 从密钥文件加载私钥，文件内容为32字节私钥的base64编码
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)?;
+        let bytes: [u8; 32] = BASE64.decode(content.trim()).ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| BackerError::Config(format!("签名私钥格式无效(应为32字节的base64编码): {}", path.display())))?;
+        Ok(Self { key: SigningKey::from_bytes(&bytes) })
+    }
+
+    /// 生成新私钥并写入文件(只有当前用户可读写)，文件已存在时报错，避免覆盖正在使用的密钥
+    pub fn generate<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut bytes = [0u8; 32];
+        rand::rngs::OsRng.fill_bytes(&mut bytes);
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::AlreadyExists => BackerError::Config(format!("密钥文件已存在: {}", path.display())),
+            _ => e.into(),
+        })?;
+        file.write_all(format!("{}\n", BASE64.encode(bytes)).as_bytes())?;
+        Ok(Self { key: SigningKey::from_bytes(&bytes) })
+    }
+
+    /// 公钥(base64)，交给需要验证报告的一方
+    pub fn public_key(&self) -> String {
+        BASE64.encode(self.key.verifying_key().as_bytes())
+    }
+
+    /// 为报告签名，签名文件写在报告旁边，返回签名文件路径
+    pub fn sign_file<P: AsRef<Path>>(&self, report: P) -> Result<PathBuf> {
+        let report = report.as_ref();
+        let mut signature = ReportSignature {
+            algorithm: ALGORITHM.to_string(),
+            file: file_name(report),
+            sha256: sha256_file(report)?,
+            signed_at: Local::now().to_rfc3339(),
+            public_key: self.public_key(),
+            signature: String::new(),
+        };
+        signature.signature = BASE64.encode(self.key.sign(signature.message().as_bytes()).to_bytes());
+        let path = signature_path(report);
+        fs::write(&path, serde_json::to_string_pretty(&signature)?)?;
+        Ok(path)
+    }
+}
+
+/// 报告对应的签名文件路径
+pub fn signature_path(report: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.{}", report.display(), SIGNATURE_EXTENSION))
+}
+
+/// 验证报告未被修改，返回签名信息
+///
+/// 签名文件中的公钥只能证明签名与内容一致；指定 `public_key` 时还要求签名来自该公钥，
+/// 提交证据时应使用事先交付的公钥验证
+pub fn verify_report(report: &Path, signature_file: &Path, public_key: Option<&str>) -> Result<ReportSignature> {
+    let signature: ReportSignature = serde_json::from_str(&fs::read_to_string(signature_file)?)
+        .map_err(|e| BackerError::Config(format!("无法解析签名文件 {}: {}", signature_file.display(), e)))?;
+    if signature.algorithm != ALGORITHM {
+        return Err(BackerError::Config(format!("不支持的签名算法: {}", signature.algorithm)));
+    }
+    if let Some(expected) = public_key {
+        if expected.trim() != signature.public_key {
+            return Err(BackerError::Other("签名公钥与指定的公钥不符".to_string()));
+        }
+    }
+    let invalid = |what: &str| BackerError::Config(format!("签名文件中的{}无效: {}", what, signature_file.display()));
+    let key_bytes: [u8; 32] = BASE64.decode(&signature.public_key).ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| invalid("公钥"))?;
+    let key = VerifyingKey::from_bytes(&key_bytes).map_err(|_| invalid("公钥"))?;
+    let signature_bytes: [u8; 64] = BASE64.decode(&signature.signature).ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| invalid("签名"))?;
+    key.verify(signature.message().as_bytes(), &Signature::from_bytes(&signature_bytes))
+        .map_err(|_| BackerError::Other("签名无效，签名文件被修改或不是该公钥签发".to_string()))?;
+    let actual = sha256_file(report)?;
+    if actual != signature.sha256 {
+        return Err(BackerError::Other(format!("报告已被修改: 校验和 {}，签名时为 {}", actual, signature.sha256)));
+    }
+    Ok(signature)
+}
+
+fn sha256_file(path: &Path) -> Result<String> {
+    let digest = Sha256::digest(fs::read(path)?);
+    Ok(digest.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+}
@@ -0,0 +1,54 @@
+/// 默认相似阈值：两个simhash的汉明距离不超过该值即视为内容近似
+pub const DEFAULT_SIMILARITY_THRESHOLD: u32 = 3;
+
+/// 计算内容的64位simhash指纹
+///
+/// 以字母数字组成的词作为特征，适合比较模板化的错误页面：
+/// 即使页面中嵌入了请求路径、时间戳等可变内容，指纹也只会有少量位不同。
+pub fn simhash(content: &[u8]) -> u64 {
+    let text = String::from_utf8_lossy(content).to_lowercase();
+    let mut weights = [0i32; 64];
+    let mut token_count = 0;
+
+    for token in text.split(|c: char| !c.is_alphanumeric()).filter(|t| !t.is_empty()) {
+        token_count += 1;
+        let hash = fnv1a(token.as_bytes());
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if (hash >> bit) & 1 == 1 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    }
+
+    // 没有可用的词(如纯二进制内容)时退化为整体哈希
+    if token_count == 0 {
+        return fnv1a(content);
+    }
+
+    weights.iter()
+        .enumerate()
+        .filter(|(_, weight)| **weight > 0)
+        .fold(0u64, |acc, (bit, _)| acc | (1 << bit))
+}
+
+/// 计算两个指纹之间的汉明距离
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// 判断两个指纹是否近似
+pub fn is_similar(a: u64, b: u64, threshold: u32) -> bool {
+    hamming_distance(a, b) <= threshold
+}
+
+/// FNV-1a 64位哈希，结果跨版本稳定，可用于持久化
+pub fn fnv1a(data: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in data {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
@@ -0,0 +1,102 @@
+use crate::{BackerError, Result, ScanResult};
+#[cfg(feature = "kafka")]
+use std::collections::BTreeMap;
+use url::Url;
+
+/// NATS默认端口
+#[cfg(feature = "nats")]
+const NATS_DEFAULT_PORT: u16 = 4222;
+
+/// Kafka默认端口
+#[cfg(feature = "kafka")]
+const KAFKA_DEFAULT_PORT: u16 = 9092;
+
+/// 消息总线，每个发现以一条JSON消息发布
+///
+/// 地址格式为 `nats://host:port/subject` 或 `kafka://broker:port/topic`，
+/// 对应的客户端需要通过 `nats` / `kafka` 特性启用
+pub enum MessageSink {
+    /// 发布到NATS主题
+    #[cfg(feature = "nats")]
+    Nats {
+        client: async_nats::Client,
+        subject: String,
+    },
+    /// 发布到Kafka主题的0号分区
+    #[cfg(feature = "kafka")]
+    Kafka {
+        partition: rskafka::client::partition::PartitionClient,
+    },
+}
+
+impl MessageSink {
+    /// 连接消息总线
+    pub async fn connect(address: &str) -> Result<Self> {
+        let url = Url::parse(address)?;
+        match url.scheme() {
+            #[cfg(feature = "nats")]
+            "nats" => {
+                let (server, subject) = endpoint(&url, NATS_DEFAULT_PORT)?;
+                let client = async_nats::connect(&server).await
+                    .map_err(|e| BackerError::Other(format!("无法连接NATS服务器 {}: {}", server, e)))?;
+                Ok(MessageSink::Nats { client, subject })
+            }
+            #[cfg(feature = "kafka")]
+            "kafka" => {
+                use rskafka::client::{partition::UnknownTopicHandling, ClientBuilder};
+                let (broker, topic) = endpoint(&url, KAFKA_DEFAULT_PORT)?;
+                let client = ClientBuilder::new(vec![broker.clone()]).build().await
+                    .map_err(|e| BackerError::Other(format!("无法连接Kafka服务器 {}: {}", broker, e)))?;
+                let partition = client.partition_client(topic.clone(), 0, UnknownTopicHandling::Error).await
+                    .map_err(|e| BackerError::Other(format!("无法打开Kafka主题 {}: {}", topic, e)))?;
+                Ok(MessageSink::Kafka { partition })
+            }
+            #[allow(unreachable_patterns)]
+            scheme @ ("nats" | "kafka") => Err(BackerError::Config(format!(
+                "{} 消息总线未编译，请使用 --features {} 重新编译", scheme, scheme
+            ))),
+            scheme => Err(BackerError::Config(format!("不支持的消息总线协议: {} (可选 nats, kafka)", scheme))),
+        }
+    }
+
+    /// 发布一个发现
+    #[cfg_attr(not(any(feature = "nats", feature = "kafka")), allow(unused_variables))]
+    pub async fn publish(&self, result: &ScanResult) -> Result<()> {
+        match *self {
+            #[cfg(feature = "nats")]
+            MessageSink::Nats { ref client, ref subject } => {
+                let payload = serde_json::to_vec(result)?;
+                client.publish(subject.clone(), payload.into()).await
+                    .map_err(|e| BackerError::Other(format!("NATS发布失败: {}", e)))?;
+                client.flush().await
+                    .map_err(|e| BackerError::Other(format!("NATS发布失败: {}", e)))?;
+                Ok(())
+            }
+            #[cfg(feature = "kafka")]
+            MessageSink::Kafka { ref partition } => {
+                use rskafka::{client::partition::Compression, record::Record};
+                let record = Record {
+                    key: Some(result.url.clone().into_bytes()),
+                    value: Some(serde_json::to_vec(result)?),
+                    headers: BTreeMap::new(),
+                    timestamp: chrono::Utc::now(),
+                };
+                partition.produce(vec![record], Compression::NoCompression).await
+                    .map_err(|e| BackerError::Other(format!("Kafka发布失败: {}", e)))?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// 从地址中取出 `host:port` 和路径中的主题
+#[cfg_attr(not(any(feature = "nats", feature = "kafka")), allow(dead_code))]
+fn endpoint(url: &Url, default_port: u16) -> Result<(String, String)> {
+    let host = url.host_str()
+        .ok_or_else(|| BackerError::Config(format!("消息总线地址缺少主机: {}", url)))?;
+    let topic = url.path().trim_start_matches('/');
+    if topic.is_empty() {
+        return Err(BackerError::Config(format!("消息总线地址缺少主题: {}", url)));
+    }
+    Ok((format!("{}:{}", host, url.port().unwrap_or(default_port)), topic.to_string()))
+}
@@ -0,0 +1,163 @@
+use flate2::read::GzDecoder;
+use serde::{Serialize, Deserialize};
+use std::io::Read;
+
+/// 用于生成摘要的转储开头大小(字节)
+pub const SQL_SUMMARY_SIZE: usize = 64 * 1024;
+
+/// 摘要中最多保留的表名数量
+const MAX_TABLES: usize = 50;
+
+/// SQL转储摘要
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SqlDumpSummary {
+    /// 转储工具及版本，如 "mysqldump 10.13 (Distrib 5.7.33)"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    /// 数据库名
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+    /// 表名
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tables: Vec<String>,
+}
+
+impl SqlDumpSummary {
+    /// 单行描述，用于表格输出
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(tool) = &self.tool {
+            parts.push(format!("工具: {}", tool));
+        }
+        if let Some(database) = &self.database {
+            parts.push(format!("数据库: {}", database));
+        }
+        if !self.tables.is_empty() {
+            parts.push(format!("表({}): {}", self.tables.len(), self.tables.join(", ")));
+        }
+        parts.join("; ")
+    }
+}
+
+/// 判断URL是否为SQL转储
+pub fn is_sql_dump_url(url: &str) -> bool {
+    let url = url.to_lowercase();
+    url.ends_with(".sql") || url.ends_with(".sql.gz")
+}
+
+/// 解析转储开头部分，提取工具、数据库名和表名，无法识别时返回None
+///
+/// gzip压缩的转储只解压已下载的部分，截断导致的解压错误会被忽略
+pub fn summarize(content: &[u8]) -> Option<SqlDumpSummary> {
+    let text = if content.starts_with(&[0x1f, 0x8b]) {
+        let mut decompressed = Vec::new();
+        let _ = GzDecoder::new(content)
+            .take(SQL_SUMMARY_SIZE as u64 * 4)
+            .read_to_end(&mut decompressed);
+        String::from_utf8_lossy(&decompressed).to_string()
+    } else {
+        String::from_utf8_lossy(content).to_string()
+    };
+
+    let mut summary = SqlDumpSummary::default();
+
+    for line in text.lines() {
+        let line = line.trim();
+        let lower = line.to_lowercase();
+
+        if summary.tool.is_none() {
+            summary.tool = detect_tool(line);
+        } else if let Some(version) = line.strip_prefix("-- Dumped by pg_dump version ") {
+            summary.tool = Some(format!("pg_dump {}", version.trim()));
+        } else if let Some(version) = line.strip_prefix("-- version ") {
+            // phpMyAdmin在下一行给出版本
+            if summary.tool.as_deref() == Some("phpMyAdmin") {
+                summary.tool = Some(format!("phpMyAdmin {}", version.trim()));
+            }
+        }
+
+        if summary.database.is_none() {
+            summary.database = detect_database(line, &lower);
+        }
+
+        if let Some(table) = detect_table(line, &lower) {
+            if summary.tables.len() < MAX_TABLES && !summary.tables.contains(&table) {
+                summary.tables.push(table);
+            }
+        }
+    }
+
+    if summary == SqlDumpSummary::default() {
+        None
+    } else {
+        Some(summary)
+    }
+}
+
+/// 识别转储工具
+fn detect_tool(line: &str) -> Option<String> {
+    if let Some(rest) = line.strip_prefix("-- MySQL dump ") {
+        let version = rest.split_whitespace().next().unwrap_or("");
+        let distrib = rest.split("Distrib").nth(1).map(|d| d.trim().trim_end_matches(',').to_string());
+        return Some(match distrib {
+            Some(distrib) => format!("mysqldump {} (Distrib {})", version, distrib),
+            None => format!("mysqldump {}", version),
+        });
+    }
+    if let Some(rest) = line.strip_prefix("-- MariaDB dump ") {
+        return Some(format!("mariadb-dump {}", rest.split_whitespace().collect::<Vec<_>>().join(" ")));
+    }
+    if line.starts_with("-- PostgreSQL database dump") {
+        return Some("pg_dump".to_string());
+    }
+    if line.starts_with("-- phpMyAdmin SQL Dump") {
+        return Some("phpMyAdmin".to_string());
+    }
+    if let Some(rest) = line.strip_prefix("-- Adminer ") {
+        return Some(format!("Adminer {}", rest.trim_end_matches(" dump")));
+    }
+    None
+}
+
+/// 识别数据库名
+fn detect_database(line: &str, lower: &str) -> Option<String> {
+    // mysqldump: "-- Host: localhost    Database: wordpress"
+    if let Some(pos) = line.find("Database: ") {
+        if line.starts_with("--") {
+            return Some(unquote(line[pos + "Database: ".len()..].trim()));
+        }
+    }
+    if lower.starts_with("create database") {
+        return line.split_whitespace().last().map(|name| unquote(name.trim_end_matches(';')));
+    }
+    if lower.starts_with("use ") {
+        return line.get(4..).map(|name| unquote(name.trim().trim_end_matches(';')));
+    }
+    // pg_dump: "\connect dbname"
+    if let Some(rest) = line.strip_prefix("\\connect ") {
+        return rest.split_whitespace().next().map(unquote);
+    }
+    None
+}
+
+/// 识别表名
+fn detect_table(line: &str, lower: &str) -> Option<String> {
+    if !lower.starts_with("create table") {
+        return None;
+    }
+    let rest = line.get("create table".len()..)?.trim_start();
+    let rest = if rest.to_lowercase().starts_with("if not exists") {
+        rest.get("if not exists".len()..)?.trim_start()
+    } else {
+        rest
+    };
+    rest.split(|c: char| c.is_whitespace() || c == '(')
+        .next()
+        .filter(|name| !name.is_empty())
+        .map(unquote)
+}
+
+/// 去除标识符两侧的引号
+fn unquote(name: &str) -> String {
+    name.trim_matches(|c| c == '`' || c == '"' || c == '\'' || c == '[' || c == ']').to_string()
+}
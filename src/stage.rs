@@ -0,0 +1,91 @@
+use crate::http::ProbeSignal;
+use crate::utils::NdjsonWriter;
+use crate::{Result, ScanResult};
+use chrono::Local;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 验证之前的全部正面信号
+pub const CANDIDATES_FILE: &str = "candidates.jsonl";
+
+/// 验证、去重并评分后的发现
+pub const FINDINGS_FILE: &str = "findings.json";
+
+/// 候选文件中的一条正面信号
+#[derive(Serialize)]
+struct CandidateRecord<'a> {
+    time: String,
+    target: &'a str,
+    url: &'a str,
+    status_code: u16,
+    content_type: Option<&'a str>,
+    content_length: Option<u64>,
+    /// 信号是否成为发现；-v 时即是否通过内容验证
+    confirmed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<&'a str>,
+}
+
+/// 发现文件中的一个发现，附带评分
+#[derive(Serialize)]
+struct FindingRecord<'a> {
+    severity: &'static str,
+    kind: &'static str,
+    #[serde(flatten)]
+    result: &'a ScanResult,
+}
+
+/// 分阶段输出，`--stage-dir` 指定
+///
+/// 扫描中每个正面信号即追加到 `candidates.jsonl`，未开启验证或验证无法确认时供人工分拣；
+/// 扫描结束后写入验证、去重并按严重程度排序的 `findings.json`
+#[derive(Debug)]
+pub struct StageOutput {
+    dir: PathBuf,
+    candidates: NdjsonWriter,
+}
+
+impl StageOutput {
+    /// 创建输出目录并打开候选文件，`append` 为false时清空上次的候选
+    pub fn open(dir: &Path, append: bool) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+        let candidates = NdjsonWriter::open(&dir.join(CANDIDATES_FILE), append)?;
+        Ok(Self { dir: dir.to_path_buf(), candidates })
+    }
+
+    /// 输出目录
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// 追加一个正面信号
+    pub fn record_candidate(&self, target: &str, url: &str, signal: &ProbeSignal, confirmed: bool, note: Option<&str>) -> Result<()> {
+        self.candidates.append(&CandidateRecord {
+            time: Local::now().to_rfc3339(),
+            target,
+            url,
+            status_code: signal.status_code,
+            content_type: signal.content_type.as_deref(),
+            content_length: signal.content_length,
+            confirmed,
+            note,
+        })
+    }
+
+    /// 写入发现文件：按ID去重，严重程度从高到低排序
+    pub fn save_findings(&self, results: &[ScanResult]) -> Result<()> {
+        let mut seen = HashSet::new();
+        let mut findings: Vec<&ScanResult> = results.iter()
+            .filter(|result| seen.insert(if result.id.is_empty() { result.url.as_str() } else { result.id.as_str() }))
+            .collect();
+        findings.sort_by_key(|result| (Reverse(result.severity()), result.url.as_str()));
+        let records: Vec<FindingRecord> = findings.into_iter()
+            .map(|result| FindingRecord { severity: result.severity().name(), kind: result.kind(), result })
+            .collect();
+        fs::write(self.dir.join(FINDINGS_FILE), serde_json::to_string_pretty(&records)?)?;
+        Ok(())
+    }
+}
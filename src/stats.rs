@@ -0,0 +1,175 @@
+use crate::Result;
+use log::debug;
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+/// 单个模式的历史统计
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct PatternStat {
+    /// 命中次数
+    pub hits: usize,
+    /// 尝试次数
+    pub attempts: usize,
+    /// 人工标记为误报的次数
+    #[serde(default)]
+    pub false_positives: usize,
+    /// 人工确认为真阳性的次数
+    #[serde(default)]
+    pub true_positives: usize,
+}
+
+impl PatternStat {
+    /// 扣除误报后的命中率，人工确认的真阳性至少计为同样多次命中
+    pub fn success_rate(&self) -> f64 {
+        let attempts = self.attempts.max(self.true_positives);
+        if attempts == 0 {
+            return 0.0;
+        }
+        self.hits.saturating_sub(self.false_positives).max(self.true_positives) as f64 / attempts as f64
+    }
+}
+
+/// 单个模式在本次扫描中的效果，用于 `--pattern-report`
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct PatternEffect {
+    /// 尝试次数
+    pub attempts: usize,
+    /// 命中次数
+    pub hits: usize,
+    /// 未命中响应的总大小(字节)，按响应的 Content-Length 计算
+    pub wasted_bytes: u64,
+}
+
+impl PatternEffect {
+    /// 命中率
+    pub fn hit_rate(&self) -> f64 {
+        if self.attempts == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / self.attempts as f64
+    }
+}
+
+/// 从URL中提取统计所用的模式，例如从 http://example.com/backup.zip 提取 backup.zip，
+/// 百分号编码被解码，与模式文件中的写法一致
+pub fn pattern_key(url: &str) -> String {
+    let name = url.rsplit('/').next().unwrap_or(url);
+    percent_encoding::percent_decode_str(name).decode_utf8_lossy().into_owned()
+}
+
+/// 加载模式统计文件，文件不存在时返回空表
+pub fn load_pattern_stats<P: AsRef<Path>>(path: P) -> Result<HashMap<String, PatternStat>> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let content = fs::read_to_string(path)?;
+    let stats: HashMap<String, PatternStat> = serde_json::from_str(&content)?;
+    debug!("从 {} 加载了 {} 个模式统计", path.display(), stats.len());
+    Ok(stats)
+}
+
+/// 保存模式统计文件
+pub fn save_pattern_stats<P: AsRef<Path>>(path: P, stats: &HashMap<String, PatternStat>) -> Result<()> {
+    let json = serde_json::to_string_pretty(stats)?;
+    fs::write(path, json)?;
+    Ok(())
+}
+
+/// 保存模式效果报告，`.json` 结尾时为JSON，否则为CSV
+///
+/// 按浪费的字节数和尝试次数降序排列，便于裁剪无效的自定义字典
+pub fn save_pattern_report<P: AsRef<Path>>(path: P, effects: &HashMap<String, PatternEffect>) -> Result<()> {
+    let path = path.as_ref();
+    let mut rows: Vec<(&String, &PatternEffect)> = effects.iter().collect();
+    rows.sort_by(|(a_name, a), (b_name, b)| {
+        b.wasted_bytes.cmp(&a.wasted_bytes)
+            .then(b.attempts.cmp(&a.attempts))
+            .then(a_name.cmp(b_name))
+    });
+
+    if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")) {
+        #[derive(Serialize)]
+        struct Row<'a> {
+            pattern: &'a str,
+            #[serde(flatten)]
+            effect: &'a PatternEffect,
+            hit_rate: f64,
+        }
+        let rows: Vec<Row> = rows.iter()
+            .map(|(pattern, effect)| Row { pattern, effect, hit_rate: effect.hit_rate() })
+            .collect();
+        fs::write(path, serde_json::to_string_pretty(&rows)?)?;
+        return Ok(());
+    }
+
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(["模式", "尝试次数", "命中次数", "命中率", "未命中浪费字节"])?;
+    for (pattern, effect) in rows {
+        writer.write_record([
+            pattern.as_str(),
+            &effect.attempts.to_string(),
+            &effect.hits.to_string(),
+            &format!("{:.4}", effect.hit_rate()),
+            &effect.wasted_bytes.to_string(),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// 加载忽略列表(每行一个URL)，文件不存在时返回空集合
+pub fn load_ignore_list<P: AsRef<Path>>(path: P) -> Result<HashSet<String>> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Ok(HashSet::new());
+    }
+
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+
+    let mut ignored = HashSet::new();
+    for line in reader.lines() {
+        let line = line?;
+        let trimmed = line.trim();
+        if !trimmed.is_empty() && !trimmed.starts_with('#') {
+            ignored.insert(trimmed.to_string());
+        }
+    }
+
+    Ok(ignored)
+}
+
+/// 向忽略列表追加URL，已存在的URL不会重复写入
+pub fn append_ignore_list<P: AsRef<Path>>(path: P, urls: &[String]) -> Result<()> {
+    let existing = load_ignore_list(&path)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+
+    for url in urls {
+        if !existing.contains(url) {
+            writeln!(file, "{}", url)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn success_rate_counts_confirmed_true_positives() {
+        let stat = PatternStat { hits: 1, attempts: 10, false_positives: 1, true_positives: 0 };
+        assert_eq!(stat.success_rate(), 0.0);
+        let stat = PatternStat { true_positives: 2, ..stat };
+        assert_eq!(stat.success_rate(), 0.2);
+        // 只有标注记录、还没有扫描记录的模式
+        let stat = PatternStat { hits: 0, attempts: 0, false_positives: 0, true_positives: 1 };
+        assert_eq!(stat.success_rate(), 1.0);
+    }
+}
@@ -0,0 +1,309 @@
+use crate::patterndb::{merge_paths, PatternDb};
+use crate::Result;
+use reqwest::header::{HeaderMap, LINK, SET_COOKIE};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// favicon的最大下载大小(字节)
+pub const FAVICON_MAX_SIZE: u64 = 1024 * 1024;
+
+/// 内置的favicon哈希库，只收录常见产品未修改过的默认图标
+///
+/// 哈希值与Shodan的 `http.favicon.hash` 一致，可通过 `--favicon-db` 补充
+const FAVICON_HASHES: &[(i32, &str)] = &[
+    (81586312, "jenkins"),
+    (1278323681, "gitlab"),
+    (116323821, "spring-boot"),
+    (-297069493, "tomcat"),
+    (1165838194, "thinkphp"),
+    (-476231906, "phpmyadmin"),
+    (892542951, "zabbix"),
+    (-305179312, "confluence"),
+    (1485257654, "sonarqube"),
+    (2123863676, "grafana"),
+];
+
+/// 可识别的技术栈
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Technology {
+    WordPress,
+    Joomla,
+    Drupal,
+    ThinkPhp,
+    Laravel,
+    Magento,
+    Django,
+    SpringBoot,
+    Tomcat,
+    PhpMyAdmin,
+    Zabbix,
+}
+
+impl Technology {
+    /// 规范名称，与内置favicon哈希库中的名称一致
+    pub fn name(&self) -> &'static str {
+        match self {
+            Technology::WordPress => "wordpress",
+            Technology::Joomla => "joomla",
+            Technology::Drupal => "drupal",
+            Technology::ThinkPhp => "thinkphp",
+            Technology::Laravel => "laravel",
+            Technology::Magento => "magento",
+            Technology::Django => "django",
+            Technology::SpringBoot => "spring-boot",
+            Technology::Tomcat => "tomcat",
+            Technology::PhpMyAdmin => "phpmyadmin",
+            Technology::Zabbix => "zabbix",
+        }
+    }
+
+    /// 按名称解析，未收录专属模式的技术返回None
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "wordpress" => Some(Technology::WordPress),
+            "joomla" => Some(Technology::Joomla),
+            "drupal" => Some(Technology::Drupal),
+            "thinkphp" => Some(Technology::ThinkPhp),
+            "laravel" => Some(Technology::Laravel),
+            "magento" => Some(Technology::Magento),
+            "django" => Some(Technology::Django),
+            "spring-boot" | "springboot" => Some(Technology::SpringBoot),
+            "tomcat" => Some(Technology::Tomcat),
+            "phpmyadmin" => Some(Technology::PhpMyAdmin),
+            "zabbix" => Some(Technology::Zabbix),
+            _ => None,
+        }
+    }
+}
+
+/// CMS和框架识别请求的路径：首页的响应头、Cookie和页面内容，WordPress登录页，以及误放在站点根目录的 `composer.json`
+pub const CMS_PROBE_PATHS: &[&str] = &["/", "/wp-login.php", "/composer.json"];
+
+/// 响应头特征：请求头名称，值包含的内容(小写)，技术栈
+const HEADER_MARKERS: &[(&str, &str, Technology)] = &[
+    ("x-pingback", "xmlrpc.php", Technology::WordPress),
+    ("x-application-context", "", Technology::SpringBoot),
+    ("x-drupal-cache", "", Technology::Drupal),
+    ("x-generator", "drupal", Technology::Drupal),
+    ("x-magento-cache-debug", "", Technology::Magento),
+];
+
+/// Cookie名称特征(小写)
+const COOKIE_MARKERS: &[(&str, Technology)] = &[
+    ("laravel_session", Technology::Laravel),
+    ("csrftoken", Technology::Django),
+    ("django_language", Technology::Django),
+    ("wordpress_test_cookie", Technology::WordPress),
+    ("wordpress_logged_in", Technology::WordPress),
+    ("thinkphp_show_page_trace", Technology::ThinkPhp),
+];
+
+/// 页面内容特征(小写)和依据说明
+const BODY_MARKERS: &[(&str, Technology, &str)] = &[
+    ("/wp-content/", Technology::WordPress, "/wp-content/ 资源"),
+    ("/wp-includes/", Technology::WordPress, "/wp-includes/ 资源"),
+    ("<meta name=\"generator\" content=\"wordpress", Technology::WordPress, "generator WordPress"),
+    ("<meta name=\"generator\" content=\"joomla", Technology::Joomla, "generator Joomla"),
+    ("<meta name=\"generator\" content=\"drupal", Technology::Drupal, "generator Drupal"),
+    ("csrfmiddlewaretoken", Technology::Django, "csrfmiddlewaretoken 表单字段"),
+    ("whitelabel error page", Technology::SpringBoot, "Whitelabel Error Page"),
+    ("mage/cookies.js", Technology::Magento, "mage/cookies.js 脚本"),
+];
+
+/// WordPress登录页特征
+const WP_LOGIN_MARKERS: &[&str] = &["wp-submit", "user_login", "wp-login.php?action=lostpassword"];
+
+/// `composer.json` 依赖的包名
+const COMPOSER_PACKAGES: &[(&str, Technology)] = &[
+    ("\"laravel/framework\"", Technology::Laravel),
+    ("\"magento/", Technology::Magento),
+    ("\"drupal/core", Technology::Drupal),
+    ("\"topthink/framework\"", Technology::ThinkPhp),
+    ("\"johnpbloch/wordpress", Technology::WordPress),
+    ("\"roots/wordpress", Technology::WordPress),
+];
+
+/// 识别出的技术栈及依据
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CmsMatch {
+    pub technology: Technology,
+    /// 依据，如 "Cookie laravel_session"
+    pub evidence: String,
+}
+
+/// 按 [`CMS_PROBE_PATHS`] 中一个路径的响应识别CMS和框架，每个技术栈只保留第一条依据
+///
+/// `body` 为响应开头的内容；登录页和 `composer.json` 只在200时检查内容，响应头和Cookie不论状态码都检查
+pub fn detect_cms(path: &str, status: u16, headers: &HeaderMap, body: &[u8]) -> Vec<CmsMatch> {
+    let mut matches: Vec<CmsMatch> = Vec::new();
+    let mut add = |technology: Technology, evidence: String| {
+        if !matches.iter().any(|found| found.technology == technology) {
+            matches.push(CmsMatch { technology, evidence });
+        }
+    };
+
+    for (name, marker, technology) in HEADER_MARKERS {
+        let value = headers.get(*name).and_then(|value| value.to_str().ok()).map(str::to_lowercase);
+        if value.is_some_and(|value| value.contains(marker)) {
+            add(*technology, format!("响应头 {}", name));
+        }
+    }
+    // WordPress REST API的发现链接，如 `<https://example.com/wp-json/>; rel="https://api.w.org/"`
+    if headers.get_all(LINK).iter().filter_map(|value| value.to_str().ok()).any(|value| value.contains("api.w.org")) {
+        add(Technology::WordPress, "响应头 link".to_string());
+    }
+    for cookie in headers.get_all(SET_COOKIE).iter().filter_map(|value| value.to_str().ok()) {
+        let name = cookie.split('=').next().unwrap_or_default().trim().to_lowercase();
+        if let Some((marker, technology)) = COOKIE_MARKERS.iter().find(|(marker, _)| name.starts_with(marker)) {
+            add(*technology, format!("Cookie {}", marker));
+        }
+    }
+
+    let text = String::from_utf8_lossy(body).to_lowercase();
+    match path {
+        "/wp-login.php" => {
+            if status == 200 && WP_LOGIN_MARKERS.iter().any(|marker| text.contains(marker)) {
+                add(Technology::WordPress, "/wp-login.php 登录页".to_string());
+            }
+        }
+        "/composer.json" => {
+            if status == 200 && text.trim_start().starts_with('{') {
+                for (package, technology) in COMPOSER_PACKAGES {
+                    if text.contains(package) {
+                        add(*technology, format!("composer.json 依赖 {}", package.trim_matches('"')));
+                    }
+                }
+            }
+        }
+        _ => {
+            for (marker, technology, label) in BODY_MARKERS {
+                if text.contains(marker) {
+                    add(*technology, format!("首页 {}", label));
+                }
+            }
+        }
+    }
+    matches
+}
+
+/// 汇总技术栈在模式库中的专属路径和导入路径，去除重复
+///
+/// `imported` 以技术栈规范名称为键，如从SecLists的CMS字典导入的路径
+pub fn technology_patterns(names: &[String], db: &PatternDb, imported: &HashMap<String, Vec<String>>) -> Vec<String> {
+    merge_paths(names.iter()
+        .filter_map(|name| Technology::parse(name))
+        .map(|technology| (db.technology(technology.name()), imported.get(technology.name()))))
+}
+
+/// favicon哈希库，哈希 -> 技术名称
+#[derive(Debug, Clone)]
+pub struct FaviconDb {
+    hashes: HashMap<i32, String>,
+}
+
+impl FaviconDb {
+    /// 内置哈希库
+    pub fn bundled() -> Self {
+        Self {
+            hashes: FAVICON_HASHES.iter().map(|(hash, name)| (*hash, name.to_string())).collect(),
+        }
+    }
+
+    /// 从文件补充哈希库，每行格式为 `哈希,技术名称`，与内置条目冲突时以文件为准
+    pub fn extend_from_file<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let content = fs::read_to_string(path)?;
+        for (line_no, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parsed = line.split_once(',')
+                .and_then(|(hash, name)| Some((hash.trim().parse::<i32>().ok()?, name.trim().to_lowercase())));
+            match parsed {
+                Some((hash, name)) if !name.is_empty() => {
+                    self.hashes.insert(hash, name);
+                }
+                _ => return Err(crate::BackerError::Config(format!("favicon哈希库第 {} 行格式错误: {}", line_no + 1, line))),
+            }
+        }
+        Ok(())
+    }
+
+    /// 查找favicon对应的技术名称
+    pub fn lookup(&self, favicon: &[u8]) -> Option<&str> {
+        self.hashes.get(&favicon_hash(favicon)).map(|name| name.as_str())
+    }
+}
+
+/// 计算favicon哈希：每76字符换行的base64编码的MurmurHash3 (x86 32位，有符号)
+pub fn favicon_hash(content: &[u8]) -> i32 {
+    murmur3_32(base64_lines(content).as_bytes(), 0) as i32
+}
+
+/// 标准base64编码，每76字符及末尾追加换行，与Python的 `base64.encodebytes` 一致
+fn base64_lines(content: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(content.len() * 4 / 3 + content.len() / 57 + 4);
+    for line in content.chunks(57) {
+        for chunk in line.chunks(3) {
+            let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+            let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+            encoded.push(ALPHABET[(n >> 18) as usize & 63] as char);
+            encoded.push(ALPHABET[(n >> 12) as usize & 63] as char);
+            encoded.push(if chunk.len() > 1 { ALPHABET[(n >> 6) as usize & 63] as char } else { '=' });
+            encoded.push(if chunk.len() > 2 { ALPHABET[n as usize & 63] as char } else { '=' });
+        }
+        encoded.push('\n');
+    }
+    encoded
+}
+
+/// MurmurHash3 x86 32位
+fn murmur3_32(data: &[u8], seed: u32) -> u32 {
+    const C1: u32 = 0xcc9e_2d51;
+    const C2: u32 = 0x1b87_3593;
+    let mut hash = seed;
+
+    let mut blocks = data.chunks_exact(4);
+    for block in blocks.by_ref() {
+        let mut k = u32::from_le_bytes([block[0], block[1], block[2], block[3]]);
+        k = k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+        hash ^= k;
+        hash = hash.rotate_left(13).wrapping_mul(5).wrapping_add(0xe654_6b64);
+    }
+
+    let tail = blocks.remainder();
+    if !tail.is_empty() {
+        let mut k = 0u32;
+        for (i, byte) in tail.iter().enumerate() {
+            k |= (*byte as u32) << (8 * i);
+        }
+        hash ^= k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+    }
+
+    hash ^= data.len() as u32;
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x85eb_ca6b);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0xc2b2_ae35);
+    hash ^= hash >> 16;
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn favicon_hash_matches_shodan() {
+        // Python mmh3 文档中的参考值
+        assert_eq!(murmur3_32(b"foo", 0) as i32, -156908512);
+        assert_eq!(murmur3_32(b"foo", 42) as i32, -1322301282);
+        assert_eq!(base64_lines(&[0u8; 58]), format!("{}\nAA==\n", "A".repeat(76)));
+
+        // Jetty默认favicon，Shodan上为 http.favicon.hash:-629047854
+        let favicon = include_bytes!("../tests/fixtures/favicon-jetty.ico");
+        assert_eq!(favicon_hash(favicon), -629047854);
+    }
+}
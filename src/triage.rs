@@ -0,0 +1,103 @@
+use crate::report::load_results;
+use crate::{Result, ScanResult};
+use crate::stats::{append_ignore_list, load_pattern_stats, pattern_key, save_pattern_stats};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+/// 分析人员对单个发现的判定
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verdict {
+    /// 真阳性
+    TruePositive,
+    /// 误报
+    FalsePositive,
+    /// 跳过
+    Skip,
+}
+
+/// 交互式标注扫描结果
+///
+/// 逐条显示结果文件中的发现，由分析人员在终端标记真阳性或误报。
+/// 误报URL写入忽略列表，判定同时计入模式统计，使后续扫描的排序更准确
+pub fn run_triage<P: AsRef<Path>>(results_path: P, ignore_list: P, pattern_stats: P) -> Result<()> {
+    let results = load_results(&results_path)?;
+
+    if results.is_empty() {
+        println!("结果文件中没有任何发现");
+        return Ok(());
+    }
+
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let mut verdicts = Vec::with_capacity(results.len());
+
+    for (index, result) in results.iter().enumerate() {
+        println!();
+        println!("[{}/{}] {}", index + 1, results.len(), result.url);
+        println!("  状态码: {}, 内容类型: {}, 内容长度: {}, 已验证: {}",
+            result.status_code,
+            result.content_type.as_deref().unwrap_or("未知"),
+            result.content_length.map_or("未知".to_string(), |len| len.to_string()),
+            result.verified
+        );
+
+        match prompt_verdict(&mut input)? {
+            Some(verdict) => verdicts.push((result, verdict)),
+            None => break,
+        }
+    }
+
+    apply_verdicts(&verdicts, ignore_list, pattern_stats)
+}
+
+/// 读取一条判定，返回None表示退出
+fn prompt_verdict(input: &mut impl BufRead) -> Result<Option<Verdict>> {
+    loop {
+        print!("  [t]真阳性 / [f]误报 / [s]跳过 / [q]退出: ");
+        io::stdout().flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        match line.trim().to_lowercase().as_str() {
+            "t" => return Ok(Some(Verdict::TruePositive)),
+            "f" => return Ok(Some(Verdict::FalsePositive)),
+            "s" | "" => return Ok(Some(Verdict::Skip)),
+            "q" => return Ok(None),
+            _ => println!("  无效输入，请重新选择"),
+        }
+    }
+}
+
+/// 将判定写入忽略列表和模式统计
+pub fn apply_verdicts<P: AsRef<Path>>(verdicts: &[(&ScanResult, Verdict)], ignore_list: P, pattern_stats: P) -> Result<()> {
+    let mut stats = load_pattern_stats(&pattern_stats)?;
+    let mut false_positives = Vec::new();
+    let mut true_positives = 0;
+
+    for (result, verdict) in verdicts {
+        match verdict {
+            Verdict::TruePositive => {
+                true_positives += 1;
+                stats.entry(pattern_key(&result.url)).or_default().true_positives += 1;
+            },
+            Verdict::FalsePositive => {
+                false_positives.push(result.url.clone());
+                stats.entry(pattern_key(&result.url)).or_default().false_positives += 1;
+            },
+            Verdict::Skip => {},
+        }
+    }
+
+    append_ignore_list(&ignore_list, &false_positives)?;
+    save_pattern_stats(&pattern_stats, &stats)?;
+
+    println!();
+    println!("标注完成: {} 个真阳性, {} 个误报", true_positives, false_positives.len());
+    println!("忽略列表: {}", ignore_list.as_ref().display());
+    println!("模式统计: {}", pattern_stats.as_ref().display());
+
+    Ok(())
+}
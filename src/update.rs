@@ -0,0 +1,218 @@
+use crate::http::use_tls_backend;
+use crate::{BackerError, Result};
+use log::debug;
+use minisign_verify::{PublicKey, Signature};
+use reqwest::Client;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::time::Duration;
+
+/// 默认的发布信息接口，返回最新版本及其附件
+pub const RELEASE_ENDPOINT: &str = "https://api.github.com/repos/47yAEs/backer/releases/latest";
+
+/// 编译时通过 `BACKER_UPDATE_PUBKEY` 环境变量嵌入的minisign公钥(base64)
+pub const EMBEDDED_PUBLIC_KEY: Option<&str> = option_env!("BACKER_UPDATE_PUBKEY");
+
+/// 校验和清单的附件名，清单本身由 `SHA256SUMS.minisig` 签名
+const CHECKSUMS_ASSET: &str = "SHA256SUMS";
+
+/// 校验和清单签名的可信注释中标明发布版本的字段，如 `release:v0.2.0`
+const RELEASE_FIELD: &str = "release:";
+
+/// 下载新版本的超时，二进制文件较大
+const UPDATE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// 发布信息
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    /// 版本标签，如 `v0.2.0`
+    pub tag_name: String,
+    /// 附件
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+}
+
+/// 发布附件
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+impl Release {
+    /// 去掉 `v` 前缀的版本号
+    pub fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    /// 按名称查找附件的下载地址
+    fn asset_url(&self, name: &str) -> Result<&str> {
+        self.assets.iter()
+            .find(|asset| asset.name == name)
+            .map(|asset| asset.browser_download_url.as_str())
+            .ok_or_else(|| BackerError::Other(format!("版本 {} 缺少附件: {}", self.tag_name, name)))
+    }
+}
+
+/// 当前平台对应的二进制附件名，如 `backer-x86_64-linux`
+pub fn platform_asset_name() -> String {
+    let suffix = if cfg!(windows) { ".exe" } else { "" };
+    format!("backer-{}-{}{}", std::env::consts::ARCH, std::env::consts::OS, suffix)
+}
+
+/// 判断 `candidate` 是否比 `current` 新，按点分数字逐段比较，预发布后缀被忽略
+pub fn is_newer(candidate: &str, current: &str) -> bool {
+    let parse = |version: &str| -> Vec<u64> {
+        version.split(['-', '+']).next().unwrap_or("")
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+    parse(candidate) > parse(current)
+}
+
+/// 自更新器
+pub struct Updater {
+    client: Client,
+    endpoint: String,
+    public_key: Option<String>,
+}
+
+impl Updater {
+    /// 创建自更新器，`public_key` 为minisign公钥(base64)，未指定时使用编译时嵌入的公钥
+    pub fn new(endpoint: &str, public_key: Option<&str>) -> Result<Self> {
+        let public_key = public_key.or(EMBEDDED_PUBLIC_KEY).map(|key| key.trim().to_string());
+        let client = use_tls_backend(Client::builder())
+            .timeout(UPDATE_TIMEOUT)
+            .user_agent(concat!("backer/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+        Ok(Self { client, endpoint: endpoint.to_string(), public_key })
+    }
+
+    /// 查询最新版本
+    pub async fn latest_release(&self) -> Result<Release> {
+        debug!("查询最新版本: {}", self.endpoint);
+        let response = self.client.get(&self.endpoint).send().await?.error_for_status()?;
+        Ok(response.json().await?)
+    }
+
+    /// 下载并验证当前平台的二进制文件，然后替换正在运行的程序
+    ///
+    /// 先用公钥验证校验和清单的签名，并确认签名的可信注释标明的版本与发布版本一致，
+    /// 防止旧版本的签名清单被当作最新版本回滚安装；再核对二进制文件的SHA-256，
+    /// 任何一步失败都不会修改当前程序；未配置公钥时拒绝更新
+    pub async fn install(&self, release: &Release) -> Result<()> {
+        let asset_name = platform_asset_name();
+        let binary_url = release.asset_url(&asset_name)?;
+        let (checksums, trusted_comment) = self.download_verified(release, CHECKSUMS_ASSET).await?;
+        check_signed_release(&trusted_comment, release)?;
+        debug!("校验和清单签名验证通过");
+
+        let expected = expected_checksum(&String::from_utf8_lossy(&checksums), &asset_name)?;
+        let binary = self.download(binary_url).await?;
+        let actual = hex_digest(&binary);
+        if !actual.eq_ignore_ascii_case(&expected) {
+            return Err(BackerError::Other(format!(
+                "{} 的SHA-256不匹配: 期望 {}，实际 {}", asset_name, expected, actual
+            )));
+        }
+
+        // 写入与当前程序同目录的临时文件后替换，避免跨文件系统移动
+        let current = std::env::current_exe()?;
+        let dir = current.parent().ok_or_else(|| BackerError::Other("无法确定程序所在目录".to_string()))?;
+        let staged = dir.join(format!(".{}.new", asset_name));
+        {
+            let mut file = std::fs::File::create(&staged)?;
+            file.write_all(&binary)?;
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o755))?;
+        }
+        let replaced = self_replace::self_replace(&staged);
+        let _ = std::fs::remove_file(&staged);
+        replaced?;
+        Ok(())
+    }
+
+    /// 下载附件及其 `.minisig` 签名，签名验证通过后返回附件内容
+    pub async fn download_signed(&self, release: &Release, asset_name: &str) -> Result<Vec<u8>> {
+        Ok(self.download_verified(release, asset_name).await?.0)
+    }
+
+    /// 下载附件及其 `.minisig` 签名，签名验证通过后返回附件内容和签名的可信注释
+    async fn download_verified(&self, release: &Release, asset_name: &str) -> Result<(Vec<u8>, String)> {
+        let public_key = self.public_key()?;
+        let content = self.download(release.asset_url(asset_name)?).await?;
+        let signature = self.download(release.asset_url(&format!("{}.minisig", asset_name))?).await?;
+        let signature = Signature::decode(&String::from_utf8_lossy(&signature))
+            .map_err(|e| BackerError::Other(format!("无法解析 {} 的签名: {}", asset_name, e)))?;
+        public_key.verify(&content, &signature, false)
+            .map_err(|e| BackerError::Other(format!("{} 签名验证失败: {}", asset_name, e)))?;
+        Ok((content, signature.trusted_comment().to_string()))
+    }
+
+    /// 解析公钥，未配置时拒绝继续
+    fn public_key(&self) -> Result<PublicKey> {
+        let public_key = self.public_key.as_deref().ok_or_else(|| BackerError::Config(
+            "未配置更新签名公钥，无法验证下载内容，请使用 --pubkey 指定或在编译时设置 BACKER_UPDATE_PUBKEY".to_string()
+        ))?;
+        PublicKey::from_base64(public_key)
+            .map_err(|e| BackerError::Config(format!("无效的更新签名公钥: {}", e)))
+    }
+
+    async fn download(&self, url: &str) -> Result<Vec<u8>> {
+        debug!("下载: {}", url);
+        let response = self.client.get(url).send().await?.error_for_status()?;
+        Ok(response.bytes().await?.to_vec())
+    }
+}
+
+/// 确认签名的可信注释中 `release:` 字段标明的版本与发布版本一致
+///
+/// 可信注释受签名保护，签名时用 `minisign -S -m SHA256SUMS -t "release:v0.2.0"` 写入
+fn check_signed_release(trusted_comment: &str, release: &Release) -> Result<()> {
+    let signed = trusted_comment.split_whitespace()
+        .find_map(|field| field.strip_prefix(RELEASE_FIELD))
+        .ok_or_else(|| BackerError::Other(format!("{} 的签名没有标明发布版本", CHECKSUMS_ASSET)))?;
+    if signed.trim_start_matches('v') != release.version() {
+        return Err(BackerError::Other(format!(
+            "{} 的签名属于版本 {}，与发布版本 {} 不一致", CHECKSUMS_ASSET, signed, release.tag_name
+        )));
+    }
+    Ok(())
+}
+
+/// 从 `sha256sum` 格式的清单中查找文件的校验和
+fn expected_checksum(checksums: &str, file_name: &str) -> Result<String> {
+    checksums.lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let hash = parts.next()?;
+            let name = parts.next()?.trim_start_matches('*');
+            (name == file_name).then(|| hash.to_string())
+        })
+        .next()
+        .ok_or_else(|| BackerError::Other(format!("校验和清单中没有 {}", file_name)))
+}
+
+/// 计算SHA-256，返回小写十六进制
+pub fn hex_digest(content: &[u8]) -> String {
+    Sha256::digest(content).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_release_must_match_tag() {
+        let release = Release { tag_name: "v0.3.0".to_string(), assets: Vec::new() };
+        assert!(check_signed_release("timestamp:1700000000 release:v0.3.0", &release).is_ok());
+        assert!(check_signed_release("release:0.3.0", &release).is_ok());
+        assert!(check_signed_release("timestamp:1700000000 release:v0.2.0", &release).is_err());
+        assert!(check_signed_release("timestamp:1700000000 file:SHA256SUMS", &release).is_err());
+    }
+}
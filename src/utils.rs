@@ -1,58 +1,243 @@
-use crate::{BackerError, OutputFormat, Result, ScanResult};
+use crate::{BackerError, CalibrationStatus, Coverage, ErrorPhase, OutputFormat, Result, ResultExt, ScanResult, Target, TargetReport, TargetStatus};
 use chrono::Local;
-use log::{info, debug};
-use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use log::{info, debug, warn};
+use std::collections::HashMap;
 use std::fs::{self, File};
-use std::io::{BufRead, BufReader};
-use std::path::Path;
-use url::Url;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use url::{Position, Url};
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::{thread_rng, Rng, SeedableRng};
 use reqwest::Client;
+use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
+use flate2::read::MultiGzDecoder;
+use ruzstd::StreamingDecoder;
 use std::time::Duration;
-use crate::patterns::PatternGenerator;
+use crate::candidate::{base_url, rule, Candidate};
+use crate::http::{identify_user_agent, parse_identify_header, use_tls_backend};
+use crate::defectdojo::to_dojo_report;
+use crate::sarif::to_sarif_log;
+use crate::patterns::{CandidateIter, PatternGenerator, DEFAULT_DIR_DEPTH};
+use crate::patterns::parser::{has_directive, PatternEntry};
+use crate::similarity::fnv1a;
 
 /// 加载并处理目标站点列表
-pub async fn load_targets<P: AsRef<Path>>(path: P) -> Result<Vec<String>> {
+///
+/// 每行一个URL，也可以是NDJSON格式的对象，如 `{"url": "example.com", "tag": "客户A"}`。
+/// 如果提供了标签映射文件，未带标签的目标将按主机名从中查找标签；
+/// `identify` 为 `--identify` 的标识请求头，检测协议的请求也会携带
+pub async fn load_targets<P: AsRef<Path>>(path: P, tags_file: Option<&Path>, identify: Option<&str>) -> Result<Vec<Target>> {
     // 不输出加载信息
     
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
+    let tag_map = match tags_file {
+        Some(tags_path) => load_tag_map(tags_path).during(ErrorPhase::Load)?,
+        None => HashMap::new(),
+    };
     
-    let mut unique_targets = HashSet::new();
+    parse_targets(&read_target_lines(path).during(ErrorPhase::Load)?, &tag_map, identify).await
+}
+
+/// 打开文本文件，按文件头自动解压gzip和zstd压缩的文件
+///
+/// 大型字典常以压缩形式保存，目标、标签和模式文件均可直接使用 `.gz`/`.zst` 文件
+pub fn open_wordlist<P: AsRef<Path>>(path: P) -> Result<Box<dyn BufRead>> {
+    let path = path.as_ref();
+    let mut reader = BufReader::new(File::open(path)?);
+    let magic = reader.fill_buf()?;
     
+    if magic.starts_with(&[0x1f, 0x8b]) {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(reader))))
+    } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        let decoder = StreamingDecoder::new(reader)
+            .map_err(|e| BackerError::Config(format!("无法解压zstd文件 {}: {}", path.display(), e)))?;
+        Ok(Box::new(BufReader::new(decoder)))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+/// 读取目标文件中的有效行，跳过空行和注释
+pub fn read_target_lines<P: AsRef<Path>>(path: P) -> Result<Vec<String>> {
+    let reader = open_wordlist(path)?;
+    
+    let mut lines = Vec::new();
     for line in reader.lines() {
         let line = line?;
         let trimmed = line.trim();
-        
         if !trimmed.is_empty() && !trimmed.starts_with('#') {
-            // 检测并修正URL协议
-            let url = detect_url_protocol(trimmed).await?;
-            unique_targets.insert(url);
+            lines.push(trimmed.to_string());
         }
     }
     
-    let targets: Vec<String> = unique_targets.into_iter().collect();
+    Ok(lines)
+}
+
+/// 解析目标行，修正URL协议并按主机附加标签
+pub async fn parse_targets(lines: &[String], tag_map: &HashMap<String, String>, identify: Option<&str>) -> Result<Vec<Target>> {
+    let mut unique_targets: HashMap<String, Target> = HashMap::new();
+    
+    for line in lines {
+        let mut target = parse_target_line(line)?;
+        
+        // 检测并修正URL协议
+        target.url = detect_url_protocol(&target.url, identify).await.during(ErrorPhase::Load).for_target(&target.url)?;
+        
+        apply_tag(&mut target, tag_map);
+        unique_targets.entry(target.url.clone()).or_insert(target);
+    }
+    
+    let targets: Vec<Target> = unique_targets.into_values().collect();
     
     Ok(targets)
 }
 
-/// 加载自定义备份文件模式
+/// 加载目标站点但不探测协议，缺少协议的目标假定为 `https://`，供不发送请求的 `--dry-run` 使用
+pub fn load_targets_unprobed<P: AsRef<Path>>(path: P, tags_file: Option<&Path>) -> Result<Vec<Target>> {
+    let tag_map = match tags_file {
+        Some(tags_path) => load_tag_map(tags_path).during(ErrorPhase::Load)?,
+        None => HashMap::new(),
+    };
+    
+    let mut unique_targets: HashMap<String, Target> = HashMap::new();
+    for line in read_target_lines(path).during(ErrorPhase::Load)? {
+        let mut target = parse_target_line(&line)?;
+        if !has_http_scheme(&target.url) {
+            target.url = format!("https://{}", target.url);
+        }
+        apply_tag(&mut target, &tag_map);
+        unique_targets.entry(target.url.clone()).or_insert(target);
+    }
+    
+    Ok(unique_targets.into_values().collect())
+}
+
+/// 解析目标文件的一行，NDJSON格式的目标可以携带标签
+fn parse_target_line(line: &str) -> Result<Target> {
+    let mut target = if line.starts_with('{') {
+        serde_json::from_str::<Target>(line).during(ErrorPhase::Load).for_target(line)?
+    } else {
+        Target::from(line.to_string())
+    };
+    if target.original.is_none() {
+        target.original = Some(target.url.clone());
+    }
+    Ok(target)
+}
+
+/// 未携带标签的目标按主机名从标签映射中取得标签
+fn apply_tag(target: &mut Target, tag_map: &HashMap<String, String>) {
+    if target.tag.is_none() {
+        target.tag = extract_host(&target.url).and_then(|host| tag_map.get(&host).cloned());
+    }
+}
+
+/// URL是否已包含 http:// 或 https:// 协议
+fn has_http_scheme(input: &str) -> bool {
+    input.starts_with("http://") || input.starts_with("https://")
+}
+
+/// 加载标签映射文件，每行格式为 `主机名或URL,标签`
+pub fn load_tag_map<P: AsRef<Path>>(path: P) -> Result<HashMap<String, String>> {
+    let reader = open_wordlist(path)?;
+    
+    let mut tag_map = HashMap::new();
+    
+    for line in reader.lines() {
+        let line = line?;
+        let trimmed = line.trim();
+        
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        
+        match trimmed.split_once(',') {
+            Some((target, tag)) => {
+                let target = target.trim();
+                let host = extract_host(target)
+                    .or_else(|| extract_host(&format!("http://{}", target)))
+                    .unwrap_or_else(|| target.to_lowercase());
+                tag_map.insert(host, tag.trim().to_string());
+            },
+            None => warn!("忽略无效的标签映射行: {}", trimmed),
+        }
+    }
+    
+    Ok(tag_map)
+}
+
+/// 提取URL中的主机名(小写)
+pub fn extract_host(url: &str) -> Option<String> {
+    Url::parse(url).ok()
+        .and_then(|u| u.host_str().map(|h| h.to_lowercase()))
+}
+
+/// 供人阅读的URL：国际化域名转换为Unicode，路径中的百分号编码解码，如
+/// `http://xn--fiqs8s.example/%E5%A4%87%E4%BB%BD.zip` 显示为 `http://中国.example/备份.zip`
+///
+/// 解码后不是有效UTF-8或包含控制字符的部分保持原样；JSON、CSV等机器读取的输出仍使用编码后的URL
+pub fn display_url(url: &str) -> String {
+    let Ok(parsed) = Url::parse(url) else { return url.to_string() };
+    let host = parsed.host_str().unwrap_or("");
+    let (unicode_host, result) = idna::domain_to_unicode(host);
+    let host = if result.is_ok() && host.contains("xn--") { unicode_host.as_str() } else { host };
+    let path = parsed.path();
+    let decoded = percent_encoding::percent_decode_str(path).decode_utf8().ok()
+        .filter(|decoded| !decoded.chars().any(char::is_control));
+    format!(
+        "{}{}{}{}{}",
+        &parsed[..Position::BeforeHost],
+        host,
+        &parsed[Position::AfterHost..Position::BeforePath],
+        decoded.as_deref().unwrap_or(path),
+        &parsed[Position::AfterPath..],
+    )
+}
+
+/// 加载User-Agent列表文件，每行一个，忽略空行和 `#` 开头的注释
+pub fn load_user_agents<P: AsRef<Path>>(path: P) -> Result<Vec<String>> {
+    let path = path.as_ref();
+    let mut user_agents = Vec::new();
+    for line in open_wordlist(path)?.lines() {
+        let line = line?;
+        let trimmed = line.trim();
+        if !trimmed.is_empty() && !trimmed.starts_with('#') {
+            user_agents.push(trimmed.to_string());
+        }
+    }
+    if user_agents.is_empty() {
+        return Err(BackerError::Config(format!("User-Agent列表文件为空: {}", path.display())));
+    }
+    Ok(user_agents)
+}
+
+/// 加载自定义备份文件模式，每行的解释方式见 [`PatternEntry`]
 pub fn load_patterns<P: AsRef<Path>>(path: P) -> Result<Vec<String>> {
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
+    let reader = open_wordlist(path)?;
     
     let mut patterns = Vec::new();
+    let mut inferred = 0;
     
-    for line in reader.lines() {
+    for (index, line) in reader.lines().enumerate() {
         let line = line?;
         let trimmed = line.trim();
         
         if !trimmed.is_empty() && !trimmed.starts_with('#') {
+            // 提前解析，语法错误在扫描开始前报告
+            PatternEntry::parse_file_line(index + 1, trimmed)?;
+            if !has_directive(trimmed) {
+                inferred += 1;
+            }
             patterns.push(trimmed.to_string());
         }
     }
     
+    if inferred > 0 {
+        debug!("模式文件中有 {} 行未加指令，按旧规则推断类型", inferred);
+    }
+    
     if patterns.is_empty() {
         // 如果加载的模式为空，使用默认模式
         patterns = get_default_patterns();
@@ -86,54 +271,103 @@ fn get_default_patterns() -> Vec<String> {
 
 /// 为目标站点生成备份文件URL列表
 pub fn generate_backup_urls(target: &str, patterns: &[String]) -> Vec<String> {
+    generate_candidates(target, patterns).iter().map(Candidate::url).collect()
+}
+
+/// 为目标站点生成备份文件候选，URL在请求时才拼接
+pub fn generate_candidates(target: &str, patterns: &[String]) -> Vec<Candidate> {
+    candidate_stream(target, patterns, DEFAULT_DIR_DEPTH).collect()
+}
+
+/// 为目标站点惰性生成备份文件候选，供扫描器按批拉取
+///
+/// `dir_depth` 为备份目录组合深度，0表示只生成根目录候选
+pub fn candidate_stream(target: &str, patterns: &[String], dir_depth: usize) -> CandidateIter {
     // 使用PatternGenerator生成更完整的URL列表
-    let mut generator = PatternGenerator::new();
-    
-    // 将patterns添加到generator中
+    let generator = PatternGenerator::from_patterns(patterns, dir_depth);
+    generator_stream(target, &generator, patterns)
+}
+
+/// 使用已解析的模式为目标站点惰性生成备份文件候选
+///
+/// 扫描多个目标时模式只解析一次；`patterns` 仅在生成失败回退到简单方法时使用
+pub fn generator_stream(target: &str, generator: &PatternGenerator, patterns: &[String]) -> CandidateIter {
+    match generator.candidates(target) {
+        Ok(candidates) => candidates,
+        Err(e) => {
+            // 生成失败时，使用更简单的方法
+            log::warn!("使用PatternGenerator生成URL失败: {:?}，回退到简单方法", e);
+            Box::new(generate_simple_candidates(target, patterns, generator.dir_depth > 0).into_iter())
+        }
+    }
+}
+
+/// 打印每行模式的解释方式和各目标由模式生成的候选URL，不发送请求
+pub fn print_dry_run(targets: &[Target], patterns: &[String], dir_depth: usize) {
+    println!("模式解释:");
+    if patterns.is_empty() {
+        println!("  未指定模式文件，仅使用内置规则");
+    }
     for pattern in patterns {
-        if pattern.starts_with('.') {
-            generator.full_paths.push(pattern.clone());
-        } else {
-            generator.prefixes.push(pattern.clone());
+        match PatternEntry::parse(pattern) {
+            Ok(entry) => {
+                let inferred = if has_directive(pattern) { "" } else { " (未加指令，按旧规则推断)" };
+                println!("  {} -> {}{}", pattern, entry.describe(), inferred);
+            }
+            Err(e) => println!("  {} -> 无效: {}", pattern, e),
         }
     }
     
-    // 生成URL列表
-    match generator.generate_urls(target) {
-        Ok(urls) => urls,
-        Err(e) => {
-            // 生成失败时，使用更简单的方法
-            log::warn!("使用PatternGenerator生成URL失败: {:?}，回退到简单方法", e);
-            generate_simple_backup_urls(target, patterns)
+    for target in targets {
+        let candidates: Vec<Candidate> = candidate_stream(&target.url, patterns, dir_depth).collect();
+        // 预演不探测协议，缺少协议的目标按 https:// 展示
+        let assumed = if target.original.as_deref().is_some_and(|original| !has_http_scheme(original)) { ", 假定 https://，未探测协议" } else { "" };
+        println!("\n{} ({} 个候选{}):", target.url, candidates.len(), assumed);
+        for candidate in &candidates {
+            match &candidate.method {
+                Some(method) => println!("  {} {}", method, candidate.url()),
+                None => println!("  {}", candidate.url()),
+            }
         }
     }
 }
 
-/// 使用简单方法生成备份文件URL列表（回退方案）
-fn generate_simple_backup_urls(target: &str, patterns: &[String]) -> Vec<String> {
-    let mut urls = Vec::new();
+/// 为技术栈或模式分类的专属路径生成根目录候选，路径原样使用
+pub fn root_candidates(target: &str, paths: &[String], pattern_id: u16) -> Vec<Candidate> {
+    let Ok(parsed_url) = Url::parse(target) else {
+        return Vec::new();
+    };
+    let base = base_url(&parsed_url);
+    paths.iter()
+        .map(|path| Candidate::new(&base, path.as_str(), pattern_id))
+        .collect()
+}
+
+/// 使用简单方法生成备份文件候选（回退方案）
+fn generate_simple_candidates(target: &str, patterns: &[String], scan_dirs: bool) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
     
     // 解析基础URL
     if let Ok(parsed_url) = Url::parse(target) {
-        let base_url = format!("{}://{}", parsed_url.scheme(), parsed_url.host_str().unwrap_or(""));
+        let base = base_url(&parsed_url);
         
         // 直接在根目录下应用模式
         for pattern in patterns {
-            urls.push(format!("{}/{}", base_url, pattern));
+            candidates.push(Candidate::new(&base, pattern.as_str(), rule::SIMPLE));
         }
         
         // 常见的备份目录
-        let backup_dirs = ["backup", "bak", "old", "archive", "db", "data"];
+        let backup_dirs: &[&str] = if scan_dirs { &["backup", "bak", "old", "archive", "db", "data"] } else { &[] };
         
         // 在备份目录下应用模式
         for dir in backup_dirs {
             for pattern in patterns {
-                urls.push(format!("{}/{}/{}", base_url, dir, pattern));
+                candidates.push(Candidate::new(&base, format!("{}/{}", dir, pattern), rule::SIMPLE));
             }
         }
     }
     
-    urls
+    candidates
 }
 
 /// 规范化URL格式
@@ -165,6 +399,7 @@ pub fn normalize_url(url: &str) -> Result<String> {
 /// 保存扫描结果
 pub fn save_results<P: AsRef<Path> + Clone>(
     results: &[ScanResult],
+    targets: &[TargetReport],
     format: OutputFormat,
     path: Option<P>,
 ) -> Result<()> {
@@ -175,19 +410,35 @@ pub fn save_results<P: AsRef<Path> + Clone>(
     
     info!("发现 {} 个潜在的备份文件", results.len());
     
-    if let Some(path) = path {
-        match format {
-            OutputFormat::Json => save_json(results, path.clone())?,
-            OutputFormat::Csv => save_csv(results, path.clone())?,
-            OutputFormat::Markdown => save_markdown(results, path.clone())?,
+    let skipped = targets.iter().filter(|t| t.status != TargetStatus::Live).count();
+    if skipped > 0 {
+        info!("{} 个目标因停放/可接管/不可达被跳过", skipped);
+    }
+    
+    // 多客户扫描时输出各标签的统计
+    if results.iter().any(|r| r.tag.is_some()) {
+        for (tag, group) in group_by_tag(results) {
+            info!("  {}: {} 个发现", tag, group.len());
         }
+    }
+    
+    if let Some(path) = path {
+        let saved = match format {
+            OutputFormat::Json => save_json(results, targets, path.clone()),
+            OutputFormat::Csv => save_csv(results, path.clone()),
+            OutputFormat::Markdown => save_markdown(results, targets, path.clone()),
+            OutputFormat::DefectDojo => save_defectdojo(results, path.clone()),
+            OutputFormat::Sarif => save_sarif(results, path.clone()),
+            OutputFormat::Ndjson => save_ndjson(results, path.clone()),
+        };
+        saved.during(ErrorPhase::Output)?;
         
         println!("结果已保存到 {}", path.as_ref().display());
     } else {
         // 如果没有指定输出文件，打印到控制台
         for result in results {
             println!("URL: {}, 状态码: {}, 内容类型: {}, 内容长度: {}, 已验证: {}", 
-                result.url, 
+                display_url(&result.url), 
                 result.status_code, 
                 result.content_type.as_deref().unwrap_or("未知"), 
                 result.content_length.map_or("未知".to_string(), |len| len.to_string()),
@@ -199,26 +450,187 @@ pub fn save_results<P: AsRef<Path> + Clone>(
     Ok(())
 }
 
+/// `-f json` 写入的结果文件格式版本
+///
+/// 版本1(旧版本)是 `ScanResult` 数组；版本2是带版本号的 [`Report`] 对象，另外记录生成时间和目标报告
+pub const REPORT_VERSION: u32 = 2;
+
+/// JSON结果文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Report {
+    /// 格式版本，见 [`REPORT_VERSION`]
+    pub version: u32,
+    /// 生成时间(RFC 3339)，由旧格式升级时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generated_at: Option<String>,
+    /// 写入结果的backer版本，由旧格式升级时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generator: Option<String>,
+    /// 发现
+    pub results: Vec<ScanResult>,
+    /// 目标报告，由旧格式升级时为空
+    #[serde(default)]
+    pub targets: Vec<TargetReport>,
+}
+
+impl Report {
+    /// 当前版本的报告
+    pub fn new(results: Vec<ScanResult>, targets: Vec<TargetReport>) -> Self {
+        Self {
+            version: REPORT_VERSION,
+            generated_at: Some(Local::now().to_rfc3339()),
+            generator: Some(format!("backer/{}", env!("CARGO_PKG_VERSION"))),
+            results,
+            targets,
+        }
+    }
+
+    /// 由旧格式的发现升级
+    fn upgraded(results: Vec<ScanResult>) -> Self {
+        Self { version: REPORT_VERSION, generated_at: None, generator: None, results, targets: Vec::new() }
+    }
+
+    /// 按版本解析JSON结果：版本1的发现数组(含 `findings.json`)、单个发现或带版本号的报告对象，都升级为当前版本
+    pub fn from_value(value: serde_json::Value) -> Result<Self> {
+        let version = match &value {
+            serde_json::Value::Array(_) => 1,
+            serde_json::Value::Object(object) => match object.get("version") {
+                Some(version) => version.as_u64()
+                    .ok_or_else(|| BackerError::Config(format!("结果文件的版本号无效: {}", version)))?,
+                // 只有一行的NDJSON结果
+                None => return Ok(Self::upgraded(vec![serde_json::from_value(value)?])),
+            },
+            _ => return Err(BackerError::Config("结果文件应为发现数组或报告对象".to_string())),
+        };
+        let mut report = match version {
+            1 => Self::upgraded(serde_json::from_value(value)?),
+            2 => serde_json::from_value(value)?,
+            version => return Err(BackerError::Config(format!(
+                "结果文件版本 {} 比当前支持的版本 {} 新，请升级backer", version, REPORT_VERSION
+            ))),
+        };
+        report.version = REPORT_VERSION;
+        Ok(report)
+    }
+
+    /// 解析结果文件内容：JSON结果(任意版本)或NDJSON，旧版本写入的发现补上ID
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut report = match serde_json::from_str(content) {
+            Ok(value) => Self::from_value(value)?,
+            // 多行NDJSON，每行一个发现
+            Err(_) => Self::upgraded(content.lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(serde_json::from_str)
+                .collect::<std::result::Result<_, _>>()?),
+        };
+        report.results.iter_mut().for_each(ScanResult::ensure_id);
+        Ok(report)
+    }
+}
+
+/// 读取JSON或NDJSON结果文件，旧版本的格式自动升级为当前的 [`Report`]
+pub fn load_report<P: AsRef<Path>>(path: P) -> Result<Report> {
+    Report::parse(&fs::read_to_string(path)?)
+}
+
 /// 将结果保存为JSON格式
-fn save_json<P: AsRef<Path>>(results: &[ScanResult], path: P) -> Result<()> {
-    let json = serde_json::to_string_pretty(results)?;
+fn save_json<P: AsRef<Path>>(results: &[ScanResult], targets: &[TargetReport], path: P) -> Result<()> {
+    let json = serde_json::to_string_pretty(&Report::new(results.to_vec(), targets.to_vec()))?;
     fs::write(path, json)?;
     Ok(())
 }
 
+/// 将结果保存为DefectDojo通用发现导入格式
+fn save_defectdojo<P: AsRef<Path>>(results: &[ScanResult], path: P) -> Result<()> {
+    let report = to_dojo_report(results, Local::now().date_naive());
+    fs::write(path, serde_json::to_string_pretty(&report)?)?;
+    Ok(())
+}
+
+/// 将结果保存为SARIF格式
+fn save_sarif<P: AsRef<Path>>(results: &[ScanResult], path: P) -> Result<()> {
+    fs::write(path, serde_json::to_string_pretty(&to_sarif_log(results))?)?;
+    Ok(())
+}
+
+/// 将结果保存为NDJSON格式，每行一个发现
+fn save_ndjson<P: AsRef<Path>>(results: &[ScanResult], path: P) -> Result<()> {
+    let mut content = String::new();
+    for result in results {
+        content.push_str(&serde_json::to_string(result)?);
+        content.push('\n');
+    }
+    fs::write(path, content)?;
+    Ok(())
+}
+
+/// 扫描时实时写入发现的NDJSON文件，每个发现一行，可用 `tail -f` 跟踪
+///
+/// 每行以一次写入追加，中断的扫描不会留下写了一半的JSON数组
+#[derive(Debug)]
+pub struct NdjsonWriter {
+    path: PathBuf,
+    /// 避免并发写入的行交错
+    lock: Mutex<()>,
+}
+
+impl NdjsonWriter {
+    /// 打开输出文件，`append` 为false时清空已有内容
+    pub fn open(path: &Path, append: bool) -> Result<Self> {
+        let mut options = fs::OpenOptions::new();
+        options.create(true);
+        if append {
+            options.append(true);
+        } else {
+            options.write(true).truncate(true);
+        }
+        options.open(path)?;
+        Ok(Self { path: path.to_path_buf(), lock: Mutex::new(()) })
+    }
+
+    /// 输出文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 追加一条记录，通常是一个发现
+    pub fn append<T: serde::Serialize>(&self, record: &T) -> Result<()> {
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        fs::OpenOptions::new().append(true).open(&self.path)?.write_all(line.as_bytes())?;
+        Ok(())
+    }
+}
+
 /// 将结果保存为CSV格式
 fn save_csv<P: AsRef<Path>>(results: &[ScanResult], path: P) -> Result<()> {
     let mut writer = csv::Writer::from_path(path)?;
     
-    writer.write_record(&["URL", "状态码", "内容类型", "内容长度", "已验证"])?;
+    writer.write_record(["ID", "URL", "状态码", "内容类型", "内容类型不符", "内容长度", "已验证", "标签", "绕过技巧", "验证说明", "高价值文件", "SQL摘要", "API摘要", "git摘要", "文件列表", "发现来源", "可疑原因", "目标", "原始目标", "请求过程"])?;
     
     for result in results {
-        writer.write_record(&[
+        writer.write_record([
+            &result.id,
             &result.url,
             &result.status_code.to_string(),
             &result.content_type.clone().unwrap_or_else(|| "未知".to_string()),
+            &result.content_type_mismatch.to_string(),
             &result.content_length.map_or("未知".to_string(), |len| len.to_string()),
             &result.verified.to_string(),
+            result.tag.as_deref().unwrap_or(""),
+            result.bypass.as_deref().unwrap_or(""),
+            result.verify_note.as_deref().unwrap_or(""),
+            &result.indicators.join(";"),
+            &result.sql_summary.as_ref().map(|s| s.describe()).unwrap_or_default(),
+            result.api_summary.as_deref().unwrap_or(""),
+            &result.git.as_ref().map(|git| git.describe()).unwrap_or_default(),
+            &result.listing.as_ref().map(|listing| listing.describe()).unwrap_or_default(),
+            result.source.as_deref().unwrap_or(""),
+            result.suspicious.as_deref().unwrap_or(""),
+            result.target.as_deref().unwrap_or(""),
+            result.original_target.as_deref().unwrap_or(""),
+            &result.attempt.as_ref().map(|attempt| attempt.describe()).unwrap_or_default(),
         ])?;
     }
     
@@ -227,32 +639,401 @@ fn save_csv<P: AsRef<Path>>(results: &[ScanResult], path: P) -> Result<()> {
 }
 
 /// 将结果保存为Markdown格式
-fn save_markdown<P: AsRef<Path>>(results: &[ScanResult], path: P) -> Result<()> {
+fn save_markdown<P: AsRef<Path>>(results: &[ScanResult], targets: &[TargetReport], path: P) -> Result<()> {
     let mut markdown = String::new();
     
     // 添加标题和日期
     let now = Local::now();
-    markdown.push_str(&format!("# 备份文件扫描结果\n\n"));
+    markdown.push_str("# 备份文件扫描结果\n\n");
     markdown.push_str(&format!("扫描时间: {}\n\n", now.format("%Y-%m-%d %H:%M:%S")));
     
+    // 结果被上限截断时首先提示，避免误以为是完整清单
+    push_truncation_section(&mut markdown, targets);
+    
+    // 列出未扫描的停放、可接管或不可达目标
+    push_target_status_section(&mut markdown, targets);
+    push_scheme_fallback_section(&mut markdown, targets);
+    push_scan_error_section(&mut markdown, targets);
+    push_neighbor_section(&mut markdown, targets);
+    push_technology_section(&mut markdown, targets);
+    push_robots_section(&mut markdown, targets);
+    push_slow_lane_section(&mut markdown, targets);
+    push_challenge_section(&mut markdown, targets);
+    push_size_excluded_section(&mut markdown, targets);
+    push_coverage_section(&mut markdown, targets);
+    
+    // 没有标签时保持单一表格
+    if results.iter().all(|r| r.tag.is_none()) {
+        push_markdown_table(&mut markdown, results.iter());
+        fs::write(path, markdown)?;
+        return Ok(());
+    }
+    
+    // 按标签分组，并输出各组统计
+    let groups = group_by_tag(results);
+    
+    markdown.push_str("## 统计\n\n");
+    markdown.push_str("| 标签 | 发现数 | 已验证 |\n");
+    markdown.push_str("|------|--------|--------|\n");
+    for (tag, group) in &groups {
+        markdown.push_str(&format!(
+            "| {} | {} | {} |\n",
+            tag,
+            group.len(),
+            group.iter().filter(|r| r.verified).count()
+        ));
+    }
+    markdown.push('\n');
+    
+    for (tag, group) in &groups {
+        markdown.push_str(&format!("## {}\n\n", tag));
+        push_markdown_table(&mut markdown, group.iter().copied());
+        markdown.push('\n');
+    }
+    
+    fs::write(path, markdown)?;
+    Ok(())
+}
+
+/// 添加Markdown结果表格
+fn push_markdown_table<'a>(markdown: &mut String, results: impl Iterator<Item = &'a ScanResult>) {
     // 添加表格头
-    markdown.push_str("| URL | 状态码 | 内容类型 | 内容长度 | 已验证 |\n");
-    markdown.push_str("|-----|--------|----------|----------|---------|\n");
+    markdown.push_str("| ID | URL | 状态码 | 内容类型 | 内容长度 | 已验证 |\n");
+    markdown.push_str("|----|-----|--------|----------|----------|---------|\n");
     
     // 添加结果行
     for result in results {
+        let status = match &result.bypass {
+            Some(technique) => format!("{} (绕过: {})", result.status_code, technique),
+            None => result.status_code.to_string(),
+        };
+        let mut url = display_url(&result.url);
+        if let Some(reason) = &result.suspicious {
+            url.push_str(&format!("<br>☣️ 可疑: {}", reason));
+        }
+        if !result.indicators.is_empty() {
+            url.push_str(&format!("<br>高价值文件: {}", result.indicators.join(", ")));
+        }
+        if let Some(summary) = &result.sql_summary {
+            url.push_str(&format!("<br>SQL转储: {}", summary.describe()));
+        }
+        if let Some(git) = &result.git {
+            url.push_str(&format!("<br>git仓库: {}", git.describe()));
+        }
+        if let Some(listing) = &result.listing {
+            url.push_str(&format!("<br>文件列表: {}", listing.describe()));
+        }
+        if let Some(summary) = &result.api_summary {
+            url.push_str(&format!("<br>API定义: {}", summary));
+        }
+        if let Some(source) = &result.source {
+            url.push_str(&format!("<br>发现来源: {}", source));
+        }
+        // 经过重试才得到的发现注明请求过程，便于对方复现
+        if let Some(attempt) = result.attempt.as_ref().filter(|attempt| attempt.retried()) {
+            url.push_str(&format!("<br>请求过程: {}", attempt.describe()));
+        }
         markdown.push_str(&format!(
-            "| {} | {} | {} | {} | {} |\n",
-            result.url,
-            result.status_code,
-            result.content_type.as_deref().unwrap_or("未知"),
+            "| {} | {} | {} | {} | {} | {} |\n",
+            result.id,
+            url,
+            status,
+            describe_content_type(result),
             result.content_length.map_or("未知".to_string(), |len| len.to_string()),
-            if result.verified { "✅" } else { "❌" }
+            match (&result.verify_note, result.verified) {
+                (Some(note), true) => format!("✅ {}", note),
+                (Some(note), false) => format!("❌ {}", note),
+                (None, true) => "✅".to_string(),
+                (None, false) => "❌".to_string(),
+            }
+        ));
+    }
+}
+
+/// 内容类型，与扩展名预期不符时附带提示
+pub fn describe_content_type(result: &ScanResult) -> String {
+    let content_type = result.content_type.as_deref().unwrap_or("未知");
+    if result.content_type_mismatch {
+        format!("{} (⚠️ 与扩展名不符)", content_type)
+    } else {
+        content_type.to_string()
+    }
+}
+
+/// 添加目标状态章节，只列出未正常扫描的目标
+fn push_target_status_section(markdown: &mut String, targets: &[TargetReport]) {
+    let skipped: Vec<&TargetReport> = targets.iter()
+        .filter(|t| t.status != TargetStatus::Live)
+        .collect();
+    if skipped.is_empty() {
+        return;
+    }
+    
+    markdown.push_str("## 未扫描的目标\n\n");
+    markdown.push_str("| 目标 | 状态 | 说明 |\n");
+    markdown.push_str("|------|------|------|\n");
+    for target in skipped {
+        let status = match target.status {
+            TargetStatus::Live => "存活",
+            TargetStatus::Parked => "停放域名",
+            TargetStatus::TakeoverCandidate => "可能可被接管",
+            TargetStatus::Unresolvable => "无法解析",
+            TargetStatus::Unreachable => "无法连接",
+            TargetStatus::ChallengeProtected => "质询保护",
+        };
+        markdown.push_str(&format!(
+            "| {} | {} | {} |\n",
+            target.target,
+            status,
+            target.detail.as_deref().unwrap_or("-")
         ));
     }
+    markdown.push('\n');
+}
+
+/// 汇总扫描覆盖情况，用于扫描结束时输出
+pub fn describe_coverage(targets: &[TargetReport]) -> String {
+    let scanned: Vec<&Coverage> = targets.iter().filter_map(|t| t.coverage.as_ref()).collect();
+    let attempted: usize = scanned.iter().map(|c| c.attempted).sum();
+    let skipped: usize = scanned.iter().map(|c| c.skipped_total()).sum();
+    let not_scanned = targets.iter().filter(|t| t.status != TargetStatus::Live).count();
+    let incomplete = targets.iter().filter(|t| t.status == TargetStatus::Live && t.coverage.is_none()).count();
+    let calibration_failed = scanned.iter().filter(|c| c.calibration == CalibrationStatus::Failed).count();
+
+    let mut summary = format!(
+        "扫描覆盖: {} 个目标已扫描 (请求 {} 个候选，跳过 {} 个)，{} 个不可扫描，{} 个未完成",
+        scanned.len(), attempted, skipped, not_scanned, incomplete
+    );
+    if calibration_failed > 0 {
+        summary.push_str(&format!("，{} 个校准失败", calibration_failed));
+    }
+    summary
+}
+
+/// 添加扫描覆盖章节，每个目标一行
+fn push_coverage_section(markdown: &mut String, targets: &[TargetReport]) {
+    if targets.is_empty() {
+        return;
+    }
     
-    fs::write(path, markdown)?;
-    Ok(())
+    markdown.push_str("## 扫描覆盖\n\n");
+    markdown.push_str("| 目标 | 可访问 | 协议 | 已请求 | 跳过 | 校准 |\n");
+    markdown.push_str("|------|--------|------|--------|------|------|\n");
+    for target in targets {
+        let url = target.scanned_url.as_deref().unwrap_or(&target.target);
+        let scheme = Url::parse(url).map(|url| url.scheme().to_string()).unwrap_or_else(|_| "-".to_string());
+        let reachable = if target.status == TargetStatus::Live { "是" } else { "否" };
+        let (attempted, skipped, calibration) = match &target.coverage {
+            Some(coverage) => {
+                let skipped = coverage.skipped.iter()
+                    .map(|(reason, count)| format!("{} ({})", count, reason))
+                    .collect::<Vec<_>>();
+                (
+                    coverage.attempted.to_string(),
+                    if skipped.is_empty() { "0".to_string() } else { skipped.join("<br>") },
+                    coverage.calibration.describe().to_string(),
+                )
+            }
+            None if target.status == TargetStatus::Live => ("未完成".to_string(), "-".to_string(), "-".to_string()),
+            None => ("未扫描".to_string(), "-".to_string(), "-".to_string()),
+        };
+        markdown.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} |\n",
+            target.target, reachable, scheme, attempted, skipped, calibration
+        ));
+    }
+    markdown.push('\n');
+}
+
+/// 添加协议回退章节
+fn push_scheme_fallback_section(markdown: &mut String, targets: &[TargetReport]) {
+    let fallbacks: Vec<&TargetReport> = targets.iter()
+        .filter(|t| t.scheme_fallback.is_some())
+        .collect();
+    if fallbacks.is_empty() {
+        return;
+    }
+    
+    markdown.push_str("## 协议回退\n\n");
+    markdown.push_str("| 目标 | 回退 | 实际扫描URL |\n");
+    markdown.push_str("|------|------|-------------|\n");
+    for target in fallbacks {
+        markdown.push_str(&format!(
+            "| {} | {} | {} |\n",
+            target.target,
+            target.scheme_fallback.as_deref().unwrap_or("-"),
+            target.scanned_url.as_deref().unwrap_or("-")
+        ));
+    }
+    markdown.push('\n');
+}
+
+/// 添加同IP站点章节
+fn push_neighbor_section(markdown: &mut String, targets: &[TargetReport]) {
+    let hosted: Vec<&TargetReport> = targets.iter()
+        .filter(|t| !t.neighbors.is_empty())
+        .collect();
+    if hosted.is_empty() {
+        return;
+    }
+    
+    markdown.push_str("## 同IP站点\n\n");
+    markdown.push_str("| 目标 | 数量 | 同IP站点 |\n");
+    markdown.push_str("|------|------|----------|\n");
+    for target in hosted {
+        markdown.push_str(&format!(
+            "| {} | {} | {} |\n",
+            target.target,
+            target.neighbors.len(),
+            target.neighbors.join("<br>")
+        ));
+    }
+    markdown.push('\n');
+}
+
+/// 添加结果截断章节，列出发现达到数量上限、结果不完整的目标
+fn push_truncation_section(markdown: &mut String, targets: &[TargetReport]) {
+    let truncated: Vec<&TargetReport> = targets.iter()
+        .filter(|t| t.truncated.is_some())
+        .collect();
+    if truncated.is_empty() {
+        return;
+    }
+    
+    markdown.push_str("## ⚠️ 结果已截断\n\n");
+    markdown.push_str("以下目标的发现达到 --max-findings 或 --max-findings-per-host 上限，结果不完整。\n\n");
+    markdown.push_str("| 目标 | 说明 |\n");
+    markdown.push_str("|------|------|\n");
+    for target in truncated {
+        markdown.push_str(&format!("| {} | {} |\n", target.target, target.truncated.as_deref().unwrap_or("")));
+    }
+    markdown.push('\n');
+}
+
+/// 添加质询页章节，列出扫描中途遇到质询页而跳过剩余候选的目标
+fn push_challenge_section(markdown: &mut String, targets: &[TargetReport]) {
+    let challenged: Vec<&TargetReport> = targets.iter()
+        .filter(|t| t.challenge.is_some())
+        .collect();
+    if challenged.is_empty() {
+        return;
+    }
+    
+    markdown.push_str("## 质询页\n\n");
+    markdown.push_str("| 目标 | 说明 |\n");
+    markdown.push_str("|------|------|\n");
+    for target in challenged {
+        markdown.push_str(&format!("| {} | {} |\n", target.target, target.challenge.as_deref().unwrap_or("")));
+    }
+    markdown.push('\n');
+}
+
+/// 添加慢速主机章节，列出移入慢速队列的目标
+fn push_slow_lane_section(markdown: &mut String, targets: &[TargetReport]) {
+    let slow: Vec<&TargetReport> = targets.iter()
+        .filter(|t| t.slow_lane.is_some())
+        .collect();
+    if slow.is_empty() {
+        return;
+    }
+    
+    markdown.push_str("## 慢速主机\n\n");
+    markdown.push_str("| 目标 | 说明 |\n");
+    markdown.push_str("|------|------|\n");
+    for target in slow {
+        markdown.push_str(&format!("| {} | {} |\n", target.target, target.slow_lane.as_deref().unwrap_or("")));
+    }
+    markdown.push('\n');
+}
+
+/// 添加文件大小排除章节，列出大小超出范围、未报告的响应
+fn push_size_excluded_section(markdown: &mut String, targets: &[TargetReport]) {
+    let excluded: Vec<&TargetReport> = targets.iter()
+        .filter(|t| !t.size_excluded.is_empty())
+        .collect();
+    if excluded.is_empty() {
+        return;
+    }
+    
+    markdown.push_str("## 因文件大小排除的响应\n\n");
+    markdown.push_str("| 目标 | 响应 |\n");
+    markdown.push_str("|------|------|\n");
+    for target in excluded {
+        markdown.push_str(&format!("| {} | {} |\n", target.target, target.size_excluded.join("<br>")));
+    }
+    markdown.push('\n');
+}
+
+/// 输出因robots.txt跳过的区域，供审计
+fn push_robots_section(markdown: &mut String, targets: &[TargetReport]) {
+    let skipped: Vec<&TargetReport> = targets.iter()
+        .filter(|t| !t.robots_skipped.is_empty())
+        .collect();
+    if skipped.is_empty() {
+        return;
+    }
+    
+    markdown.push_str("## robots.txt 跳过区域\n\n");
+    markdown.push_str("| 目标 | 跳过规则 |\n");
+    markdown.push_str("|------|----------|\n");
+    for target in skipped {
+        markdown.push_str(&format!("| {} | {} |\n", target.target, target.robots_skipped.join("<br>")));
+    }
+    markdown.push('\n');
+}
+
+/// 添加技术识别章节
+fn push_technology_section(markdown: &mut String, targets: &[TargetReport]) {
+    let identified: Vec<&TargetReport> = targets.iter()
+        .filter(|t| !t.technologies.is_empty())
+        .collect();
+    if identified.is_empty() {
+        return;
+    }
+    
+    markdown.push_str("## 技术识别\n\n");
+    markdown.push_str("| 目标 | 技术栈 |\n");
+    markdown.push_str("|------|--------|\n");
+    for target in identified {
+        markdown.push_str(&format!("| {} | {} |\n", target.target, target.technologies.join(", ")));
+    }
+    markdown.push('\n');
+}
+
+/// 获取互斥锁，持有锁的任务panic后仍继续使用其中的数据
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// 添加扫描错误章节
+fn push_scan_error_section(markdown: &mut String, targets: &[TargetReport]) {
+    let failed: Vec<&TargetReport> = targets.iter()
+        .filter(|t| !t.errors.is_empty())
+        .collect();
+    if failed.is_empty() {
+        return;
+    }
+    
+    markdown.push_str("## 扫描错误\n\n");
+    markdown.push_str("| 目标 | 错误 |\n");
+    markdown.push_str("|------|------|\n");
+    for target in failed {
+        markdown.push_str(&format!("| {} | {} |\n", target.target, target.errors.join("<br>")));
+    }
+    markdown.push('\n');
+}
+
+/// 按标签对结果分组，未带标签的结果归入"未分组"，分组按标签名排序
+pub fn group_by_tag(results: &[ScanResult]) -> Vec<(String, Vec<&ScanResult>)> {
+    let mut groups: HashMap<String, Vec<&ScanResult>> = HashMap::new();
+    for result in results {
+        let tag = result.tag.clone().unwrap_or_else(|| "未分组".to_string());
+        groups.entry(tag).or_default().push(result);
+    }
+    
+    let mut groups: Vec<(String, Vec<&ScanResult>)> = groups.into_iter().collect();
+    groups.sort_by(|(a, _), (b, _)| a.cmp(b));
+    groups
 }
 
 /// 分析多个URL，提取其共同的根域名
@@ -284,7 +1065,12 @@ pub fn extract_common_root_domain(urls: &[String]) -> Option<String> {
 
 /// 生成随机User-Agent
 pub fn get_random_user_agent() -> String {
-    let user_agents = vec![
+    random_user_agent(&mut thread_rng())
+}
+
+/// 使用指定的随机数生成器选择User-Agent
+pub fn random_user_agent<R: Rng + ?Sized>(rng: &mut R) -> String {
+    let user_agents = [
         "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36",
         "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/92.0.4515.159 Safari/537.36",
         "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/15.0 Safari/605.1.15",
@@ -297,15 +1083,26 @@ pub fn get_random_user_agent() -> String {
         "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/92.0.4515.107 Safari/537.36 OPR/78.0.4093.112",
     ];
     
-    user_agents.choose(&mut thread_rng())
+    user_agents.choose(rng)
         .unwrap_or(&"Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36")
         .to_string()
 }
 
-/// 自动检测URL协议(http/https)
-pub async fn detect_url_protocol(input: &str) -> Result<String> {
+/// 创建随机数生成器
+///
+/// 指定种子时由种子和 `key`(如请求URL)确定，同一种子下结果与任务调度顺序无关，
+/// 扫描可以完整复现；未指定种子时使用系统熵
+pub fn seeded_rng(seed: Option<u64>, key: &str) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed ^ fnv1a(key.as_bytes())),
+        None => StdRng::from_entropy(),
+    }
+}
+
+/// 自动检测URL协议(http/https)，指定标识请求头时不随机UA
+pub async fn detect_url_protocol(input: &str, identify: Option<&str>) -> Result<String> {
     // 如果已经包含协议，直接返回
-    if input.starts_with("http://") || input.starts_with("https://") {
+    if has_http_scheme(input) {
         return Ok(input.to_string());
     }
 
@@ -315,10 +1112,17 @@ pub async fn detect_url_protocol(input: &str) -> Result<String> {
     debug!("尝试检测域名协议: {}", domain);
     
     // 创建一个临时客户端用于探测，更短的超时
-    let client = Client::builder()
+    let client = use_tls_backend(Client::builder())
         .timeout(Duration::from_secs(3)) // 更短的超时
-        .use_rustls_tls() // 使用rustls提高性能
         .build()?;
+    let user_agent = if identify.is_some() { identify_user_agent() } else { get_random_user_agent() };
+    let mut headers = HeaderMap::new();
+    if let Ok(value) = HeaderValue::from_str(&user_agent) {
+        headers.insert(USER_AGENT, value);
+    }
+    if let Some((name, value)) = identify.map(parse_identify_header).transpose()? {
+        headers.insert(name, value);
+    }
     
     // 首先尝试HTTPS
     let https_url = format!("https://{}", domain);
@@ -327,7 +1131,7 @@ pub async fn detect_url_protocol(input: &str) -> Result<String> {
     let https_result = tokio::time::timeout(
         Duration::from_secs(3), 
         client.head(&https_url)
-            .header("User-Agent", get_random_user_agent())
+            .headers(headers.clone())
             .send()
     ).await;
     
@@ -350,7 +1154,7 @@ pub async fn detect_url_protocol(input: &str) -> Result<String> {
     let http_result = tokio::time::timeout(
         Duration::from_secs(3),
         client.head(&http_url)
-            .header("User-Agent", get_random_user_agent())
+            .headers(headers.clone())
             .send()
     ).await;
     
@@ -369,3 +1173,72 @@ pub async fn detect_url_protocol(input: &str) -> Result<String> {
     debug!("默认使用HTTP: {}", http_url);
     Ok(http_url)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 加入版本号前 `-f json` 写入的发现数组，发现还没有ID
+    const V1_ARRAY: &str = include_str!("../tests/fixtures/report-v1.json");
+    /// 加入版本号前 `-f ndjson` 写入的结果，只有一个发现
+    const V1_SINGLE_NDJSON: &str = include_str!("../tests/fixtures/report-v1-single.ndjson");
+    /// 加入版本号前 `-f ndjson` 写入的结果，每行一个发现
+    const V1_NDJSON: &str = include_str!("../tests/fixtures/report-v1.ndjson");
+
+    #[test]
+    fn upgrades_v1_array_and_backfills_ids() {
+        let report = Report::parse(V1_ARRAY).unwrap();
+        assert_eq!(report.version, REPORT_VERSION);
+        assert_eq!(report.generated_at, None);
+        assert!(report.targets.is_empty());
+        assert_eq!(report.results.len(), 1);
+        let result = &report.results[0];
+        assert_eq!(result.url, "http://127.0.0.1/127001.zip");
+        assert_eq!(result.content_length, Some(641));
+        assert!(result.id.starts_with("bk-"));
+        assert_eq!(result.id, result.finding_id());
+    }
+
+    #[test]
+    fn from_value_upgrades_v1_array_without_backfilling() {
+        let report = Report::from_value(serde_json::from_str(V1_ARRAY).unwrap()).unwrap();
+        assert_eq!(report.version, REPORT_VERSION);
+        assert_eq!(report.results[0].id, "");
+    }
+
+    #[test]
+    fn upgrades_single_ndjson_line() {
+        let report = Report::parse(V1_SINGLE_NDJSON).unwrap();
+        assert_eq!(report.version, REPORT_VERSION);
+        assert_eq!(report.results.len(), 1);
+        assert_eq!(report.results[0].url, "http://127.0.0.1:8097/127001.zip");
+        // 已有的ID保持不变
+        assert_eq!(report.results[0].id, "bk-94cda387732e5a06");
+    }
+
+    #[test]
+    fn upgrades_multi_line_ndjson() {
+        let report = Report::parse(V1_NDJSON).unwrap();
+        let urls: Vec<&str> = report.results.iter().map(|result| result.url.as_str()).collect();
+        assert_eq!(urls, ["http://127.0.0.1:8097/127001.zip", "http://127.0.0.1:8097/127001.tar.gz"]);
+        assert_eq!(report.results[1].id, "bk-f00c6a1dee0b96f9");
+    }
+
+    #[test]
+    fn reads_current_version() {
+        let written = serde_json::to_string(&Report::new(Report::parse(V1_NDJSON).unwrap().results, Vec::new())).unwrap();
+        let report = Report::parse(&written).unwrap();
+        assert_eq!(report.version, REPORT_VERSION);
+        assert!(report.generated_at.is_some());
+        assert_eq!(report.results.len(), 2);
+    }
+
+    #[test]
+    fn rejects_newer_and_invalid_versions() {
+        let newer = format!(r#"{{"version": {}, "results": []}}"#, REPORT_VERSION + 1);
+        let error = Report::parse(&newer).unwrap_err().to_string();
+        assert!(error.contains(&format!("结果文件版本 {} 比当前支持的版本 {} 新", REPORT_VERSION + 1, REPORT_VERSION)));
+        assert!(Report::parse(r#"{"version": "2", "results": []}"#).unwrap_err().to_string().contains("版本号无效"));
+        assert!(Report::parse("42").is_err());
+    }
+}
@@ -0,0 +1,169 @@
+use crate::mime::file_kind;
+
+/// 超过该大小(字节)的文件使用多段采样验证
+pub const LARGE_FILE_THRESHOLD: u64 = 10 * 1024 * 1024;
+
+/// 每段采样的大小(字节)
+pub const SAMPLE_SIZE: u64 = 4096;
+
+/// zip结尾采样的大小(字节)：中央目录结束记录(22字节)加上最长的文件注释(65535字节)，
+/// 带注释的zip的结束记录可能离文件结尾超过一段普通采样
+pub const ZIP_TAIL_SAMPLE_SIZE: u64 = 22 + 65_535;
+
+/// zip中央目录结束记录签名
+const ZIP_EOCD_SIGNATURE: &[u8] = b"PK\x05\x06";
+
+/// 采样验证结论
+#[derive(Debug, Clone, PartialEq)]
+pub enum SampleVerdict {
+    /// 采样内容符合预期
+    Intact,
+    /// 文件不完整或已损坏
+    Corrupt(String),
+    /// 实际是HTML错误页
+    ErrorPage,
+}
+
+/// 采样位置
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SamplePosition {
+    Start,
+    Middle,
+    End,
+}
+
+/// 根据文件大小生成采样区间(闭区间)，依次为开头、中间和结尾；zip的结尾采样覆盖结束记录可能出现的全部范围
+pub fn sample_plan(url: &str, content_length: u64) -> Vec<(SamplePosition, u64, u64)> {
+    if content_length <= SAMPLE_SIZE {
+        return vec![(SamplePosition::Start, 0, content_length.saturating_sub(1))];
+    }
+
+    let middle = content_length / 2;
+    let tail = if is_zip_url(url) { ZIP_TAIL_SAMPLE_SIZE } else { SAMPLE_SIZE };
+    vec![
+        (SamplePosition::Start, 0, SAMPLE_SIZE - 1),
+        (SamplePosition::Middle, middle, (middle + SAMPLE_SIZE - 1).min(content_length - 1)),
+        (SamplePosition::End, content_length.saturating_sub(tail), content_length - 1),
+    ]
+}
+
+fn is_zip_url(url: &str) -> bool {
+    url.to_lowercase().ends_with(".zip")
+}
+
+/// 判断内容是否像HTML页面
+pub fn looks_like_html(content: &[u8]) -> bool {
+    let head = &content[..content.len().min(512)];
+    let text = String::from_utf8_lossy(head).trim_start().to_lowercase();
+    text.starts_with("<!doctype html") || text.starts_with("<html") || text.contains("<head>") || text.contains("<body")
+}
+
+/// 判断URL是否为压缩包类文件，这类文件不应是HTML
+pub fn is_archive_url(url: &str) -> bool {
+    file_kind(url).is_some_and(|kind| kind.archive)
+}
+
+/// SQL转储开头常见的语句和注释，小写
+const SQL_KEYWORDS: &[&str] = &[
+    "create table", "insert into", "drop table", "create database", "lock tables", "set names",
+    "begin transaction", "pragma ", "-- mysql dump", "-- mariadb dump", "postgresql database dump",
+];
+
+/// 文件头检查结论
+#[derive(Debug, Clone, PartialEq)]
+pub enum MagicVerdict {
+    /// 文件头与扩展名相符
+    Match,
+    /// 文件头与扩展名不符，附带期望的类型名称
+    Mismatch(&'static str),
+    /// 扩展名没有可检查的文件头，如 `.bak`
+    Unknown,
+}
+
+/// 用文件开头的内容检查文件头是否与扩展名相符
+///
+/// 二进制类型比较签名字节，SQL转储查找常见的语句和注释
+pub fn check_magic(url: &str, content: &[u8]) -> MagicVerdict {
+    let Some(kind) = file_kind(url) else { return MagicVerdict::Unknown };
+    let matched = if kind.name == "sql" {
+        let text = String::from_utf8_lossy(content).to_lowercase();
+        SQL_KEYWORDS.iter().any(|keyword| text.contains(keyword))
+    } else if kind.magic.is_empty() {
+        return MagicVerdict::Unknown;
+    } else {
+        kind.magic.iter().any(|(offset, signature)| content.get(*offset..).is_some_and(|rest| rest.starts_with(signature)))
+    };
+    if matched {
+        MagicVerdict::Match
+    } else {
+        MagicVerdict::Mismatch(kind.name)
+    }
+}
+
+/// 判断URL是否为二进制文件(压缩包、数据库等)，这类文件不应是HTML
+pub fn is_binary_url(url: &str) -> bool {
+    file_kind(url).is_some_and(|kind| kind.binary)
+}
+
+/// 判断内容中是否包含zip中央目录结束记录
+pub fn has_zip_eocd(content: &[u8]) -> bool {
+    content.windows(ZIP_EOCD_SIGNATURE.len()).any(|w| w == ZIP_EOCD_SIGNATURE)
+}
+
+/// 根据各段采样内容判断文件是否完整
+pub fn judge_samples(url: &str, samples: &[(SamplePosition, Vec<u8>)]) -> SampleVerdict {
+    let is_archive = is_archive_url(url);
+
+    for (position, content) in samples {
+        if content.is_empty() {
+            return SampleVerdict::Corrupt(format!("{:?} 采样为空", position));
+        }
+        if *position == SamplePosition::Start && is_archive && looks_like_html(content) {
+            return SampleVerdict::ErrorPage;
+        }
+    }
+
+    // zip文件结尾必须有中央目录结束记录，否则说明文件被截断
+    if is_zip_url(url) {
+        if let Some((_, tail)) = samples.iter().find(|(p, _)| *p == SamplePosition::End) {
+            if !has_zip_eocd(tail) {
+                return SampleVerdict::Corrupt("zip结尾缺少中央目录记录，文件可能被截断".to_string());
+            }
+        }
+    }
+
+    SampleVerdict::Intact
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zip_tail_sample_covers_longest_comment() {
+        let length = 20 * 1024 * 1024;
+        let plan = sample_plan("https://example.com/site.zip", length);
+        assert_eq!(plan[2], (SamplePosition::End, length - ZIP_TAIL_SAMPLE_SIZE, length - 1));
+        let plan = sample_plan("https://example.com/site.tar.gz", length);
+        assert_eq!(plan[2], (SamplePosition::End, length - SAMPLE_SIZE, length - 1));
+    }
+
+    #[test]
+    fn finds_eocd_before_long_comment() {
+        // 结束记录后是最长的注释，记录位于结尾采样的开头
+        let mut tail = ZIP_EOCD_SIGNATURE.to_vec();
+        tail.resize(ZIP_TAIL_SAMPLE_SIZE as usize, b' ');
+        let samples = vec![
+            (SamplePosition::Start, b"PK\x03\x04".to_vec()),
+            (SamplePosition::End, tail.clone()),
+        ];
+        assert_eq!(judge_samples("https://example.com/site.zip", &samples), SampleVerdict::Intact);
+
+        // 只看最后一段普通采样时找不到
+        let samples = vec![
+            (SamplePosition::Start, b"PK\x03\x04".to_vec()),
+            (SamplePosition::End, tail[tail.len() - SAMPLE_SIZE as usize..].to_vec()),
+        ];
+        assert!(matches!(judge_samples("https://example.com/site.zip", &samples), SampleVerdict::Corrupt(_)));
+    }
+}
@@ -0,0 +1,350 @@
+use crate::redact::save_redacted_results;
+use crate::scanner::Scanner;
+use crate::signing::ReportSigner;
+use crate::utils::{display_url, load_tag_map, load_targets, parse_targets, read_target_lines, save_results};
+use crate::{BackerError, OutputFormat, Result, ScanResult, Target, TargetReport};
+use chrono::Local;
+use log::debug;
+use notify::{RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// 文件变化后等待写入完成的时间，期间的连续事件合并处理
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// 定时扫描的最短间隔
+pub const MIN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 定时扫描的默认历史文件
+pub const DEFAULT_HISTORY_FILE: &str = "backer-history.json";
+
+/// 每轮扫描结果的保存选项
+#[derive(Debug, Clone)]
+pub struct SaveOptions {
+    /// 输出格式
+    pub format: OutputFormat,
+    /// 结果输出文件
+    pub output: Option<PathBuf>,
+    /// 输出脱敏后的结果
+    pub redact: bool,
+    /// 脱敏时另存未脱敏结果的文件
+    pub unredacted_output: Option<PathBuf>,
+    /// 每轮保存后为结果文件签名
+    pub signer: Option<ReportSigner>,
+}
+
+/// 监视模式选项
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    /// 监视的目标文件
+    pub targets_file: PathBuf,
+    /// 目标标签映射文件
+    pub tags_file: Option<PathBuf>,
+    /// 只扫描启动后新增的目标
+    pub new_only: bool,
+    /// 标识请求头，检测新目标协议的请求也会携带
+    pub identify: Option<String>,
+    /// 结果保存选项，每轮扫描后写入累计结果
+    pub save: SaveOptions,
+}
+
+/// 定时扫描选项
+#[derive(Debug, Clone)]
+pub struct ScheduleOptions {
+    /// 目标文件，每轮扫描前重新读取
+    pub targets_file: PathBuf,
+    /// 目标标签映射文件
+    pub tags_file: Option<PathBuf>,
+    /// 标识请求头
+    pub identify: Option<String>,
+    /// 两轮扫描开始之间的间隔
+    pub interval: Duration,
+    /// 历史文件，记录出现过的发现，重启后继续使用
+    pub history: PathBuf,
+    /// 结果保存选项，每轮扫描后写入本轮结果
+    pub save: SaveOptions,
+}
+
+/// 解析定时扫描的间隔，如 `24h`、`30m`、`90s`、`7d`，不带单位时为秒，不能短于 [`MIN_INTERVAL`]
+pub fn parse_interval(value: &str) -> std::result::Result<Duration, String> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number.parse().map_err(|_| format!("无效的间隔: {} (如 24h、30m、90s、7d)", value))?;
+    let seconds = match unit {
+        "" | "s" => number,
+        "m" => number.saturating_mul(60),
+        "h" => number.saturating_mul(3600),
+        "d" => number.saturating_mul(86400),
+        _ => return Err(format!("无效的间隔单位: {} (可用 s、m、h、d)", unit)),
+    };
+    let interval = Duration::from_secs(seconds);
+    if interval < MIN_INTERVAL {
+        return Err(format!("间隔不能短于 {} 秒", MIN_INTERVAL.as_secs()));
+    }
+    Ok(interval)
+}
+
+/// 间隔说明，如 "24 小时"
+fn describe_interval(interval: Duration) -> String {
+    let seconds = interval.as_secs();
+    match seconds {
+        s if s % 86400 == 0 => format!("{} 天", s / 86400),
+        s if s % 3600 == 0 => format!("{} 小时", s / 3600),
+        s if s % 60 == 0 => format!("{} 分钟", s / 60),
+        s => format!("{} 秒", s),
+    }
+}
+
+/// 历史中的一个发现
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub url: String,
+    /// 首次发现的时间(RFC 3339)
+    pub first_seen: String,
+    /// 最近一次发现的时间(RFC 3339)
+    pub last_seen: String,
+}
+
+/// 定时扫描的历史：出现过的发现(按发现ID)及首次、最近一次发现的时间
+///
+/// 已在历史中的发现不再通知，进程重启后读取同一文件继续
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ScanHistory {
+    /// 已完成的扫描轮数
+    pub rounds: u64,
+    pub findings: BTreeMap<String, HistoryEntry>,
+}
+
+impl ScanHistory {
+    /// 读取历史文件，文件不存在时为空历史
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)?;
+        serde_json::from_str(&content)
+            .map_err(|e| BackerError::Config(format!("无法解析历史文件 {}: {}", path.display(), e)))
+    }
+
+    /// 写入历史文件，先写临时文件再重命名，中断时不会留下不完整的文件
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&temp, path)?;
+        Ok(())
+    }
+
+    /// 历史中的发现ID
+    pub fn ids(&self) -> HashSet<String> {
+        self.findings.keys().cloned().collect()
+    }
+
+    /// 记录一轮扫描的发现，返回其中不在历史中的新发现
+    pub fn record<'a>(&mut self, results: &'a [ScanResult]) -> Vec<&'a ScanResult> {
+        let now = Local::now().to_rfc3339();
+        self.rounds += 1;
+        let mut new = Vec::new();
+        for result in results {
+            let id = if result.id.is_empty() { result.finding_id() } else { result.id.clone() };
+            match self.findings.get_mut(&id) {
+                Some(entry) => entry.last_seen = now.clone(),
+                None => {
+                    self.findings.insert(id, HistoryEntry { url: result.url.clone(), first_seen: now.clone(), last_seen: now.clone() });
+                    new.push(result);
+                }
+            }
+        }
+        new
+    }
+}
+
+/// 监视目标文件，新增的目标出现后自动扫描，按Ctrl+C退出
+///
+/// 编辑器通常先写临时文件再重命名保存，因此监视的是文件所在目录，
+/// 只处理指向目标文件的事件。已处理过的行不会重复扫描
+pub async fn watch_targets(scanner: &mut Scanner, options: &WatchOptions) -> Result<()> {
+    let path = &options.targets_file;
+    let file_name = path.file_name()
+        .map(|name| name.to_os_string())
+        .ok_or_else(|| BackerError::Config(format!("无效的目标文件路径: {}", path.display())))?;
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let tag_map = match &options.tags_file {
+        Some(tags_path) => load_tag_map(tags_path)?,
+        None => HashMap::new(),
+    };
+    
+    // 文件监视器在独立线程中回调，通过通道通知扫描循环
+    let (changes, mut change_rx) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        match event {
+            Ok(event) if event.paths.iter().any(|p| p.file_name() == Some(file_name.as_os_str())) => {
+                let _ = changes.send(());
+            }
+            Ok(_) => {}
+            Err(e) => debug!("监视目标文件出错: {:?}", e),
+        }
+    }).map_err(|e| BackerError::Other(format!("无法创建文件监视器: {}", e)))?;
+    watcher.watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| BackerError::Other(format!("无法监视目录 {}: {}", dir.display(), e)))?;
+    
+    let mut seen: HashSet<String> = HashSet::new();
+    if options.new_only {
+        seen.extend(read_target_lines(path)?);
+    }
+    let mut all_results: Vec<ScanResult> = Vec::new();
+    let mut all_reports: Vec<TargetReport> = Vec::new();
+    
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    
+    println!("正在监视目标文件: {} (Ctrl+C 退出)", path.display());
+    loop {
+        // 文件被暂时移走或正在重写时读取失败，等待下一次变化
+        let new_lines: Vec<String> = match read_target_lines(path) {
+            Ok(lines) => lines.into_iter().filter(|line| !seen.contains(line)).collect(),
+            Err(e) => {
+                debug!("读取目标文件失败: {:?}", e);
+                Vec::new()
+            }
+        };
+        
+        // 逐行解析，解析成功后才记为已处理，失败的行在文件下次变化时重试
+        let mut targets: Vec<Target> = Vec::new();
+        for line in new_lines {
+            match parse_targets(std::slice::from_ref(&line), &tag_map, options.identify.as_deref()).await {
+                Ok(parsed) => {
+                    seen.insert(line);
+                    for target in parsed {
+                        if !targets.iter().any(|t| t.url == target.url) {
+                            targets.push(target);
+                        }
+                    }
+                }
+                Err(e) => eprintln!("解析新增目标失败: {}", e),
+            }
+        }
+        
+        if !targets.is_empty() {
+            println!("新增 {} 个目标，开始扫描", targets.len());
+            let interrupted = tokio::select! {
+                result = scanner.scan_targets(targets) => {
+                    match result {
+                        Ok(results) => {
+                            all_results.extend(results);
+                            all_reports.extend(scanner.get_target_reports());
+                            if let Err(e) = save_round(&options.save, &all_results, &all_reports) {
+                                eprintln!("保存结果失败: {}", e);
+                            }
+                        }
+                        Err(e) => eprintln!("扫描过程中发生错误: {}", e),
+                    }
+                    false
+                },
+                _ = &mut ctrl_c => true,
+            };
+            if interrupted {
+                // 扫描中途退出时保存本批已提交的发现
+                all_results.extend(scanner.get_partial_results().unwrap_or_default());
+                all_reports.extend(scanner.get_target_reports());
+                if let Err(e) = save_round(&options.save, &all_results, &all_reports) {
+                    eprintln!("保存结果失败: {}", e);
+                }
+                break;
+            }
+            println!("继续监视目标文件: {}", path.display());
+        }
+        
+        tokio::select! {
+            changed = change_rx.recv() => if changed.is_none() {
+                break;
+            },
+            _ = &mut ctrl_c => break,
+        }
+        tokio::time::sleep(DEBOUNCE).await;
+        while change_rx.try_recv().is_ok() {}
+    }
+    
+    println!("停止监视，共发现 {} 个备份文件", all_results.len());
+    Ok(())
+}
+
+/// 按固定间隔重复扫描目标文件中的目标，按Ctrl+C退出
+///
+/// 每轮扫描前重新读取目标文件，扫描后把本轮结果写入输出文件、把发现记入历史；
+/// 历史中已有的发现不再通知，扫描汇总也只在有新发现时发送
+pub async fn watch_schedule(scanner: &mut Scanner, options: &ScheduleOptions) -> Result<()> {
+    let mut history = ScanHistory::load(&options.history)?;
+    println!("定时扫描: 每 {} 扫描一次，历史文件 {} ({} 个已知发现，Ctrl+C 退出)",
+        describe_interval(options.interval), options.history.display(), history.findings.len());
+    
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    
+    loop {
+        let started = tokio::time::Instant::now();
+        scanner.set_notified_findings(Some(history.ids()));
+        // 目标文件可能在两轮之间更新，读取失败时跳过本轮
+        match load_targets(&options.targets_file, options.tags_file.as_deref(), options.identify.as_deref()).await {
+            Ok(targets) if targets.is_empty() => eprintln!("没有找到有效的目标站点，跳过本轮扫描"),
+            Ok(targets) => {
+                println!("第 {} 轮扫描开始: {} 个目标", history.rounds + 1, targets.len());
+                tokio::select! {
+                    result = scanner.scan_targets(targets) => match result {
+                        Ok(results) => {
+                            let new = history.record(&results);
+                            println!("第 {} 轮扫描完成: {} 个发现，其中 {} 个新发现", history.rounds, results.len(), new.len());
+                            for result in &new {
+                                println!("  新发现: [{}] {}", result.severity().name(), display_url(&result.url));
+                            }
+                            if let Err(e) = history.save(&options.history) {
+                                eprintln!("保存历史文件失败: {}", e);
+                            }
+                            if let Err(e) = save_round(&options.save, &results, &scanner.get_target_reports()) {
+                                eprintln!("保存结果失败: {}", e);
+                            }
+                        }
+                        Err(e) => eprintln!("扫描过程中发生错误: {}", e),
+                    },
+                    _ = &mut ctrl_c => break,
+                }
+            }
+            Err(e) => eprintln!("加载目标站点失败: {}", e),
+        }
+        
+        let next = started + options.interval;
+        let wait = next.saturating_duration_since(tokio::time::Instant::now());
+        let next_time = Local::now() + chrono::Duration::from_std(wait).unwrap_or_default();
+        println!("下一轮扫描: {}", next_time.format("%Y-%m-%d %H:%M:%S"));
+        tokio::select! {
+            _ = tokio::time::sleep_until(next) => {},
+            _ = &mut ctrl_c => break,
+        }
+    }
+    
+    println!("停止定时扫描，共完成 {} 轮，历史中有 {} 个发现", history.rounds, history.findings.len());
+    Ok(())
+}
+
+/// 保存结果，按选项脱敏并签名
+fn save_round(options: &SaveOptions, results: &[ScanResult], reports: &[TargetReport]) -> Result<()> {
+    if options.redact {
+        save_redacted_results(results, reports, options.format, options.output.as_deref(), options.unredacted_output.as_deref())?;
+    } else if options.format != OutputFormat::Ndjson {
+        // ndjson格式的发现已在扫描中写入输出文件
+        save_results(results, reports, options.format, options.output.as_ref())?;
+    }
+    if let (Some(signer), Some(output)) = (&options.signer, &options.output) {
+        if !results.is_empty() {
+            signer.sign_file(output)?;
+        }
+    }
+    Ok(())
+}